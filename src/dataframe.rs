@@ -1,7 +1,8 @@
 use std::fs::{metadata, read_dir, File};
-use std::io::BufReader;
+use std::io::{BufReader, Cursor, Read, Seek};
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use arrow::array::{Array, ArrayDataBuilder, ArrayDataRef, ArrayRef, UInt32Array, UInt64Array};
@@ -9,7 +10,10 @@ use arrow::compute;
 use arrow::csv::{Reader as CsvReader, ReaderBuilder as CsvReaderBuilder};
 use arrow::datatypes::*;
 use arrow::error::ArrowError;
-use arrow::ipc::{reader::FileReader as IpcFileReader, writer::FileWriter as IpcFileWriter};
+use arrow::ipc::{
+    reader::{FileReader as IpcFileReader, StreamReader as IpcStreamReader},
+    writer::FileWriter as IpcFileWriter,
+};
 use arrow::json::{Reader as JsonReader, ReaderBuilder as JsonReaderBuilder};
 use arrow::record_batch::{RecordBatch, RecordBatchReader};
 use arrow::util::pretty;
@@ -18,14 +22,18 @@ use parquet::file::reader::{FileReader, SerializedFileReader};
 
 use crate::error::{DataFrameError, Result};
 use crate::expression::{
-    BooleanFilter, BooleanFilterEval, BooleanInput, JoinCriteria, SortCriteria, SqlDatabase,
-    SqlWriteOptions,
+    Aggregation, BooleanFilter, BooleanFilterEval, BooleanInput, CastMode, Dataset, JoinCriteria,
+    SortCriteria, SqlDatabase, SqlWriteOptions,
 };
+use crate::functions::aggregate::HashAggregator;
+use crate::io::compression::CompressedFile;
+use crate::io::datasource::check_cancelled;
+use crate::io::parquet::{conform_batch, union_schemas, ParquetReadOptions};
 use crate::io::sql::{self, SqlDataSink, SqlDataSource};
 use crate::table::Column;
 use crate::utils;
 
-use compute::{SortColumn, SortOptions};
+use compute::SortColumn;
 
 pub struct DataFrame {
     schema: Arc<Schema>,
@@ -123,6 +131,27 @@ impl DataFrame {
         self
     }
 
+    /// Returns the `DataFrame` with the specified column cast to another data type
+    ///
+    /// Values that cannot be cast are handled according to `mode`, with `CastMode::Unsafe`
+    /// returning an error if any value fails to cast.
+    pub fn with_column_cast(&self, name: &str, to_type: &DataType, mode: CastMode) -> Result<Self> {
+        let index = self.schema.index_of(name)?;
+        let arrays = self
+            .column(index)
+            .data()
+            .chunks()
+            .iter()
+            .map(|array| crate::functions::cast::cast(array, to_type, mode))
+            .collect::<std::result::Result<Vec<ArrayRef>, ArrowError>>()?;
+        let field = Field::new(name, to_type.clone(), true);
+        let mut fields = self.schema.fields().clone();
+        fields[index] = field.clone();
+        let mut columns = self.columns.clone();
+        columns[index] = Column::from_arrays(arrays, field);
+        Ok(Self::from_columns(Arc::new(Schema::new(fields)), columns))
+    }
+
     /// Returns dataframe as an Arrow `RecordBatch`
     /// TODO: add a method to break into smaller batches
     pub fn to_record_batches(&self) -> Vec<RecordBatch> {
@@ -203,10 +232,7 @@ impl DataFrame {
             let array = column.to_array()?;
             lex_criteria.push(SortColumn {
                 values: array,
-                options: Some(SortOptions {
-                    descending: c.descending,
-                    nulls_first: false,
-                }),
+                options: Some(c.to_arrow_sort_options()),
             });
         }
         let sort_indices = compute::kernels::sort::lexsort_to_indices(&lex_criteria)?;
@@ -336,6 +362,32 @@ impl DataFrame {
         }
     }
 
+    /// Returns a dataframe with the `(input, output)` columns in the given order, renaming
+    /// each input column to its output name.
+    ///
+    /// An input column can be projected more than once.
+    pub fn project(&self, columns: &[(String, String)]) -> Result<Self> {
+        let mut fields = Vec::with_capacity(columns.len());
+        let mut projected = Vec::with_capacity(columns.len());
+        for (input, output) in columns {
+            let index = self
+                .schema
+                .index_of(input)
+                .map_err(|_| DataFrameError::ColumnNotFound(input.clone()))?;
+            let field = self.schema.field(index);
+            let field = Field::new(output, field.data_type().clone(), field.is_nullable());
+            projected.push(Column::from_chunked_array(
+                self.columns[index].data().clone(),
+                field.clone(),
+            ));
+            fields.push(field);
+        }
+        Ok(DataFrame::from_columns(
+            Arc::new(Schema::new(fields)),
+            projected,
+        ))
+    }
+
     /// Create a dataframe from an Arrow Table.
     ///
     /// Arrow Tables are not yet in the Rust library, and we are hashing them out here
@@ -346,8 +398,21 @@ impl DataFrame {
         }
     }
 
+    /// Read a CSV file, decompressing it if it has a `.gz` or `.zst` extension
     pub fn from_csv(path: &str, schema: Option<Arc<Schema>>) -> Self {
-        let file = File::open(path).unwrap();
+        match CompressedFile::open(path, None).unwrap() {
+            CompressedFile::Uncompressed(file) => DataFrame::from_csv_reader(file, schema),
+            mut file => {
+                // the CSV reader infers schemas from seekable inputs, so the decompressed file
+                // is read into memory
+                let mut data = vec![];
+                file.read_to_end(&mut data).unwrap();
+                DataFrame::from_csv_reader(Cursor::new(data), schema)
+            }
+        }
+    }
+
+    fn from_csv_reader<R: Read + Seek>(file: R, schema: Option<Arc<Schema>>) -> Self {
         let mut reader = match schema {
             Some(schema) => CsvReader::new(file, schema, true, None, 1024, None),
             None => {
@@ -406,6 +471,28 @@ impl DataFrame {
         })
     }
 
+    /// Create a dataframe from an Arrow IPC stream, such as one read from a socket or pipe
+    ///
+    /// Unlike the file format, the stream format has no footer, so batches are read in the
+    /// order that they were written, after the schema in the stream's first message.
+    pub fn from_arrow_stream<R: Read>(reader: R) -> Result<Self> {
+        let mut reader = IpcStreamReader::try_new(reader)?;
+
+        let schema = reader.schema();
+        let mut batches = vec![];
+
+        while let Some(batch) = reader.next().transpose()? {
+            batches.push(batch);
+        }
+
+        let table = crate::table::Table::from_record_batches(schema.clone(), batches);
+
+        Ok(DataFrame {
+            schema,
+            columns: table.columns,
+        })
+    }
+
     pub fn from_json(path: &str, schema: Option<Arc<Schema>>) -> Self {
         let file = File::open(path).unwrap();
         let mut reader = match schema {
@@ -448,8 +535,15 @@ impl DataFrame {
     }
 
     pub fn from_parquet(path: &str) -> Result<Self> {
+        Self::from_parquet_with_options(path, ParquetReadOptions::default())
+    }
+
+    /// Create a dataframe from a Parquet file, or a directory of Parquet files
+    ///
+    /// Files in a directory are read in the order of their names.
+    pub fn from_parquet_with_options(path: &str, options: ParquetReadOptions) -> Result<Self> {
         let attr = metadata(path)?;
-        let paths = if attr.is_dir() {
+        let mut paths: Vec<PathBuf> = if attr.is_dir() {
             let readdir = read_dir(path)?;
             readdir
                 .filter_map(|r| r.ok())
@@ -458,6 +552,7 @@ impl DataFrame {
         } else {
             vec![PathBuf::from(path)]
         };
+        paths.sort();
 
         let mut schema = None;
         let mut batches = vec![];
@@ -470,17 +565,30 @@ impl DataFrame {
             }
 
             let mut arrow_reader = ParquetFileArrowReader::new(Rc::new(file_reader));
-            if schema.is_none() {
-                schema = Some(Arc::new(arrow_reader.get_schema()?));
-            }
+            let file_schema = Arc::new(arrow_reader.get_schema()?);
+            schema = match schema {
+                Some(schema) if options.schema_evolution => {
+                    Some(Arc::new(union_schemas(&schema, &file_schema)?))
+                }
+                Some(schema) => Some(schema),
+                None => Some(file_schema),
+            };
 
             let mut record_batch_reader = arrow_reader.get_record_reader(1024)?;
             while let Ok(Some(batch)) = record_batch_reader.next().transpose() {
                 batches.push(batch);
             }
         }
+        let schema = schema.unwrap();
+        if options.schema_evolution {
+            // batches are conformed once the schemas of all files are known
+            batches = batches
+                .iter()
+                .map(|batch| conform_batch(batch, schema.clone()))
+                .collect::<Result<Vec<RecordBatch>>>()?;
+        }
 
-        let table = crate::table::Table::from_record_batches(schema.unwrap(), batches);
+        let table = crate::table::Table::from_record_batches(schema, batches);
 
         Ok(Self {
             schema: table.schema().clone(),
@@ -589,6 +697,11 @@ impl DataFrame {
                     "MySQL database not yet supported".to_string(),
                 ))
             }
+            SqlDatabase::Sqlite => {
+                return Err(DataFrameError::SqlError(
+                    "Writing to SQLite databases not yet supported".to_string(),
+                ))
+            }
         }
         Ok(())
     }
@@ -623,6 +736,68 @@ impl DataFrame {
         Ok(column)
     }
 
+    /// Group the dataframe by key columns, to be aggregated with `GroupBy::agg`
+    ///
+    /// Rows with null keys are grouped together.
+    pub fn group_by(&self, keys: Vec<&str>) -> GroupBy {
+        GroupBy {
+            frame: self,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    /// Group the dataframe by columns, computing the aggregations for each group
+    ///
+    /// The output has the group columns, followed by a column for each aggregated column.
+    pub fn aggregate(&self, groups: &[String], aggregations: &[Aggregation]) -> Result<Self> {
+        self.aggregate_with_cancel(groups, aggregations, Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Group the dataframe by columns, computing the aggregations for each group, until the
+    /// `cancel` flag is set
+    ///
+    /// The flag is checked before each batch is aggregated. If it is set, a
+    /// `DataFrameError::Cancelled` error is returned.
+    pub fn aggregate_with_cancel(
+        &self,
+        groups: &[String],
+        aggregations: &[Aggregation],
+        cancel: Arc<AtomicBool>,
+    ) -> Result<Self> {
+        let dataset = Dataset {
+            name: "dataframe".to_string(),
+            columns: self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        };
+        let group_names: Vec<&str> = groups.iter().map(|g| g.as_str()).collect();
+        let output = dataset.try_aggregate(&group_names, aggregations)?;
+        let schema = Arc::new(Schema::new(
+            output.columns.into_iter().map(|c| c.into()).collect(),
+        ));
+
+        let group_columns = groups
+            .iter()
+            .map(|name| self.schema.index_of(name))
+            .collect::<std::result::Result<Vec<usize>, ArrowError>>()?;
+        let mut aggregates = vec![];
+        for aggregation in aggregations {
+            for name in &aggregation.columns {
+                aggregates.push((aggregation.function.clone(), self.schema.index_of(name)?));
+            }
+        }
+        let mut aggregator = HashAggregator::new(group_columns, aggregates);
+        for batch in self.to_record_batches() {
+            check_cancelled(&cancel)?;
+            aggregator.update(&batch)?;
+        }
+        let batch = aggregator.finish(schema.clone())?;
+        Ok(Self::from_arrays(schema, batch.columns().to_vec()))
+    }
+
     pub fn join(&self, other: &Self, criteria: &JoinCriteria) -> Result<Self> {
         // get join indices
         let (left_indices, right_indices) =
@@ -719,6 +894,20 @@ impl DataFrame {
     }
 }
 
+/// A dataframe grouped by key columns
+pub struct GroupBy<'a> {
+    frame: &'a DataFrame,
+    keys: Vec<String>,
+}
+
+impl<'a> GroupBy<'a> {
+    /// Compute the aggregations for each group, returning a dataframe with the key columns
+    /// followed by the aggregated columns
+    pub fn agg(&self, aggregations: Vec<Aggregation>) -> Result<DataFrame> {
+        self.frame.aggregate(&self.keys, &aggregations)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -871,6 +1060,31 @@ mod tests {
         assert!(write.is_ok());
     }
 
+    #[test]
+    fn test_arrow_stream_io() {
+        use arrow::ipc::writer::StreamWriter;
+        use std::io::Cursor;
+
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        let mut bytes = vec![];
+        {
+            let mut writer = StreamWriter::try_new(&mut bytes, &dataframe.schema()).unwrap();
+            for batch in dataframe.to_record_batches() {
+                writer.write(&batch).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let streamed = DataFrame::from_arrow_stream(Cursor::new(bytes)).unwrap();
+        assert_eq!(dataframe.schema(), streamed.schema());
+        assert_eq!(37, streamed.num_rows());
+        let city = streamed.column_by_name("city");
+        assert_eq!(
+            "Elgin, Scotland, the UK",
+            col_to_string_arrays(city)[0].value(0)
+        );
+    }
+
     #[test]
     fn test_csv_io() {
         let mut dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
@@ -1058,4 +1272,112 @@ mod tests {
         assert_eq!(joined.num_rows(), 4);
         assert_eq!(joined.num_columns(), 6);
     }
+
+    #[test]
+    fn test_aggregate_same_column() {
+        use crate::expression::AggregateFunction;
+
+        let schema = Schema::new(vec![
+            Field::new("key", DataType::Int32, false),
+            Field::new("x", DataType::Float64, true),
+        ]);
+        let key = Int32Array::from(vec![1, 2, 1, 2, 1]);
+        let x = Float64Array::from(vec![Some(1.0), Some(4.0), Some(3.0), None, Some(8.0)]);
+        let frame = DataFrame::from_arrays(Arc::new(schema), vec![Arc::new(key), Arc::new(x)]);
+        let aggregations = ["min", "max", "avg"]
+            .iter()
+            .map(|f| Aggregation {
+                function: match *f {
+                    "min" => AggregateFunction::Min,
+                    "max" => AggregateFunction::Max,
+                    _ => AggregateFunction::Avg,
+                },
+                columns: vec!["x".to_string()],
+            })
+            .collect::<Vec<Aggregation>>();
+        let aggregated = frame
+            .aggregate(&["key".to_string()], &aggregations)
+            .unwrap();
+        assert_eq!(2, aggregated.num_rows());
+        let names: Vec<&str> = aggregated
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(vec!["key", "min(x)", "max(x)", "avg(x)"], names);
+        let values = |i: usize| -> Vec<f64> {
+            let array = aggregated.column(i).to_array().unwrap();
+            let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+            (0..array.len()).map(|j| array.value(j)).collect()
+        };
+        assert_eq!(vec![1.0, 4.0], values(1));
+        assert_eq!(vec![8.0, 4.0], values(2));
+        assert_eq!(vec![4.0, 4.0], values(3));
+
+        // repeating an aggregation would create duplicate columns
+        let repeated = vec![aggregations[0].clone(), aggregations[0].clone()];
+        assert!(frame.aggregate(&["key".to_string()], &repeated).is_err());
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        match frame.aggregate_with_cancel(&["key".to_string()], &aggregations, cancel) {
+            Err(DataFrameError::Cancelled) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Expected aggregation to be cancelled"),
+        }
+    }
+
+    #[test]
+    fn test_group_by_sum() {
+        use crate::expression::AggregateFunction;
+
+        let schema = Schema::new(vec![
+            Field::new("key", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]);
+        let key = StringArray::from(vec![Some("a"), Some("b"), None, Some("a"), None]);
+        let value = Int64Array::from(vec![1, 2, 3, 4, 5]);
+        let frame = DataFrame::from_arrays(Arc::new(schema), vec![Arc::new(key), Arc::new(value)]);
+        let grouped = frame
+            .group_by(vec!["key"])
+            .agg(vec![Aggregation {
+                function: AggregateFunction::Sum,
+                columns: vec!["value".to_string()],
+            }])
+            .unwrap();
+        assert_eq!(3, grouped.num_rows());
+        let keys = grouped.column(0).to_array().unwrap();
+        let keys = keys.as_any().downcast_ref::<StringArray>().unwrap();
+        let sums = grouped.column(1).to_array().unwrap();
+        let sums = sums.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!("a", keys.value(0));
+        assert_eq!(5, sums.value(0));
+        assert_eq!("b", keys.value(1));
+        assert_eq!(2, sums.value(1));
+        // null keys are a group of their own
+        assert!(keys.is_null(2));
+        assert_eq!(8, sums.value(2));
+    }
+
+    #[test]
+    fn test_with_column_cast_modes() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        let a = StringArray::from(vec![Some("12"), Some("abc"), None]);
+        let frame = DataFrame::from_arrays(Arc::new(schema), vec![Arc::new(a)]);
+
+        let casted = frame
+            .with_column_cast("a", &DataType::Int32, CastMode::Safe)
+            .unwrap();
+        assert_eq!(&DataType::Int32, casted.schema().field(0).data_type());
+        let values = casted.column(0).to_array().unwrap();
+        let values = values.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(12, values.value(0));
+        // "abc" is not a number, so it becomes null
+        assert!(values.is_null(1));
+        assert!(values.is_null(2));
+
+        assert!(frame
+            .with_column_cast("a", &DataType::Int32, CastMode::Unsafe)
+            .is_err());
+    }
 }
@@ -1,5 +1,6 @@
 use std::error::Error;
 
+use arrow::datatypes::DataType;
 use arrow::error::ArrowError;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -12,8 +13,69 @@ pub enum DataFrameError {
     NoneError,
     ArrowError(String),
     SqlError(String),
+    /// Execution was stopped because its cancellation flag was set
+    Cancelled,
+    /// An operation could not be created from its inputs, with a suggestion on how to fix it
+    /// if one is known, e.g. the name of a similar column
+    InvalidOperation {
+        message: String,
+        suggestion: Option<String>,
+    },
+    /// An operation does not support its inputs, e.g. a numeric operation on a string column
+    UnsupportedOperation {
+        op: String,
+        reason: String,
+    },
+    /// A column does not have the type that was expected
+    SchemaMismatch {
+        column: String,
+        expected: DataType,
+        actual: DataType,
+    },
+    /// A column that an operation reads is not in its input
+    ColumnNotFound(String),
 }
 
+impl std::fmt::Display for DataFrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use DataFrameError::*;
+        match self {
+            MemoryError(message) => write!(f, "Memory error: {}", message),
+            ParseError(message) => write!(f, "Parse error: {}", message),
+            ComputeError(message) => write!(f, "Compute error: {}", message),
+            DivideByZero => write!(f, "Divide by zero"),
+            IoError(message) => write!(f, "IO error: {}", message),
+            NoneError => write!(f, "Expected a value, but found none"),
+            DataFrameError::ArrowError(message) => write!(f, "Arrow error: {}", message),
+            SqlError(message) => write!(f, "SQL error: {}", message),
+            Cancelled => write!(f, "Execution was cancelled"),
+            InvalidOperation {
+                message,
+                suggestion: Some(suggestion),
+            } => write!(f, "{}, {}", message, suggestion),
+            InvalidOperation {
+                message,
+                suggestion: None,
+            } => write!(f, "{}", message),
+            UnsupportedOperation { op, reason } => {
+                write!(f, "Unsupported operation {}: {}", op, reason)
+            }
+            SchemaMismatch {
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Column {} has type {:?}, but {:?} was expected",
+                column, actual, expected
+            ),
+            ColumnNotFound(column) => write!(f, "Column {} not found", column),
+        }
+    }
+}
+
+impl Error for DataFrameError {}
+
 impl From<ArrowError> for DataFrameError {
     fn from(error: ArrowError) -> Self {
         DataFrameError::ArrowError(error.to_string())
@@ -44,6 +106,24 @@ impl From<postgres::error::Error> for DataFrameError {
     }
 }
 
+impl From<rusqlite::Error> for DataFrameError {
+    fn from(error: rusqlite::Error) -> Self {
+        DataFrameError::SqlError(error.to_string())
+    }
+}
+
+impl From<csv::Error> for DataFrameError {
+    fn from(error: csv::Error) -> Self {
+        DataFrameError::ParseError(error.to_string())
+    }
+}
+
+impl From<avro_rs::Error> for DataFrameError {
+    fn from(error: avro_rs::Error) -> Self {
+        DataFrameError::IoError(error.to_string())
+    }
+}
+
 impl From<parquet::errors::ParquetError> for DataFrameError {
     fn from(error: parquet::errors::ParquetError) -> Self {
         DataFrameError::IoError(error.to_string())
@@ -6,12 +6,16 @@
 use crate::dataframe::DataFrame;
 use crate::error::DataFrameError;
 use crate::expression::*;
+use crate::functions::aggregate::{from_ordered_integers, is_integer_ordered, to_ordered_integers};
+use crate::functions::array::ArrayFunctions as ArrayFn;
 use crate::functions::scalar::ScalarFunctions as ScalarFn;
+use crate::functions::window::MovingAverage;
 use crate::table;
 
 use arrow::array::*;
 use arrow::datatypes::*;
 use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
 use num::{abs, Signed, Zero};
 use num_traits::Float;
 use std::sync::Arc;
@@ -22,7 +26,7 @@ fn eval_numeric_scalar_op<T, F>(
     a: Vec<&PrimitiveArray<T>>,
     b: Vec<&PrimitiveArray<T>>,
     op: F,
-) -> Vec<ArrayRef>
+) -> Result<Vec<ArrayRef>, ArrowError>
 where
     T: ArrowNumericType,
     F: Fn(
@@ -30,32 +34,72 @@ where
         Vec<&PrimitiveArray<T>>,
     ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>,
 {
-    op(a, b)
-        .unwrap()
+    Ok(op(a, b)?
         .into_iter()
         .map(|arr| Arc::new(arr) as ArrayRef)
-        .collect()
+        .collect())
 }
 
 /// Evaluate a float scalar op that takes 1 input array
-fn eval_float1_scalar_op<T, F>(a: Vec<&PrimitiveArray<T>>, op: F) -> Vec<ArrayRef>
+fn eval_float1_scalar_op<T, F>(
+    a: Vec<&PrimitiveArray<T>>,
+    op: F,
+) -> Result<Vec<ArrayRef>, ArrowError>
 where
     T: ArrowNumericType,
     T::Native: num_traits::Float,
     F: Fn(Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>,
 {
-    op(a)
-        .unwrap()
+    Ok(op(a)?
         .into_iter()
         .map(|arr| Arc::new(arr) as ArrayRef)
-        .collect()
+        .collect())
+}
+
+/// Row counts recorded when applying a filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterStats {
+    pub input_rows: usize,
+    pub output_rows: usize,
+}
+
+impl FilterStats {
+    /// The fraction of input rows that matched the filter
+    ///
+    /// An empty input has a selectivity of 1.0, as no rows were excluded
+    pub fn selectivity(&self) -> f64 {
+        if self.input_rows == 0 {
+            1.0
+        } else {
+            self.output_rows as f64 / self.input_rows as f64
+        }
+    }
+}
+
+/// A report of what happened while evaluating computations
+///
+/// This is useful for validating planner estimates against actual data
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionReport {
+    /// Statistics for each filter step, in the order in which they were applied
+    pub filters: Vec<FilterStats>,
 }
 
 pub trait Evaluate: Sized {
     /// Evaluate a list of computations
-    fn evaluate(self, comp: &[Computation]) -> Self;
+    fn evaluate(self, comp: &[Computation]) -> Self {
+        self.evaluate_with_report(comp).0
+    }
+    /// Evaluate a list of computations, returning a report of the evaluation
+    fn evaluate_with_report(self, comp: &[Computation]) -> (Self, ExecutionReport);
     /// Evaluate a calculation transformation
-    fn calculate(self, calculation: &Calculation) -> Self;
+    fn calculate(self, calculation: &Calculation) -> Self {
+        self.try_calculate(calculation)
+            .expect("Unable to evaluate calculation")
+    }
+    /// Evaluate a calculation transformation, returning an error if it cannot be evaluated,
+    /// such as when checked arithmetic overflows
+    fn try_calculate(self, calculation: &Calculation) -> Result<Self, DataFrameError>;
     /// Evaluate a `Read` operation, returning the read data
     fn read(reader: &Reader) -> Self;
     /// Evaluate a write operation, and write the data to the writer
@@ -63,14 +107,17 @@ pub trait Evaluate: Sized {
 }
 
 impl Evaluate for DataFrame {
-    fn evaluate(self, comp: &[Computation]) -> Self {
+    fn evaluate_with_report(self, comp: &[Computation]) -> (Self, ExecutionReport) {
         use Transformation::*;
         let mut frame = self;
+        let mut report = ExecutionReport::default();
         // get the input columns from the dataframe
         for c in comp.iter().rev() {
             for transform in &c.transformations {
                 frame = match transform {
-                    GroupAggregate(_, _) => panic!("aggregations not supported"),
+                    GroupAggregate(groups, aggregations) => frame
+                        .aggregate(groups, aggregations)
+                        .expect("Unable to aggregate dataframe"),
                     Calculate(operation) => frame.calculate(&operation),
                     Join(a, b, criteria) => {
                         let mut frame_a = DataFrame::empty();
@@ -84,162 +131,110 @@ impl Evaluate for DataFrame {
                     }
                     Select(cols) => frame.select(cols.iter().map(|s| s.as_str()).collect()),
                     Drop(cols) => frame.drop(cols.iter().map(|s| s.as_str()).collect()),
+                    Project(cols) => frame.project(cols).expect("Unable to project dataframe"),
                     Read(reader) => Self::read(&reader),
-                    Filter(cond) => frame.filter(cond),
+                    Filter(cond) => {
+                        let input_rows = frame.num_rows();
+                        let filtered = frame.filter(cond);
+                        report.filters.push(FilterStats {
+                            input_rows,
+                            output_rows: filtered.num_rows(),
+                        });
+                        filtered
+                    }
                     Limit(size) => frame.limit(*size),
                     Sort(criteria) => frame.sort(criteria).expect("Unable to sort dataframe"),
                 };
             }
         }
 
-        frame
+        (frame, report)
     }
-    fn calculate(self, calculation: &Calculation) -> Self {
+    fn try_calculate(self, calculation: &Calculation) -> Result<Self, DataFrameError> {
+        calculation.validate(self.schema())?;
         let columns: Vec<&table::Column> = calculation
             .inputs
             .clone()
             .into_iter()
             .map(|col: Column| self.column_by_name(&col.name))
             .collect();
-        match &calculation.function {
+        Ok(match &calculation.function {
             Function::Scalar(expr) => match expr {
                 // scalars that take 2 variables
-                ScalarFunction::Add
-                | ScalarFunction::Subtract
+                ScalarFunction::Add(_)
+                | ScalarFunction::Subtract(_)
                 | ScalarFunction::Divide
-                | ScalarFunction::Multiply => {
+                | ScalarFunction::Multiply(_)
+                | ScalarFunction::BitAnd
+                | ScalarFunction::BitOr
+                | ScalarFunction::BitXor
+                | ScalarFunction::ShiftLeft
+                | ScalarFunction::ShiftRight => {
                     // we are adding 2 columns together to create a third
-                    let column: Vec<ArrayRef> = if let ColumnType::Scalar(dtype) =
-                        &calculation.output.column_type
-                    {
-                        match dtype {
-                            DataType::UInt16 => {
-                                // assign op to use
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a = table::col_to_prim_arrays::<UInt16Type>(
-                                    columns.get(0).unwrap(),
-                                );
-                                let b = table::col_to_prim_arrays::<UInt16Type>(
-                                    columns.get(1).unwrap(),
-                                );
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::UInt32 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a = table::col_to_prim_arrays::<UInt32Type>(
-                                    columns.get(0).unwrap(),
-                                );
-                                let b = table::col_to_prim_arrays::<UInt32Type>(
-                                    columns.get(1).unwrap(),
-                                );
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::UInt64 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a = table::col_to_prim_arrays::<UInt64Type>(
-                                    columns.get(0).unwrap(),
-                                );
-                                let b = table::col_to_prim_arrays::<UInt64Type>(
-                                    columns.get(1).unwrap(),
-                                );
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::Int16 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a =
-                                    table::col_to_prim_arrays::<Int16Type>(columns.get(0).unwrap());
-                                let b =
-                                    table::col_to_prim_arrays::<Int16Type>(columns.get(1).unwrap());
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::Int32 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a =
-                                    table::col_to_prim_arrays::<Int32Type>(columns.get(0).unwrap());
-                                let b =
-                                    table::col_to_prim_arrays::<Int32Type>(columns.get(1).unwrap());
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::Int64 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a =
-                                    table::col_to_prim_arrays::<Int64Type>(columns.get(0).unwrap());
-                                let b =
-                                    table::col_to_prim_arrays::<Int64Type>(columns.get(1).unwrap());
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::Float32 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a = table::col_to_prim_arrays::<Float32Type>(
-                                    columns.get(0).unwrap(),
-                                );
-                                let b = table::col_to_prim_arrays::<Float32Type>(
-                                    columns.get(1).unwrap(),
-                                );
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            DataType::Float64 => {
-                                let op = match expr {
-                                    ScalarFunction::Add => ScalarFn::add,
-                                    ScalarFunction::Subtract => ScalarFn::subtract,
-                                    ScalarFunction::Divide => ScalarFn::divide,
-                                    ScalarFunction::Multiply => ScalarFn::multiply,
-                                    _ => unreachable!(),
-                                };
-                                let a = table::col_to_prim_arrays::<Float64Type>(
-                                    columns.get(0).unwrap(),
-                                );
-                                let b = table::col_to_prim_arrays::<Float64Type>(
-                                    columns.get(1).unwrap(),
-                                );
-                                eval_numeric_scalar_op(a, b, op)
-                            }
-                            _ => panic!("Unsupported operation"),
-                        }
-                    } else {
-                        unreachable!()
+                    let dtype = match &calculation.output.column_type {
+                        ColumnType::Scalar(dtype) => dtype,
+                        ColumnType::Array(_) | ColumnType::Struct(_) => unreachable!(),
+                    };
+                    // integer arithmetic can be checked for overflow
+                    macro_rules! integer_op {
+                        ($arrow_type:ty) => {{
+                            type Op = fn(
+                                Vec<&PrimitiveArray<$arrow_type>>,
+                                Vec<&PrimitiveArray<$arrow_type>>,
+                            )
+                                -> Result<Vec<PrimitiveArray<$arrow_type>>, ArrowError>;
+                            let op: Op = match expr {
+                                ScalarFunction::Add(options) if options.checked => {
+                                    ScalarFn::checked_add
+                                }
+                                ScalarFunction::Subtract(options) if options.checked => {
+                                    ScalarFn::checked_subtract
+                                }
+                                ScalarFunction::Multiply(options) if options.checked => {
+                                    ScalarFn::checked_multiply
+                                }
+                                ScalarFunction::Add(_) => ScalarFn::add,
+                                ScalarFunction::Subtract(_) => ScalarFn::subtract,
+                                ScalarFunction::Divide => ScalarFn::divide,
+                                ScalarFunction::Multiply(_) => ScalarFn::multiply,
+                                ScalarFunction::BitAnd => ScalarFn::bit_and,
+                                ScalarFunction::BitOr => ScalarFn::bit_or,
+                                ScalarFunction::BitXor => ScalarFn::bit_xor,
+                                ScalarFunction::ShiftLeft => ScalarFn::shift_left,
+                                ScalarFunction::ShiftRight => ScalarFn::shift_right,
+                                _ => unreachable!(),
+                            };
+                            let a = table::col_to_prim_arrays::<$arrow_type>(columns[0]);
+                            let b = table::col_to_prim_arrays::<$arrow_type>(columns[1]);
+                            eval_numeric_scalar_op(a, b, op)?
+                        }};
+                    }
+                    macro_rules! float_op {
+                        ($arrow_type:ty) => {{
+                            let op = match expr {
+                                ScalarFunction::Add(_) => ScalarFn::add,
+                                ScalarFunction::Subtract(_) => ScalarFn::subtract,
+                                ScalarFunction::Divide => ScalarFn::divide,
+                                ScalarFunction::Multiply(_) => ScalarFn::multiply,
+                                _ => unreachable!(),
+                            };
+                            let a = table::col_to_prim_arrays::<$arrow_type>(columns[0]);
+                            let b = table::col_to_prim_arrays::<$arrow_type>(columns[1]);
+                            eval_numeric_scalar_op(a, b, op)?
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match dtype {
+                        DataType::Int8 => integer_op!(Int8Type),
+                        DataType::Int16 => integer_op!(Int16Type),
+                        DataType::Int32 => integer_op!(Int32Type),
+                        DataType::Int64 => integer_op!(Int64Type),
+                        DataType::UInt8 => integer_op!(UInt8Type),
+                        DataType::UInt16 => integer_op!(UInt16Type),
+                        DataType::UInt32 => integer_op!(UInt32Type),
+                        DataType::UInt64 => integer_op!(UInt64Type),
+                        DataType::Float32 => float_op!(Float32Type),
+                        DataType::Float64 => float_op!(Float64Type),
+                        _ => panic!("Unsupported operation"),
                     };
                     self.with_column(
                         &calculation.output.name,
@@ -264,7 +259,7 @@ impl Evaluate for DataFrame {
                                 let a = table::col_to_prim_arrays::<Float32Type>(
                                     columns.get(0).unwrap(),
                                 );
-                                eval_float1_scalar_op(a, op)
+                                eval_float1_scalar_op(a, op)?
                             }
                             DataType::Float64 => {
                                 let op = match expr {
@@ -277,7 +272,7 @@ impl Evaluate for DataFrame {
                                 let a = table::col_to_prim_arrays::<Float64Type>(
                                     columns.get(0).unwrap(),
                                 );
-                                eval_float1_scalar_op(a, op)
+                                eval_float1_scalar_op(a, op)?
                             }
                             _ => {
                                 panic!("Expecting float datatype for operation, found {:?}", dtype)
@@ -291,9 +286,440 @@ impl Evaluate for DataFrame {
                         table::Column::from_arrays(column, calculation.output.clone().into()),
                     )
                 }
+                ScalarFunction::Concat(null_handling) => {
+                    let arrays = columns
+                        .iter()
+                        .map(|col| table::col_to_string_arrays(col))
+                        .collect();
+                    let column: Vec<ArrayRef> = ScalarFn::concat(arrays, *null_handling)?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Upper | ScalarFunction::Lower | ScalarFunction::Trim => {
+                    let op = match expr {
+                        ScalarFunction::Upper => ScalarFn::upper,
+                        ScalarFunction::Lower => ScalarFn::lower,
+                        ScalarFunction::Trim => ScalarFn::trim,
+                        _ => unreachable!(),
+                    };
+                    let a = table::col_to_string_arrays(columns.get(0).unwrap());
+                    let column: Vec<ArrayRef> = op(a)?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Substring { start, length } => {
+                    let a = table::col_to_string_arrays(columns.get(0).unwrap());
+                    let column: Vec<ArrayRef> = ScalarFn::substring(a, *start, *length)?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Like(pattern) | ScalarFunction::ILike(pattern) => {
+                    let case_insensitive = matches!(expr, ScalarFunction::ILike(_));
+                    let a = table::col_to_string_arrays(columns.get(0).unwrap());
+                    let column: Vec<ArrayRef> = ScalarFn::like(a, pattern, case_insensitive)?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::RegexMatch(pattern) => {
+                    let a = table::col_to_string_arrays(columns.get(0).unwrap());
+                    let column: Vec<ArrayRef> = ScalarFn::regex_match(a, pattern.regex())?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::IsNull | ScalarFunction::IsNotNull => {
+                    let input_col = self.column_by_name(columns.get(0).unwrap().name());
+                    let arrays = input_col.data().chunks();
+                    let column = match expr {
+                        ScalarFunction::IsNull => ScalarFn::is_null(arrays),
+                        _ => ScalarFn::is_not_null(arrays),
+                    };
+                    let column: Vec<ArrayRef> = column?
+                        .into_iter()
+                        .map(|arr| Arc::new(arr) as ArrayRef)
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Coalesce => {
+                    let arrays = columns
+                        .iter()
+                        .map(|col| col.data().chunks().as_slice())
+                        .collect();
+                    let column = ScalarFn::coalesce(arrays)?;
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::CaseWhen => {
+                    let column = ScalarFn::case_when(
+                        columns[0].data().chunks(),
+                        columns[1].data().chunks(),
+                        columns[2].data().chunks(),
+                    )?;
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::ArrayToString(separator) => {
+                    let column: Vec<ArrayRef> = columns[0]
+                        .data()
+                        .chunks()
+                        .iter()
+                        .map(|array| {
+                            let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+                            Ok(Arc::new(ArrayFn::array_join(array, separator)?) as ArrayRef)
+                        })
+                        .collect::<Result<_, ArrowError>>()?;
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::GetField(field) => {
+                    let column: Vec<ArrayRef> = columns[0]
+                        .data()
+                        .chunks()
+                        .iter()
+                        .map(|array| {
+                            let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+                            array.column_by_name(field).unwrap().clone()
+                        })
+                        .collect();
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::ArrayAdd | ScalarFunction::ArraySubtract => {
+                    macro_rules! array_math {
+                        ($data_type:ty) => {{
+                            columns[0]
+                                .data()
+                                .chunks()
+                                .iter()
+                                .zip(columns[1].data().chunks())
+                                .map(|(a, b)| {
+                                    let a = a.as_any().downcast_ref::<ListArray>().unwrap();
+                                    let b = b.as_any().downcast_ref::<ListArray>().unwrap();
+                                    let array = match expr {
+                                        ScalarFunction::ArrayAdd => {
+                                            ArrayFn::array_add::<$data_type>(a, b)
+                                        }
+                                        ScalarFunction::ArraySubtract => {
+                                            ArrayFn::array_subtract::<$data_type>(a, b)
+                                        }
+                                        _ => unreachable!(),
+                                    };
+                                    Ok(Arc::new(array?) as ArrayRef)
+                                })
+                                .collect::<Result<_, ArrowError>>()?
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.output.column_type {
+                        ColumnType::Array(DataType::Int8) => array_math!(Int8Type),
+                        ColumnType::Array(DataType::Int16) => array_math!(Int16Type),
+                        ColumnType::Array(DataType::Int32) => array_math!(Int32Type),
+                        ColumnType::Array(DataType::Int64) => array_math!(Int64Type),
+                        ColumnType::Array(DataType::UInt8) => array_math!(UInt8Type),
+                        ColumnType::Array(DataType::UInt16) => array_math!(UInt16Type),
+                        ColumnType::Array(DataType::UInt32) => array_math!(UInt32Type),
+                        ColumnType::Array(DataType::UInt64) => array_math!(UInt64Type),
+                        ColumnType::Array(DataType::Float32) => array_math!(Float32Type),
+                        ColumnType::Array(DataType::Float64) => array_math!(Float64Type),
+                        t => panic!(
+                            "Expecting numeric list datatype for operation, found {:?}",
+                            t
+                        ),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::ArrayLength => {
+                    let column: Vec<ArrayRef> = columns[0]
+                        .data()
+                        .chunks()
+                        .iter()
+                        .map(|array| {
+                            let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+                            Ok(Arc::new(ArrayFn::array_length(array)?) as ArrayRef)
+                        })
+                        .collect::<Result<_, ArrowError>>()?;
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::ArraySum | ScalarFunction::ArrayMax => {
+                    macro_rules! array_reduce {
+                        ($data_type:ty) => {{
+                            columns[0]
+                                .data()
+                                .chunks()
+                                .iter()
+                                .map(|array| {
+                                    let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+                                    Ok(match expr {
+                                        ScalarFunction::ArraySum => {
+                                            Arc::new(ArrayFn::array_sum::<$data_type>(array)?)
+                                                as ArrayRef
+                                        }
+                                        ScalarFunction::ArrayMax => {
+                                            Arc::new(ArrayFn::array_max::<$data_type>(array)?)
+                                                as ArrayRef
+                                        }
+                                        _ => unreachable!(),
+                                    })
+                                })
+                                .collect::<Result<_, ArrowError>>()?
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.output.column_type {
+                        ColumnType::Scalar(DataType::Int8) => array_reduce!(Int8Type),
+                        ColumnType::Scalar(DataType::Int16) => array_reduce!(Int16Type),
+                        ColumnType::Scalar(DataType::Int32) => array_reduce!(Int32Type),
+                        ColumnType::Scalar(DataType::Int64) => array_reduce!(Int64Type),
+                        ColumnType::Scalar(DataType::UInt8) => array_reduce!(UInt8Type),
+                        ColumnType::Scalar(DataType::UInt16) => array_reduce!(UInt16Type),
+                        ColumnType::Scalar(DataType::UInt32) => array_reduce!(UInt32Type),
+                        ColumnType::Scalar(DataType::UInt64) => array_reduce!(UInt64Type),
+                        ColumnType::Scalar(DataType::Float32) => array_reduce!(Float32Type),
+                        ColumnType::Scalar(DataType::Float64) => array_reduce!(Float64Type),
+                        t => panic!("Expecting numeric datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::ArrayContains => {
+                    macro_rules! array_contains {
+                        ($array_type:ty, $contains:expr) => {{
+                            columns[0]
+                                .data()
+                                .chunks()
+                                .iter()
+                                .zip(columns[1].data().chunks())
+                                .map(|(array, values)| {
+                                    let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+                                    let values =
+                                        values.as_any().downcast_ref::<$array_type>().unwrap();
+                                    Ok(Arc::new($contains(array, values)?) as ArrayRef)
+                                })
+                                .collect::<Result<_, ArrowError>>()?
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.inputs[1].column_type {
+                        ColumnType::Scalar(DataType::Int8) => {
+                            array_contains!(Int8Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Int16) => {
+                            array_contains!(Int16Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Int32) => {
+                            array_contains!(Int32Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Int64) => {
+                            array_contains!(Int64Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::UInt8) => {
+                            array_contains!(UInt8Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::UInt16) => {
+                            array_contains!(UInt16Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::UInt32) => {
+                            array_contains!(UInt32Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::UInt64) => {
+                            array_contains!(UInt64Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Float32) => {
+                            array_contains!(Float32Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Float64) => {
+                            array_contains!(Float64Array, ArrayFn::array_contains_each)
+                        }
+                        ColumnType::Scalar(DataType::Utf8) => {
+                            array_contains!(StringArray, ArrayFn::array_contains_each_string)
+                        }
+                        t => panic!("Unsupported value datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Ceil | ScalarFunction::Floor | ScalarFunction::Round(_) => {
+                    macro_rules! round {
+                        ($data_type:ty) => {{
+                            let a = table::col_to_prim_arrays::<$data_type>(&columns[0]);
+                            match expr {
+                                ScalarFunction::Ceil => ScalarFn::ceil(a),
+                                ScalarFunction::Floor => ScalarFn::floor(a),
+                                ScalarFunction::Round(decimals) => ScalarFn::round(a, *decimals),
+                                _ => unreachable!(),
+                            }?
+                            .into_iter()
+                            .map(|arr| Arc::new(arr) as ArrayRef)
+                            .collect()
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.output.column_type {
+                        ColumnType::Scalar(DataType::Float32) => round!(Float32Type),
+                        ColumnType::Scalar(DataType::Float64) => round!(Float64Type),
+                        t => panic!("Expecting float datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Sqrt
+                | ScalarFunction::Exp
+                | ScalarFunction::Ln
+                | ScalarFunction::Log10
+                | ScalarFunction::Pow => {
+                    macro_rules! math {
+                        ($data_type:ty) => {{
+                            let a = table::col_to_prim_arrays::<$data_type>(&columns[0]);
+                            match expr {
+                                ScalarFunction::Sqrt => ScalarFn::sqrt(a),
+                                ScalarFunction::Exp => ScalarFn::exp(a),
+                                ScalarFunction::Ln => ScalarFn::ln(a),
+                                ScalarFunction::Log10 => ScalarFn::log10(a),
+                                ScalarFunction::Pow => ScalarFn::pow(
+                                    a,
+                                    table::col_to_prim_arrays::<$data_type>(&columns[1]),
+                                ),
+                                _ => unreachable!(),
+                            }?
+                            .into_iter()
+                            .map(|arr| Arc::new(arr) as ArrayRef)
+                            .collect()
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.output.column_type {
+                        ColumnType::Scalar(DataType::Float32) => math!(Float32Type),
+                        ColumnType::Scalar(DataType::Float64) => math!(Float64Type),
+                        t => panic!("Expecting float datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::Extract(part) => {
+                    macro_rules! extract {
+                        ($data_type:ty) => {{
+                            let a = table::col_to_prim_arrays::<$data_type>(&columns[0]);
+                            match part {
+                                DatePart::Year => ScalarFn::year(a),
+                                DatePart::Month => ScalarFn::month(a),
+                                DatePart::Day => ScalarFn::day_of_month(a),
+                                DatePart::Hour => ScalarFn::hour(a),
+                            }?
+                            .into_iter()
+                            .map(|arr| Arc::new(arr) as ArrayRef)
+                            .collect()
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.inputs[0].column_type {
+                        ColumnType::Scalar(DataType::Date32(_)) => extract!(Date32Type),
+                        ColumnType::Scalar(DataType::Date64(_)) => extract!(Date64Type),
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Second, _)) => {
+                            extract!(TimestampSecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Millisecond, _)) => {
+                            extract!(TimestampMillisecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Microsecond, _)) => {
+                            extract!(TimestampMicrosecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Nanosecond, _)) => {
+                            extract!(TimestampNanosecondType)
+                        }
+                        t => panic!("Expecting temporal datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::DateTrunc(granularity) => {
+                    macro_rules! date_trunc {
+                        ($data_type:ty) => {{
+                            let a = table::col_to_prim_arrays::<$data_type>(&columns[0]);
+                            ScalarFn::date_trunc(a, *granularity)?
+                                .into_iter()
+                                .map(|arr| Arc::new(arr) as ArrayRef)
+                                .collect()
+                        }};
+                    }
+                    let column: Vec<ArrayRef> = match &calculation.output.column_type {
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Second, _)) => {
+                            date_trunc!(TimestampSecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Millisecond, _)) => {
+                            date_trunc!(TimestampMillisecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Microsecond, _)) => {
+                            date_trunc!(TimestampMicrosecondType)
+                        }
+                        ColumnType::Scalar(DataType::Timestamp(TimeUnit::Nanosecond, _)) => {
+                            date_trunc!(TimestampNanosecondType)
+                        }
+                        t => panic!("Expecting timestamp datatype for operation, found {:?}", t),
+                    };
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
+                ScalarFunction::HashPartition(num_partitions) => {
+                    let arrays = columns
+                        .iter()
+                        .map(|col| col.data().chunks().as_slice())
+                        .collect();
+                    let column = ScalarFn::hash_partition(arrays, *num_partitions)?;
+                    self.with_column(
+                        &calculation.output.name,
+                        table::Column::from_arrays(column, calculation.output.clone().into()),
+                    )
+                }
                 _ => panic!("Scalar Function {:?} not supported", expr),
             },
-            Function::Cast => {
+            Function::Cast(mode, format) => {
                 let input_col: &table::Column = columns.get(0).unwrap();
                 let input_col = self.column_by_name(input_col.name());
                 let arrays: Vec<ArrayRef> = input_col
@@ -301,13 +727,14 @@ impl Evaluate for DataFrame {
                     .chunks()
                     .iter()
                     .map(|array_ref: &ArrayRef| {
-                        arrow::compute::cast(
+                        crate::functions::cast::cast_with_format(
                             array_ref,
                             &DataType::from(calculation.output.column_type.clone()),
+                            *mode,
+                            format.as_deref(),
                         )
-                        .unwrap()
                     })
-                    .collect();
+                    .collect::<Result<_, ArrowError>>()?;
                 self.with_column(
                     &calculation.output.name,
                     table::Column::from_arrays(arrays, calculation.output.clone().into()),
@@ -318,21 +745,82 @@ impl Evaluate for DataFrame {
                 &calculation.output.name,
             ),
             Function::Filter(filter) => self.filter(filter),
+            Function::Window(WindowFunction::MovingAverage(window)) => {
+                // a single moving average is used across chunks, so that windows span them
+                let mut moving_average = MovingAverage::try_new(*window)?;
+                let column: Vec<ArrayRef> = columns[0]
+                    .data()
+                    .chunks()
+                    .iter()
+                    .map(|array| Ok(Arc::new(moving_average.update(array)?) as ArrayRef))
+                    .collect::<Result<_, ArrowError>>()?;
+                self.with_column(
+                    &calculation.output.name,
+                    table::Column::from_arrays(column, calculation.output.clone().into()),
+                )
+            }
+            Function::Literal(value) => {
+                // the literal is chunked like the frame's columns, so that they can be zipped
+                let column: Vec<ArrayRef> = match self.columns().first() {
+                    Some(column) => column
+                        .data()
+                        .chunks()
+                        .iter()
+                        .map(|array| value.to_array(array.len()))
+                        .collect(),
+                    None => vec![],
+                };
+                self.with_column(
+                    &calculation.output.name,
+                    table::Column::from_arrays(column, calculation.output.clone().into()),
+                )
+            }
             expr => panic!("Function {:?} not supported", expr),
-        }
+        })
     }
     fn read(reader: &Reader) -> Self {
         use DataSourceType::*;
         match &reader.source {
             // TODO build with options, good first issue
-            Csv(path, options) => DataFrame::from_csv(&path, None),
+            Csv(path, options) => DataFrame::from_csv(&path, options.schema.clone()),
             Json(path) => DataFrame::from_json(&path, None),
             Parquet(path) => DataFrame::from_parquet(&path).expect("Unable to read Parquet file"),
+            Avro(path) => {
+                use crate::io::datasource::DataSource;
+                let mut source = crate::io::avro::AvroDataSource::try_new(&path, 1024)
+                    .expect("Unable to read Avro file");
+                let batches = source
+                    .execute(Default::default())
+                    .expect("Unable to read Avro file");
+                DataFrame::from_table(table::Table::from_record_batches(source.schema(), batches))
+            }
+            Memory(name) => panic!("In-memory data source {} cannot be read by name", name),
+            Flight(address, _) => panic!(
+                "Flight service {} can only be read without blocking, with a FlightDataSource",
+                address
+            ),
+            CsvStream(name) => panic!("CSV stream data source {} cannot be read by name", name),
             Arrow(path) => DataFrame::from_arrow(&path).unwrap(),
+            ArrowStream(path) => DataFrame::from_arrow_stream(
+                std::fs::File::open(&path).expect("Unable to open Arrow stream"),
+            )
+            .unwrap(),
             Sql(table, options) => match &options.db {
                 SqlDatabase::Postgres => {
                     DataFrame::from_sql_table(&options.connection_string, &table)
                 }
+                SqlDatabase::Sqlite => {
+                    use crate::io::sql::{sqlite::Sqlite, SqlDataSource};
+                    let schema = Sqlite::get_table_schema(&options.connection_string, &table)
+                        .expect("Unable to read SQLite table schema");
+                    let batches =
+                        Sqlite::read_table(&options.connection_string, &table, options.limit, 1024)
+                            .expect("Unable to read SQLite table");
+                    DataFrame::from_table(table::Table::from_record_batches(
+                        Arc::new(schema),
+                        batches,
+                    ))
+                }
                 t => unimplemented!("SQL Protocol {:?} not yet supported", t),
             },
         }
@@ -347,6 +835,103 @@ impl Evaluate for DataFrame {
     }
 }
 
+/// Compute an aggregate over a column of a record batch, returning an array with the single value
+pub fn evaluate_aggregate(
+    function: &AggregateFunction,
+    batch: &RecordBatch,
+    column: usize,
+) -> Result<ArrayRef, ArrowError> {
+    let array = batch.column(column);
+    macro_rules! numeric_aggregate {
+        ($kernel:path) => {{
+            macro_rules! aggregate {
+                ($array_type:ty) => {{
+                    let array = array.as_any().downcast_ref::<$array_type>().unwrap();
+                    Arc::new(<$array_type>::from(vec![$kernel(array)])) as ArrayRef
+                }};
+            }
+            match array.data_type() {
+                DataType::Int8 => aggregate!(Int8Array),
+                DataType::Int16 => aggregate!(Int16Array),
+                DataType::Int32 => aggregate!(Int32Array),
+                DataType::Int64 => aggregate!(Int64Array),
+                DataType::UInt8 => aggregate!(UInt8Array),
+                DataType::UInt16 => aggregate!(UInt16Array),
+                DataType::UInt32 => aggregate!(UInt32Array),
+                DataType::UInt64 => aggregate!(UInt64Array),
+                DataType::Float32 => aggregate!(Float32Array),
+                DataType::Float64 => aggregate!(Float64Array),
+                t => {
+                    return Err(ArrowError::ComputeError(format!(
+                        "{:?} aggregation is not supported for {:?} columns",
+                        function, t
+                    )))
+                }
+            }
+        }};
+    }
+    let result = match (function, array.data_type()) {
+        (AggregateFunction::Count, _) => Arc::new(Int64Array::from(vec![
+            (array.len() - array.null_count()) as i64,
+        ])) as ArrayRef,
+        (AggregateFunction::Sum, _) => numeric_aggregate!(arrow::compute::sum),
+        (AggregateFunction::Min, DataType::Utf8) => {
+            let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+            Arc::new(StringArray::from(vec![arrow::compute::min_string(array)])) as ArrayRef
+        }
+        (AggregateFunction::Max, DataType::Utf8) => {
+            let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+            Arc::new(StringArray::from(vec![arrow::compute::max_string(array)])) as ArrayRef
+        }
+        (AggregateFunction::Mode, _) => crate::functions::aggregate::mode(array)?,
+        (AggregateFunction::Min, t) | (AggregateFunction::Max, t) if is_integer_ordered(t) => {
+            // compare the integers that dates, times and booleans are stored as
+            let values = to_ordered_integers(array)?;
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            let extreme = match function {
+                AggregateFunction::Min => arrow::compute::min(values),
+                _ => arrow::compute::max(values),
+            };
+            let extreme = Arc::new(Int64Array::from(vec![extreme])) as ArrayRef;
+            from_ordered_integers(&extreme, t)?
+        }
+        (AggregateFunction::Min, _) => numeric_aggregate!(arrow::compute::min),
+        (AggregateFunction::Max, _) => numeric_aggregate!(arrow::compute::max),
+        (AggregateFunction::Avg, t)
+            if matches!(
+                t,
+                DataType::Int8
+                    | DataType::Int16
+                    | DataType::Int32
+                    | DataType::Int64
+                    | DataType::UInt8
+                    | DataType::UInt16
+                    | DataType::UInt32
+                    | DataType::UInt64
+                    | DataType::Float32
+                    | DataType::Float64
+            ) =>
+        {
+            let values = arrow::compute::cast(array, &DataType::Float64)?;
+            let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+            let count = values.len() - values.null_count();
+            let mean = if count == 0 {
+                None
+            } else {
+                arrow::compute::sum(values).map(|sum| sum / count as f64)
+            };
+            Arc::new(Float64Array::from(vec![mean])) as ArrayRef
+        }
+        (f, _) => {
+            return Err(ArrowError::ComputeError(format!(
+                "{:?} aggregation is not yet supported",
+                f
+            )))
+        }
+    };
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -366,6 +951,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -433,6 +1021,514 @@ mod tests {
         assert_eq!(25, dataframe.num_rows());
     }
 
+    #[test]
+    fn test_filter_selectivity_report() {
+        let reader = Reader {
+            source: DataSourceType::Csv(
+                "./test/data/uk_cities_with_headers.csv".to_owned(),
+                CsvReadOptions {
+                    has_headers: true,
+                    batch_size: 1024,
+                    delimiter: None,
+                    max_records: Some(1024),
+                    projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
+                },
+            ),
+        };
+        let compute = Computation::compute_read(&reader);
+        let mut frame = LazyFrame::read(compute);
+        let lat = frame.column("lat").unwrap().1.clone();
+        frame = frame.filter(BooleanFilter::Gt(
+            BooleanFilter::column(lat),
+            BooleanFilter::scalar(55.0f64),
+        ));
+        let ops = frame.expression.unroll();
+
+        let (dataframe, report) = DataFrame::empty().evaluate_with_report(&ops);
+        // 5 of the 37 cities are north of 55 degrees
+        assert_eq!(5, dataframe.num_rows());
+        assert_eq!(1, report.filters.len());
+        let stats = &report.filters[0];
+        assert_eq!(37, stats.input_rows);
+        assert_eq!(dataframe.num_rows(), stats.output_rows);
+        assert!((stats.selectivity() - 5.0 / 37.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_join() {}
+
+    #[test]
+    fn test_evaluate_aggregates() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from(vec![
+                Some(4),
+                None,
+                Some(-2),
+                Some(7),
+            ]))],
+        )
+        .unwrap();
+        let int_value = |function: AggregateFunction| -> i64 {
+            let result = evaluate_aggregate(&function, &batch, 0).unwrap();
+            assert_eq!(1, result.len());
+            result
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .value(0)
+        };
+        assert_eq!(9, int_value(AggregateFunction::Sum));
+        assert_eq!(3, int_value(AggregateFunction::Count));
+        assert_eq!(-2, int_value(AggregateFunction::Min));
+        assert_eq!(7, int_value(AggregateFunction::Max));
+
+        let mean = evaluate_aggregate(&AggregateFunction::Avg, &batch, 0).unwrap();
+        let mean = mean.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(3.0, mean.value(0));
+
+        assert!(evaluate_aggregate(&AggregateFunction::Kurtosis, &batch, 0).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_temporal_aggregates() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("date", DataType::Date32(DateUnit::Day), true),
+            Field::new("flag", DataType::Boolean, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Date32Array::from(vec![
+                    Some(18000),
+                    None,
+                    Some(-3),
+                    Some(400),
+                ])),
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    None,
+                    Some(true),
+                    Some(false),
+                ])),
+            ],
+        )
+        .unwrap();
+
+        let min = evaluate_aggregate(&AggregateFunction::Min, &batch, 0).unwrap();
+        assert_eq!(&DataType::Date32(DateUnit::Day), min.data_type());
+        assert_eq!(
+            -3,
+            min.as_any().downcast_ref::<Date32Array>().unwrap().value(0)
+        );
+        let max = evaluate_aggregate(&AggregateFunction::Max, &batch, 0).unwrap();
+        assert_eq!(
+            18000,
+            max.as_any().downcast_ref::<Date32Array>().unwrap().value(0)
+        );
+
+        let min = evaluate_aggregate(&AggregateFunction::Min, &batch, 1).unwrap();
+        assert!(!min
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap()
+            .value(0));
+        let max = evaluate_aggregate(&AggregateFunction::Max, &batch, 1).unwrap();
+        assert!(max
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap()
+            .value(0));
+    }
+
+    #[test]
+    fn test_evaluate_literals() {
+        use crate::operation::expr::{col, lit};
+        use arrow::datatypes::{Field, Schema};
+        use arrow::record_batch::RecordBatch;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let mut frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let dataset = Dataset {
+            name: "numbers".to_owned(),
+            columns: vec![Column {
+                name: "x".to_owned(),
+                column_type: ColumnType::Scalar(DataType::Int64),
+                nullable: true,
+            }],
+        };
+        for calculation in (col("x") + lit(5i64)).to_calculations(&dataset).unwrap() {
+            frame = frame.calculate(&calculation);
+        }
+        let sum = frame.column_by_name("add(x, 5)").data().chunk(0).clone();
+        let sum = sum.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(vec![6, 7, 8], sum.value_slice(0, 3).to_vec());
+
+        // other literal types are repeated for each row
+        for value in vec![
+            Scalar::Float64(1.5),
+            Scalar::String("uk".to_owned()),
+            Scalar::Boolean(true),
+        ] {
+            let literal = Calculation::literal(value.clone(), None).unwrap();
+            frame = frame.calculate(&literal);
+            let column = frame.column_by_name(&value.to_string()).data().chunk(0);
+            assert_eq!(3, column.len());
+            assert_eq!(&value.data_type().unwrap(), column.data_type());
+        }
+        assert!(Calculation::literal(Scalar::Null, None).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_checked_arithmetic() {
+        use crate::operation::scalar::AddOperation;
+        use arrow::datatypes::{Field, Schema};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int8, false),
+            Field::new("b", DataType::Int8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int8Array::from(vec![127, 100])),
+                Arc::new(Int8Array::from(vec![1, 20])),
+            ],
+        )
+        .unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let a = frame.expr_column_by_name("a");
+        let b = frame.expr_column_by_name("b");
+        let wrapping = AddOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        let frame = frame.calculate(&wrapping[0]);
+        let sum = frame.column_by_name("add(a, b)").data().chunk(0).clone();
+        let sum = sum.as_any().downcast_ref::<Int8Array>().unwrap();
+        assert_eq!(vec![-128, 120], sum.value_slice(0, 2).to_vec());
+
+        // checked arithmetic returns an error when it overflows
+        let checked = AddOperation::transform_with_options(
+            vec![a, b],
+            Some("checked".to_owned()),
+            ArithmeticOptions { checked: true },
+        )
+        .unwrap();
+        match frame.try_calculate(&checked[0]) {
+            Err(DataFrameError::ArrowError(message)) => {
+                assert!(message.contains("Overflow"), "{}", message)
+            }
+            Err(e) => panic!("Expected an overflow error, got {:?}", e),
+            Ok(_) => panic!("Expected an overflow error"),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_bitwise_operations() {
+        use crate::operation::scalar::{BitAndOperation, ShiftLeftOperation};
+        use arrow::datatypes::{Field, Schema};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![12, 7, -1])),
+                Arc::new(Int32Array::from(vec![10, 2, 255])),
+            ],
+        )
+        .unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let a = frame.expr_column_by_name("a");
+        let b = frame.expr_column_by_name("b");
+        let bitand = BitAndOperation::transform(vec![a.clone(), b], None, None).unwrap();
+        assert_eq!(1, bitand.len());
+        let frame = frame.calculate(&bitand[0]);
+        let result = frame.column_by_name("bitand(a, b)").data().chunk(0).clone();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(vec![8, 2, 255], result.value_slice(0, 3).to_vec());
+
+        // shift by a literal amount
+        let amount = Calculation::literal(Scalar::Int32(2), Some("two".to_owned())).unwrap();
+        let frame = frame.calculate(&amount);
+        let shift = ShiftLeftOperation::transform(vec![a, amount.output], None, None).unwrap();
+        let frame = frame.calculate(&shift[0]);
+        let result = frame
+            .column_by_name("shift_left(a, two)")
+            .data()
+            .chunk(0)
+            .clone();
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(vec![48, 28, -4], result.value_slice(0, 3).to_vec());
+    }
+
+    #[test]
+    fn test_evaluate_projection() {
+        use crate::operation::projection::ProjectOperation;
+        use arrow::datatypes::{Field, Schema};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+            Field::new("c", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y"])),
+                Arc::new(Float64Array::from(vec![1.5, 2.5])),
+            ],
+        )
+        .unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let input = Dataset {
+            name: "input".to_owned(),
+            columns: vec![
+                frame.expr_column_by_name("a"),
+                frame.expr_column_by_name("b"),
+                frame.expr_column_by_name("c"),
+            ],
+        };
+        let (transformation, output) =
+            ProjectOperation::transform(&input, &[("b", "b"), ("a", "first"), ("a", "second")])
+                .unwrap();
+        let frame = frame.evaluate(&[Computation {
+            input: vec![input],
+            transformations: vec![transformation],
+            output,
+        }]);
+        let names: Vec<&str> = frame
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(vec!["b", "first", "second"], names);
+        let b = frame.column(0).data().chunk(0).clone();
+        let b = b.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!("y", b.value(1));
+        let second = frame.column_by_name("second").data().chunk(0).clone();
+        let second = second.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(vec![1, 2], second.value_slice(0, 2).to_vec());
+    }
+
+    #[test]
+    fn test_evaluate_get_field() {
+        use crate::operation::scalar::GetFieldOperation;
+        use arrow::datatypes::{Field, Schema};
+
+        let location = StructArray::from(vec![
+            (
+                Field::new("lat", DataType::Float64, false),
+                Arc::new(Float64Array::from(vec![51.507222, 53.479444])) as ArrayRef,
+            ),
+            (
+                Field::new("lng", DataType::Float64, false),
+                Arc::new(Float64Array::from(vec![-0.1275, -2.245278])) as ArrayRef,
+            ),
+        ]);
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "location",
+            location.data_type().clone(),
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(location)]).unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let location = frame.expr_column_by_name("location");
+        let lng = GetFieldOperation::transform_with(vec![location.clone()], "lng", None).unwrap();
+        assert_eq!("get_field(location.lng)", lng[0].output.name());
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            lng[0].output.column_type
+        );
+        assert!(!lng[0].output.is_nullable());
+        let frame = frame.calculate(&lng[0]);
+        let result = frame
+            .column_by_name("get_field(location.lng)")
+            .data()
+            .chunk(0)
+            .clone();
+        let result = result.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(vec![-0.1275, -2.245278], result.value_slice(0, 2).to_vec());
+
+        // the struct's fields are known when the operation is created
+        match GetFieldOperation::transform_with(vec![location], "long", None) {
+            Err(DataFrameError::InvalidOperation { suggestion, .. }) => {
+                assert_eq!(Some("did you mean 'lng'?".to_string()), suggestion)
+            }
+            result => panic!("Expected an invalid operation, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_array_add() {
+        use crate::operation::scalar::ArrayAddOperation;
+        use arrow::datatypes::{Field, Schema};
+
+        let mut a = ListBuilder::new(Int64Builder::new(4));
+        a.values().append_slice(&[1, 2]).unwrap();
+        a.append(true).unwrap();
+        a.values().append_slice(&[3]).unwrap();
+        a.append(true).unwrap();
+        let mut b = ListBuilder::new(Int64Builder::new(4));
+        b.values().append_slice(&[10, 20]).unwrap();
+        b.append(true).unwrap();
+        b.values().append_slice(&[30]).unwrap();
+        b.append(true).unwrap();
+        let list_type = DataType::List(Box::new(DataType::Int64));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", list_type.clone(), false),
+            Field::new("b", list_type, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(a.finish()), Arc::new(b.finish())],
+        )
+        .unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let sum = ArrayAddOperation::transform(
+            vec![
+                frame.expr_column_by_name("a"),
+                frame.expr_column_by_name("b"),
+            ],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            ColumnType::Array(DataType::Int64),
+            sum[0].output.column_type
+        );
+        let frame = frame.calculate(&sum[0]);
+        let result = frame
+            .column_by_name("array_add(a, b)")
+            .data()
+            .chunk(0)
+            .clone();
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let values = result.values();
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(vec![11, 22, 33], values.value_slice(0, 3).to_vec());
+        assert_eq!(2, result.value_length(0));
+        assert_eq!(1, result.value_length(1));
+    }
+
+    #[test]
+    fn test_evaluate_array_length_and_sum() {
+        use crate::operation::scalar::{ArrayLengthOperation, ArraySumOperation};
+        use arrow::datatypes::{Field, Schema};
+
+        let mut a = ListBuilder::new(Int64Builder::new(4));
+        a.values().append_slice(&[1, 2, 3]).unwrap();
+        a.append(true).unwrap();
+        a.append(true).unwrap();
+        a.values().append_value(4).unwrap();
+        a.append(true).unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "a",
+            DataType::List(Box::new(DataType::Int64)),
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(a.finish())]).unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let a = frame.expr_column_by_name("a");
+
+        let length = ArrayLengthOperation::transform(vec![a.clone()], None, None).unwrap();
+        let sum = ArraySumOperation::transform(vec![a], None, None).unwrap();
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int32),
+            length[0].output.column_type
+        );
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int64),
+            sum[0].output.column_type
+        );
+        let frame = frame.calculate(&length[0]).calculate(&sum[0]);
+        let lengths = frame
+            .column_by_name("array_length(a)")
+            .data()
+            .chunk(0)
+            .clone();
+        let lengths = lengths.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(vec![3, 0, 1], lengths.value_slice(0, 3).to_vec());
+        // the empty list sums to 0
+        let sums = frame.column_by_name("array_sum(a)").data().chunk(0).clone();
+        let sums = sums.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(vec![6, 0, 4], sums.value_slice(0, 3).to_vec());
+    }
+
+    #[test]
+    fn test_evaluate_array_contains() {
+        use crate::operation::scalar::ArrayContainsOperation;
+        use arrow::datatypes::{Field, Schema};
+
+        let mut tags = ListBuilder::new(StringBuilder::new(8));
+        tags.values().append_value("rust").unwrap();
+        tags.values().append_value("arrow").unwrap();
+        tags.append(true).unwrap();
+        tags.values().append_value("python").unwrap();
+        tags.append(true).unwrap();
+        tags.append(true).unwrap();
+        tags.append(false).unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "tags",
+            DataType::List(Box::new(DataType::Utf8)),
+            true,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(tags.finish())]).unwrap();
+        let frame = DataFrame::from_table(crate::table::Table::from_record_batches(
+            schema,
+            vec![batch],
+        ));
+        let literal = Calculation::literal(Scalar::from("rust"), None).unwrap();
+        let contains = ArrayContainsOperation::transform(
+            vec![frame.expr_column_by_name("tags"), literal.output.clone()],
+            None,
+            None,
+        )
+        .unwrap();
+        let frame = frame.calculate(&literal).calculate(&contains[0]);
+        let result = frame
+            .column_by_name("array_contains(tags, 'rust')")
+            .data()
+            .chunk(0)
+            .clone();
+        let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert!(result.value(0));
+        assert!(!result.value(1));
+        // empty lists contain nothing, and null lists are null
+        assert!(!result.value(2));
+        assert!(result.is_null(3));
+    }
 }
@@ -3,7 +3,7 @@
 use crate::error::DataFrameError;
 use crate::io::datasource::DataSourceEval;
 
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, SchemaRef};
 use arrow::{compute::kernels::sort::SortOptions, error::ArrowError};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -12,12 +12,16 @@ use std::sync::Arc;
 pub enum ColumnType {
     Array(DataType),
     Scalar(DataType),
+    /// A nested record, with a column per field
+    Struct(Vec<Column>),
 }
 
 impl From<DataType> for ColumnType {
     fn from(dtype: DataType) -> Self {
         match dtype {
-            DataType::Struct(_) => panic!("struct array conversion not yet supported"),
+            DataType::Struct(fields) => {
+                ColumnType::Struct(fields.into_iter().map(Column::from).collect())
+            }
             DataType::List(inner) => ColumnType::Array(*inner),
             _ => ColumnType::Scalar(dtype.clone()),
         }
@@ -29,17 +33,30 @@ impl From<ColumnType> for DataType {
         match from {
             ColumnType::Array(dtype) => DataType::List(Box::new(dtype)),
             ColumnType::Scalar(dtype) => dtype,
+            ColumnType::Struct(columns) => DataType::Struct(
+                columns
+                    .into_iter()
+                    .map(arrow::datatypes::Field::from)
+                    .collect(),
+            ),
         }
     }
 }
 
-/// A column is an expression of an Arrow `Field`, excluding metadata about nullability
+/// A column is an expression of an Arrow `Field`, excluding its metadata
 ///
-/// Columns can be converted to and from `Field`, with the `Field::nullable()` data lost
+/// Columns can be converted to and from `Field`, keeping whether the field is nullable
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Column {
     pub(crate) name: String,
     pub(crate) column_type: ColumnType,
+    /// Whether the column can have null values, which columns are assumed to have if unknown
+    #[serde(default = "nullable_by_default")]
+    pub(crate) nullable: bool,
+}
+
+fn nullable_by_default() -> bool {
+    true
 }
 
 impl Column {
@@ -47,11 +64,16 @@ impl Column {
         self.name.as_ref()
     }
 
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
     /// Rename column
     pub fn rename(&self, name: &str) -> Self {
         Self {
             name: name.to_owned(),
             column_type: self.column_type.clone(),
+            nullable: self.nullable,
         }
     }
 }
@@ -61,13 +83,18 @@ impl From<arrow::datatypes::Field> for Column {
         Column {
             name: field.name().clone(),
             column_type: field.data_type().clone().into(),
+            nullable: field.is_nullable(),
         }
     }
 }
 
 impl From<Column> for arrow::datatypes::Field {
     fn from(column: Column) -> Self {
-        arrow::datatypes::Field::new(column.name.as_str(), column.column_type.into(), true)
+        arrow::datatypes::Field::new(
+            column.name.as_str(),
+            column.column_type.into(),
+            column.nullable,
+        )
     }
 }
 
@@ -111,11 +138,57 @@ impl Dataset {
         }
     }
 
+    /// Add a column, returning an error if a column with the same name exists
+    pub fn add_column(&self, column: Column) -> Result<Self, DataFrameError> {
+        if self.get_column(&column.name).is_some() {
+            return Err(DataFrameError::ComputeError(format!(
+                "Column {:?} cannot be added as it already exists",
+                column.name
+            )));
+        }
+        Ok(self.append_column(column))
+    }
+
+    /// Rename a column, keeping its position
+    ///
+    /// An error is returned if the column does not exist, or another column has the new name.
+    pub fn rename_column(&self, old_name: &str, new_name: &str) -> Result<Self, DataFrameError> {
+        let (index, _) = self
+            .get_column(old_name)
+            .ok_or_else(|| DataFrameError::ColumnNotFound(old_name.to_string()))?;
+        if old_name != new_name && self.get_column(new_name).is_some() {
+            return Err(DataFrameError::ComputeError(format!(
+                "Column {:?} cannot be renamed to {:?} as it already exists",
+                old_name, new_name
+            )));
+        }
+        let mut columns = self.columns.clone();
+        columns[index].name = new_name.to_string();
+        Ok(Self {
+            name: self.name.clone(),
+            columns,
+        })
+    }
+
+    /// Drop a column, returning an error if it does not exist
+    pub fn drop_column(&self, name: &str) -> Result<Self, DataFrameError> {
+        let (index, _) = self
+            .get_column(name)
+            .ok_or_else(|| DataFrameError::ColumnNotFound(name.to_string()))?;
+        let mut columns = self.columns.clone();
+        columns.remove(index);
+        Ok(Self {
+            name: self.name.clone(),
+            columns,
+        })
+    }
+
     pub fn try_aggregate(
         &self,
         groups: &[&str],
         aggr: &[Aggregation],
     ) -> Result<Self, DataFrameError> {
+        use crate::operation::aggregate::*;
         // check that the columns in the aggregate exist
         // TODO: might be a better way to loop
         let mut output_cols: Vec<Column> = vec![];
@@ -147,58 +220,43 @@ impl Dataset {
                     }
                     Some((_, col)) => {
                         // check if column can be aggregated with aggregation type
-                        // TODO: extract this logic to somewhere more appropriate
                         match aggregation.function {
                             AggregateFunction::Avg => {
-                                // only numeric types should be aggregated
-                                output_cols.push(Column {
-                                    name: format!("avg({})", col.name),
-                                    column_type: col.column_type.clone(),
-                                })
+                                output_cols.push(MeanAggregation::transform(col, None)?)
                             }
                             AggregateFunction::Sum => {
-                                // only numeric types should be summed (excluding temporal)
-                                output_cols.push(Column {
-                                    name: format!("sum({})", col.name),
-                                    column_type: col.column_type.clone(),
-                                })
+                                output_cols.push(SumAggregation::transform(col, None)?)
                             }
                             AggregateFunction::Max => {
-                                // only numeric types (including temporal)
-                                output_cols.push(Column {
-                                    name: format!("max({})", col.name),
-                                    column_type: col.column_type.clone(),
-                                })
+                                output_cols.push(MaxAggregation::transform(col, None)?)
                             }
                             AggregateFunction::Min => {
-                                // only numeric types (including temporal)
-                                output_cols.push(Column {
-                                    name: format!("min({})", col.name),
-                                    column_type: col.column_type.clone(),
-                                })
+                                output_cols.push(MinAggregation::transform(col, None)?)
                             }
                             AggregateFunction::Count => {
-                                // count should support most/all column types
-                                output_cols.push(Column {
-                                    name: format!("count({})", col.name),
-                                    column_type: ColumnType::Scalar(DataType::UInt32),
-                                })
+                                output_cols.push(CountAggregation::transform(col, None)?)
                             }
                             AggregateFunction::CountDistinct => {
                                 // count should support most/all column types
                                 output_cols.push(Column {
                                     name: format!("count_distinct({})", col.name),
                                     column_type: ColumnType::Scalar(DataType::UInt32),
+                                    nullable: false,
                                 })
                             }
                             AggregateFunction::First => output_cols.push(Column {
                                 name: format!("first({})", col.name),
                                 column_type: col.column_type.clone(),
+                                nullable: col.nullable,
                             }),
                             AggregateFunction::Last => output_cols.push(Column {
                                 name: format!("last({})", col.name),
                                 column_type: col.column_type.clone(),
+                                nullable: col.nullable,
                             }),
+                            AggregateFunction::Mode => {
+                                output_cols.push(ModeAggregation::transform(col, None)?)
+                            }
                             AggregateFunction::Kurtosis
                             | AggregateFunction::Skewness
                             | AggregateFunction::StdDev
@@ -214,6 +272,16 @@ impl Dataset {
             }
         }
 
+        // the same aggregation can't be computed more than once
+        for (i, col) in output_cols.iter().enumerate() {
+            if output_cols[..i].iter().any(|c| c.name == col.name) {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Aggregation output column {:?} is not unique",
+                    col.name
+                )));
+            }
+        }
+
         Ok(Self {
             name: "aggregated_dataset".to_string(),
             columns: output_cols,
@@ -283,6 +351,73 @@ impl Dataset {
             columns,
         })
     }
+
+    /// Print the dataset's columns and their types to console
+    pub fn print_schema(&self) {
+        println!("{}", self)
+    }
+}
+
+impl std::fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnType::Array(dtype) => write!(f, "list<{:?}>", dtype),
+            ColumnType::Scalar(dtype) => write!(f, "{:?}", dtype),
+            ColumnType::Struct(columns) => {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|column| format!("{}: {}", column.name, column.column_type))
+                    .collect();
+                write!(f, "struct<{}>", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// Formats the dataset as a table of its columns' names and types
+impl std::fmt::Display for Dataset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<(&str, String)> = self
+            .columns
+            .iter()
+            .map(|col| (col.name(), col.column_type.to_string()))
+            .collect();
+        let name_width = rows
+            .iter()
+            .map(|r| r.0.len())
+            .fold("name".len(), usize::max);
+        let type_width = rows
+            .iter()
+            .map(|r| r.1.len())
+            .fold("type".len(), usize::max);
+        let border = format!(
+            "+{}+{}+",
+            "-".repeat(name_width + 2),
+            "-".repeat(type_width + 2)
+        );
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "{}", border)?;
+        writeln!(
+            f,
+            "| {:nw$} | {:tw$} |",
+            "name",
+            "type",
+            nw = name_width,
+            tw = type_width
+        )?;
+        writeln!(f, "{}", border)?;
+        for (name, column_type) in &rows {
+            writeln!(
+                f,
+                "| {:nw$} | {:tw$} |",
+                name,
+                column_type,
+                nw = name_width,
+                tw = type_width
+            )?;
+        }
+        write!(f, "{}", border)
+    }
 }
 
 /// Transformations perform some calculation on data sets
@@ -296,6 +431,8 @@ pub enum Transformation {
     Select(Vec<String>),
     /// Drops columns by name from the dataset
     Drop(Vec<String>),
+    /// Selects `(input, output)` columns in order, renaming each input column to its output name
+    Project(Vec<(String, String)>),
     Read(Reader),
     Limit(usize),
     Filter(BooleanFilter),
@@ -310,10 +447,19 @@ pub struct SortCriteria {
 }
 
 impl SortCriteria {
+    /// Sort by a column, with nulls placed as in SQL: last when ascending, first when descending
+    pub fn new(column: &str, descending: bool) -> Self {
+        Self {
+            column: column.to_string(),
+            descending,
+            nulls_first: descending,
+        }
+    }
+
     pub fn to_arrow_sort_options(&self) -> SortOptions {
         SortOptions {
             descending: self.descending,
-            nulls_first: false,
+            nulls_first: self.nulls_first,
         }
     }
 }
@@ -356,9 +502,19 @@ pub enum DataSourceType {
     Csv(String, CsvReadOptions),
     Json(String),
     Arrow(String),
+    /// An Arrow IPC stream, such as one written to a pipe, which has no file footer
+    ArrowStream(String),
     // TODO provide an option between a table name and a SQL query
     Sql(String, SqlReadOptions),
     Parquet(String),
+    /// An Avro object container file
+    Avro(String),
+    /// Batches that are already in memory, identified by a name
+    Memory(String),
+    /// CSV data read from a stream, such as stdin or an HTTP response, identified by a name
+    CsvStream(String),
+    /// An Arrow Flight service, identified by its address and the data requested from it
+    Flight(String, FlightRequest),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -375,6 +531,22 @@ pub struct CsvReadOptions {
     pub(crate) max_records: Option<usize>,
     pub(crate) batch_size: usize,
     pub(crate) projection: Option<Vec<usize>>,
+    /// The schema of the file, which is inferred from the data if not set
+    pub(crate) schema: Option<SchemaRef>,
+    /// The compression of the file, which is detected from its extension if not set
+    #[serde(default)]
+    pub(crate) compression: Option<Compression>,
+    /// Values that are read as nulls, in addition to empty fields
+    #[serde(default)]
+    pub(crate) null_values: Vec<String>,
+}
+
+/// The compression of a file that is read
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Uncompressed,
+    Gzip,
+    Zstd,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -389,6 +561,16 @@ pub enum SqlDatabase {
     Postgres,
     MsSql,
     MySql,
+    Sqlite,
+}
+
+/// The data that is read from an Arrow Flight service
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum FlightRequest {
+    /// An opaque ticket, which is read with a single `do_get` request
+    Ticket(Vec<u8>),
+    /// A command such as a SQL query, whose endpoints are found with `get_flight_info`
+    Command(Vec<u8>),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -406,7 +588,7 @@ pub struct SqlWriteOptions {
 }
 
 /// A calculation on one or many columns, producing an output column
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Calculation {
     pub(crate) name: String,
     pub(crate) inputs: Vec<Column>,
@@ -422,11 +604,49 @@ impl Calculation {
             output: Column {
                 name: to.to_owned(),
                 column_type: col.column_type.clone(),
+                nullable: col.nullable,
             },
             function: Function::Rename,
         }
     }
 
+    /// create a calculation that repeats a value for each row
+    ///
+    /// The output column is named after the value, unless a name is given.
+    pub(crate) fn literal(value: Scalar, name: Option<String>) -> Result<Self, DataFrameError> {
+        let data_type = value.data_type().ok_or_else(|| {
+            DataFrameError::ComputeError("Null literals do not have a data type".to_string())
+        })?;
+        Ok(Self {
+            name: "literal".to_owned(),
+            inputs: vec![],
+            output: Column {
+                name: name.unwrap_or_else(|| value.to_string()),
+                column_type: ColumnType::Scalar(data_type),
+                nullable: false,
+            },
+            function: Function::Literal(value),
+        })
+    }
+
+    /// Check that the calculation's inputs exist in the schema, with the same types
+    pub fn validate(&self, schema: &arrow::datatypes::Schema) -> Result<(), DataFrameError> {
+        for input in &self.inputs {
+            let field = schema
+                .field_with_name(&input.name)
+                .map_err(|_| DataFrameError::ColumnNotFound(input.name.clone()))?;
+            let data_type: DataType = input.column_type.clone().into();
+            if field.data_type() != &data_type {
+                return Err(DataFrameError::SchemaMismatch {
+                    column: input.name.clone(),
+                    expected: data_type,
+                    actual: field.data_type().clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// create a calculation operation
     ///
     /// The operation can return one or more transformations (e.g. if a column needs casting before the required operation)
@@ -448,46 +668,282 @@ impl Calculation {
                 Some((index, col)) => {
                     inputs.push(col.clone());
                 }
-                None => {
-                    return Err(DataFrameError::ParseError(format!(
-                        "Column {} not found",
-                        name
-                    )));
-                }
+                None => return Err(DataFrameError::ColumnNotFound(name.to_string())),
             }
         }
+        // the inputs are moved into operations, but are needed to describe their errors
+        let input_columns = inputs.clone();
         match function {
             Rename => panic!("Please use rename function directly for now"),
-            Cast => unimplemented!("cast op"),
+            Literal(_) => panic!("Please use literal function directly for now"),
+            Cast(_, _) => unimplemented!("cast op"),
             Scalar(s) => {
                 use ScalarFunction::*;
                 let operations = match s {
                     ScalarFunction::Abs => panic!(),
-                    ScalarFunction::Add => crate::operation::scalar::AddOperation::transform(
-                        inputs,
-                        out_col_name,
-                        out_col_type,
-                    )?,
-                    ScalarFunction::Subtract => {
-                        crate::operation::scalar::SubtractOperation::transform(
+                    ScalarFunction::Add(options) => {
+                        crate::operation::scalar::AddOperation::transform_with_options(
                             inputs,
                             out_col_name,
-                            out_col_type,
-                        )?
+                            *options,
+                        )
                     }
-                    ScalarFunction::Multiply => panic!(),
-                    ScalarFunction::Divide => panic!(),
+                    ScalarFunction::Subtract(options) => {
+                        crate::operation::scalar::SubtractOperation::transform_with_options(
+                            inputs,
+                            out_col_name,
+                            *options,
+                        )
+                    }
+                    ScalarFunction::Multiply(options) => {
+                        crate::operation::scalar::MultiplyOperation::transform_with_options(
+                            inputs,
+                            out_col_name,
+                            *options,
+                        )
+                    }
+                    ScalarFunction::Divide => crate::operation::scalar::DivideOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
                     ScalarFunction::Sine => crate::operation::scalar::SinOperation::transform(
                         inputs,
                         out_col_name,
                         out_col_type,
-                    )?,
+                    ),
                     ScalarFunction::Cosine => panic!(),
                     ScalarFunction::Tangent => panic!(),
                     ScalarFunction::Cosecant => panic!(),
                     ScalarFunction::Secant => panic!(),
                     ScalarFunction::Cotangent => panic!(),
-                };
+                    ScalarFunction::Concat(null_handling) => {
+                        crate::operation::scalar::ConcatOperation::transform_with_null_handling(
+                            inputs,
+                            out_col_name,
+                            *null_handling,
+                        )
+                    }
+                    ScalarFunction::Upper => crate::operation::scalar::UpperOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Lower => crate::operation::scalar::LowerOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Trim => crate::operation::scalar::TrimOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Substring { start, length } => {
+                        crate::operation::scalar::SubstringOperation::transform_with(
+                            inputs,
+                            start,
+                            length,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::Like(pattern) => {
+                        crate::operation::scalar::LikeOperation::transform_with(
+                            inputs,
+                            &pattern,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::ILike(pattern) => {
+                        crate::operation::scalar::ILikeOperation::transform_with(
+                            inputs,
+                            &pattern,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::RegexMatch(pattern) => {
+                        crate::operation::scalar::RegexMatchOperation::transform_with_pattern(
+                            inputs,
+                            pattern.clone(),
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::IsNull => crate::operation::scalar::IsNullOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::IsNotNull => {
+                        crate::operation::scalar::IsNotNullOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::Coalesce => {
+                        crate::operation::scalar::CoalesceOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::CaseWhen => {
+                        crate::operation::scalar::CaseWhenOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArrayToString(separator) => {
+                        crate::operation::scalar::ArrayToStringOperation::transform_with(
+                            inputs,
+                            &separator,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::GetField(field) => {
+                        crate::operation::scalar::GetFieldOperation::transform_with(
+                            inputs,
+                            &field,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::ArrayAdd => {
+                        crate::operation::scalar::ArrayAddOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArraySubtract => {
+                        crate::operation::scalar::ArraySubtractOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArrayLength => {
+                        crate::operation::scalar::ArrayLengthOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArraySum => {
+                        crate::operation::scalar::ArraySumOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArrayMax => {
+                        crate::operation::scalar::ArrayMaxOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ArrayContains => {
+                        crate::operation::scalar::ArrayContainsOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::Ceil => crate::operation::scalar::CeilOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Floor => crate::operation::scalar::FloorOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Round(decimals) => {
+                        crate::operation::scalar::RoundOperation::transform_with(
+                            inputs,
+                            *decimals,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::Sqrt => crate::operation::scalar::SqrtOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Exp => crate::operation::scalar::ExpOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Ln => crate::operation::scalar::LnOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Log10 => crate::operation::scalar::Log10Operation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Pow => crate::operation::scalar::PowOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::Extract(part) => {
+                        crate::operation::scalar::ExtractOperation::transform_with(
+                            inputs,
+                            *part,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::DateTrunc(granularity) => {
+                        crate::operation::scalar::DateTruncOperation::transform_with(
+                            inputs,
+                            *granularity,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::HashPartition(num_partitions) => {
+                        crate::operation::scalar::HashPartitionOperation::transform_with(
+                            inputs,
+                            *num_partitions,
+                            out_col_name,
+                        )
+                    }
+                    ScalarFunction::BitAnd => crate::operation::scalar::BitAndOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::BitOr => crate::operation::scalar::BitOrOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::BitXor => crate::operation::scalar::BitXorOperation::transform(
+                        inputs,
+                        out_col_name,
+                        out_col_type,
+                    ),
+                    ScalarFunction::ShiftLeft => {
+                        crate::operation::scalar::ShiftLeftOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                    ScalarFunction::ShiftRight => {
+                        crate::operation::scalar::ShiftRightOperation::transform(
+                            inputs,
+                            out_col_name,
+                            out_col_type,
+                        )
+                    }
+                }
+                .map_err(|e| operation_error(e, &input_columns))?;
                 Ok(operations
                     .into_iter()
                     .map(Transformation::Calculate)
@@ -495,10 +951,49 @@ impl Calculation {
             }
             Array(a) => unimplemented!("array op"),
             Filter(cond) => Ok(vec![Transformation::Filter(cond)]),
+            Window(WindowFunction::MovingAverage(window)) => {
+                if inputs.len() != 1 {
+                    return Err(DataFrameError::UnsupportedOperation {
+                        op: crate::operation::window::MovingAverageOperation::name().to_string(),
+                        reason: "Moving average expects 1 input".to_string(),
+                    });
+                }
+                let operations = crate::operation::window::MovingAverageOperation::transform(
+                    &inputs[0],
+                    window,
+                    out_col_name,
+                )?;
+                Ok(operations
+                    .into_iter()
+                    .map(Transformation::Calculate)
+                    .collect())
+            }
         }
     }
 }
 
+/// Convert the error of an operation into an error with a suggestion on how to fix it
+///
+/// If the inputs have different types, inserting a cast is suggested, as most operations
+/// expect inputs of the same type. Errors without a suggestion are returned unchanged.
+fn operation_error(error: DataFrameError, inputs: &[Column]) -> DataFrameError {
+    match inputs {
+        [a, b, ..] if a.column_type != b.column_type => DataFrameError::InvalidOperation {
+            message: match error {
+                DataFrameError::UnsupportedOperation { reason, .. } => reason,
+                error => error.to_string(),
+            },
+            suggestion: Some(format!(
+                "insert a cast of column '{}' from {:?} to {:?}",
+                b.name,
+                DataType::from(b.column_type.clone()),
+                DataType::from(a.column_type.clone())
+            )),
+        },
+        _ => error,
+    }
+}
+
 /// Expressions are the types of transformations that can be applied on a frame.
 /// Write and other 'actions' are not expressed as `Expression`s as they materialise
 /// the frame as an output.
@@ -647,22 +1142,166 @@ impl Computation {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Function {
     Scalar(ScalarFunction),
     Array(ArrayFunction),
-    Cast,
+    /// A cast, with an optional format for parsing strings into temporal types
+    Cast(CastMode, Option<String>),
     Rename,
     Filter(BooleanFilter),
+    Window(WindowFunction),
+    /// A constant value, repeated for each row
+    Literal(Scalar),
     // Limit(usize),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Functions that are computed over a window of preceding rows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// The average of the current row and up to `n - 1` preceding rows
+    MovingAverage(usize),
+}
+
+/// Determines how a cast handles values that cannot be represented in the target type
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastMode {
+    /// Values that cannot be cast become nulls
+    Safe,
+    /// Values that cannot be cast return an error
+    Unsafe,
+    /// Integer values are clamped to the bounds of the target type
+    Saturating,
+}
+
+impl Default for CastMode {
+    fn default() -> Self {
+        CastMode::Safe
+    }
+}
+
+/// Determines how nulls in the inputs of an operation that combines values are handled
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullHandling {
+    /// A null in any input makes the output null
+    Propagate,
+    /// Nulls are treated as empty values
+    Empty,
+}
+
+impl Default for NullHandling {
+    fn default() -> Self {
+        NullHandling::Propagate
+    }
+}
+
+/// Determines how integer arithmetic behaves on overflow
+///
+/// By default, integer results wrap around on overflow, which is the fastest. Checked
+/// arithmetic returns an error instead. Floats are not affected, as they do not overflow.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ArithmeticOptions {
+    pub checked: bool,
+}
+
+/// A component of a date or timestamp
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePart {
+    Year,
+    Month,
+    /// The day of the month
+    Day,
+    Hour,
+}
+
+/// The unit that timestamps are truncated to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl std::str::FromStr for Granularity {
+    type Err = ArrowError;
+
+    /// Parse a granularity from its lowercase name, e.g. `"day"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "year" => Ok(Granularity::Year),
+            "month" => Ok(Granularity::Month),
+            "day" => Ok(Granularity::Day),
+            "hour" => Ok(Granularity::Hour),
+            "minute" => Ok(Granularity::Minute),
+            "second" => Ok(Granularity::Second),
+            _ => Err(ArrowError::InvalidArgumentError(format!(
+                "Unknown date granularity {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// A regular expression, which is compiled once when it is created or deserialized
+///
+/// Patterns are serialized, compared and debug printed as their string.
+#[derive(Clone)]
+pub struct RegexPattern {
+    pattern: String,
+    regex: regex::Regex,
+}
+
+impl RegexPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            regex: regex::Regex::new(pattern)?,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn regex(&self) -> &regex::Regex {
+        &self.regex
+    }
+}
+
+impl PartialEq for RegexPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl std::fmt::Debug for RegexPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.pattern)
+    }
+}
+
+impl Serialize for RegexPattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for RegexPattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        RegexPattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ScalarFunction {
-    Add,
-    Subtract,
+    Add(ArithmeticOptions),
+    Subtract(ArithmeticOptions),
     Divide,
-    Multiply,
+    Multiply(ArithmeticOptions),
     Abs,
     Sine,
     Cosine,
@@ -670,9 +1309,70 @@ pub enum ScalarFunction {
     Cotangent,
     Secant,
     Cosecant,
+    Concat(NullHandling),
+    Upper,
+    Lower,
+    Trim,
+    /// Extract a substring from a 1-based start position, optionally limited to a length
+    Substring {
+        start: i64,
+        length: Option<i64>,
+    },
+    /// Match against a SQL `LIKE` pattern
+    Like(String),
+    /// Match against a case-insensitive SQL `LIKE` pattern
+    ILike(String),
+    /// Match against a regular expression
+    RegexMatch(RegexPattern),
+    IsNull,
+    IsNotNull,
+    /// The first non-null value of the inputs
+    Coalesce,
+    /// Choose between two values based on a boolean condition
+    CaseWhen,
+    /// Join the elements of a list with a separator
+    ArrayToString(String),
+    /// The named field of a struct
+    GetField(String),
+    /// Add the elements at the same positions of two lists
+    ArrayAdd,
+    /// Subtract the elements of the second list from those at the same positions of the first
+    ArraySubtract,
+    /// The number of elements of a list
+    ArrayLength,
+    /// The sum of the elements of a list
+    ArraySum,
+    /// The largest element of a list
+    ArrayMax,
+    /// Whether a list contains a value
+    ArrayContains,
+    Ceil,
+    Floor,
+    /// Round to a number of decimal places, which can be negative to round to tens, hundreds, etc.
+    Round(i32),
+    Sqrt,
+    Exp,
+    /// Natural logarithm
+    Ln,
+    Log10,
+    /// Raise the first input to the power of the second input
+    Pow,
+    /// Extract a part of a date or timestamp as an integer
+    Extract(DatePart),
+    /// Truncate timestamps to the start of their year, month, day, etc.
+    DateTrunc(Granularity),
+    /// Assign rows to a number of partitions by the hash of the inputs
+    HashPartition(u32),
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// Shift the bits of the first input left by the second input
+    ShiftLeft,
+    /// Shift the bits of the first input right by the second input, preserving the sign
+    ShiftRight,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ArrayFunction {
     Contains,
     Join,
@@ -703,6 +1403,8 @@ pub enum AggregateFunction {
     First,
     Kurtosis,
     Last,
+    /// The most frequent value, with ties broken by the value that was seen first
+    Mode,
     Skewness,
     StdDev,
     SumDistinct,
@@ -714,7 +1416,7 @@ impl AggregateFunction {
 }
 
 // TODO: This is a temporary work-around until there are scalars in Arrow
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Scalar {
     Null,
     Int32(i32),
@@ -735,6 +1437,49 @@ macro_rules! scalar_from_trait {
     };
 }
 
+impl Scalar {
+    /// The data type of the value, which null values do not have
+    pub fn data_type(&self) -> Option<DataType> {
+        match self {
+            Scalar::Null => None,
+            Scalar::Int32(_) => Some(DataType::Int32),
+            Scalar::Int64(_) => Some(DataType::Int64),
+            Scalar::Float32(_) => Some(DataType::Float32),
+            Scalar::Float64(_) => Some(DataType::Float64),
+            Scalar::Boolean(_) => Some(DataType::Boolean),
+            Scalar::String(_) => Some(DataType::Utf8),
+        }
+    }
+
+    /// Create an array that repeats the value `len` times
+    pub fn to_array(&self, len: usize) -> arrow::array::ArrayRef {
+        use arrow::array::*;
+        match self {
+            Scalar::Null => Arc::new(NullArray::new(len)),
+            Scalar::Int32(v) => Arc::new(Int32Array::from(vec![*v; len])),
+            Scalar::Int64(v) => Arc::new(Int64Array::from(vec![*v; len])),
+            Scalar::Float32(v) => Arc::new(Float32Array::from(vec![*v; len])),
+            Scalar::Float64(v) => Arc::new(Float64Array::from(vec![*v; len])),
+            Scalar::Boolean(v) => Arc::new(BooleanArray::from(vec![*v; len])),
+            Scalar::String(v) => Arc::new(StringArray::from(vec![v.as_str(); len])),
+        }
+    }
+}
+
+impl std::fmt::Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scalar::Null => write!(f, "null"),
+            Scalar::Int32(v) => write!(f, "{}", v),
+            Scalar::Int64(v) => write!(f, "{}", v),
+            Scalar::Float32(v) => write!(f, "{}", v),
+            Scalar::Float64(v) => write!(f, "{}", v),
+            Scalar::Boolean(v) => write!(f, "{}", v),
+            Scalar::String(v) => write!(f, "'{}'", v),
+        }
+    }
+}
+
 scalar_from_trait!(f32, Float32);
 scalar_from_trait!(i32, Int32);
 scalar_from_trait!(f64, Float64);
@@ -742,13 +1487,19 @@ scalar_from_trait!(i64, Int64);
 scalar_from_trait!(bool, Boolean);
 scalar_from_trait!(String, String);
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl From<&str> for Scalar {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_owned())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BooleanInput {
     Scalar(Scalar),
     Column(Column),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BooleanFilter {
     Input(BooleanInput),
     Not(Box<BooleanFilter>),
@@ -760,6 +1511,9 @@ pub enum BooleanFilter {
     Ne(Box<BooleanFilter>, Box<BooleanFilter>),
     Lt(Box<BooleanFilter>, Box<BooleanFilter>),
     Le(Box<BooleanFilter>, Box<BooleanFilter>),
+    /// Equality within a tolerance, which only applies if either side is a float.
+    /// Other types are compared exactly.
+    ApproxEq(Box<BooleanFilter>, Box<BooleanFilter>, f64),
 }
 
 impl BooleanFilter {
@@ -774,33 +1528,11 @@ impl BooleanFilter {
         match self {
             // either extract a column or create a scalar with batch length
             Input(input) => match input {
-                BooleanInput::Scalar(scalar) => match scalar {
-                    Scalar::Boolean(v) => {
-                        Ok(Arc::new(arrow::array::BooleanArray::from(vec![*v; len])) as ArrayRef)
-                    }
-                    Scalar::Float32(v) => {
-                        Ok(Arc::new(arrow::array::Float32Array::from(vec![*v; len])) as ArrayRef)
-                    }
-                    Scalar::Float64(v) => {
-                        Ok(Arc::new(arrow::array::Float64Array::from(vec![*v; len])) as ArrayRef)
-                    }
-                    Scalar::Int32(v) => {
-                        Ok(Arc::new(arrow::array::Int32Array::from(vec![*v; len])) as ArrayRef)
-                    }
-                    Scalar::Int64(v) => {
-                        Ok(Arc::new(arrow::array::Int64Array::from(vec![*v; len])) as ArrayRef)
-                    }
-                    Scalar::Null => {
-                        Ok(Arc::new(arrow::array::BooleanArray::from(vec![false; len]))
-                            as ArrayRef)
-                    }
-                    Scalar::String(v) => {
-                        Ok(
-                            Arc::new(arrow::array::StringArray::from(vec![v.as_str(); len]))
-                                as ArrayRef,
-                        )
-                    }
-                },
+                // null scalars are false in boolean contexts
+                BooleanInput::Scalar(Scalar::Null) => {
+                    Ok(Arc::new(arrow::array::BooleanArray::from(vec![false; len])) as ArrayRef)
+                }
+                BooleanInput::Scalar(scalar) => Ok(scalar.to_array(len)),
                 BooleanInput::Column(column) => {
                     let schema = batch.schema();
                     let col = schema.column_with_name(&column.name);
@@ -857,6 +1589,28 @@ impl BooleanFilter {
                     &Float64Array::from(r.data()),
                 )?) as ArrayRef)
             }
+            ApproxEq(ref left, ref right, tolerance) => {
+                let l = left.eval_to_array(batch)?;
+                let r = right.eval_to_array(batch)?;
+                let is_float = |t: &DataType| matches!(t, DataType::Float32 | DataType::Float64);
+                if !is_float(l.data_type()) && !is_float(r.data_type()) {
+                    return Eq(left.clone(), right.clone()).eval_to_array(batch);
+                }
+                let l = arrow::compute::cast(&l, &DataType::Float64)?;
+                let r = arrow::compute::cast(&r, &DataType::Float64)?;
+                let l = l.as_any().downcast_ref::<Float64Array>().unwrap();
+                let r = r.as_any().downcast_ref::<Float64Array>().unwrap();
+                let values: Vec<Option<bool>> = (0..len)
+                    .map(|i| {
+                        if l.is_null(i) || r.is_null(i) {
+                            None
+                        } else {
+                            Some((l.value(i) - r.value(i)).abs() <= *tolerance)
+                        }
+                    })
+                    .collect();
+                Ok(Arc::new(BooleanArray::from(values)) as ArrayRef)
+            }
         }
     }
 
@@ -884,11 +1638,215 @@ mod tests {
             columns: vec![Column {
                 name: "id".to_owned(),
                 column_type: ColumnType::Scalar(DataType::Int64),
+                nullable: true,
             }],
         };
 
-        assert_eq!("Dataset { name: \"Input Table 1\", columns: [Column { name: \"id\", column_type: Scalar(Int64) }] }", format!("{:?}", dataset));
+        assert_eq!("Dataset { name: \"Input Table 1\", columns: [Column { name: \"id\", column_type: Scalar(Int64), nullable: true }] }", format!("{:?}", dataset));
         let as_json = serde_json::to_string(&dataset).unwrap();
-        assert_eq!("{\"name\":\"Input Table 1\",\"columns\":[{\"name\":\"id\",\"column_type\":{\"Scalar\":\"Int64\"}}]}", as_json);
+        assert_eq!("{\"name\":\"Input Table 1\",\"columns\":[{\"name\":\"id\",\"column_type\":{\"Scalar\":\"Int64\"},\"nullable\":true}]}", as_json);
+    }
+
+    #[test]
+    fn test_approx_eq_filter() -> Result<(), DataFrameError> {
+        use arrow::array::{BooleanArray, Float64Array, Int64Array};
+        use arrow::datatypes::{Field, Schema};
+        use arrow::record_batch::RecordBatch;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Float64, true),
+            Field::new("id", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Float64Array::from(vec![
+                    Some(0.1),
+                    Some(0.1000001),
+                    Some(0.2),
+                    None,
+                ])),
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            ],
+        )?;
+        let x = BooleanFilter::column(Column {
+            name: "x".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        });
+        let filter = BooleanFilter::ApproxEq(x, BooleanFilter::scalar(0.1), 1e-6);
+        let mask = filter.eval_to_array(&batch)?;
+        let mask = mask.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert!(mask.value(0));
+        assert!(mask.value(1));
+        assert!(!mask.value(2));
+        assert!(mask.is_null(3));
+
+        // integers are compared exactly
+        let id = BooleanFilter::column(Column {
+            name: "id".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        });
+        let filter = BooleanFilter::ApproxEq(id, BooleanFilter::scalar(2i64), 1.0);
+        let mask = filter.eval_to_array(&batch)?;
+        let mask = mask.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert!(!mask.value(0));
+        assert!(mask.value(1));
+        assert!(!mask.value(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculation_error_suggestions() {
+        let column = |name: &str, data_type: DataType| Column {
+            name: name.to_owned(),
+            column_type: ColumnType::Scalar(data_type),
+            nullable: true,
+        };
+        let dataset = Dataset {
+            name: "cities".to_owned(),
+            columns: vec![
+                column("city", DataType::Utf8),
+                column("lat", DataType::Float64),
+                column("lng", DataType::Float64),
+            ],
+        };
+        // missing columns are not found, rather than invalid
+        let err = Calculation::calculate(
+            &dataset,
+            vec!["lat", "lnng"],
+            Function::Scalar(ScalarFunction::Add(Default::default())),
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(DataFrameError::ColumnNotFound("lnng".to_string()), err);
+        assert_eq!("Column lnng not found", err.to_string());
+        // strings and floats can't be coalesced without a cast
+        match Calculation::calculate(
+            &dataset,
+            vec!["lat", "city"],
+            Function::Scalar(ScalarFunction::Coalesce),
+            None,
+            None,
+        ) {
+            Err(DataFrameError::InvalidOperation { suggestion, .. }) => assert_eq!(
+                Some("insert a cast of column 'city' from Utf8 to Float64".to_string()),
+                suggestion
+            ),
+            result => panic!("Expected an invalid operation, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_display_dataset() {
+        let dataset = Dataset {
+            name: "cities".to_owned(),
+            columns: vec![
+                Column {
+                    name: "city".to_owned(),
+                    column_type: ColumnType::Scalar(DataType::Utf8),
+                    nullable: true,
+                },
+                Column {
+                    name: "coordinates".to_owned(),
+                    column_type: ColumnType::Array(DataType::Float64),
+                    nullable: true,
+                },
+            ],
+        };
+        let expected = "cities
++-------------+---------------+
+| name        | type          |
++-------------+---------------+
+| city        | Utf8          |
+| coordinates | list<Float64> |
++-------------+---------------+";
+        assert_eq!(expected, dataset.to_string());
+    }
+
+    #[test]
+    fn test_validate_calculation() {
+        use arrow::datatypes::{Field, Schema};
+        let column = |name: &str, data_type: DataType| Column {
+            name: name.to_owned(),
+            column_type: ColumnType::Scalar(data_type),
+            nullable: true,
+        };
+        let calculation = Calculation {
+            name: "add".to_owned(),
+            inputs: vec![column("a", DataType::Int64), column("b", DataType::Int64)],
+            output: column("add(a, b)", DataType::Int64),
+            function: Function::Scalar(ScalarFunction::Add(Default::default())),
+        };
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Int64, true),
+        ]);
+        assert!(calculation.validate(&schema).is_ok());
+
+        let missing = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        assert_eq!(
+            DataFrameError::ColumnNotFound("b".to_string()),
+            calculation.validate(&missing).unwrap_err()
+        );
+
+        let mismatched = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Float64, true),
+        ]);
+        assert_eq!(
+            DataFrameError::SchemaMismatch {
+                column: "b".to_string(),
+                expected: DataType::Int64,
+                actual: DataType::Float64,
+            },
+            calculation.validate(&mismatched).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn evolve_dataset() {
+        let column = |name: &str, data_type: DataType| Column {
+            name: name.to_owned(),
+            column_type: ColumnType::Scalar(data_type),
+            nullable: true,
+        };
+        let dataset = Dataset {
+            name: "cities".to_owned(),
+            columns: vec![
+                column("city", DataType::Utf8),
+                column("lat", DataType::Float64),
+            ],
+        };
+        let names = |dataset: &Dataset| -> Vec<String> {
+            dataset.columns.iter().map(|c| c.name.clone()).collect()
+        };
+
+        let added = dataset
+            .add_column(column("lng", DataType::Float64))
+            .unwrap();
+        assert_eq!(vec!["city", "lat", "lng"], names(&added));
+        assert!(dataset.add_column(column("lat", DataType::Int64)).is_err());
+
+        let renamed = added.rename_column("lat", "latitude").unwrap();
+        assert_eq!(vec!["city", "latitude", "lng"], names(&renamed));
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            renamed.columns[1].column_type
+        );
+        assert!(added.rename_column("lat", "lng").is_err());
+        assert_eq!(
+            DataFrameError::ColumnNotFound("population".to_owned()),
+            added.rename_column("population", "pop").unwrap_err()
+        );
+
+        let dropped = renamed.drop_column("city").unwrap();
+        assert_eq!(vec!["latitude", "lng"], names(&dropped));
+        assert_eq!(
+            DataFrameError::ColumnNotFound("city".to_owned()),
+            dropped.drop_column("city").unwrap_err()
+        );
     }
 }
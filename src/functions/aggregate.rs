@@ -1,10 +1,21 @@
 use arrow::array::Array;
-use arrow::array::{Int64Array, PrimitiveArray};
+use arrow::array::{ArrayRef, Float64Array, Int64Array, PrimitiveArray, StringArray, UInt32Array};
 use arrow::compute;
 use arrow::datatypes::ArrowNumericType;
 use arrow::datatypes::ArrowPrimitiveType;
 use arrow::datatypes::Int64Type;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::ops::Add;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::expression::AggregateFunction;
+use crate::functions::distinct::row_keys;
 
 struct AggregateFunctions;
 
@@ -102,6 +113,993 @@ impl AggregateFunctions {
     // TODO population and sample variances
 }
 
+/// A numeric value of an aggregate, which is kept as an integer for integer columns, so that
+/// large values are not rounded
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(v) => v as f64,
+            Number::Float(v) => v,
+        }
+    }
+
+    fn checked_add(self, other: Number) -> Result<Number, ArrowError> {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a
+                .checked_add(b)
+                .map(Number::Int)
+                .ok_or_else(|| ArrowError::ComputeError("Overflow happened on sum".to_string())),
+            (a, b) => Ok(Number::Float(a.to_f64() + b.to_f64())),
+        }
+    }
+
+    fn min(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(a.min(b)),
+            (a, b) => Number::Float(a.to_f64().min(b.to_f64())),
+        }
+    }
+
+    fn max(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(a.max(b)),
+            (a, b) => Number::Float(a.to_f64().max(b.to_f64())),
+        }
+    }
+}
+
+/// The state of an aggregate function for a single group
+#[derive(Debug, Clone)]
+enum Accumulator {
+    Sum(Option<Number>),
+    Min(Option<Number>),
+    Max(Option<Number>),
+    MinText(Option<String>),
+    MaxText(Option<String>),
+    Avg {
+        sum: f64,
+        count: u64,
+    },
+    Count(u64),
+    /// The keys of the distinct non-null values of a group
+    Distinct(HashSet<Vec<u8>>),
+    Mode(ModeCounts),
+    /// The values of `First` and `Last` aggregates are kept by the aggregators
+    Value,
+}
+
+/// The number of times that each distinct value of a group was seen, for finding its mode
+#[derive(Debug, Clone, Default)]
+struct ModeCounts {
+    /// The count of each value's key, and the index of the value in `values`
+    counts: HashMap<Vec<u8>, (u64, usize)>,
+    /// Single-row slices of the distinct values, in the order that they were first seen
+    values: Vec<ArrayRef>,
+    /// A single-row slice of a null value, which is the mode of a group without values
+    null: Option<ArrayRef>,
+}
+
+impl ModeCounts {
+    fn update(&mut self, input: &AggregateInput, row: usize) {
+        if input.values.is_null(row) {
+            if self.null.is_none() {
+                self.null = Some(input.values.slice(row, 1));
+            }
+            return;
+        }
+        let key = &input.keys.as_ref().unwrap()[row];
+        match self.counts.get_mut(key) {
+            Some((count, _)) => *count += 1,
+            None => {
+                self.counts.insert(key.clone(), (1, self.values.len()));
+                self.values.push(input.values.slice(row, 1));
+            }
+        }
+    }
+
+    /// Merge the counts of rows that follow the rows of these counts, so that values that were
+    /// first seen in `other` are ordered after the values of these counts
+    fn merge(&mut self, other: ModeCounts) {
+        let mut counts: Vec<(Vec<u8>, (u64, usize))> = other.counts.into_iter().collect();
+        counts.sort_by_key(|(_, (_, index))| *index);
+        for (key, (count, index)) in counts {
+            match self.counts.get_mut(&key) {
+                Some((c, _)) => *c += count,
+                None => {
+                    self.counts.insert(key, (count, self.values.len()));
+                    self.values.push(other.values[index].clone());
+                }
+            }
+        }
+        if self.null.is_none() {
+            self.null = other.null;
+        }
+    }
+
+    /// The most frequent value, with ties broken by the value that was seen first
+    fn value(&self) -> Result<ArrayRef, ArrowError> {
+        self.counts
+            .values()
+            .max_by(|(a_count, a_index), (b_count, b_index)| {
+                a_count.cmp(b_count).then(b_index.cmp(a_index))
+            })
+            .map(|(_, index)| self.values[*index].clone())
+            .or_else(|| self.null.clone())
+            .ok_or_else(|| {
+                ArrowError::ComputeError("Cannot compute the mode of an empty group".to_string())
+            })
+    }
+}
+
+impl Accumulator {
+    /// Create the state of an aggregate function over a column of `data_type`
+    fn try_new(function: &AggregateFunction, data_type: &DataType) -> Result<Self, ArrowError> {
+        use AggregateFunction::*;
+        match function {
+            Sum => Ok(Accumulator::Sum(None)),
+            Min if data_type == &DataType::Utf8 => Ok(Accumulator::MinText(None)),
+            Max if data_type == &DataType::Utf8 => Ok(Accumulator::MaxText(None)),
+            Min => Ok(Accumulator::Min(None)),
+            Max => Ok(Accumulator::Max(None)),
+            Avg => Ok(Accumulator::Avg { sum: 0.0, count: 0 }),
+            Count => Ok(Accumulator::Count(0)),
+            CountDistinct => Ok(Accumulator::Distinct(HashSet::new())),
+            Mode => Ok(Accumulator::Mode(ModeCounts::default())),
+            First | Last => Ok(Accumulator::Value),
+            f => Err(ArrowError::ComputeError(format!(
+                "Hash aggregation of {:?} is not yet supported",
+                f
+            ))),
+        }
+    }
+
+    /// Update the state with the value of an aggregate input at a row
+    fn update(&mut self, input: &AggregateInput, row: usize) -> Result<(), ArrowError> {
+        match self {
+            Accumulator::MinText(min) => {
+                update_text(min, text_value(&input.values, row), Ordering::Less)
+            }
+            Accumulator::MaxText(max) => {
+                update_text(max, text_value(&input.values, row), Ordering::Greater)
+            }
+            Accumulator::Distinct(keys) => {
+                if input.values.is_valid(row) {
+                    keys.insert(input.keys.as_ref().unwrap()[row].clone());
+                }
+            }
+            Accumulator::Mode(counts) => counts.update(input, row),
+            _ => return self.update_value(aggregate_input_value(&input.values, row)),
+        }
+        Ok(())
+    }
+
+    /// Update the state with a row's value, which is only read for numeric aggregates
+    fn update_value(&mut self, value: Option<Number>) -> Result<(), ArrowError> {
+        match (self, value) {
+            (Accumulator::Sum(sum), Some(v)) => {
+                *sum = Some(match *sum {
+                    Some(sum) => sum.checked_add(v)?,
+                    None => v,
+                })
+            }
+            (Accumulator::Min(min), Some(v)) => *min = Some(min.map_or(v, |m| m.min(v))),
+            (Accumulator::Max(max), Some(v)) => *max = Some(max.map_or(v, |m| m.max(v))),
+            (Accumulator::Avg { sum, count }, Some(v)) => {
+                *sum += v.to_f64();
+                *count += 1;
+            }
+            (Accumulator::Count(count), Some(_)) => *count += 1,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Merge the state of rows that follow the rows of this state
+    fn merge(&mut self, other: Accumulator) -> Result<(), ArrowError> {
+        match (self, other) {
+            (Accumulator::Sum(a), Accumulator::Sum(b)) => {
+                *a = match (*a, b) {
+                    (Some(a), Some(b)) => Some(a.checked_add(b)?),
+                    (a, b) => a.or(b),
+                }
+            }
+            (Accumulator::Min(a), Accumulator::Min(b)) => {
+                *a = match (*a, b) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                }
+            }
+            (Accumulator::Max(a), Accumulator::Max(b)) => {
+                *a = match (*a, b) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    (a, b) => a.or(b),
+                }
+            }
+            (Accumulator::MinText(a), Accumulator::MinText(b)) => {
+                update_text(a, b.as_deref(), Ordering::Less)
+            }
+            (Accumulator::MaxText(a), Accumulator::MaxText(b)) => {
+                update_text(a, b.as_deref(), Ordering::Greater)
+            }
+            (Accumulator::Avg { sum, count }, Accumulator::Avg { sum: s, count: c }) => {
+                *sum += s;
+                *count += c;
+            }
+            (Accumulator::Count(a), Accumulator::Count(b)) => *a += b,
+            (Accumulator::Distinct(a), Accumulator::Distinct(b)) => a.extend(b),
+            (Accumulator::Mode(a), Accumulator::Mode(b)) => a.merge(b),
+            (Accumulator::Value, Accumulator::Value) => {}
+            (a, b) => unreachable!("Cannot merge {:?} with {:?}", a, b),
+        }
+        Ok(())
+    }
+
+    fn value(&self) -> Option<Number> {
+        match self {
+            Accumulator::Sum(v) | Accumulator::Min(v) | Accumulator::Max(v) => *v,
+            Accumulator::Avg { count: 0, .. } => None,
+            Accumulator::Avg { sum, count } => Some(Number::Float(sum / *count as f64)),
+            _ => None,
+        }
+    }
+}
+
+/// Replace the smallest (`Less`) or largest (`Greater`) text of a group with a value, if the
+/// value is ordered before or after it
+fn update_text(extreme: &mut Option<String>, value: Option<&str>, ordering: Ordering) {
+    if let Some(value) = value {
+        if extreme
+            .as_deref()
+            .map_or(true, |e| value.cmp(e) == ordering)
+        {
+            *extreme = Some(value.to_owned());
+        }
+    }
+}
+
+/// Whether an aggregate function can only be computed on numeric columns, or on columns that
+/// are ordered as numbers
+fn is_numeric_aggregate(function: &AggregateFunction) -> bool {
+    use AggregateFunction::*;
+    matches!(function, Sum | Min | Max | Avg)
+}
+
+/// Whether values of the type are ordered by the integers that they are stored as, with
+/// booleans ordered as 0 and 1
+pub(crate) fn is_integer_ordered(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Boolean
+            | DataType::Date32(_)
+            | DataType::Date64(_)
+            | DataType::Time32(_)
+            | DataType::Time64(_)
+            | DataType::Timestamp(_, _)
+    )
+}
+
+/// Cast values of an integer-ordered type to the i64 values that they are ordered by
+pub(crate) fn to_ordered_integers(array: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    match array.data_type() {
+        // 32-bit temporal types can only be cast to and from Int32
+        DataType::Date32(_) | DataType::Time32(_) => {
+            compute::cast(&compute::cast(array, &DataType::Int32)?, &DataType::Int64)
+        }
+        _ => compute::cast(array, &DataType::Int64),
+    }
+}
+
+/// Cast i64 values back to the integer-ordered type that they were cast from
+pub(crate) fn from_ordered_integers(
+    values: &ArrayRef,
+    data_type: &DataType,
+) -> Result<ArrayRef, ArrowError> {
+    match data_type {
+        DataType::Date32(_) | DataType::Time32(_) => {
+            compute::cast(&compute::cast(values, &DataType::Int32)?, data_type)
+        }
+        _ => compute::cast(values, data_type),
+    }
+}
+
+/// The input of an aggregate in a batch
+struct AggregateInput {
+    values: ArrayRef,
+    /// The key of each row's value, for aggregates that compare values of any type
+    keys: Option<Vec<Vec<u8>>>,
+}
+
+/// The inputs of aggregates in a batch
+///
+/// Numeric aggregates are computed on i64 values of integer columns, and on f64 values of other
+/// numeric columns. The minimum and maximum of integer-ordered columns are computed on the
+/// integers that they are ordered by, and of text columns on the text. `CountDistinct` and `Mode`
+/// compare the keys of values. Other aggregates only need validity, so their columns are not cast.
+fn aggregate_inputs(
+    aggregates: &[(AggregateFunction, usize)],
+    batch: &RecordBatch,
+) -> Result<Vec<AggregateInput>, ArrowError> {
+    aggregates
+        .iter()
+        .map(|(function, i)| {
+            let column = batch.column(*i);
+            let keys = match function {
+                AggregateFunction::CountDistinct | AggregateFunction::Mode => {
+                    Some(row_keys(&[column.clone()], column.len())?)
+                }
+                _ => None,
+            };
+            Ok(AggregateInput {
+                values: aggregate_input_values(function, column)?,
+                keys,
+            })
+        })
+        .collect()
+}
+
+fn aggregate_input_values(
+    function: &AggregateFunction,
+    column: &ArrayRef,
+) -> Result<ArrayRef, ArrowError> {
+    if is_numeric_aggregate(function) {
+        match column.data_type() {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32 => compute::cast(column, &DataType::Int64),
+            t if is_numeric(t) => compute::cast(column, &DataType::Float64),
+            t if is_extreme(function) && is_integer_ordered(t) => to_ordered_integers(column),
+            DataType::Utf8 if is_extreme(function) => Ok(column.clone()),
+            t => Err(ArrowError::ComputeError(format!(
+                "Cannot compute {:?} of {:?} column",
+                function, t
+            ))),
+        }
+    } else {
+        Ok(column.clone())
+    }
+}
+
+fn is_extreme(function: &AggregateFunction) -> bool {
+    matches!(function, AggregateFunction::Min | AggregateFunction::Max)
+}
+
+/// The text of an aggregate input at a row, if the input is a valid text value
+fn text_value(input: &ArrayRef, row: usize) -> Option<&str> {
+    if input.is_null(row) {
+        return None;
+    }
+    input
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .map(|array| array.value(row))
+}
+
+/// The value of an aggregate input at a row, for updating an accumulator
+fn aggregate_input_value(input: &ArrayRef, row: usize) -> Option<Number> {
+    if input.is_null(row) {
+        return None;
+    }
+    if let Some(array) = input.as_any().downcast_ref::<Int64Array>() {
+        return Some(Number::Int(array.value(row)));
+    }
+    match input.as_any().downcast_ref::<Float64Array>() {
+        Some(array) => Some(Number::Float(array.value(row))),
+        // non-numeric aggregates only need to know if the value is valid
+        None => Some(Number::Int(0)),
+    }
+}
+
+/// Create the array of an aggregate's values from the accumulators of each group
+///
+/// The values of `First` and `Last` aggregates are taken from their columns, so they are not
+/// created from accumulators.
+fn accumulator_array<'a>(
+    function: &AggregateFunction,
+    accumulators: impl Iterator<Item = &'a Accumulator>,
+    data_type: &DataType,
+) -> Result<ArrayRef, ArrowError> {
+    match function {
+        AggregateFunction::Count => Ok(Arc::new(Int64Array::from(
+            accumulators
+                .map(|acc| match acc {
+                    Accumulator::Count(count) => *count as i64,
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<i64>>(),
+        ))),
+        AggregateFunction::First | AggregateFunction::Last => Err(ArrowError::ComputeError(
+            format!("{:?} values are not computed by accumulators", function),
+        )),
+        AggregateFunction::CountDistinct => Ok(Arc::new(UInt32Array::from(
+            accumulators
+                .map(|acc| match acc {
+                    Accumulator::Distinct(keys) => keys.len() as u32,
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<u32>>(),
+        ))),
+        AggregateFunction::Mode => compute::concat(
+            &accumulators
+                .map(|acc| match acc {
+                    Accumulator::Mode(counts) => counts.value(),
+                    _ => unreachable!(),
+                })
+                .collect::<Result<Vec<ArrayRef>, ArrowError>>()?,
+        ),
+        AggregateFunction::Min | AggregateFunction::Max if data_type == &DataType::Utf8 => {
+            Ok(Arc::new(StringArray::from(
+                accumulators
+                    .map(|acc| match acc {
+                        Accumulator::MinText(v) | Accumulator::MaxText(v) => v.as_deref(),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<Option<&str>>>(),
+            )))
+        }
+        _ => {
+            let values: Vec<Option<Number>> = accumulators.map(|acc| acc.value()).collect();
+            let values: ArrayRef = if values.iter().any(|v| matches!(v, Some(Number::Float(_)))) {
+                Arc::new(Float64Array::from(
+                    values
+                        .into_iter()
+                        .map(|v| v.map(Number::to_f64))
+                        .collect::<Vec<Option<f64>>>(),
+                ))
+            } else {
+                Arc::new(Int64Array::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(Number::Int(v)) => Some(v),
+                            _ => None,
+                        })
+                        .collect::<Vec<Option<i64>>>(),
+                ))
+            };
+            from_ordered_integers(&values, data_type)
+        }
+    }
+}
+
+/// Groups rows by the values of key columns and computes aggregates for each group
+///
+/// Batches are processed as they are received, so that multiple aggregates are computed in a
+/// single pass, including aggregates over the same column (e.g. the `min` and `max` of a column).
+/// Batches are not retained once they are aggregated, as only the keys of each group and the
+/// values of `First` and `Last` aggregates are copied from them.
+pub struct HashAggregator {
+    group_columns: Vec<usize>,
+    aggregates: Vec<(AggregateFunction, usize)>,
+    state: GroupState,
+    /// Batches that are waiting to be aggregated in parallel
+    pending: Vec<RecordBatch>,
+    num_batches: usize,
+    num_threads: usize,
+    pool: Option<rayon::ThreadPool>,
+}
+
+/// The groups of a set of rows, and the state of each group's aggregates
+struct GroupState {
+    groups: HashMap<Vec<u8>, usize>,
+    /// the chunks of each group column's values, with a row per group
+    keys: Vec<Vec<ArrayRef>>,
+    accumulators: Vec<Vec<Accumulator>>,
+    /// the chunks of each `First` and `Last` aggregate's values, with a row per group
+    ///
+    /// The values of `First` aggregates are only appended to, while the values of `Last`
+    /// aggregates are replaced with a single chunk whenever they change. `Mode` aggregates only
+    /// keep an empty chunk, which is their column when there are no groups.
+    values: Vec<Vec<ArrayRef>>,
+}
+
+impl GroupState {
+    fn new(num_group_columns: usize, num_aggregates: usize) -> Self {
+        Self {
+            groups: HashMap::new(),
+            keys: vec![vec![]; num_group_columns],
+            accumulators: vec![],
+            values: vec![vec![]; num_aggregates],
+        }
+    }
+
+    /// Update the groups with a batch
+    fn update(
+        &mut self,
+        group_columns: &[usize],
+        aggregates: &[(AggregateFunction, usize)],
+        batch: &RecordBatch,
+    ) -> Result<(), ArrowError> {
+        let group_arrays: Vec<ArrayRef> = group_columns
+            .iter()
+            .map(|i| batch.column(*i).clone())
+            .collect();
+        let keys = row_keys(&group_arrays, batch.num_rows())?;
+        let inputs = aggregate_inputs(aggregates, batch)?;
+
+        let mut new_rows = vec![];
+        let mut last_rows = HashMap::new();
+        for (row, key) in keys.into_iter().enumerate() {
+            let group = match self.groups.get(&key) {
+                Some(group) => *group,
+                None => {
+                    let group = self.accumulators.len();
+                    self.groups.insert(key, group);
+                    new_rows.push(row as u32);
+                    self.accumulators.push(
+                        aggregates
+                            .iter()
+                            .map(|(function, i)| {
+                                Accumulator::try_new(function, batch.column(*i).data_type())
+                            })
+                            .collect::<Result<Vec<Accumulator>, ArrowError>>()?,
+                    );
+                    group
+                }
+            };
+            for (j, input) in inputs.iter().enumerate() {
+                self.accumulators[group][j].update(input, row)?;
+            }
+            last_rows.insert(group, row as u32);
+        }
+        let values: Vec<Option<ArrayRef>> = aggregates
+            .iter()
+            .map(|(function, i)| match function {
+                AggregateFunction::First | AggregateFunction::Last | AggregateFunction::Mode => {
+                    Some(batch.column(*i).clone())
+                }
+                _ => None,
+            })
+            .collect();
+        self.copy_values(aggregates, &group_arrays, &values, new_rows, &last_rows)
+    }
+
+    /// Merge the state of rows that follow the rows of this state, preserving group order
+    fn merge(
+        &mut self,
+        aggregates: &[(AggregateFunction, usize)],
+        other: GroupState,
+    ) -> Result<(), ArrowError> {
+        let keys = other
+            .keys
+            .iter()
+            .map(|chunks| compute::concat(chunks))
+            .collect::<Result<Vec<ArrayRef>, ArrowError>>()?;
+        let values = other
+            .values
+            .iter()
+            .map(|chunks| {
+                if chunks.is_empty() {
+                    Ok(None)
+                } else {
+                    compute::concat(chunks).map(Some)
+                }
+            })
+            .collect::<Result<Vec<Option<ArrayRef>>, ArrowError>>()?;
+
+        let mut groups: Vec<(Vec<u8>, usize)> = other.groups.into_iter().collect();
+        groups.sort_by_key(|(_, group)| *group);
+        let mut new_rows = vec![];
+        let mut last_rows = HashMap::new();
+        for ((key, other_group), accumulators) in groups.into_iter().zip(other.accumulators) {
+            let group = match self.groups.get(&key).copied() {
+                Some(group) => {
+                    for (acc, other_acc) in self.accumulators[group].iter_mut().zip(accumulators) {
+                        acc.merge(other_acc)?;
+                    }
+                    group
+                }
+                None => {
+                    let group = self.accumulators.len();
+                    self.groups.insert(key, group);
+                    new_rows.push(other_group as u32);
+                    self.accumulators.push(accumulators);
+                    group
+                }
+            };
+            last_rows.insert(group, other_group as u32);
+        }
+        self.copy_values(aggregates, &keys, &values, new_rows, &last_rows)
+    }
+
+    /// Copy the values of groups from a set of rows
+    ///
+    /// `keys` are the group columns of the rows, and `values` are the columns of `First` and
+    /// `Last` aggregates. `new_rows` are the rows that created groups, in the order that the
+    /// groups were created, and `last_rows` are the last row of each group in the rows.
+    fn copy_values(
+        &mut self,
+        aggregates: &[(AggregateFunction, usize)],
+        keys: &[ArrayRef],
+        values: &[Option<ArrayRef>],
+        new_rows: Vec<u32>,
+        last_rows: &HashMap<usize, u32>,
+    ) -> Result<(), ArrowError> {
+        // an empty chunk is kept for the first rows, so that there are values to concatenate
+        // when there are no groups
+        let has_new_groups = !new_rows.is_empty();
+        let new_rows = UInt32Array::from(new_rows);
+        for (chunks, array) in self.keys.iter_mut().zip(keys) {
+            if has_new_groups || chunks.is_empty() {
+                chunks.push(compute::take(array, &new_rows, None)?);
+            }
+        }
+        let num_groups = self.accumulators.len();
+        for ((function, _), (chunks, array)) in
+            aggregates.iter().zip(self.values.iter_mut().zip(values))
+        {
+            match (function, array) {
+                (AggregateFunction::First, Some(array)) => {
+                    if has_new_groups || chunks.is_empty() {
+                        chunks.push(compute::take(array, &new_rows, None)?);
+                    }
+                }
+                (AggregateFunction::Last, Some(array)) => {
+                    // the rows follow the existing values, so groups with rows take the value
+                    // of their last row
+                    let offset = chunks.iter().map(|chunk| chunk.len()).sum::<usize>() as u32;
+                    chunks.push(array.clone());
+                    let indices = UInt32Array::from(
+                        (0..num_groups)
+                            .map(|group| match last_rows.get(&group) {
+                                Some(row) => offset + row,
+                                None => group as u32,
+                            })
+                            .collect::<Vec<u32>>(),
+                    );
+                    let last_values = compute::take(&compute::concat(chunks)?, &indices, None)?;
+                    *chunks = vec![last_values];
+                }
+                (AggregateFunction::Mode, Some(array)) => {
+                    if chunks.is_empty() {
+                        chunks.push(array.slice(0, 0));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HashAggregator {
+    /// Create an aggregator that groups by the `group_columns` indices, and computes aggregate
+    /// functions over the column indices in `aggregates`
+    pub fn new(group_columns: Vec<usize>, aggregates: Vec<(AggregateFunction, usize)>) -> Self {
+        Self {
+            state: GroupState::new(group_columns.len(), aggregates.len()),
+            group_columns,
+            aggregates,
+            pending: vec![],
+            num_batches: 0,
+            num_threads: 1,
+            pool: None,
+        }
+    }
+
+    /// Aggregate batches across threads, by computing partial aggregates of `num_threads`
+    /// batches at a time, which are merged in order.
+    ///
+    /// Up to `num_threads` batches are buffered until they are aggregated.
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads.max(1);
+        self
+    }
+
+    pub fn update(&mut self, batch: &RecordBatch) -> Result<(), ArrowError> {
+        self.num_batches += 1;
+        if self.num_threads == 1 {
+            return self
+                .state
+                .update(&self.group_columns, &self.aggregates, batch);
+        }
+        self.pending.push(batch.clone());
+        if self.pending.len() >= self.num_threads {
+            self.aggregate_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Aggregate the pending batches in parallel, and merge their states in order
+    fn aggregate_pending(&mut self) -> Result<(), ArrowError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if self.pool.is_none() {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(self.num_threads)
+                .build()
+                .map_err(|e| ArrowError::ComputeError(e.to_string()))?;
+            self.pool = Some(pool);
+        }
+        let batches = std::mem::take(&mut self.pending);
+        let group_columns = &self.group_columns;
+        let aggregates = &self.aggregates;
+        let states = self.pool.as_ref().unwrap().install(|| {
+            batches
+                .par_iter()
+                .map(|batch| {
+                    let mut state = GroupState::new(group_columns.len(), aggregates.len());
+                    state.update(group_columns, aggregates, batch)?;
+                    Ok(state)
+                })
+                .collect::<Result<Vec<GroupState>, ArrowError>>()
+        })?;
+        for state in states {
+            self.state.merge(&self.aggregates, state)?;
+        }
+        Ok(())
+    }
+
+    /// Create a batch with a row per group, made up of the group columns followed by aggregates
+    pub fn finish(mut self, schema: SchemaRef) -> Result<RecordBatch, ArrowError> {
+        if self.num_batches == 0 {
+            return Err(ArrowError::ComputeError(
+                "Cannot aggregate a dataset without batches".to_string(),
+            ));
+        }
+        self.aggregate_pending()?;
+        let state = self.state;
+        let mut columns = Vec::with_capacity(self.group_columns.len() + self.aggregates.len());
+        for chunks in &state.keys {
+            columns.push(compute::concat(chunks)?);
+        }
+        for (j, (function, _)) in self.aggregates.iter().enumerate() {
+            let array: ArrayRef = match function {
+                AggregateFunction::First | AggregateFunction::Last => {
+                    compute::concat(&state.values[j])?
+                }
+                AggregateFunction::Mode => {
+                    let mut chunks = state.values[j].clone();
+                    for group in &state.accumulators {
+                        if let Accumulator::Mode(counts) = &group[j] {
+                            chunks.push(counts.value()?);
+                        }
+                    }
+                    compute::concat(&chunks)?
+                }
+                _ => {
+                    let field = schema.field(self.group_columns.len() + j);
+                    accumulator_array(
+                        function,
+                        state.accumulators.iter().map(|group| &group[j]),
+                        field.data_type(),
+                    )?
+                }
+            };
+            columns.push(array);
+        }
+        RecordBatch::try_new(schema, columns)
+    }
+}
+
+/// Groups rows whose keys are sorted, or otherwise contiguous, and computes aggregates for
+/// each group
+///
+/// Unlike `HashAggregator`, groups are not kept once they are complete. A group's aggregates are
+/// complete as soon as a row with a different key is seen, so each update returns the groups
+/// that the batch completed, and only the current group is kept in memory.
+///
+/// By default, groups are validated to be contiguous, which requires remembering the keys of
+/// completed groups. Input that is known to be sorted can be trusted with `with_validation`.
+pub struct SortedAggregator {
+    group_columns: Vec<usize>,
+    aggregates: Vec<(AggregateFunction, usize)>,
+    schema: SchemaRef,
+    /// The keys of completed groups, if groups are validated to be contiguous
+    completed_keys: Option<HashSet<Vec<u8>>>,
+    current: Option<SortedGroup>,
+}
+
+/// The state of a group of contiguous rows
+struct SortedGroup {
+    key: Vec<u8>,
+    /// Single-row slices of the group's key columns
+    key_values: Vec<ArrayRef>,
+    accumulators: Vec<Accumulator>,
+    /// Single-row slices of the values of `First` and `Last` aggregates
+    values: Vec<Option<ArrayRef>>,
+}
+
+impl SortedAggregator {
+    /// Create an aggregator that groups by the `group_columns` indices, and computes aggregate
+    /// functions over the column indices in `aggregates`, returning batches with `schema`
+    pub fn new(
+        group_columns: Vec<usize>,
+        aggregates: Vec<(AggregateFunction, usize)>,
+        schema: SchemaRef,
+    ) -> Self {
+        Self {
+            group_columns,
+            aggregates,
+            schema,
+            completed_keys: Some(HashSet::new()),
+            current: None,
+        }
+    }
+
+    /// Whether to check that groups are contiguous, returning an error if a group's key is
+    /// seen again after the group was completed
+    ///
+    /// Without validation, memory use does not grow with the number of groups, but input that
+    /// is not sorted by its keys produces more than one row for the same group.
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.completed_keys = if validate { Some(HashSet::new()) } else { None };
+        self
+    }
+
+    /// Aggregate a batch, returning a batch with the groups that it completed, if any
+    pub fn update(&mut self, batch: &RecordBatch) -> Result<Option<RecordBatch>, ArrowError> {
+        let group_arrays: Vec<ArrayRef> = self
+            .group_columns
+            .iter()
+            .map(|i| batch.column(*i).clone())
+            .collect();
+        let keys = row_keys(&group_arrays, batch.num_rows())?;
+        let inputs = aggregate_inputs(&self.aggregates, batch)?;
+        let mut completed = vec![];
+        let mut start = 0;
+        while start < keys.len() {
+            // the rows of a group are contiguous, so they are aggregated as a run
+            let mut end = start + 1;
+            while end < keys.len() && keys[end] == keys[start] {
+                end += 1;
+            }
+            let is_current = matches!(&self.current, Some(group) if group.key == keys[start]);
+            if !is_current {
+                if let Some(group) = self.current.take() {
+                    completed.push(self.complete(group));
+                }
+                if let Some(completed_keys) = &self.completed_keys {
+                    if completed_keys.contains(&keys[start]) {
+                        return Err(ArrowError::ComputeError(
+                            "Cannot aggregate groups that are not sorted by their keys".to_string(),
+                        ));
+                    }
+                }
+                self.current = Some(self.start_group(keys[start].clone(), batch, start)?);
+            }
+            let group = self.current.as_mut().unwrap();
+            for row in start..end {
+                for (j, input) in inputs.iter().enumerate() {
+                    group.accumulators[j].update(input, row)?;
+                }
+            }
+            for (j, (function, column)) in self.aggregates.iter().enumerate() {
+                if let AggregateFunction::Last = function {
+                    group.values[j] = Some(batch.column(*column).slice(end - 1, 1));
+                }
+            }
+            start = end;
+        }
+        self.to_batch(completed)
+    }
+
+    /// Return a batch with the last group, if any rows were aggregated
+    pub fn finish(mut self) -> Result<Option<RecordBatch>, ArrowError> {
+        let completed = self.current.take().into_iter().collect();
+        self.to_batch(completed)
+    }
+
+    fn start_group(
+        &self,
+        key: Vec<u8>,
+        batch: &RecordBatch,
+        row: usize,
+    ) -> Result<SortedGroup, ArrowError> {
+        Ok(SortedGroup {
+            key,
+            key_values: self
+                .group_columns
+                .iter()
+                .map(|i| batch.column(*i).slice(row, 1))
+                .collect(),
+            accumulators: self
+                .aggregates
+                .iter()
+                .map(|(function, column)| {
+                    Accumulator::try_new(function, batch.column(*column).data_type())
+                })
+                .collect::<Result<Vec<Accumulator>, ArrowError>>()?,
+            values: self
+                .aggregates
+                .iter()
+                .map(|(function, column)| match function {
+                    AggregateFunction::First => Some(batch.column(*column).slice(row, 1)),
+                    _ => None,
+                })
+                .collect(),
+        })
+    }
+
+    fn complete(&mut self, mut group: SortedGroup) -> SortedGroup {
+        if let Some(completed_keys) = &mut self.completed_keys {
+            completed_keys.insert(std::mem::take(&mut group.key));
+        }
+        group
+    }
+
+    /// Create a batch with a row per group, made up of the group columns followed by aggregates
+    fn to_batch(&self, groups: Vec<SortedGroup>) -> Result<Option<RecordBatch>, ArrowError> {
+        if groups.is_empty() {
+            return Ok(None);
+        }
+        let mut columns = Vec::with_capacity(self.group_columns.len() + self.aggregates.len());
+        for k in 0..self.group_columns.len() {
+            let values: Vec<ArrayRef> = groups.iter().map(|g| g.key_values[k].clone()).collect();
+            columns.push(compute::concat(&values)?);
+        }
+        for (j, (function, _)) in self.aggregates.iter().enumerate() {
+            let array = match function {
+                AggregateFunction::First | AggregateFunction::Last => {
+                    let values: Vec<ArrayRef> = groups
+                        .iter()
+                        .map(|g| g.values[j].clone().unwrap())
+                        .collect();
+                    compute::concat(&values)?
+                }
+                _ => {
+                    let field = self.schema.field(self.group_columns.len() + j);
+                    accumulator_array(
+                        function,
+                        groups.iter().map(|g| &g.accumulators[j]),
+                        field.data_type(),
+                    )?
+                }
+            };
+            columns.push(array);
+        }
+        RecordBatch::try_new(self.schema.clone(), columns).map(Some)
+    }
+}
+
+/// Find the most frequent non-null value of an array, returning a single-value array
+///
+/// Ties are broken by returning the value that appears first. If all values are null,
+/// the result is null.
+pub fn mode(array: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let keys = row_keys(&[array.clone()], array.len())?;
+    // the count and first index of each value
+    let mut counts: HashMap<Vec<u8>, (usize, usize)> = HashMap::new();
+    for (i, key) in keys.into_iter().enumerate() {
+        if array.is_valid(i) {
+            counts.entry(key).or_insert((0, i)).0 += 1;
+        }
+    }
+    let index = counts
+        .values()
+        .max_by(|(a_count, a_first), (b_count, b_first)| {
+            a_count.cmp(b_count).then(b_first.cmp(a_first))
+        })
+        .map(|(_, first)| *first as u32);
+    compute::take(array, &UInt32Array::from(vec![index]), None)
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,4 +1142,591 @@ mod tests {
         let e = AggregateFunctions::avg(vec![&d, &b]);
         assert_eq!(Some(4.5), e);
     }
+
+    #[test]
+    fn test_hash_aggregate_same_column() {
+        use arrow::array::StringArray;
+        use arrow::datatypes::{Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("x", DataType::Int64, true),
+        ]));
+        let batches = vec![
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["a", "b", "a"])),
+                    Arc::new(Int64Array::from(vec![Some(1), Some(2), Some(3)])),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                input_schema,
+                vec![
+                    Arc::new(StringArray::from(vec!["b", "a", "b"])),
+                    Arc::new(Int64Array::from(vec![Some(6), Some(8), None])),
+                ],
+            )
+            .unwrap(),
+        ];
+        let mut aggregator = HashAggregator::new(
+            vec![0],
+            vec![
+                (AggregateFunction::Min, 1),
+                (AggregateFunction::Max, 1),
+                (AggregateFunction::Avg, 1),
+            ],
+        );
+        for batch in &batches {
+            aggregator.update(batch).unwrap();
+        }
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("min(x)", DataType::Int64, true),
+            Field::new("max(x)", DataType::Int64, true),
+            Field::new("avg(x)", DataType::Float64, true),
+        ]));
+        let output = aggregator.finish(output_schema).unwrap();
+        assert_eq!(2, output.num_rows());
+        assert_eq!(4, output.num_columns());
+
+        let keys = output
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let min = output
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let max = output
+            .column(2)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let avg = output
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        // groups are returned in the order that they are first seen
+        assert_eq!("a", keys.value(0));
+        assert_eq!(1, min.value(0));
+        assert_eq!(8, max.value(0));
+        assert_eq!(4.0, avg.value(0));
+        assert_eq!("b", keys.value(1));
+        assert_eq!(2, min.value(1));
+        assert_eq!(6, max.value(1));
+        assert_eq!(4.0, avg.value(1));
+    }
+
+    #[test]
+    fn test_hash_aggregate_multiple_keys() {
+        use arrow::datatypes::{Field, Schema};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("x", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![Some(1), Some(1), Some(1), None])),
+                Arc::new(Int64Array::from(vec![Some(1), Some(2), Some(1), None])),
+                Arc::new(Int64Array::from(vec![1, 10, 100, 1000])),
+            ],
+        )
+        .unwrap();
+        let mut aggregator = HashAggregator::new(vec![0, 1], vec![(AggregateFunction::Count, 2)]);
+        aggregator.update(&batch).unwrap();
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("count(x)", DataType::Int64, true),
+        ]));
+        let output = aggregator.finish(output_schema).unwrap();
+        assert_eq!(3, output.num_rows());
+        let counts = output
+            .column(2)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(2, counts.value(0));
+        assert_eq!(1, counts.value(1));
+        assert_eq!(1, counts.value(2));
+    }
+
+    #[test]
+    fn test_mode() {
+        use arrow::array::StringArray;
+
+        let array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("b"),
+            Some("a"),
+            None,
+            Some("a"),
+            None,
+            None,
+        ]));
+        let result = mode(&array).unwrap();
+        let result = result.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!("a", result.value(0));
+
+        // values that are seen equally often are broken by the first value seen
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["c", "d", "d", "c"]));
+        let result = mode(&array).unwrap();
+        let result = result.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!("c", result.value(0));
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["d", "c", "c", "d"]));
+        let result = mode(&array).unwrap();
+        let result = result.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!("d", result.value(0));
+    }
+
+    #[test]
+    fn test_hash_aggregate_multiple_threads() {
+        use arrow::array::StringArray;
+        use arrow::datatypes::{Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("x", DataType::Int64, true),
+        ]));
+        let batches: Vec<RecordBatch> = (0..10)
+            .map(|batch| {
+                let keys: Vec<String> = (0..100).map(|i| format!("k{}", (i + batch) % 7)).collect();
+                let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+                let values: Vec<Option<i64>> = (0..100)
+                    .map(|i| {
+                        if i % 11 == 0 {
+                            None
+                        } else {
+                            Some(i * batch - 50)
+                        }
+                    })
+                    .collect();
+                RecordBatch::try_new(
+                    input_schema.clone(),
+                    vec![
+                        Arc::new(StringArray::from(keys)),
+                        Arc::new(Int64Array::from(values)),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect();
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("sum(x)", DataType::Int64, true),
+            Field::new("count(x)", DataType::Int64, true),
+            Field::new("min(x)", DataType::Int64, true),
+            Field::new("max(x)", DataType::Int64, true),
+            Field::new("avg(x)", DataType::Float64, true),
+        ]));
+        let aggregate = |num_threads: usize| {
+            let mut aggregator = HashAggregator::new(
+                vec![0],
+                vec![
+                    (AggregateFunction::Sum, 1),
+                    (AggregateFunction::Count, 1),
+                    (AggregateFunction::Min, 1),
+                    (AggregateFunction::Max, 1),
+                    (AggregateFunction::Avg, 1),
+                ],
+            )
+            .with_num_threads(num_threads);
+            for batch in &batches {
+                aggregator.update(batch).unwrap();
+            }
+            aggregator.finish(output_schema.clone()).unwrap()
+        };
+
+        let single = aggregate(1);
+        let parallel = aggregate(4);
+        assert_eq!(7, single.num_rows());
+        assert_eq!(single.num_rows(), parallel.num_rows());
+        for i in 0..single.num_columns() {
+            assert_eq!(single.column(i).data(), parallel.column(i).data());
+        }
+    }
+
+    #[test]
+    fn test_hash_aggregate_large_integers() {
+        use arrow::array::StringArray;
+        use arrow::datatypes::{Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("x", DataType::Int64, true),
+        ]));
+        let large = 1 << 53;
+        let batches = vec![
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["a", "b", "a"])),
+                    Arc::new(Int64Array::from(vec![Some(large + 1), None, Some(2)])),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["c", "a"])),
+                    Arc::new(Int64Array::from(vec![Some(-large - 1), Some(large + 3)])),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                input_schema,
+                vec![
+                    Arc::new(StringArray::from(vec!["b", "c"])),
+                    Arc::new(Int64Array::from(vec![Some(7), Some(large - 1)])),
+                ],
+            )
+            .unwrap(),
+        ];
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("sum(x)", DataType::Int64, true),
+            Field::new("min(x)", DataType::Int64, true),
+            Field::new("max(x)", DataType::Int64, true),
+            Field::new("first(x)", DataType::Int64, true),
+            Field::new("last(x)", DataType::Int64, true),
+        ]));
+        let values = |batch: &RecordBatch, i: usize| {
+            let array = batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            (0..array.len())
+                .map(|row| {
+                    if array.is_null(row) {
+                        None
+                    } else {
+                        Some(array.value(row))
+                    }
+                })
+                .collect::<Vec<Option<i64>>>()
+        };
+        for num_threads in 1..=3 {
+            let mut aggregator = HashAggregator::new(
+                vec![0],
+                vec![
+                    (AggregateFunction::Sum, 1),
+                    (AggregateFunction::Min, 1),
+                    (AggregateFunction::Max, 1),
+                    (AggregateFunction::First, 1),
+                    (AggregateFunction::Last, 1),
+                ],
+            )
+            .with_num_threads(num_threads);
+            for batch in &batches {
+                aggregator.update(batch).unwrap();
+            }
+            let output = aggregator.finish(output_schema.clone()).unwrap();
+            let keys = output
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            assert_eq!(
+                vec!["a", "b", "c"],
+                (0..3).map(|i| keys.value(i)).collect::<Vec<&str>>()
+            );
+            // integers above 2^53 are not rounded
+            assert_eq!(
+                vec![Some(2 * large + 6), Some(7), Some(-2)],
+                values(&output, 1)
+            );
+            assert_eq!(vec![Some(2), Some(7), Some(-large - 1)], values(&output, 2));
+            assert_eq!(
+                vec![Some(large + 3), Some(7), Some(large - 1)],
+                values(&output, 3)
+            );
+            assert_eq!(
+                vec![Some(large + 1), None, Some(-large - 1)],
+                values(&output, 4)
+            );
+            assert_eq!(
+                vec![Some(large + 3), Some(7), Some(large - 1)],
+                values(&output, 5)
+            );
+        }
+
+        // sums that overflow are errors
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![i64::MAX, 1]))],
+        )
+        .unwrap();
+        let mut aggregator = HashAggregator::new(vec![], vec![(AggregateFunction::Sum, 0)]);
+        assert!(aggregator.update(&batch).is_err());
+    }
+
+    #[test]
+    fn test_hash_aggregate_mode() {
+        use arrow::datatypes::{Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = |keys: Vec<&str>, names: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(keys)),
+                    Arc::new(StringArray::from(names)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = vec![
+            batch(vec!["a", "a", "b"], vec![Some("x"), Some("y"), None]),
+            batch(vec!["a", "c", "b"], vec![Some("y"), Some("z"), None]),
+            batch(vec!["c", "a", "c"], vec![Some("w"), Some("x"), Some("w")]),
+        ];
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("mode(name)", DataType::Utf8, true),
+            Field::new("count_distinct(name)", DataType::UInt32, false),
+        ]));
+        for num_threads in 1..=3 {
+            let mut aggregator = HashAggregator::new(
+                vec![0],
+                vec![
+                    (AggregateFunction::Mode, 1),
+                    (AggregateFunction::CountDistinct, 1),
+                ],
+            )
+            .with_num_threads(num_threads);
+            for batch in &batches {
+                aggregator.update(batch).unwrap();
+            }
+            let output = aggregator.finish(output_schema.clone()).unwrap();
+            let modes = output
+                .column(1)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            // "a" has two "x" and two "y", so the value that was seen first is its mode, and
+            // "b" has no values
+            assert_eq!("x", modes.value(0));
+            assert!(modes.is_null(1));
+            assert_eq!("w", modes.value(2));
+            let counts = output
+                .column(2)
+                .as_any()
+                .downcast_ref::<UInt32Array>()
+                .unwrap();
+            assert_eq!(vec![2, 0, 2], counts.value_slice(0, 3).to_vec());
+        }
+
+        // there are no modes without groups
+        let mut aggregator = HashAggregator::new(vec![0], vec![(AggregateFunction::Mode, 1)]);
+        aggregator.update(&batch(vec![], vec![])).unwrap();
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("mode(name)", DataType::Utf8, true),
+        ]));
+        let output = aggregator.finish(output_schema).unwrap();
+        assert_eq!(0, output.num_rows());
+    }
+
+    #[test]
+    fn test_hash_aggregate_ordered_types() {
+        use arrow::array::{BooleanArray, Date32Array};
+        use arrow::datatypes::{DateUnit, Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("date", DataType::Date32(DateUnit::Day), true),
+            Field::new("flag", DataType::Boolean, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batches = vec![
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["a", "b", "a"])),
+                    Arc::new(Date32Array::from(vec![Some(10), Some(-5), None])),
+                    Arc::new(BooleanArray::from(vec![Some(true), Some(false), None])),
+                    Arc::new(StringArray::from(vec![Some("kiwi"), None, Some("fig")])),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                input_schema,
+                vec![
+                    Arc::new(StringArray::from(vec!["b", "a"])),
+                    Arc::new(Date32Array::from(vec![Some(3), Some(2)])),
+                    Arc::new(BooleanArray::from(vec![Some(false), Some(false)])),
+                    Arc::new(StringArray::from(vec![Some("pear"), Some("lime")])),
+                ],
+            )
+            .unwrap(),
+        ];
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("min(date)", DataType::Date32(DateUnit::Day), true),
+            Field::new("max(date)", DataType::Date32(DateUnit::Day), true),
+            Field::new("min(flag)", DataType::Boolean, true),
+            Field::new("max(flag)", DataType::Boolean, true),
+            Field::new("min(name)", DataType::Utf8, true),
+            Field::new("max(name)", DataType::Utf8, true),
+        ]));
+        for num_threads in 1..=2 {
+            let mut aggregator = HashAggregator::new(
+                vec![0],
+                vec![
+                    (AggregateFunction::Min, 1),
+                    (AggregateFunction::Max, 1),
+                    (AggregateFunction::Min, 2),
+                    (AggregateFunction::Max, 2),
+                    (AggregateFunction::Min, 3),
+                    (AggregateFunction::Max, 3),
+                ],
+            )
+            .with_num_threads(num_threads);
+            for batch in &batches {
+                aggregator.update(batch).unwrap();
+            }
+            let output = aggregator.finish(output_schema.clone()).unwrap();
+            let dates = |i: usize| {
+                let array = output.column(i);
+                let array = array.as_any().downcast_ref::<Date32Array>().unwrap();
+                (0..2).map(|row| array.value(row)).collect::<Vec<i32>>()
+            };
+            assert_eq!(vec![2, -5], dates(1));
+            assert_eq!(vec![10, 3], dates(2));
+            let flags = |i: usize| {
+                let array = output.column(i);
+                let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+                (0..2).map(|row| array.value(row)).collect::<Vec<bool>>()
+            };
+            assert_eq!(vec![false, false], flags(3));
+            assert_eq!(vec![true, false], flags(4));
+            let names = |i: usize| {
+                let array = output.column(i);
+                let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+                (0..2)
+                    .map(|row| array.value(row).to_owned())
+                    .collect::<Vec<String>>()
+            };
+            assert_eq!(vec!["fig", "pear"], names(5));
+            assert_eq!(vec!["lime", "pear"], names(6));
+        }
+    }
+
+    #[test]
+    fn test_sorted_aggregate() {
+        use arrow::array::StringArray;
+        use arrow::datatypes::{Field, Schema};
+
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let batch = |keys: Vec<&str>, values: Vec<Option<i64>>| {
+            RecordBatch::try_new(
+                input_schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(keys)),
+                    Arc::new(Int64Array::from(values)),
+                ],
+            )
+            .unwrap()
+        };
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("sum", DataType::Int64, true),
+            Field::new("count", DataType::Int64, false),
+            Field::new("last", DataType::Int64, true),
+        ]));
+        let aggregates = vec![
+            (AggregateFunction::Sum, 1),
+            (AggregateFunction::Count, 1),
+            (AggregateFunction::Last, 1),
+        ];
+        let mut aggregator = SortedAggregator::new(vec![0], aggregates, output_schema);
+
+        // group b continues in the second batch, so only group a is complete
+        let a = aggregator
+            .update(&batch(
+                vec!["a", "a", "b", "b"],
+                vec![Some(1), Some(2), Some(3), Some(4)],
+            ))
+            .unwrap()
+            .unwrap();
+        assert_eq!(1, a.num_rows());
+        let b = aggregator
+            .update(&batch(vec!["b", "c", "c"], vec![Some(5), Some(6), None]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(1, b.num_rows());
+        let c = aggregator.finish().unwrap().unwrap();
+        assert_eq!(1, c.num_rows());
+
+        let value = |batch: &RecordBatch, i: usize| {
+            let array = batch
+                .column(i)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            if array.is_null(0) {
+                None
+            } else {
+                Some(array.value(0))
+            }
+        };
+        let key = |batch: &RecordBatch| {
+            let keys = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            keys.value(0).to_string()
+        };
+        assert_eq!("a", key(&a));
+        assert_eq!(
+            vec![Some(3), Some(2), Some(2)],
+            vec![value(&a, 1), value(&a, 2), value(&a, 3)]
+        );
+        assert_eq!("b", key(&b));
+        assert_eq!(
+            vec![Some(12), Some(3), Some(5)],
+            vec![value(&b, 1), value(&b, 2), value(&b, 3)]
+        );
+        // the last value of c is null, and nulls are not counted
+        assert_eq!("c", key(&c));
+        assert_eq!(
+            vec![Some(6), Some(1), None],
+            vec![value(&c, 1), value(&c, 2), value(&c, 3)]
+        );
+
+        // unsorted groups are rejected, unless the input is trusted
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Utf8, false),
+            Field::new("count", DataType::Int64, false),
+        ]));
+        let unsorted = batch(vec!["a", "b", "a"], vec![Some(1), Some(2), Some(3)]);
+        let mut aggregator = SortedAggregator::new(
+            vec![0],
+            vec![(AggregateFunction::Count, 1)],
+            output_schema.clone(),
+        );
+        assert!(aggregator.update(&unsorted).is_err());
+        let mut aggregator =
+            SortedAggregator::new(vec![0], vec![(AggregateFunction::Count, 1)], output_schema)
+                .with_validation(false);
+        let groups = aggregator.update(&unsorted).unwrap().unwrap();
+        assert_eq!(2, groups.num_rows());
+        assert_eq!(1, aggregator.finish().unwrap().unwrap().num_rows());
+    }
 }
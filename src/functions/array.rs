@@ -1,9 +1,10 @@
 use array_tool::vec::*;
 use arrow::array::*;
+use arrow::compute;
 use arrow::datatypes::*;
 use arrow::error::ArrowError;
 
-struct ArrayFunctions;
+pub struct ArrayFunctions;
 
 impl ArrayFunctions {
     // pub fn array<T>(arrays: Vec<&PrimitiveArray<T>>) -> Result<ListArray, ArrowError> {
@@ -35,7 +36,86 @@ impl ArrayFunctions {
         }
         Ok(b.finish())
     }
-    fn array_join() {}
+    /// Checks whether each list contains the value of the same row.
+    ///
+    /// Returns null if the list or the value is null. Null elements never match.
+    pub fn array_contains_each<T>(
+        array: &ListArray,
+        values: &PrimitiveArray<T>,
+    ) -> Result<BooleanArray, ArrowError>
+    where
+        T: ArrowPrimitiveType + ArrowNumericType,
+        T::Native: std::cmp::PartialEq<T::Native>,
+    {
+        let elements = array.values();
+        let elements = elements
+            .as_any()
+            .downcast_ref::<PrimitiveArray<T>>()
+            .unwrap();
+        Self::contains_each(array, values, |i, j| elements.value(j) == values.value(i))
+    }
+    /// Checks whether each list of strings contains the string of the same row.
+    ///
+    /// Returns null if the list or the value is null. Null elements never match.
+    pub fn array_contains_each_string(
+        array: &ListArray,
+        values: &StringArray,
+    ) -> Result<BooleanArray, ArrowError> {
+        let elements = array.values();
+        let elements = elements.as_any().downcast_ref::<StringArray>().unwrap();
+        Self::contains_each(array, values, |i, j| elements.value(j) == values.value(i))
+    }
+    /// Checks whether any valid element `j` of each list matches row `i` of the values
+    fn contains_each<F>(
+        array: &ListArray,
+        values: &dyn Array,
+        matches: F,
+    ) -> Result<BooleanArray, ArrowError>
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        if array.len() != values.len() {
+            return Err(ArrowError::ComputeError(
+                "Expected the array and values to have the same length".to_string(),
+            ));
+        }
+        let elements = array.values();
+        let mut b = BooleanBuilder::new(array.len());
+        for i in 0..array.len() {
+            if array.is_null(i) || values.is_null(i) {
+                b.append_null()?
+            } else {
+                let offset = array.value_offset(i) as usize;
+                let contains = (offset..offset + array.value_length(i) as usize)
+                    .any(|j| elements.is_valid(j) && matches(i, j));
+                b.append_value(contains)?;
+            }
+        }
+        Ok(b.finish())
+    }
+    /// Join the values of each list into a string, separated by the delimiter.
+    ///
+    /// Null values within a list are skipped, and null lists produce nulls.
+    pub fn array_join(array: &ListArray, delimiter: &str) -> Result<StringArray, ArrowError> {
+        let values = compute::cast(&array.values(), &DataType::Utf8)?;
+        let values = values.as_any().downcast_ref::<StringArray>().unwrap();
+        let mut b = StringBuilder::new(array.len());
+        for i in 0..array.len() {
+            if array.is_null(i) {
+                b.append(false)?;
+            } else {
+                let offset = array.value_offset(i) as usize;
+                let length = array.value_length(i) as usize;
+                let joined = (offset..offset + length)
+                    .filter(|j| values.is_valid(*j))
+                    .map(|j| values.value(j))
+                    .collect::<Vec<&str>>()
+                    .join(delimiter);
+                b.append_value(&joined)?;
+            }
+        }
+        Ok(b.finish())
+    }
     fn array_distinct<T>(array: &ListArray) -> Result<ListArray, ArrowError>
     where
         T: ArrowPrimitiveType + ArrowNumericType,
@@ -179,27 +259,69 @@ impl ArrayFunctions {
     //     }
     //     Ok(c.finish())
     // }
-    pub fn array_max<T>(array: &ListArray) -> Result<PrimitiveArray<T>, ArrowError>
+    /// The number of elements of each list, including null elements
+    ///
+    /// Returns null if the list is null.
+    pub fn array_length(array: &ListArray) -> Result<Int32Array, ArrowError> {
+        let mut b = Int32Builder::new(array.len());
+        for i in 0..array.len() {
+            if array.is_null(i) {
+                b.append_null()?
+            } else {
+                b.append_value(array.value_length(i))?
+            }
+        }
+        Ok(b.finish())
+    }
+    /// The sum of the non-null elements of each list
+    ///
+    /// Returns null if the list is null, and 0 if the list is empty or only has null elements.
+    pub fn array_sum<T>(array: &ListArray) -> Result<PrimitiveArray<T>, ArrowError>
     where
         T: ArrowPrimitiveType + ArrowNumericType,
-        T::Native: ::std::cmp::Ord,
+        T::Native: std::ops::Add<Output = T::Native> + num::Zero,
     {
         let mut b = PrimitiveBuilder::<T>::new(array.len());
-        // get array datatype so we can downcast appropriately
-        let data_type = array.value_type();
+        let values = array.values();
+        let values = values.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
         for i in 0..array.len() {
             if array.is_null(i) {
                 b.append_null()?
             } else {
-                let values = array.values();
-                let values = values.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
-                let values = values.value_slice(
-                    array.value_offset(i) as usize,
-                    array.value_length(i) as usize,
-                );
-                let max: T::Native = *values.iter().max().unwrap();
-                b.append_value(max)?;
+                let offset = array.value_offset(i) as usize;
+                let sum = (offset..offset + array.value_length(i) as usize)
+                    .filter(|j| values.is_valid(*j))
+                    .fold(T::Native::zero(), |sum, j| sum + values.value(j));
+                b.append_value(sum)?;
+            }
+        }
+        Ok(b.finish())
+    }
+    /// The largest of the non-null elements of each list
+    ///
+    /// Returns null if the list is null, empty, or only has null elements.
+    pub fn array_max<T>(array: &ListArray) -> Result<PrimitiveArray<T>, ArrowError>
+    where
+        T: ArrowPrimitiveType + ArrowNumericType,
+        T::Native: std::cmp::PartialOrd,
+    {
+        let mut b = PrimitiveBuilder::<T>::new(array.len());
+        let values = array.values();
+        let values = values.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+        for i in 0..array.len() {
+            if array.is_null(i) {
+                b.append_null()?;
+                continue;
             }
+            let offset = array.value_offset(i) as usize;
+            let max = (offset..offset + array.value_length(i) as usize)
+                .filter(|j| values.is_valid(*j))
+                .map(|j| values.value(j))
+                .fold(None, |max: Option<T::Native>, value| match max {
+                    Some(max) if max >= value => Some(max),
+                    _ => Some(value),
+                });
+            b.append_option(max)?;
         }
         Ok(b.finish())
     }
@@ -397,6 +519,79 @@ impl ArrayFunctions {
         }
         Ok(c.finish())
     }
+    /// Add the elements at the same positions of each pair of lists
+    ///
+    /// Returns null if either list is null, and an error if the lists of a row have different
+    /// lengths. Elements that are null in either list are null.
+    pub fn array_add<T>(a: &ListArray, b: &ListArray) -> Result<ListArray, ArrowError>
+    where
+        T: ArrowPrimitiveType + ArrowNumericType,
+        T::Native: std::ops::Add<Output = T::Native>,
+    {
+        Self::array_math_op::<T, _>(a, b, |x, y| x + y)
+    }
+    /// Subtract the elements of each list in `b` from those at the same positions in `a`
+    ///
+    /// Returns null if either list is null, and an error if the lists of a row have different
+    /// lengths. Elements that are null in either list are null.
+    pub fn array_subtract<T>(a: &ListArray, b: &ListArray) -> Result<ListArray, ArrowError>
+    where
+        T: ArrowPrimitiveType + ArrowNumericType,
+        T::Native: std::ops::Sub<Output = T::Native>,
+    {
+        Self::array_math_op::<T, _>(a, b, |x, y| x - y)
+    }
+    fn array_math_op<T, F>(a: &ListArray, b: &ListArray, op: F) -> Result<ListArray, ArrowError>
+    where
+        T: ArrowPrimitiveType + ArrowNumericType,
+        F: Fn(T::Native, T::Native) -> T::Native,
+    {
+        if a.len() != b.len() {
+            return Err(ArrowError::ComputeError(
+                "Expected array a and b to have the same length".to_string(),
+            ));
+        }
+        let a_values = a.values();
+        let a_values = a_values
+            .as_any()
+            .downcast_ref::<PrimitiveArray<T>>()
+            .unwrap();
+        let b_values = b.values();
+        let b_values = b_values
+            .as_any()
+            .downcast_ref::<PrimitiveArray<T>>()
+            .unwrap();
+        let values_builder: PrimitiveBuilder<T> = PrimitiveBuilder::new(a_values.len());
+        let mut c = ListBuilder::new(values_builder);
+        for i in 0..a.len() {
+            if a.is_null(i) || b.is_null(i) {
+                c.append(false)?;
+                continue;
+            }
+            // the offsets of the lists can differ, even when their lengths are the same
+            let (a_offset, b_offset) = (a.value_offset(i) as usize, b.value_offset(i) as usize);
+            let length = a.value_length(i);
+            if length != b.value_length(i) {
+                return Err(ArrowError::ComputeError(format!(
+                    "Expected lists of the same length, found {} and {} at row {}",
+                    length,
+                    b.value_length(i),
+                    i
+                )));
+            }
+            for j in 0..length as usize {
+                let (x, y) = (a_offset + j, b_offset + j);
+                if a_values.is_null(x) || b_values.is_null(y) {
+                    c.values().append_null()?;
+                } else {
+                    c.values()
+                        .append_value(op(a_values.value(x), b_values.value(y)))?;
+                }
+            }
+            c.append(true)?;
+        }
+        Ok(c.finish())
+    }
     fn arrays_overlap() {}
     fn arrays_zip() {}
 
@@ -650,4 +845,135 @@ mod tests {
     //         assert_eq!(x, d);
     //     }
     // }
+
+    #[test]
+    fn test_array_join() {
+        let mut b = ListBuilder::new(Int32Builder::new(8));
+        b.values().append_slice(&[1, 2, 3]).unwrap();
+        b.append(true).unwrap();
+        b.append(false).unwrap();
+        b.values().append_value(4).unwrap();
+        b.values().append_null().unwrap();
+        b.values().append_value(5).unwrap();
+        b.append(true).unwrap();
+        b.append(true).unwrap();
+        let list_array = b.finish();
+
+        let strings = ArrayFunctions::array_join(&list_array, ",").unwrap();
+        assert_eq!("1,2,3", strings.value(0));
+        assert!(strings.is_null(1));
+        assert_eq!("4,5", strings.value(2));
+        assert_eq!("", strings.value(3));
+    }
+
+    #[test]
+    fn test_array_add_and_subtract() {
+        let mut a = ListBuilder::new(Int64Builder::new(8));
+        a.values().append_slice(&[1, 2, 3]).unwrap();
+        a.append(true).unwrap();
+        a.values().append_value(4).unwrap();
+        a.values().append_null().unwrap();
+        a.append(true).unwrap();
+        a.append(false).unwrap();
+        let a = a.finish();
+        // the lists of b start at different offsets than those of a
+        let mut b = ListBuilder::new(Int64Builder::new(8));
+        b.append(true).unwrap();
+        b.values().append_slice(&[10, 20, 30]).unwrap();
+        b.append(true).unwrap();
+        b.values().append_slice(&[40, 50]).unwrap();
+        b.append(true).unwrap();
+        b.values().append_value(60).unwrap();
+        b.append(true).unwrap();
+        let b = b.finish().slice(1, 3);
+        let b = b.as_any().downcast_ref::<ListArray>().unwrap();
+
+        let sums = ArrayFunctions::array_add::<Int64Type>(&a, b).unwrap();
+        let values = sums.value(0);
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(&[11, 22, 33], values.value_slice(0, 3));
+        let values = sums.value(1);
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(44, values.value(0));
+        assert!(values.is_null(1));
+        assert!(sums.is_null(2));
+
+        let differences = ArrayFunctions::array_subtract::<Int64Type>(b, &a).unwrap();
+        let values = differences.value(0);
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(&[9, 18, 27], values.value_slice(0, 3));
+
+        // lists of different lengths in the same row are an error
+        let c = b.slice(1, 2);
+        let c = c.as_any().downcast_ref::<ListArray>().unwrap();
+        let d = a.slice(0, 2);
+        let d = d.as_any().downcast_ref::<ListArray>().unwrap();
+        assert!(ArrayFunctions::array_add::<Int64Type>(c, d).is_err());
+    }
+
+    #[test]
+    fn test_array_length_sum_and_max() {
+        let mut b = ListBuilder::new(Int64Builder::new(8));
+        b.values().append_slice(&[1, 5, 3]).unwrap();
+        b.append(true).unwrap();
+        b.append(true).unwrap();
+        b.append(false).unwrap();
+        b.values().append_null().unwrap();
+        b.values().append_value(-4).unwrap();
+        b.append(true).unwrap();
+        b.values().append_null().unwrap();
+        b.append(true).unwrap();
+        let list_array = b.finish();
+
+        let lengths = ArrayFunctions::array_length(&list_array).unwrap();
+        assert_eq!(&[3, 0], lengths.value_slice(0, 2));
+        assert!(lengths.is_null(2));
+        assert_eq!(&[2, 1], lengths.value_slice(3, 2));
+
+        // empty lists sum to 0, and null elements are skipped
+        let sums = ArrayFunctions::array_sum::<Int64Type>(&list_array).unwrap();
+        assert_eq!(&[9, 0], sums.value_slice(0, 2));
+        assert!(sums.is_null(2));
+        assert_eq!(&[-4, 0], sums.value_slice(3, 2));
+
+        let maxima = ArrayFunctions::array_max::<Int64Type>(&list_array).unwrap();
+        assert_eq!(5, maxima.value(0));
+        assert!(maxima.is_null(1));
+        assert!(maxima.is_null(2));
+        assert_eq!(-4, maxima.value(3));
+        assert!(maxima.is_null(4));
+    }
+
+    #[test]
+    fn test_array_contains_each() {
+        let mut b = ListBuilder::new(StringBuilder::new(8));
+        b.values().append_value("a").unwrap();
+        b.values().append_value("b").unwrap();
+        b.append(true).unwrap();
+        b.values().append_null().unwrap();
+        b.values().append_value("c").unwrap();
+        b.append(true).unwrap();
+        b.append(false).unwrap();
+        b.values().append_value("a").unwrap();
+        b.append(true).unwrap();
+        let list_array = b.finish();
+        let values = StringArray::from(vec![Some("b"), Some("b"), Some("b"), None]);
+
+        let contains = ArrayFunctions::array_contains_each_string(&list_array, &values).unwrap();
+        assert!(contains.value(0));
+        assert!(!contains.value(1));
+        assert!(contains.is_null(2));
+        assert!(contains.is_null(3));
+
+        let mut b = ListBuilder::new(Int64Builder::new(4));
+        b.values().append_slice(&[1, 2]).unwrap();
+        b.append(true).unwrap();
+        b.values().append_slice(&[3]).unwrap();
+        b.append(true).unwrap();
+        let list_array = b.finish();
+        let values = Int64Array::from(vec![2, 2]);
+        let contains = ArrayFunctions::array_contains_each(&list_array, &values).unwrap();
+        assert!(contains.value(0));
+        assert!(!contains.value(1));
+    }
 }
@@ -0,0 +1,394 @@
+//! Cast functions that extend Arrow's cast kernel with configurable failure handling
+
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::compute;
+use arrow::datatypes::*;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::expression::CastMode;
+
+/// Cast an array to the target type, handling unrepresentable values according to `mode`
+///
+/// Arrow's cast kernel converts values that do not fit the target type to nulls, which is
+/// what `CastMode::Safe` does. `CastMode::Unsafe` returns an error if any non-null value
+/// could not be cast, and `CastMode::Saturating` clamps integers to the target's bounds.
+pub fn cast(array: &ArrayRef, to_type: &DataType, mode: CastMode) -> Result<ArrayRef, ArrowError> {
+    match mode {
+        CastMode::Safe => compute::cast(array, to_type),
+        CastMode::Unsafe => {
+            let casted = compute::cast(array, to_type)?;
+            if casted.null_count() > array.null_count() {
+                Err(ArrowError::ComputeError(format!(
+                    "Unable to cast {} value(s) from {:?} to {:?}",
+                    casted.null_count() - array.null_count(),
+                    array.data_type(),
+                    to_type
+                )))
+            } else {
+                Ok(casted)
+            }
+        }
+        CastMode::Saturating => match (integer_values(array), integer_bounds(to_type)) {
+            (Some(values), Some((min, max))) => {
+                let clamped = values
+                    .into_iter()
+                    .map(|v| v.map(|v| v.max(min).min(max)))
+                    .collect();
+                Ok(integer_array(clamped, to_type))
+            }
+            // only integers are saturated, other types are cast safely
+            _ => compute::cast(array, to_type),
+        },
+    }
+}
+
+/// Cast an array to the target type, parsing strings into temporal types with a chrono `format`
+///
+/// Without a format, or if the cast is not from Utf8 to a date or timestamp, this is the same as
+/// `cast`. Strings that do not match the format are handled according to `mode`, except that
+/// they can't be saturated, so `CastMode::Saturating` converts them to nulls.
+pub fn cast_with_format(
+    array: &ArrayRef,
+    to_type: &DataType,
+    mode: CastMode,
+    format: Option<&str>,
+) -> Result<ArrayRef, ArrowError> {
+    let format = match (format, array.data_type(), to_type) {
+        (
+            Some(format),
+            DataType::Utf8,
+            DataType::Date32(_) | DataType::Date64(_) | DataType::Timestamp(_, _),
+        ) => format,
+        _ => return cast(array, to_type, mode),
+    };
+    let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+    let mut values = Vec::with_capacity(strings.len());
+    for i in 0..strings.len() {
+        if strings.is_null(i) {
+            values.push(None);
+            continue;
+        }
+        let value = strings.value(i);
+        // parsed values are none if they are out of the range of the target type
+        let parsed = match to_type {
+            DataType::Date32(_) => NaiveDate::parse_from_str(value, format).map(|date| {
+                Some(
+                    date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+                        .num_days(),
+                )
+            }),
+            DataType::Date64(_) => NaiveDate::parse_from_str(value, format)
+                .map(|date| Some(date.and_hms(0, 0, 0).timestamp_millis())),
+            DataType::Timestamp(unit, _) => {
+                NaiveDateTime::parse_from_str(value, format).map(|datetime| match unit {
+                    TimeUnit::Second => Some(datetime.timestamp()),
+                    TimeUnit::Millisecond => Some(datetime.timestamp_millis()),
+                    TimeUnit::Microsecond => Some(
+                        datetime.timestamp() * 1_000_000
+                            + datetime.timestamp_subsec_micros() as i64,
+                    ),
+                    // nanosecond timestamps overflow outside about the years 1677 to 2262
+                    TimeUnit::Nanosecond => datetime
+                        .timestamp()
+                        .checked_mul(1_000_000_000)
+                        .and_then(|nanos| {
+                            nanos.checked_add(datetime.timestamp_subsec_nanos() as i64)
+                        }),
+                })
+            }
+            _ => unreachable!(),
+        };
+        match (parsed, mode) {
+            (Ok(Some(parsed)), _) => values.push(Some(parsed)),
+            (Ok(None), CastMode::Unsafe) => {
+                return Err(ArrowError::ComputeError(format!(
+                    "{:?} is out of the range of {:?} values",
+                    value, to_type
+                )))
+            }
+            (Err(e), CastMode::Unsafe) => {
+                return Err(ArrowError::ParseError(format!(
+                    "Unable to parse {:?} with format {:?}: {}",
+                    value, format, e
+                )))
+            }
+            (_, _) => values.push(None),
+        }
+    }
+    // the parsed values are cast from their integer representation to the temporal type
+    let values: ArrayRef = match to_type {
+        DataType::Date32(_) => Arc::new(Int32Array::from(
+            values
+                .into_iter()
+                .map(|v| v.map(|v| v as i32))
+                .collect::<Vec<Option<i32>>>(),
+        )),
+        _ => Arc::new(Int64Array::from(values)),
+    };
+    compute::cast(&values, to_type)
+}
+
+/// Cast every column of a batch to the type of the same-named field in the target schema
+///
+/// The output batch has the target schema's columns, in its order. An error is returned if a
+/// target field is missing from the batch, or if a column cannot be cast to its target type.
+pub fn cast_batch(
+    batch: &RecordBatch,
+    schema: SchemaRef,
+    mode: CastMode,
+) -> Result<RecordBatch, ArrowError> {
+    let batch_schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let index = batch_schema.index_of(field.name())?;
+            let column = batch.column(index);
+            if column.data_type() == field.data_type() {
+                return Ok(column.clone());
+            }
+            cast(column, field.data_type(), mode)
+        })
+        .collect::<Result<Vec<ArrayRef>, ArrowError>>()?;
+    RecordBatch::try_new(schema, columns)
+}
+
+/// Widen the values of an integer array, returning `None` if the array is not an integer array
+fn integer_values(array: &ArrayRef) -> Option<Vec<Option<i128>>> {
+    macro_rules! widen {
+        ($array_type:ty) => {{
+            let array = array.as_any().downcast_ref::<$array_type>().unwrap();
+            Some(
+                (0..array.len())
+                    .map(|i| {
+                        if array.is_null(i) {
+                            None
+                        } else {
+                            Some(array.value(i) as i128)
+                        }
+                    })
+                    .collect(),
+            )
+        }};
+    }
+    match array.data_type() {
+        DataType::Int8 => widen!(Int8Array),
+        DataType::Int16 => widen!(Int16Array),
+        DataType::Int32 => widen!(Int32Array),
+        DataType::Int64 => widen!(Int64Array),
+        DataType::UInt8 => widen!(UInt8Array),
+        DataType::UInt16 => widen!(UInt16Array),
+        DataType::UInt32 => widen!(UInt32Array),
+        DataType::UInt64 => widen!(UInt64Array),
+        _ => None,
+    }
+}
+
+/// The minimum and maximum values of an integer type
+fn integer_bounds(data_type: &DataType) -> Option<(i128, i128)> {
+    match data_type {
+        DataType::Int8 => Some((std::i8::MIN as i128, std::i8::MAX as i128)),
+        DataType::Int16 => Some((std::i16::MIN as i128, std::i16::MAX as i128)),
+        DataType::Int32 => Some((std::i32::MIN as i128, std::i32::MAX as i128)),
+        DataType::Int64 => Some((std::i64::MIN as i128, std::i64::MAX as i128)),
+        DataType::UInt8 => Some((0, std::u8::MAX as i128)),
+        DataType::UInt16 => Some((0, std::u16::MAX as i128)),
+        DataType::UInt32 => Some((0, std::u32::MAX as i128)),
+        DataType::UInt64 => Some((0, std::u64::MAX as i128)),
+        _ => None,
+    }
+}
+
+/// Create an integer array from values that are known to be within the type's bounds
+fn integer_array(values: Vec<Option<i128>>, data_type: &DataType) -> ArrayRef {
+    macro_rules! narrow {
+        ($array_type:ty, $native:ty) => {
+            Arc::new(<$array_type>::from(
+                values
+                    .into_iter()
+                    .map(|v| v.map(|v| v as $native))
+                    .collect::<Vec<Option<$native>>>(),
+            )) as ArrayRef
+        };
+    }
+    match data_type {
+        DataType::Int8 => narrow!(Int8Array, i8),
+        DataType::Int16 => narrow!(Int16Array, i16),
+        DataType::Int32 => narrow!(Int32Array, i32),
+        DataType::Int64 => narrow!(Int64Array, i64),
+        DataType::UInt8 => narrow!(UInt8Array, u8),
+        DataType::UInt16 => narrow!(UInt16Array, u16),
+        DataType::UInt32 => narrow!(UInt32Array, u32),
+        DataType::UInt64 => narrow!(UInt64Array, u64),
+        t => unreachable!("{:?} is not an integer type", t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negative_ints() -> ArrayRef {
+        Arc::new(Int32Array::from(vec![Some(5), Some(-3), None]))
+    }
+
+    #[test]
+    fn test_cast_signed_to_unsigned_safe() {
+        let casted = cast(&negative_ints(), &DataType::UInt32, CastMode::Safe).unwrap();
+        let casted = casted.as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(5, casted.value(0));
+        assert!(casted.is_null(1));
+        assert!(casted.is_null(2));
+    }
+
+    #[test]
+    fn test_cast_signed_to_unsigned_unsafe() {
+        let casted = cast(&negative_ints(), &DataType::UInt32, CastMode::Unsafe);
+        assert!(casted.is_err());
+    }
+
+    #[test]
+    fn test_cast_signed_to_unsigned_saturating() {
+        let casted = cast(&negative_ints(), &DataType::UInt32, CastMode::Saturating).unwrap();
+        let casted = casted.as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(5, casted.value(0));
+        assert_eq!(0, casted.value(1));
+        assert!(casted.is_null(2));
+    }
+
+    #[test]
+    fn test_cast_batch() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])),
+            ],
+        )
+        .unwrap();
+        let target = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let casted = cast_batch(&batch, target.clone(), CastMode::Safe).unwrap();
+        assert_eq!(target, casted.schema());
+        let a = casted
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(1, a.value(0));
+        assert!(a.is_null(1));
+        assert_eq!(3, a.value(2));
+        let b = casted
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!("z", b.value(2));
+
+        // missing columns can't be cast
+        let target = Arc::new(Schema::new(vec![Field::new("c", DataType::Int64, true)]));
+        assert!(cast_batch(&batch, target, CastMode::Safe).is_err());
+    }
+
+    #[test]
+    fn test_cast_list() {
+        let mut builder = ListBuilder::new(Int32Builder::new(4));
+        builder.values().append_slice(&[1, 2]).unwrap();
+        builder.append(true).unwrap();
+        builder.append(false).unwrap();
+        builder.values().append_value(3).unwrap();
+        builder.append(true).unwrap();
+        let array: ArrayRef = Arc::new(builder.finish());
+
+        let to_type = DataType::List(Box::new(DataType::Int64));
+        let casted = cast(&array, &to_type, CastMode::Safe).unwrap();
+        assert_eq!(&to_type, casted.data_type());
+        let casted = casted.as_any().downcast_ref::<ListArray>().unwrap();
+        assert!(casted.is_null(1));
+        let values = casted.value(2);
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(3, values.value(0));
+    }
+
+    #[test]
+    fn test_cast_invalid_string() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec![Some("abc"), Some("7"), None]));
+        let casted = cast(&array, &DataType::Int32, CastMode::Safe).unwrap();
+        let casted = casted.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert!(casted.is_null(0));
+        assert_eq!(7, casted.value(1));
+        assert!(casted.is_null(2));
+        assert!(cast(&array, &DataType::Int32, CastMode::Unsafe).is_err());
+    }
+
+    #[test]
+    fn test_cast_with_format() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("15/01/2020 10:30:00.5"),
+            Some("2020-01-15 10:30:00"),
+            None,
+        ]));
+        let to_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+        let casted = cast_with_format(
+            &array,
+            &to_type,
+            CastMode::Safe,
+            Some("%d/%m/%Y %H:%M:%S%.f"),
+        )
+        .unwrap();
+        assert_eq!(&to_type, casted.data_type());
+        let casted = casted
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        // 2020-01-15T10:30:00.5
+        assert_eq!(1_579_084_200_500_000, casted.value(0));
+        // the second value doesn't match the format
+        assert!(casted.is_null(1));
+        assert!(casted.is_null(2));
+
+        assert!(cast_with_format(
+            &array,
+            &to_type,
+            CastMode::Unsafe,
+            Some("%d/%m/%Y %H:%M:%S%.f")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_cast_with_format_out_of_range() {
+        let array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2020-01-15 10:30:00"),
+            Some("2300-01-01 00:00:00"),
+            Some("1600-01-01 00:00:00"),
+        ]));
+        let to_type = DataType::Timestamp(TimeUnit::Nanosecond, None);
+        let format = Some("%Y-%m-%d %H:%M:%S");
+        // values that nanosecond timestamps can't represent are null, rather than overflowing
+        let casted = cast_with_format(&array, &to_type, CastMode::Safe, format).unwrap();
+        let casted = casted
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        assert_eq!(1_579_084_200_000_000_000, casted.value(0));
+        assert!(casted.is_null(1));
+        assert!(casted.is_null(2));
+        assert!(cast_with_format(&array, &to_type, CastMode::Unsafe, format).is_err());
+
+        // microsecond timestamps can represent them
+        let to_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+        let casted = cast_with_format(&array, &to_type, CastMode::Unsafe, format).unwrap();
+        assert_eq!(0, casted.null_count());
+    }
+}
@@ -0,0 +1,276 @@
+//! Distinct algorithms
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::error::ArrowError;
+
+/// A bloom filter over row keys
+///
+/// A bloom filter can say that a key has definitely not been seen, but can only say that a key
+/// has *probably* been seen. It is thus only used to accelerate exact checks, never to decide them.
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u64,
+}
+
+impl BloomFilter {
+    /// Create a bloom filter sized for the expected number of items, using ~10 bits per item
+    fn with_capacity(expected_items: usize) -> Self {
+        let num_words = (expected_items.max(64) * 10 + 63) / 64;
+        Self {
+            bits: vec![0; num_words],
+            num_hashes: 7,
+        }
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    /// Set the bits of a key, returning whether they were all set already
+    ///
+    /// The key is hashed once, and its bit positions are derived with double hashing. If any bit
+    /// was not set, the key has definitely not been inserted before.
+    fn insert(&mut self, key: &[u8]) -> bool {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // hash the first hash to get an independent second hash
+        h1.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+        let num_bits = self.num_bits();
+        let mut was_set = true;
+        for i in 0..self.num_hashes {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+            was_set &= self.bits[word] & mask != 0;
+            self.bits[word] |= mask;
+        }
+        was_set
+    }
+}
+
+/// Finds the first occurrence of each distinct row across a stream of batches
+///
+/// State is retained between calls to `distinct_indices`, so that rows that were seen
+/// in earlier batches are excluded from later batches. Nulls are considered equal to each other.
+pub struct Distinct {
+    seen: HashSet<Vec<u8>>,
+    bloom: Option<BloomFilter>,
+    /// The number of keys that were looked up in the hash set
+    lookups: usize,
+}
+
+impl Distinct {
+    /// Create a distinct operator that only uses a hash set of row keys
+    pub fn new() -> Self {
+        Self {
+            seen: HashSet::new(),
+            bloom: None,
+            lookups: 0,
+        }
+    }
+
+    /// Create a distinct operator that checks a bloom filter before the hash set.
+    ///
+    /// Keys that the bloom filter has definitely not seen are new without a lookup, and are
+    /// added to the hash set together once the batch is processed. The hash set is only looked
+    /// up for keys that may have been seen, which is rare while the filter is not saturated.
+    pub fn with_bloom_filter(expected_items: usize) -> Self {
+        Self {
+            seen: HashSet::with_capacity(expected_items),
+            bloom: Some(BloomFilter::with_capacity(expected_items)),
+            lookups: 0,
+        }
+    }
+
+    /// The number of keys that have been looked up in the hash set
+    pub fn num_lookups(&self) -> usize {
+        self.lookups
+    }
+
+    /// Return the indices of rows in `arrays` that have not been seen before
+    pub fn distinct_indices(&mut self, arrays: &[ArrayRef]) -> Result<UInt32Array, ArrowError> {
+        let num_rows = arrays.first().map(|array| array.len()).unwrap_or(0);
+        let keys = row_keys(arrays, num_rows)?;
+        let mut indices = Vec::with_capacity(num_rows);
+        // keys that are definitely new, which have not been added to the hash set yet
+        let mut new_keys = vec![];
+        for (i, key) in keys.into_iter().enumerate() {
+            let is_new = match &mut self.bloom {
+                Some(bloom) if !bloom.insert(&key) => {
+                    new_keys.push(key);
+                    true
+                }
+                // the key might have been seen, possibly earlier in this batch, so the hash set
+                // has to decide once it has all the keys that were seen
+                Some(_) => {
+                    self.seen.extend(new_keys.drain(..));
+                    self.lookups += 1;
+                    self.seen.insert(key)
+                }
+                None => {
+                    self.lookups += 1;
+                    self.seen.insert(key)
+                }
+            };
+            if is_new {
+                indices.push(i as u32);
+            }
+        }
+        self.seen.extend(new_keys);
+        Ok(UInt32Array::from(indices))
+    }
+}
+
+impl Default for Distinct {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encode each row of the arrays into bytes that can be hashed and compared
+///
+/// Each value is prefixed with a validity byte so that nulls are distinguishable from values.
+pub(crate) fn row_keys(arrays: &[ArrayRef], num_rows: usize) -> Result<Vec<Vec<u8>>, ArrowError> {
+    let mut keys = vec![vec![]; num_rows];
+    for array in arrays {
+        macro_rules! encode_primitive {
+            ($array_type:ty) => {{
+                let array = array.as_any().downcast_ref::<$array_type>().unwrap();
+                for (i, key) in keys.iter_mut().enumerate() {
+                    if array.is_null(i) {
+                        key.push(0);
+                    } else {
+                        key.push(1);
+                        key.extend_from_slice(array.value(i).to_byte_slice());
+                    }
+                }
+            }};
+        }
+        match array.data_type() {
+            DataType::Boolean => {
+                let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+                for (i, key) in keys.iter_mut().enumerate() {
+                    if array.is_null(i) {
+                        key.push(0);
+                    } else {
+                        key.push(1);
+                        key.push(array.value(i) as u8);
+                    }
+                }
+            }
+            DataType::Int8 => encode_primitive!(Int8Array),
+            DataType::Int16 => encode_primitive!(Int16Array),
+            DataType::Int32 => encode_primitive!(Int32Array),
+            DataType::Int64 => encode_primitive!(Int64Array),
+            DataType::UInt8 => encode_primitive!(UInt8Array),
+            DataType::UInt16 => encode_primitive!(UInt16Array),
+            DataType::UInt32 => encode_primitive!(UInt32Array),
+            DataType::UInt64 => encode_primitive!(UInt64Array),
+            DataType::Float32 => encode_primitive!(Float32Array),
+            DataType::Float64 => encode_primitive!(Float64Array),
+            DataType::Date32(_) => encode_primitive!(Date32Array),
+            DataType::Date64(_) => encode_primitive!(Date64Array),
+            DataType::Timestamp(TimeUnit::Second, _) => encode_primitive!(TimestampSecondArray),
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                encode_primitive!(TimestampMillisecondArray)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                encode_primitive!(TimestampMicrosecondArray)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                encode_primitive!(TimestampNanosecondArray)
+            }
+            DataType::Utf8 => {
+                let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+                for (i, key) in keys.iter_mut().enumerate() {
+                    if array.is_null(i) {
+                        key.push(0);
+                    } else {
+                        let value = array.value(i).as_bytes();
+                        key.push(1);
+                        // prefix the length so that ("ab", "c") and ("a", "bc") differ
+                        key.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                        key.extend_from_slice(value);
+                    }
+                }
+            }
+            t => {
+                return Err(ArrowError::ComputeError(format!(
+                    "Unable to compute row keys for {:?} data type",
+                    t
+                )))
+            }
+        }
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_bloom_distinct_matches_plain_distinct() {
+        let batches: Vec<Vec<ArrayRef>> = (0..2)
+            .map(|batch| {
+                let ints: Vec<i64> = (0..1000).map(|i| (i * (batch + 1)) % 37).collect();
+                let strings: Vec<String> = (0..1000).map(|i| format!("s{}", i % 3)).collect();
+                let strings: Vec<&str> = strings.iter().map(|s| s.as_str()).collect();
+                vec![
+                    Arc::new(Int64Array::from(ints)) as ArrayRef,
+                    Arc::new(StringArray::from(strings)) as ArrayRef,
+                ]
+            })
+            .collect();
+
+        let mut plain = Distinct::new();
+        let mut bloom = Distinct::with_bloom_filter(1024);
+        let mut total = 0;
+        for arrays in &batches {
+            let expected = plain.distinct_indices(arrays).unwrap();
+            let actual = bloom.distinct_indices(arrays).unwrap();
+            assert_eq!(expected.len(), actual.len());
+            for i in 0..expected.len() {
+                assert_eq!(expected.value(i), actual.value(i));
+            }
+            total += actual.len();
+        }
+        // 37 * 3 combinations can be created from the 2 columns
+        assert_eq!(111, total);
+    }
+
+    #[test]
+    fn test_bloom_distinct_lookups() {
+        // mostly distinct values, with every tenth value repeated in the same batch
+        let values: Vec<i64> = (0..10_000)
+            .map(|i| if i % 10 == 9 { i - 1 } else { i })
+            .collect();
+        let arrays = vec![Arc::new(Int64Array::from(values)) as ArrayRef];
+
+        let mut plain = Distinct::new();
+        let mut bloom = Distinct::with_bloom_filter(10_000);
+        let expected = plain.distinct_indices(&arrays).unwrap();
+        let actual = bloom.distinct_indices(&arrays).unwrap();
+        assert_eq!(9_000, expected.len());
+        assert_eq!(expected.len(), actual.len());
+        for i in 0..expected.len() {
+            assert_eq!(expected.value(i), actual.value(i));
+        }
+        // every key is looked up without a bloom filter, while with one only the repeated keys
+        // and the false positives of the filter are
+        assert_eq!(10_000, plain.num_lookups());
+        assert!(bloom.num_lookups() >= 1_000);
+        assert!(
+            bloom.num_lookups() < 1_500,
+            "{} lookups",
+            bloom.num_lookups()
+        );
+    }
+}
@@ -1,14 +1,20 @@
 //! Join algorithms
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use arrow::array::*;
+use arrow::compute;
 use arrow::datatypes::*;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
 use byteorder::{LittleEndian, WriteBytesExt};
 
 use crate::{
     dataframe::DataFrame,
     expression::{JoinCriteria, JoinType},
+    functions::distinct::row_keys,
+    operation::scalar::common_type,
     table::{col_to_prim_arrays, Column},
 };
 
@@ -233,3 +239,303 @@ fn populate_primitive_bytes<T: ArrowPrimitiveType>(
         }
     }
 }
+
+/// Joins two record batches on the equality of their key columns
+///
+/// A hash table is built on the batch with fewer rows, and probed with the other batch.
+/// Rows with a null in any key column never match, but are kept as unmatched rows by outer
+/// joins, with nulls in the other batch's columns. Joined rows are emitted in the order of
+/// the left batch, except for right joins which follow the order of the right batch.
+/// Columns of the right batch whose names clash with the left batch's are suffixed with `_right`.
+pub struct HashJoin {
+    join_type: JoinType,
+    left_on: Vec<String>,
+    right_on: Vec<String>,
+}
+
+impl HashJoin {
+    pub fn new(join_type: JoinType, left_on: Vec<&str>, right_on: Vec<&str>) -> Self {
+        Self {
+            join_type,
+            left_on: left_on.into_iter().map(|s| s.to_owned()).collect(),
+            right_on: right_on.into_iter().map(|s| s.to_owned()).collect(),
+        }
+    }
+
+    pub fn join(&self, left: &RecordBatch, right: &RecordBatch) -> Result<RecordBatch, ArrowError> {
+        let (left_keys, right_keys) = join_keys(left, right, &self.left_on, &self.right_on)?;
+        let matches: Vec<(u32, u32)> = if left.num_rows() <= right.num_rows() {
+            hash_probe(&left_keys, &right_keys)?
+        } else {
+            hash_probe(&right_keys, &left_keys)?
+                .into_iter()
+                .map(|(r, l)| (l, r))
+                .collect()
+        };
+        let mut pairs: Vec<(Option<u32>, Option<u32>)> =
+            matches.iter().map(|(l, r)| (Some(*l), Some(*r))).collect();
+        let keep_left = matches!(self.join_type, JoinType::LeftJoin | JoinType::FullJoin);
+        let keep_right = matches!(self.join_type, JoinType::RightJoin | JoinType::FullJoin);
+        if keep_left {
+            let matched: HashSet<u32> = matches.iter().map(|(l, _)| *l).collect();
+            pairs.extend(
+                (0..left.num_rows() as u32)
+                    .filter(|l| !matched.contains(l))
+                    .map(|l| (Some(l), None)),
+            );
+        }
+        if keep_right {
+            let matched: HashSet<u32> = matches.iter().map(|(_, r)| *r).collect();
+            pairs.extend(
+                (0..right.num_rows() as u32)
+                    .filter(|r| !matched.contains(r))
+                    .map(|r| (None, Some(r))),
+            );
+        }
+        match self.join_type {
+            JoinType::RightJoin => pairs.sort_unstable_by_key(|(l, r)| (*r, *l)),
+            // unmatched right rows of a full join are emitted after the left rows
+            _ => pairs.sort_unstable_by_key(|(l, r)| (l.is_none(), *l, *r)),
+        }
+        let (left_indices, right_indices): (Vec<Option<u32>>, Vec<Option<u32>>) =
+            pairs.into_iter().unzip();
+        take_joined(
+            left,
+            right,
+            &UInt32Array::from(left_indices),
+            &UInt32Array::from(right_indices),
+            keep_right,
+            keep_left,
+        )
+    }
+}
+
+/// Get the key columns of both sides of a join, coercing each pair of keys to a common type
+fn join_keys(
+    left: &RecordBatch,
+    right: &RecordBatch,
+    left_on: &[String],
+    right_on: &[String],
+) -> Result<(Vec<ArrayRef>, Vec<ArrayRef>), ArrowError> {
+    if left_on.is_empty() || left_on.len() != right_on.len() {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "Join requires the same number of left and right keys, got {} and {}",
+            left_on.len(),
+            right_on.len()
+        )));
+    }
+    let mut left_keys = Vec::with_capacity(left_on.len());
+    let mut right_keys = Vec::with_capacity(right_on.len());
+    for (l, r) in left_on.iter().zip(right_on) {
+        let l = left.column(left.schema().index_of(l)?);
+        let r = right.column(right.schema().index_of(r)?);
+        let data_type = common_type(&[l.data_type(), r.data_type()])
+            .map_err(|e| ArrowError::ComputeError(e.to_string()))?;
+        left_keys.push(compute::cast(l, &data_type)?);
+        right_keys.push(compute::cast(r, &data_type)?);
+    }
+    Ok((left_keys, right_keys))
+}
+
+/// Build a hash table on the `build` keys, and return the `(build, probe)` index of each match
+fn hash_probe(build: &[ArrayRef], probe: &[ArrayRef]) -> Result<Vec<(u32, u32)>, ArrowError> {
+    let build_rows = build[0].len();
+    let mut table: HashMap<Vec<u8>, Vec<u32>> = HashMap::with_capacity(build_rows);
+    for (i, key) in row_keys(build, build_rows)?.into_iter().enumerate() {
+        if !has_null_key(build, i) {
+            table.entry(key).or_insert_with(Vec::new).push(i as u32);
+        }
+    }
+    let mut pairs = vec![];
+    for (i, key) in row_keys(probe, probe[0].len())?.into_iter().enumerate() {
+        if has_null_key(probe, i) {
+            continue;
+        }
+        if let Some(matches) = table.get(&key) {
+            pairs.extend(matches.iter().map(|m| (*m, i as u32)));
+        }
+    }
+    Ok(pairs)
+}
+
+fn has_null_key(keys: &[ArrayRef], i: usize) -> bool {
+    keys.iter().any(|key| key.is_null(i))
+}
+
+/// Take the joined rows of both batches, combining their schemas
+///
+/// A side is nullable if its rows can be missing from the join, in which case its indices are null.
+fn take_joined(
+    left: &RecordBatch,
+    right: &RecordBatch,
+    left_indices: &UInt32Array,
+    right_indices: &UInt32Array,
+    left_nullable: bool,
+    right_nullable: bool,
+) -> Result<RecordBatch, ArrowError> {
+    let left_schema = left.schema();
+    let mut fields: Vec<Field> = left_schema
+        .fields()
+        .iter()
+        .map(|f| {
+            Field::new(
+                f.name(),
+                f.data_type().clone(),
+                f.is_nullable() || left_nullable,
+            )
+        })
+        .collect();
+    for field in right.schema().fields() {
+        let name = if left_schema.index_of(field.name()).is_ok() {
+            format!("{}_right", field.name())
+        } else {
+            field.name().clone()
+        };
+        fields.push(Field::new(
+            &name,
+            field.data_type().clone(),
+            field.is_nullable() || right_nullable,
+        ));
+    }
+    let mut columns = Vec::with_capacity(fields.len());
+    for column in left.columns() {
+        columns.push(compute::take(column, left_indices, None)?);
+    }
+    for column in right.columns() {
+        columns.push(compute::take(column, right_indices, None)?);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Batches with a null key on each side, and keys that only exist on one side
+    fn join_batches() -> (RecordBatch, RecordBatch) {
+        let left = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, true),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(1), Some(2), Some(3), None])),
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d"])),
+            ],
+        )
+        .unwrap();
+        // the right key is an Int32, which is coerced to Int64
+        let right = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int32, true),
+                Field::new("value", DataType::Float64, true),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(3),
+                    Some(1),
+                    Some(3),
+                    None,
+                    Some(5),
+                ])),
+                Arc::new(Float64Array::from(vec![30.0, 10.0, 31.0, 0.0, 50.0])),
+            ],
+        )
+        .unwrap();
+        (left, right)
+    }
+
+    #[test]
+    fn test_inner_join_batches() {
+        let (left, right) = join_batches();
+        let joined = HashJoin::new(JoinType::InnerJoin, vec!["id"], vec!["id"])
+            .join(&left, &right)
+            .unwrap();
+        // null keys don't match each other
+        assert_eq!(3, joined.num_rows());
+        let schema = joined.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(vec!["id", "name", "id_right", "value"], names);
+
+        let names = joined
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let values = joined
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!("a", names.value(0));
+        assert_eq!(10.0, values.value(0));
+        assert_eq!("c", names.value(1));
+        assert_eq!(30.0, values.value(1));
+        assert_eq!("c", names.value(2));
+        assert_eq!(31.0, values.value(2));
+    }
+
+    #[test]
+    fn test_left_join_batches() {
+        let (left, right) = join_batches();
+        let joined = HashJoin::new(JoinType::LeftJoin, vec!["id"], vec!["id"])
+            .join(&left, &right)
+            .unwrap();
+        // "b" and the null "d" key have no match, and appear once each
+        assert_eq!(5, joined.num_rows());
+        assert!(joined.schema().field(3).is_nullable());
+        let names = joined
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let ids = joined
+            .column(2)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        let values = joined
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(
+            vec!["a", "b", "c", "c", "d"],
+            (0..5).map(|i| names.value(i)).collect::<Vec<&str>>()
+        );
+        assert_eq!(10.0, values.value(0));
+        assert!(ids.is_null(1));
+        assert!(values.is_null(1));
+        assert!(ids.is_null(4));
+        assert!(values.is_null(4));
+    }
+
+    #[test]
+    fn test_right_join_batches() {
+        let (left, right) = join_batches();
+        let joined = HashJoin::new(JoinType::RightJoin, vec!["id"], vec!["id"])
+            .join(&left, &right)
+            .unwrap();
+        // the right null key and 5 have no match, and appear once each
+        assert_eq!(5, joined.num_rows());
+        assert!(joined.schema().field(1).is_nullable());
+        let names = joined
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let values = joined
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(
+            vec![30.0, 10.0, 31.0, 0.0, 50.0],
+            (0..5).map(|i| values.value(i)).collect::<Vec<f64>>()
+        );
+        assert_eq!("c", names.value(0));
+        assert_eq!("a", names.value(1));
+        assert!(names.is_null(3));
+        assert!(names.is_null(4));
+    }
+}
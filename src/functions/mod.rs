@@ -1,5 +1,7 @@
 pub mod aggregate;
 pub mod array;
+pub mod cast;
+pub mod distinct;
 pub mod join;
 pub mod scalar;
 pub mod window;
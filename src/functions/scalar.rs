@@ -2,11 +2,16 @@ use arrow::array::*;
 use arrow::compute;
 use arrow::datatypes::*;
 use arrow::error::ArrowError;
-use num::{abs, One, Signed, Zero};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use num::{abs, One, PrimInt, Signed, Zero};
 use num_traits::Float;
 use rayon::prelude::*;
+use std::sync::Arc;
 use std::{ops::Add, ops::Div, ops::Mul, ops::Sub};
 
+use crate::expression::{Granularity, NullHandling};
+use crate::functions::distinct::row_keys;
+
 extern crate test;
 
 pub struct ScalarFunctions;
@@ -102,6 +107,131 @@ impl ScalarFunctions {
             .collect()
     }
 
+    /// Add two columns of integer arrays, returning an error if a sum overflows
+    pub fn checked_add<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: num::CheckedAdd,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| checked(a.checked_add(&b), "add", a, b)))
+            .collect()
+    }
+
+    /// Subtract two columns of integer arrays, returning an error if a difference overflows
+    pub fn checked_subtract<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: num::CheckedSub,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| checked(a.checked_sub(&b), "subtract", a, b)))
+            .collect()
+    }
+
+    /// Multiply two columns of integer arrays, returning an error if a product overflows
+    pub fn checked_multiply<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: num::CheckedMul,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| checked(a.checked_mul(&b), "multiply", a, b)))
+            .collect()
+    }
+
+    /// Compute the bitwise AND of two columns of integer arrays
+    pub fn bit_and<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: PrimInt,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(a & b)))
+            .collect()
+    }
+
+    /// Compute the bitwise OR of two columns of integer arrays
+    pub fn bit_or<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: PrimInt,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(a | b)))
+            .collect()
+    }
+
+    /// Compute the bitwise XOR of two columns of integer arrays
+    pub fn bit_xor<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: PrimInt,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(a ^ b)))
+            .collect()
+    }
+
+    /// Shift the values of integer arrays left by the values of the right arrays
+    ///
+    /// Negative shift amounts, or amounts that are not less than the bit width of the type,
+    /// return an error.
+    pub fn shift_left<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: PrimInt + std::fmt::Debug,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(a << shift_amount::<T::Native>(b)?)))
+            .collect()
+    }
+
+    /// Shift the values of integer arrays right by the values of the right arrays
+    ///
+    /// Signed values are shifted arithmetically, so that their sign is preserved.
+    pub fn shift_right<T>(
+        left: Vec<&PrimitiveArray<T>>,
+        right: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: PrimInt + std::fmt::Debug,
+    {
+        left.iter()
+            .zip(right.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(a >> shift_amount::<T::Native>(b)?)))
+            .collect()
+    }
+
     /// Compute the absolute of a numeric array
     pub fn abs<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
@@ -167,6 +297,40 @@ impl ScalarFunctions {
             .map(|a| scalar_op(a, |a| Ok(num::Float::cbrt(a))))
             .collect()
     }
+    /// Choose values from `then` where the condition is true, and from `otherwise` where it is
+    /// false or null
+    ///
+    /// Each input is a column made up of chunks, and all columns must have the same chunk lengths.
+    pub fn case_when(
+        condition: &[ArrayRef],
+        then: &[ArrayRef],
+        otherwise: &[ArrayRef],
+    ) -> Result<Vec<ArrayRef>, ArrowError> {
+        condition
+            .iter()
+            .zip(then.iter().zip(otherwise))
+            .map(|(condition, (then, otherwise))| {
+                let condition = condition
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .ok_or_else(|| {
+                        ArrowError::ComputeError("Condition must be a boolean array".to_string())
+                    })?;
+                if condition.len() != then.len() {
+                    return Err(ArrowError::ComputeError(
+                        "Condition and values must have the same length".to_string(),
+                    ));
+                }
+                select_rows(&[then, otherwise], |i| {
+                    if condition.is_valid(i) && condition.value(i) {
+                        Some(0)
+                    } else {
+                        Some(1)
+                    }
+                })
+            })
+            .collect()
+    }
     pub fn ceil<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
         T: ArrowNumericType,
@@ -177,8 +341,57 @@ impl ScalarFunctions {
             .map(|a| scalar_op(a, |a| Ok(num::Float::ceil(a))))
             .collect()
     }
-    pub fn coalesce() {}
-    pub fn concat() {}
+    /// Return the first non-null value of each row across columns of the same type
+    ///
+    /// Each input is a column made up of chunks, and all columns must have the same chunk lengths.
+    pub fn coalesce(columns: Vec<&[ArrayRef]>) -> Result<Vec<ArrayRef>, ArrowError> {
+        let num_chunks = columns.first().map(|col| col.len()).unwrap_or(0);
+        (0..num_chunks)
+            .map(|chunk| {
+                let arrays: Vec<&ArrayRef> = columns.iter().map(|col| &col[chunk]).collect();
+                select_rows(&arrays, |i| {
+                    arrays.iter().position(|array| array.is_valid(i))
+                })
+            })
+            .collect()
+    }
+    /// Concatenate string columns together
+    ///
+    /// Each input is a column made up of chunks, and all columns must have the same chunk lengths.
+    /// With `NullHandling::Propagate`, if any value in a row is null, the output value is null.
+    /// With `NullHandling::Empty`, nulls are concatenated as empty strings.
+    pub fn concat(
+        columns: Vec<Vec<&StringArray>>,
+        null_handling: NullHandling,
+    ) -> Result<Vec<StringArray>, ArrowError> {
+        let num_chunks = columns.first().map(|col| col.len()).unwrap_or(0);
+        (0..num_chunks)
+            .map(|chunk| {
+                let arrays: Vec<&StringArray> = columns.iter().map(|col| col[chunk]).collect();
+                let len = arrays[0].len();
+                if arrays.iter().any(|array| array.len() != len) {
+                    return Err(ArrowError::ComputeError(
+                        "Cannot concatenate arrays of different length".to_string(),
+                    ));
+                }
+                let mut b = StringBuilder::new(len);
+                for i in 0..len {
+                    let has_null = arrays.iter().any(|array| array.is_null(i));
+                    if has_null && null_handling == NullHandling::Propagate {
+                        b.append(false)?;
+                    } else {
+                        let s: String = arrays
+                            .iter()
+                            .filter(|array| array.is_valid(i))
+                            .map(|array| array.value(i))
+                            .collect();
+                        b.append_value(&s)?;
+                    }
+                }
+                Ok(b.finish())
+            })
+            .collect()
+    }
     pub fn concat_ws() {}
     pub fn conv() {}
     pub fn corr() {}
@@ -208,9 +421,59 @@ impl ScalarFunctions {
     pub fn date_add() {}
     pub fn date_format() {}
     pub fn date_sub() {}
-    pub fn date_trunc() {}
+    /// Truncate timestamps to the start of the year, month, day, etc. that they fall in
+    pub fn date_trunc<T>(
+        array: Vec<&PrimitiveArray<T>>,
+        granularity: Granularity,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowTimestampType + ArrowNumericType + ArrowPrimitiveType<Native = i64>,
+    {
+        let units_per_second: i64 = match T::get_time_unit() {
+            TimeUnit::Second => 1,
+            TimeUnit::Millisecond => 1_000,
+            TimeUnit::Microsecond => 1_000_000,
+            TimeUnit::Nanosecond => 1_000_000_000,
+        };
+        let truncate = |value: i64| -> i64 {
+            let seconds = match granularity {
+                Granularity::Second => 1,
+                Granularity::Minute => 60,
+                Granularity::Hour => 3_600,
+                Granularity::Day => 86_400,
+                Granularity::Month | Granularity::Year => {
+                    let date =
+                        NaiveDateTime::from_timestamp(value.div_euclid(units_per_second), 0).date();
+                    let month = match granularity {
+                        Granularity::Year => 1,
+                        _ => date.month(),
+                    };
+                    return NaiveDate::from_ymd(date.year(), month, 1)
+                        .and_hms(0, 0, 0)
+                        .timestamp()
+                        * units_per_second;
+                }
+            };
+            // rounds towards negative infinity, so that timestamps before 1970 truncate correctly
+            value - value.rem_euclid(seconds * units_per_second)
+        };
+        array
+            .iter()
+            .map(|a| scalar_op(a, |value| Ok(truncate(value))))
+            .collect()
+    }
     pub fn date_diff() {}
-    pub fn day_of_month() {}
+    /// Extract the day of the month (1 to 31) of temporal values
+    pub fn day_of_month<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<Int32Array>, ArrowError>
+    where
+        T: ArrowNumericType + ArrowTemporalType,
+        i64: std::convert::From<T::Native>,
+    {
+        array
+            .iter()
+            .map(|a| date_part_op(a, |dt| dt.day() as i32))
+            .collect()
+    }
     pub fn day_of_week() {}
     pub fn day_of_year() {}
     pub fn degrees<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
@@ -225,12 +488,15 @@ impl ScalarFunctions {
     }
     // sort expression
     pub fn desc() {}
-    pub fn exp<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
+    pub fn exp<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
         T: ArrowNumericType,
-        T::Native: Add<Output = T::Native> + num_traits::Float,
+        T::Native: num_traits::Float,
     {
-        scalar_op(array, |array| Ok(num::Float::exp(array)))
+        array
+            .iter()
+            .map(|a| scalar_op(a, |a| Ok(num::Float::exp(a))))
+            .collect()
     }
 
     // TODO might make sense as a DataFrame function
@@ -263,6 +529,33 @@ impl ScalarFunctions {
     pub fn from_utc_timestamp() {}
     pub fn greatest() {}
     pub fn hash() {}
+    /// Assign each row to one of `num_partitions` partitions by hashing its key columns
+    ///
+    /// Rows with equal keys, including null keys, are assigned to the same partition. Keys are
+    /// hashed with FNV-1a, which does not change between runs or Rust versions, so datasets
+    /// partitioned separately are co-partitioned on their keys.
+    pub fn hash_partition(
+        columns: Vec<&[ArrayRef]>,
+        num_partitions: u32,
+    ) -> Result<Vec<ArrayRef>, ArrowError> {
+        if num_partitions == 0 {
+            return Err(ArrowError::InvalidArgumentError(
+                "Cannot hash partition into 0 partitions".to_string(),
+            ));
+        }
+        let num_chunks = columns.first().map(|col| col.len()).unwrap_or(0);
+        (0..num_chunks)
+            .map(|chunk| {
+                let arrays: Vec<ArrayRef> = columns.iter().map(|col| col[chunk].clone()).collect();
+                let num_rows = arrays.first().map(|array| array.len()).unwrap_or(0);
+                let partitions: Vec<u32> = row_keys(&arrays, num_rows)?
+                    .iter()
+                    .map(|key| (fnv1a(key) % num_partitions as u64) as u32)
+                    .collect();
+                Ok(Arc::new(UInt32Array::from(partitions)) as ArrayRef)
+            })
+            .collect()
+    }
     pub fn hex() {}
     pub fn hour<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<Int32Array>, ArrowError>
     where
@@ -282,6 +575,26 @@ impl ScalarFunctions {
         math_op(a, b, |a, b| Ok(num::Float::hypot(a, b)))
     }
     pub fn initcap() {}
+    /// Return whether each value of the arrays is null, for arrays of any type
+    pub fn is_null(arrays: &[ArrayRef]) -> Result<Vec<BooleanArray>, ArrowError> {
+        Ok(arrays
+            .iter()
+            .map(|array| {
+                let values: Vec<bool> = (0..array.len()).map(|i| array.is_null(i)).collect();
+                BooleanArray::from(values)
+            })
+            .collect())
+    }
+    /// Return whether each value of the arrays is not null, for arrays of any type
+    pub fn is_not_null(arrays: &[ArrayRef]) -> Result<Vec<BooleanArray>, ArrowError> {
+        Ok(arrays
+            .iter()
+            .map(|array| {
+                let values: Vec<bool> = (0..array.len()).map(|i| array.is_valid(i)).collect();
+                BooleanArray::from(values)
+            })
+            .collect())
+    }
     pub fn last_day() {}
     pub fn least() {}
     pub fn length() {}
@@ -298,12 +611,26 @@ impl ScalarFunctions {
     {
         math_op(a, b, |a, b| Ok(num::Float::log(a, b)))
     }
-    pub fn log10<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
+    /// Compute the natural logarithm of values
+    pub fn ln<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
         T: ArrowNumericType,
-        T::Native: Add<Output = T::Native> + num_traits::Float,
+        T::Native: num_traits::Float,
     {
-        scalar_op(array, |array| Ok(num::Float::log10(array)))
+        array
+            .iter()
+            .map(|a| scalar_op(a, |a| Ok(num::Float::ln(a))))
+            .collect()
+    }
+    pub fn log10<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: num_traits::Float,
+    {
+        array
+            .iter()
+            .map(|a| scalar_op(a, |a| Ok(num::Float::log10(a))))
+            .collect()
     }
     pub fn log2<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
     where
@@ -312,6 +639,60 @@ impl ScalarFunctions {
     {
         scalar_op(array, |array| Ok(num::Float::log2(array)))
     }
+    /// Match strings against a SQL `LIKE` pattern, where `%` matches any number of characters
+    /// and `_` matches a single character
+    ///
+    /// Escaping wildcards is not yet supported.
+    pub fn like(
+        arrays: Vec<&StringArray>,
+        pattern: &str,
+        case_insensitive: bool,
+    ) -> Result<Vec<BooleanArray>, ArrowError> {
+        let pattern: Vec<char> = if case_insensitive {
+            pattern.to_lowercase().chars().collect()
+        } else {
+            pattern.chars().collect()
+        };
+        arrays
+            .iter()
+            .map(|array| {
+                let mut b = BooleanBuilder::new(array.len());
+                for i in 0..array.len() {
+                    if array.is_null(i) {
+                        b.append_null()?;
+                    } else {
+                        let value: Vec<char> = if case_insensitive {
+                            array.value(i).to_lowercase().chars().collect()
+                        } else {
+                            array.value(i).chars().collect()
+                        };
+                        b.append_value(like_match(&value, &pattern))?;
+                    }
+                }
+                Ok(b.finish())
+            })
+            .collect()
+    }
+    /// Match strings against a compiled regular expression
+    pub fn regex_match(
+        arrays: Vec<&StringArray>,
+        regex: &regex::Regex,
+    ) -> Result<Vec<BooleanArray>, ArrowError> {
+        arrays
+            .iter()
+            .map(|array| {
+                let mut b = BooleanBuilder::new(array.len());
+                for i in 0..array.len() {
+                    if array.is_null(i) {
+                        b.append_null()?;
+                    } else {
+                        b.append_value(regex.is_match(array.value(i)))?;
+                    }
+                }
+                Ok(b.finish())
+            })
+            .collect()
+    }
     pub fn lower(arrays: Vec<&StringArray>) -> Result<Vec<StringArray>, ArrowError> {
         arrays
             .iter()
@@ -338,20 +719,35 @@ impl ScalarFunctions {
     pub fn md5() {}
     pub fn minute() {}
     fn monotonically_increasing_id() {}
-    fn month() {}
+    /// Extract the month (1 to 12) of temporal values
+    pub fn month<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<Int32Array>, ArrowError>
+    where
+        T: ArrowNumericType + ArrowTemporalType,
+        i64: std::convert::From<T::Native>,
+    {
+        array
+            .iter()
+            .map(|a| date_part_op(a, |dt| dt.month() as i32))
+            .collect()
+    }
     fn months_between() {}
     fn nanv1() {}
     fn next_day() {}
     fn ntile() {}
-    // TODO pow requires usize, we might want to supply 2 arrays instead
-    // fn pow<T>(a: &PrimitiveArray<T>, b: usize) -> Result<PrimitiveArray<T>, ArrowError>
-    // where
-    //     T: ArrowNumericType,
-    //     T::Native: Mul<Output = T::Native>
-    //         + num_traits::Num,
-    // {
-    //     math_op(a, b, |a, b| Ok(num::pow::pow(a, b)))
-    // }
+    /// Raise each value of the `base` column to the power of the `exponent` column
+    pub fn pow<T>(
+        base: Vec<&PrimitiveArray<T>>,
+        exponent: Vec<&PrimitiveArray<T>>,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
+    where
+        T: ArrowNumericType,
+        T::Native: num_traits::Float,
+    {
+        base.iter()
+            .zip(exponent.iter())
+            .map(|(a, b)| math_op(a, b, |a, b| Ok(num::Float::powf(a, b))))
+            .collect()
+    }
     fn quarter() {}
     fn radians<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
     where
@@ -368,15 +764,22 @@ impl ScalarFunctions {
     // this can be a scalar and an array
     fn reverse() {}
     fn rint() {}
-    // TODO Spark's round has a scale, whereas here we scale to 0
-    fn round<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
+    fn rpad() {}
+    /// Round values to a number of decimal places, with halves rounded away from 0
+    pub fn round<T>(
+        array: Vec<&PrimitiveArray<T>>,
+        decimals: i32,
+    ) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
         T: ArrowNumericType,
-        T::Native: Add<Output = T::Native> + num_traits::Float,
+        T::Native: num_traits::Float,
     {
-        scalar_op(array, |array| Ok(num::Float::round(array)))
+        let scale: T::Native = num_traits::cast(10.0f64.powi(decimals)).unwrap();
+        array
+            .iter()
+            .map(|a| scalar_op(a, |a| Ok(num::Float::round(a * scale) / scale)))
+            .collect()
     }
-    fn rpad() {}
     pub fn rtrim(array: Vec<&StringArray>) -> Result<Vec<StringArray>, ArrowError> {
         array
             .iter()
@@ -388,8 +791,6 @@ impl ScalarFunctions {
     fn sequence() {}
     fn sha1() {}
     fn sha2() {}
-    fn shift_left() {}
-    fn shift_right() {}
     fn shift_right_unsigned() {}
     // collection function
     fn shuffle() {}
@@ -417,26 +818,56 @@ impl ScalarFunctions {
     fn sort_array() {}
     fn soundex() {}
     fn split() {}
-    fn sqrt<T>(array: &PrimitiveArray<T>) -> Result<PrimitiveArray<T>, ArrowError>
+    /// Compute the square root of values, which is NaN for negative values
+    pub fn sqrt<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
     where
         T: ArrowNumericType,
-        T::Native: Add<Output = T::Native> + num_traits::Float,
+        T::Native: num_traits::Float,
     {
-        scalar_op(array, |array| Ok(num::Float::sqrt(array)))
+        array
+            .iter()
+            .map(|a| scalar_op(a, |a| Ok(num::Float::sqrt(a))))
+            .collect()
     }
     fn r#struct() {}
-    fn substring(array: &StringArray, pos: usize, len: usize) -> Result<StringArray, ArrowError> {
-        let mut b = StringBuilder::new(array.len());
-        for i in 0..array.len() {
-            let index = i;
-            if array.is_null(i) {
-                b.append(false)?;
-            } else {
-                let s: String = array.value(i).chars().skip(pos).take(len).collect();
-                b.append_value(&s)?
-            }
-        }
-        Ok(b.finish())
+    /// Extract a substring of `length` characters, starting at the 1-based position `start`
+    ///
+    /// Follows Spark's conventions: a `start` of 0 is treated as 1, and a negative `start`
+    /// counts from the end of the string. If `length` is `None`, the rest of the string is taken.
+    pub fn substring(
+        arrays: Vec<&StringArray>,
+        start: i64,
+        length: Option<i64>,
+    ) -> Result<Vec<StringArray>, ArrowError> {
+        arrays
+            .iter()
+            .map(|array| {
+                let mut b = StringBuilder::new(array.len());
+                for i in 0..array.len() {
+                    if array.is_null(i) {
+                        b.append(false)?;
+                    } else {
+                        let value = array.value(i);
+                        let num_chars = value.chars().count() as i64;
+                        let from = match start {
+                            s if s > 0 => s - 1,
+                            s if s < 0 => num_chars + s,
+                            _ => 0,
+                        };
+                        let to = length.map(|len| from + len).unwrap_or(num_chars);
+                        let from = from.max(0).min(num_chars);
+                        let to = to.max(from).min(num_chars);
+                        let s: String = value
+                            .chars()
+                            .skip(from as usize)
+                            .take((to - from) as usize)
+                            .collect();
+                        b.append_value(&s)?
+                    }
+                }
+                Ok(b.finish())
+            })
+            .collect()
     }
     fn substring_index() {}
     pub fn tan<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<PrimitiveArray<T>>, ArrowError>
@@ -491,7 +922,17 @@ impl ScalarFunctions {
     // this will be interesting to implement
     fn when() {}
     fn window() {}
-    fn year() {}
+    /// Extract the year of temporal values
+    pub fn year<T>(array: Vec<&PrimitiveArray<T>>) -> Result<Vec<Int32Array>, ArrowError>
+    where
+        T: ArrowNumericType + ArrowTemporalType,
+        i64: std::convert::From<T::Native>,
+    {
+        array
+            .iter()
+            .map(|a| date_part_op(a, |dt| dt.year()))
+            .collect()
+    }
 }
 
 /// Helper function to perform math lambda function on values from two arrays. If either left or
@@ -522,6 +963,142 @@ where
     Ok(b.finish())
 }
 
+/// Hash bytes with 64-bit FNV-1a
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Return the result of a checked operation, or an overflow error if there is none
+fn checked<T: std::fmt::Debug>(
+    result: Option<T>,
+    op_name: &str,
+    a: T,
+    b: T,
+) -> Result<T, ArrowError> {
+    result.ok_or_else(|| {
+        ArrowError::ComputeError(format!(
+            "Overflow when trying to {} {:?} and {:?}",
+            op_name, a, b
+        ))
+    })
+}
+
+/// Convert a shift amount to a number of bits, if it is within the bit width of the type
+fn shift_amount<T: PrimInt + std::fmt::Debug>(amount: T) -> Result<usize, ArrowError> {
+    let bits = std::mem::size_of::<T>() * 8;
+    match amount.to_usize() {
+        Some(amount) if amount < bits => Ok(amount),
+        _ => Err(ArrowError::ComputeError(format!(
+            "Cannot shift a {}-bit integer by {:?} bits",
+            bits, amount
+        ))),
+    }
+}
+
+/// Match a value against a `LIKE` pattern, backtracking to the last `%` on a mismatch
+fn like_match(value: &[char], pattern: &[char]) -> bool {
+    let (mut v, mut p) = (0, 0);
+    // the pattern position after the last `%`, and the value position it was matched from
+    let mut backtrack: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '_' || pattern[p] == value[v]) {
+            v += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '%' {
+            p += 1;
+            backtrack = Some((p, v));
+        } else if let Some((bp, bv)) = backtrack {
+            // let the last `%` consume one more character
+            p = bp;
+            v = bv + 1;
+            backtrack = Some((bp, bv + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '%')
+}
+
+/// Create an array by choosing each row's value from one of the arrays, which must all have the
+/// same length and type
+///
+/// A row is null if `choose` returns `None`, or if the chosen array's value is null.
+fn select_rows<F>(arrays: &[&ArrayRef], choose: F) -> Result<ArrayRef, ArrowError>
+where
+    F: Fn(usize) -> Option<usize>,
+{
+    let len = arrays[0].len();
+    let data_type = arrays[0].data_type();
+    if arrays
+        .iter()
+        .any(|array| array.len() != len || array.data_type() != data_type)
+    {
+        return Err(ArrowError::ComputeError(
+            "Cannot select values from arrays of different lengths or types".to_string(),
+        ));
+    }
+    macro_rules! select {
+        ($array_type:ty) => {{
+            let arrays: Vec<&$array_type> = arrays
+                .iter()
+                .map(|array| array.as_any().downcast_ref::<$array_type>().unwrap())
+                .collect();
+            let values: Vec<Option<_>> = (0..len)
+                .map(|i| {
+                    choose(i)
+                        .map(|j| arrays[j])
+                        .filter(|array| array.is_valid(i))
+                        .map(|array| array.value(i))
+                })
+                .collect();
+            Arc::new(<$array_type>::from(values)) as ArrayRef
+        }};
+    }
+    let array = match data_type {
+        DataType::Boolean => select!(BooleanArray),
+        DataType::Int8 => select!(Int8Array),
+        DataType::Int16 => select!(Int16Array),
+        DataType::Int32 => select!(Int32Array),
+        DataType::Int64 => select!(Int64Array),
+        DataType::UInt8 => select!(UInt8Array),
+        DataType::UInt16 => select!(UInt16Array),
+        DataType::UInt32 => select!(UInt32Array),
+        DataType::UInt64 => select!(UInt64Array),
+        DataType::Float32 => select!(Float32Array),
+        DataType::Float64 => select!(Float64Array),
+        DataType::Date32(_) => select!(Date32Array),
+        DataType::Date64(_) => select!(Date64Array),
+        DataType::Utf8 => select!(StringArray),
+        t => {
+            return Err(ArrowError::ComputeError(format!(
+                "Selecting values is not supported for {:?} data type",
+                t
+            )))
+        }
+    };
+    Ok(array)
+}
+
+/// Helper function to extract a part of each value of a temporal array
+fn date_part_op<T, F>(array: &PrimitiveArray<T>, op: F) -> Result<Int32Array, ArrowError>
+where
+    T: ArrowNumericType + ArrowTemporalType,
+    i64: std::convert::From<T::Native>,
+    F: Fn(NaiveDateTime) -> i32,
+{
+    let mut b = Int32Builder::new(array.len());
+    for i in 0..array.len() {
+        match array.value_as_datetime(i) {
+            Some(datetime) if array.is_valid(i) => b.append_value(op(datetime))?,
+            _ => b.append_null()?,
+        }
+    }
+    Ok(b.finish())
+}
 fn scalar_op<T, F>(array: &PrimitiveArray<T>, op: F) -> Result<PrimitiveArray<T>, ArrowError>
 where
     T: ArrowNumericType,
@@ -560,6 +1137,7 @@ mod tests {
     use super::*;
     use crate::functions::scalar::test::Bencher;
     use arrow::array::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_primitive_array_abs_f64() {
@@ -721,4 +1299,305 @@ mod tests {
     //         .unwrap()
     //     });
     // }
+
+    #[test]
+    fn test_str_concat() {
+        let a = StringArray::from(vec![Some("Hello"), Some("Arrow"), None]);
+        let b = StringArray::from(vec![Some(" World"), Some("!"), Some("?")]);
+        let c = ScalarFunctions::concat(vec![vec![&a], vec![&b]], NullHandling::Propagate).unwrap();
+        assert_eq!("Hello World", c[0].value(0));
+        assert_eq!("Arrow!", c[0].value(1));
+        assert!(c[0].is_null(2));
+    }
+
+    #[test]
+    fn test_str_concat_null_handling() {
+        let a = StringArray::from(vec![Some("a"), None, None]);
+        let b = StringArray::from(vec![Some("b"), Some("c"), None]);
+        let c = ScalarFunctions::concat(vec![vec![&a], vec![&b]], NullHandling::Propagate).unwrap();
+        assert_eq!("ab", c[0].value(0));
+        assert!(c[0].is_null(1));
+        assert!(c[0].is_null(2));
+        let c = ScalarFunctions::concat(vec![vec![&a], vec![&b]], NullHandling::Empty).unwrap();
+        assert_eq!("ab", c[0].value(0));
+        assert_eq!("c", c[0].value(1));
+        assert!(c[0].is_valid(2));
+        assert_eq!("", c[0].value(2));
+    }
+
+    #[test]
+    fn test_str_substring() {
+        let a = StringArray::from(vec![Some("dataframe"), None, Some("arrow")]);
+        // positive start
+        let c = ScalarFunctions::substring(vec![&a], 5, Some(3)).unwrap();
+        assert_eq!("fra", c[0].value(0));
+        assert!(c[0].is_null(1));
+        assert_eq!("w", c[0].value(2));
+        // negative start counts from the end
+        let c = ScalarFunctions::substring(vec![&a], -5, Some(2)).unwrap();
+        assert_eq!("fr", c[0].value(0));
+        assert_eq!("ar", c[0].value(2));
+        // omitted length takes the rest of the string
+        let c = ScalarFunctions::substring(vec![&a], 5, None).unwrap();
+        assert_eq!("frame", c[0].value(0));
+        assert_eq!("w", c[0].value(2));
+    }
+
+    #[test]
+    fn test_str_like() {
+        let a = StringArray::from(vec![Some("foobar"), Some("bar"), Some("FOO"), None]);
+        let c = ScalarFunctions::like(vec![&a], "foo%", false).unwrap();
+        assert_eq!(true, c[0].value(0));
+        assert_eq!(false, c[0].value(1));
+        assert_eq!(false, c[0].value(2));
+        assert!(c[0].is_null(3));
+        let c = ScalarFunctions::like(vec![&a], "%bar%", false).unwrap();
+        assert_eq!(true, c[0].value(0));
+        assert_eq!(true, c[0].value(1));
+        assert_eq!(false, c[0].value(2));
+        let c = ScalarFunctions::like(vec![&a], "f_o", true).unwrap();
+        assert_eq!(false, c[0].value(0));
+        assert_eq!(false, c[0].value(1));
+        assert_eq!(true, c[0].value(2));
+    }
+
+    #[test]
+    fn test_str_regex_match() {
+        let a = StringArray::from(vec![Some("foo123"), Some("a foo1"), None]);
+        let regex = regex::Regex::new(r"^foo\d+$").unwrap();
+        let c = ScalarFunctions::regex_match(vec![&a], &regex).unwrap();
+        assert_eq!(true, c[0].value(0));
+        assert_eq!(false, c[0].value(1));
+        assert!(c[0].is_null(2));
+    }
+
+    #[test]
+    fn test_is_null() {
+        let a: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), None, Some("c")]));
+        let c = ScalarFunctions::is_null(&[a.clone()]).unwrap();
+        assert_eq!(false, c[0].value(0));
+        assert_eq!(true, c[0].value(1));
+        assert_eq!(0, c[0].null_count());
+        let c = ScalarFunctions::is_not_null(&[a]).unwrap();
+        assert_eq!(true, c[0].value(0));
+        assert_eq!(false, c[0].value(1));
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let a: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![
+            Some(1),
+            None,
+            Some(3),
+            None,
+        ]))];
+        let b: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![
+            Some(10),
+            Some(20),
+            None,
+            None,
+        ]))];
+        let c = ScalarFunctions::coalesce(vec![a.as_slice(), b.as_slice()]).unwrap();
+        let c = c[0].as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(1, c.value(0));
+        assert_eq!(20, c.value(1));
+        assert_eq!(3, c.value(2));
+        assert!(c.is_null(3));
+    }
+
+    #[test]
+    fn test_case_when() {
+        let condition: Vec<ArrayRef> = vec![Arc::new(BooleanArray::from(vec![
+            Some(true),
+            Some(false),
+            None,
+            Some(true),
+        ]))];
+        let a: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![
+            Some(1),
+            Some(2),
+            Some(3),
+            None,
+        ]))];
+        let b: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![10, 20, 30, 40]))];
+        let c = ScalarFunctions::case_when(&condition, &a, &b).unwrap();
+        let c = c[0].as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(1, c.value(0));
+        assert_eq!(20, c.value(1));
+        // a null condition falls through to the else value
+        assert_eq!(30, c.value(2));
+        assert!(c.is_null(3));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let a = Float64Array::from(vec![Some(1.234), Some(-1.5), None, Some(2.675)]);
+        let c = ScalarFunctions::ceil(vec![&a]).unwrap();
+        assert_eq!(2.0, c[0].value(0));
+        assert_eq!(-1.0, c[0].value(1));
+        assert!(c[0].is_null(2));
+        let c = ScalarFunctions::floor(vec![&a]).unwrap();
+        assert_eq!(1.0, c[0].value(0));
+        assert_eq!(-2.0, c[0].value(1));
+        let c = ScalarFunctions::round(vec![&a], 0).unwrap();
+        assert_eq!(1.0, c[0].value(0));
+        // halves are rounded away from 0
+        assert_eq!(-2.0, c[0].value(1));
+        let c = ScalarFunctions::round(vec![&a], 2).unwrap();
+        assert_eq!(1.23, c[0].value(0));
+        assert_eq!(-1.5, c[0].value(1));
+        assert!(c[0].is_null(2));
+        assert_eq!(2.68, c[0].value(3));
+    }
+
+    #[test]
+    fn test_sqrt_and_pow() {
+        let a = Float64Array::from(vec![Some(4.0), Some(-1.0), None, Some(2.25)]);
+        let c = ScalarFunctions::sqrt(vec![&a]).unwrap();
+        assert_eq!(2.0, c[0].value(0));
+        // negative values have no real square root
+        assert!(c[0].value(1).is_nan());
+        assert!(c[0].is_null(2));
+        assert_eq!(1.5, c[0].value(3));
+
+        let b = Float64Array::from(vec![Some(2.0), Some(3.0), Some(1.0), Some(-1.0)]);
+        let c = ScalarFunctions::pow(vec![&a], vec![&b]).unwrap();
+        assert_eq!(16.0, c[0].value(0));
+        assert_eq!(-1.0, c[0].value(1));
+        assert!(c[0].is_null(2));
+        assert_eq!(1.0 / 2.25, c[0].value(3));
+    }
+
+    #[test]
+    fn test_extract_date_parts() {
+        // 2020-03-15 10:30:00, null, 1999-12-31 23:59:59
+        let a = TimestampMillisecondArray::from(vec![
+            Some(1_584_268_200_000),
+            None,
+            Some(946_684_799_000),
+        ]);
+        let years = ScalarFunctions::year(vec![&a]).unwrap();
+        assert_eq!(2020, years[0].value(0));
+        assert!(years[0].is_null(1));
+        assert_eq!(1999, years[0].value(2));
+        let months = ScalarFunctions::month(vec![&a]).unwrap();
+        assert_eq!(3, months[0].value(0));
+        assert!(months[0].is_null(1));
+        assert_eq!(12, months[0].value(2));
+        let days = ScalarFunctions::day_of_month(vec![&a]).unwrap();
+        assert_eq!(15, days[0].value(0));
+        assert_eq!(31, days[0].value(2));
+    }
+
+    #[test]
+    fn test_date_trunc() {
+        // 2020-03-15 10:30:45.5, null, 1969-12-31 23:30:00
+        let a = TimestampMicrosecondArray::from(vec![
+            Some(1_584_268_245_500_000),
+            None,
+            Some(-1_800_000_000),
+        ]);
+        let days = ScalarFunctions::date_trunc(vec![&a], Granularity::Day).unwrap();
+        assert_eq!(1_584_230_400_000_000, days[0].value(0));
+        assert!(days[0].is_null(1));
+        // timestamps before the epoch are truncated to the start of their own day
+        assert_eq!(-86_400_000_000, days[0].value(2));
+        let hours = ScalarFunctions::date_trunc(vec![&a], Granularity::Hour).unwrap();
+        assert_eq!(1_584_266_400_000_000, hours[0].value(0));
+        assert_eq!(-3_600_000_000, hours[0].value(2));
+        let months = ScalarFunctions::date_trunc(vec![&a], Granularity::Month).unwrap();
+        assert_eq!(1_583_020_800_000_000, months[0].value(0));
+        assert_eq!("day".parse::<Granularity>().unwrap(), Granularity::Day);
+        assert!("fortnight".parse::<Granularity>().is_err());
+    }
+
+    #[test]
+    fn test_hash_partition() {
+        use std::collections::HashMap;
+
+        let keys: Vec<ArrayRef> = (0..2)
+            .map(|chunk| {
+                let values: Vec<Option<String>> = (0..100)
+                    .map(|i| match (i + chunk) % 13 {
+                        0 => None,
+                        k => Some(format!("key{}", k)),
+                    })
+                    .collect();
+                let values: Vec<Option<&str>> = values.iter().map(|v| v.as_deref()).collect();
+                Arc::new(StringArray::from(values)) as ArrayRef
+            })
+            .collect();
+        let partitions = ScalarFunctions::hash_partition(vec![&keys], 4).unwrap();
+        assert_eq!(2, partitions.len());
+
+        let mut assigned: HashMap<Option<String>, u32> = HashMap::new();
+        for (keys, partitions) in keys.iter().zip(&partitions) {
+            let keys = keys.as_any().downcast_ref::<StringArray>().unwrap();
+            let partitions = partitions.as_any().downcast_ref::<UInt32Array>().unwrap();
+            for i in 0..keys.len() {
+                let key = if keys.is_null(i) {
+                    None
+                } else {
+                    Some(keys.value(i).to_string())
+                };
+                let partition = partitions.value(i);
+                assert!(partition < 4);
+                // identical keys are always assigned to the same partition
+                assert_eq!(partition, *assigned.entry(key).or_insert(partition));
+            }
+        }
+        assert_eq!(13, assigned.len());
+        assert!(ScalarFunctions::hash_partition(vec![&keys], 0).is_err());
+
+        // partitions are stable, as they do not depend on the standard library's hasher
+        let keys: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(vec![1, 2, 3, 42]))];
+        let partitions = ScalarFunctions::hash_partition(vec![&keys], 16).unwrap();
+        let partitions = partitions[0]
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .unwrap();
+        assert_eq!(vec![13, 14, 15, 6], partitions.value_slice(0, 4).to_vec());
+        assert_eq!(0xaf63_dc4c_8601_ec8c, fnv1a(b"a"));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Int8Array::from(vec![Some(127), None, Some(-128)]);
+        let b = Int8Array::from(vec![Some(1), Some(1), Some(0)]);
+        // wrapping arithmetic overflows silently
+        let sum = &ScalarFunctions::add(vec![&a], vec![&b]).unwrap()[0];
+        assert_eq!(-128, sum.value(0));
+        assert!(sum.is_null(1));
+        // checked arithmetic returns an error
+        assert!(ScalarFunctions::checked_add(vec![&a], vec![&b]).is_err());
+        let c = Int8Array::from(vec![Some(100), None, Some(-100)]);
+        let d = Int8Array::from(vec![Some(20), Some(1), Some(20)]);
+        let sum = &ScalarFunctions::checked_add(vec![&c], vec![&d]).unwrap()[0];
+        assert_eq!(120, sum.value(0));
+        assert!(sum.is_null(1));
+        assert_eq!(-80, sum.value(2));
+        assert!(ScalarFunctions::checked_subtract(vec![&a], vec![&b]).is_ok());
+        assert!(ScalarFunctions::checked_subtract(vec![&a], vec![&d]).is_err());
+        assert!(ScalarFunctions::checked_multiply(vec![&c], vec![&d]).is_err());
+    }
+
+    #[test]
+    fn test_bitwise_functions() {
+        let a = Int32Array::from(vec![Some(12), None, Some(-16)]);
+        let b = Int32Array::from(vec![Some(10), Some(1), Some(2)]);
+        let and = &ScalarFunctions::bit_and(vec![&a], vec![&b]).unwrap()[0];
+        assert_eq!(8, and.value(0));
+        assert!(and.is_null(1));
+        let or = &ScalarFunctions::bit_or(vec![&a], vec![&b]).unwrap()[0];
+        assert_eq!(14, or.value(0));
+        let xor = &ScalarFunctions::bit_xor(vec![&a], vec![&b]).unwrap()[0];
+        assert_eq!(6, xor.value(0));
+        let right = &ScalarFunctions::shift_right(vec![&a], vec![&b]).unwrap()[0];
+        assert_eq!(-4, right.value(2));
+        // shifting by the bit width or a negative amount is an error
+        let c = Int32Array::from(vec![32]);
+        let d = Int32Array::from(vec![-1]);
+        assert!(ScalarFunctions::shift_left(vec![&c], vec![&c]).is_err());
+        assert!(ScalarFunctions::shift_left(vec![&c], vec![&d]).is_err());
+    }
 }
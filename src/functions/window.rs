@@ -1,3 +1,12 @@
+//! Window functions
+
+use std::collections::VecDeque;
+
+use arrow::array::{Array, ArrayRef, Float64Array};
+use arrow::compute;
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+
 struct WindowFunctions;
 
 impl WindowFunctions {
@@ -9,3 +18,85 @@ impl WindowFunctions {
     fn rank() {}
     fn row_number() {}
 }
+
+/// Computes the trailing moving average of a numeric column, across a stream of arrays
+///
+/// The last `window - 1` values are retained between calls to `update`, so that windows
+/// continue across batches. The first rows of the stream have a partial window, and are the
+/// average of the rows seen so far. Nulls are excluded from averages, and a window with only
+/// nulls has a null average.
+pub struct MovingAverage {
+    window: usize,
+    values: VecDeque<Option<f64>>,
+}
+
+impl MovingAverage {
+    pub fn try_new(window: usize) -> Result<Self, ArrowError> {
+        if window == 0 {
+            return Err(ArrowError::InvalidArgumentError(
+                "Moving average window must have at least 1 row".to_string(),
+            ));
+        }
+        Ok(Self {
+            window,
+            values: VecDeque::with_capacity(window),
+        })
+    }
+
+    /// Compute the moving average of each value of the array, which continues from previous arrays
+    pub fn update(&mut self, array: &ArrayRef) -> Result<Float64Array, ArrowError> {
+        let array = compute::cast(array, &DataType::Float64)?;
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        let mut averages = Vec::with_capacity(array.len());
+        for i in 0..array.len() {
+            if self.values.len() == self.window {
+                self.values.pop_front();
+            }
+            self.values.push_back(if array.is_valid(i) {
+                Some(array.value(i))
+            } else {
+                None
+            });
+            let (sum, count) = self
+                .values
+                .iter()
+                .flatten()
+                .fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+            averages.push(if count == 0 {
+                None
+            } else {
+                Some(sum / count as f64)
+            });
+        }
+        Ok(Float64Array::from(averages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_moving_average_across_batches() {
+        let mut moving_average = MovingAverage::try_new(3).unwrap();
+        let first: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(3), Some(4)]));
+        let second: ArrayRef = Arc::new(Int32Array::from(vec![Some(5), None, Some(9)]));
+
+        let averages = moving_average.update(&first).unwrap();
+        // the first 2 rows have partial windows
+        assert_eq!(1.0, averages.value(0));
+        assert_eq!(1.5, averages.value(1));
+        assert_eq!(2.0, averages.value(2));
+        assert_eq!(3.0, averages.value(3));
+
+        // the windows of the second batch include the last rows of the first batch
+        let averages = moving_average.update(&second).unwrap();
+        assert_eq!(4.0, averages.value(0));
+        assert_eq!(4.5, averages.value(1));
+        assert_eq!(7.0, averages.value(2));
+
+        assert!(MovingAverage::try_new(0).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Data sources that read batches without blocking
+//!
+//! Reading from a network source, such as a database, blocks the thread that reads it with
+//! `DataSource`. An `AsyncDataSource` instead yields to the executor while it waits for data.
+//! File sources are read with `DataSource`.
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+
+#[async_trait]
+pub trait AsyncDataSource: Send {
+    fn get_dataset(&self) -> Result<Dataset>;
+    fn source(&self) -> DataSourceType;
+    fn format(&self) -> &str;
+    fn schema(&self) -> SchemaRef;
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>>;
+
+    fn supports_projection(&self) -> bool {
+        false
+    }
+    fn supports_filtering(&self) -> bool {
+        false
+    }
+    fn supports_sorting(&self) -> bool {
+        false
+    }
+    fn supports_limit(&self) -> bool {
+        false
+    }
+
+    async fn limit(&mut self, limit: usize) -> Result<()>;
+    async fn filter(&mut self, filter: BooleanFilter) -> Result<()>;
+    async fn project(&mut self, columns: Vec<String>) -> Result<()>;
+    async fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()>;
+
+    /// Read all remaining batches of the data source
+    async fn collect(&mut self) -> Result<Vec<RecordBatch>> {
+        let mut batches = vec![];
+        while let Some(batch) = self.next_batch().await? {
+            batches.push(batch);
+        }
+        Ok(batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DataFrameError;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    /// A source that yields to the executor before returning each batch, as a network
+    /// source would while it waits for data
+    struct MockAsyncSource {
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+        index: usize,
+    }
+
+    #[async_trait]
+    impl AsyncDataSource for MockAsyncSource {
+        fn get_dataset(&self) -> Result<Dataset> {
+            Ok(Dataset {
+                name: "mock_source".to_owned(),
+                columns: self
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(|f| f.clone().into())
+                    .collect(),
+            })
+        }
+        fn source(&self) -> DataSourceType {
+            DataSourceType::Memory("mock_source".to_owned())
+        }
+        fn format(&self) -> &str {
+            "mock"
+        }
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+        async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+            tokio::task::yield_now().await;
+            let batch = self.batches.get(self.index).cloned();
+            self.index += 1;
+            Ok(batch)
+        }
+        async fn limit(&mut self, _limit: usize) -> Result<()> {
+            Err(DataFrameError::ComputeError("Not supported".to_string()))
+        }
+        async fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+            Err(DataFrameError::ComputeError("Not supported".to_string()))
+        }
+        async fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+            Err(DataFrameError::ComputeError("Not supported".to_string()))
+        }
+        async fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+            Err(DataFrameError::ComputeError("Not supported".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_async_source() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let batch = |ids: Vec<i64>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids))])
+        };
+        let mut source = MockAsyncSource {
+            schema: schema.clone(),
+            batches: vec![batch(vec![1, 2, 3])?, batch(vec![4, 5])?],
+            index: 0,
+        };
+        assert!(!source.supports_limit());
+        let first = source.next_batch().await?.unwrap();
+        assert_eq!(3, first.num_rows());
+        // the remaining batches are read by collecting them
+        let rest = source.collect().await?;
+        assert_eq!(1, rest.len());
+        assert_eq!(2, rest[0].num_rows());
+        assert!(source.next_batch().await?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Reading Avro object container files
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::datatypes::{DataType, DateUnit, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use avro_rs::types::Value;
+use avro_rs::Schema as AvroSchema;
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+
+/// Remove the null variant of a `["null", T]` union, returning `T` and whether it is nullable
+///
+/// Other unions are not supported, as Arrow has no equivalent of a union column here.
+fn non_null_schema(schema: &AvroSchema) -> Result<(&AvroSchema, bool)> {
+    match schema {
+        AvroSchema::Union(union) => {
+            let variants: Vec<&AvroSchema> = union
+                .variants()
+                .iter()
+                .filter(|variant| !matches!(variant, AvroSchema::Null))
+                .collect();
+            match variants.as_slice() {
+                [variant] => Ok((variant, true)),
+                _ => Err(DataFrameError::ComputeError(format!(
+                    "Avro union {:?} is not supported, only unions of null and one type are",
+                    schema
+                ))),
+            }
+        }
+        AvroSchema::Null => Ok((schema, true)),
+        _ => Ok((schema, false)),
+    }
+}
+
+/// The Arrow type that values of an Avro type are read as
+///
+/// Decimals are read as `Float64`, as there are no decimal arrays, and can lose precision.
+fn avro_to_arrow_type(schema: &AvroSchema) -> Result<DataType> {
+    Ok(match schema {
+        AvroSchema::Null => DataType::Null,
+        AvroSchema::Boolean => DataType::Boolean,
+        AvroSchema::Int => DataType::Int32,
+        AvroSchema::Long => DataType::Int64,
+        AvroSchema::Float => DataType::Float32,
+        AvroSchema::Double => DataType::Float64,
+        AvroSchema::Bytes | AvroSchema::Fixed { .. } => DataType::Binary,
+        AvroSchema::String | AvroSchema::Enum { .. } | AvroSchema::Uuid => DataType::Utf8,
+        AvroSchema::Decimal { .. } => DataType::Float64,
+        AvroSchema::Date => DataType::Date32(DateUnit::Day),
+        AvroSchema::TimeMillis => DataType::Time32(TimeUnit::Millisecond),
+        AvroSchema::TimeMicros => DataType::Time64(TimeUnit::Microsecond),
+        AvroSchema::TimestampMillis => DataType::Timestamp(TimeUnit::Millisecond, None),
+        AvroSchema::TimestampMicros => DataType::Timestamp(TimeUnit::Microsecond, None),
+        t => {
+            return Err(DataFrameError::ComputeError(format!(
+                "Avro {:?} type is not supported",
+                t
+            )))
+        }
+    })
+}
+
+/// The schemas of the fields of a record schema
+fn record_fields(schema: &AvroSchema) -> Result<Vec<(String, AvroSchema)>> {
+    match schema {
+        AvroSchema::Record { fields, .. } => Ok(fields
+            .iter()
+            .map(|field| (field.name.clone(), field.schema.clone()))
+            .collect()),
+        t => Err(DataFrameError::ComputeError(format!(
+            "Avro files must contain records to be read, found {:?}",
+            t
+        ))),
+    }
+}
+
+/// Convert the schema of the records of an Avro file to an Arrow schema
+///
+/// Fields that are a union of null and another type are nullable, with the other type.
+pub fn avro_to_arrow_schema(schema: &AvroSchema) -> Result<Schema> {
+    let fields = record_fields(schema)?
+        .iter()
+        .map(|(name, schema)| {
+            let (schema, nullable) = non_null_schema(schema)?;
+            Ok(Field::new(name, avro_to_arrow_type(schema)?, nullable))
+        })
+        .collect::<Result<Vec<Field>>>()?;
+    Ok(Schema::new(fields))
+}
+
+/// Read the Arrow schema of an Avro file, from the Avro schema in its header
+pub fn avro_file_schema(path: &str) -> Result<Schema> {
+    let reader = avro_rs::Reader::new(BufReader::new(File::open(path)?))?;
+    avro_to_arrow_schema(reader.writer_schema())
+}
+
+/// Convert the unscaled two's complement bytes of a decimal to a float
+fn decimal_to_f64(decimal: &avro_rs::Decimal, scale: usize) -> Result<f64> {
+    let bytes = Vec::<u8>::try_from(decimal)?;
+    if bytes.len() > 16 {
+        return Err(DataFrameError::ComputeError(format!(
+            "Avro decimal of {} bytes is too large to read",
+            bytes.len()
+        )));
+    }
+    // sign extend the big-endian bytes to an i128
+    let fill = if bytes.first().map_or(false, |b| b & 0x80 != 0) {
+        0xff
+    } else {
+        0
+    };
+    let mut unscaled = [fill; 16];
+    unscaled[16 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(i128::from_be_bytes(unscaled) as f64 / 10f64.powi(scale as i32))
+}
+
+/// Build an array of values that were read with an Avro schema
+fn values_to_array(
+    schema: &AvroSchema,
+    data_type: &DataType,
+    values: &[Value],
+) -> Result<ArrayRef> {
+    let (schema, _) = non_null_schema(schema)?;
+    let unexpected = |value: &Value| {
+        DataFrameError::ComputeError(format!(
+            "Unexpected Avro value {:?} for {:?} column",
+            value, data_type
+        ))
+    };
+    macro_rules! build {
+        ($builder:ty, $($pattern:pat => $value:expr),+) => {{
+            let mut builder = <$builder>::new(values.len());
+            for value in values {
+                let value = match value {
+                    Value::Union(value) => value.as_ref(),
+                    value => value,
+                };
+                match value {
+                    Value::Null => builder.append_null()?,
+                    $($pattern => builder.append_value($value)?,)+
+                    value => return Err(unexpected(value)),
+                }
+            }
+            Arc::new(builder.finish()) as ArrayRef
+        }};
+    }
+    Ok(match data_type {
+        DataType::Null => Arc::new(NullArray::new(values.len())),
+        DataType::Boolean => build!(BooleanBuilder, Value::Boolean(v) => *v),
+        DataType::Int32 => build!(Int32Builder, Value::Int(v) => *v),
+        DataType::Int64 => build!(Int64Builder, Value::Long(v) => *v),
+        DataType::Float32 => build!(Float32Builder, Value::Float(v) => *v),
+        DataType::Float64 => match schema {
+            AvroSchema::Decimal { scale, .. } => {
+                build!(Float64Builder, Value::Decimal(v) => decimal_to_f64(v, *scale)?)
+            }
+            _ => build!(Float64Builder, Value::Double(v) => *v),
+        },
+        DataType::Binary => build!(
+            BinaryBuilder,
+            Value::Bytes(v) => v.as_slice(),
+            Value::Fixed(_, v) => v.as_slice()
+        ),
+        DataType::Utf8 => build!(
+            StringBuilder,
+            Value::String(v) => v.as_str(),
+            Value::Enum(_, v) => v.as_str(),
+            Value::Uuid(v) => &v.to_string()
+        ),
+        DataType::Date32(_) => build!(Date32Builder, Value::Date(v) => *v),
+        DataType::Time32(_) => build!(Time32MillisecondBuilder, Value::TimeMillis(v) => *v),
+        DataType::Time64(_) => build!(Time64MicrosecondBuilder, Value::TimeMicros(v) => *v),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            build!(TimestampMillisecondBuilder, Value::TimestampMillis(v) => *v)
+        }
+        DataType::Timestamp(_, _) => {
+            build!(TimestampMicrosecondBuilder, Value::TimestampMicros(v) => *v)
+        }
+        t => {
+            return Err(DataFrameError::ComputeError(format!(
+                "Unable to read Avro values as {:?}",
+                t
+            )))
+        }
+    })
+}
+
+/// A data source that reads the records of an Avro file in batches
+pub struct AvroDataSource {
+    path: String,
+    /// The schema of the file
+    schema: SchemaRef,
+    projected_schema: SchemaRef,
+    /// The Avro schemas of the file's fields
+    fields: Vec<AvroSchema>,
+    /// The positions of the projected fields in the records
+    projection: Vec<usize>,
+    reader: avro_rs::Reader<'static, BufReader<File>>,
+    batch_size: usize,
+    limit: Option<usize>,
+    rows_read: usize,
+}
+
+impl AvroDataSource {
+    pub fn try_new(path: &str, batch_size: usize) -> Result<Self> {
+        let reader = avro_rs::Reader::new(BufReader::new(File::open(path)?))?;
+        let schema = Arc::new(avro_to_arrow_schema(reader.writer_schema())?);
+        let fields = record_fields(reader.writer_schema())?
+            .into_iter()
+            .map(|(_, schema)| schema)
+            .collect();
+        Ok(Self {
+            path: path.to_string(),
+            projected_schema: schema.clone(),
+            projection: (0..schema.fields().len()).collect(),
+            schema,
+            fields,
+            reader,
+            batch_size,
+            limit: None,
+            rows_read: 0,
+        })
+    }
+}
+
+impl DataSource for AvroDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: "avro_file_source".to_owned(),
+            columns: self
+                .projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Avro(self.path.clone())
+    }
+    fn format(&self) -> &str {
+        "avro"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let batch_size = match self.limit {
+            Some(limit) => self.batch_size.min(limit.saturating_sub(self.rows_read)),
+            None => self.batch_size,
+        };
+        let mut columns: Vec<Vec<Value>> =
+            vec![Vec::with_capacity(batch_size); self.projection.len()];
+        let mut num_rows = 0;
+        while num_rows < batch_size {
+            let mut fields = match self.reader.next() {
+                Some(record) => match record? {
+                    Value::Record(fields) => fields,
+                    value => {
+                        return Err(DataFrameError::ComputeError(format!(
+                            "Expected an Avro record, found {:?}",
+                            value
+                        )))
+                    }
+                },
+                None => break,
+            };
+            for (column, i) in columns.iter_mut().zip(&self.projection) {
+                column.push(std::mem::replace(&mut fields[*i].1, Value::Null));
+            }
+            num_rows += 1;
+        }
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        self.rows_read += num_rows;
+        let arrays = columns
+            .iter()
+            .zip(&self.projection)
+            .map(|(values, i)| {
+                values_to_array(&self.fields[*i], self.schema.field(*i).data_type(), values)
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(Some(RecordBatch::try_new(
+            self.projected_schema.clone(),
+            arrays,
+        )?))
+    }
+    fn supports_projection(&self) -> bool {
+        true
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Avro data source does not support filtering".to_string(),
+        ))
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        let projection = columns
+            .iter()
+            .map(|name| self.schema.index_of(name))
+            .collect::<arrow::error::Result<Vec<usize>>>()?;
+        // the file is read again from the start with the projection
+        self.reader = avro_rs::Reader::new(BufReader::new(File::open(&self.path)?))?;
+        self.projected_schema = Arc::new(Schema::new(
+            projection
+                .iter()
+                .map(|i| self.schema.field(*i).clone())
+                .collect(),
+        ));
+        self.projection = projection;
+        self.rows_read = 0;
+        Ok(())
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Avro data source does not support sorting".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_avro_file() -> Result<()> {
+        let mut source = AvroDataSource::try_new("./test/data/uk_cities.avro", 2)?;
+        let expected = Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("population", DataType::Int64, true),
+            Field::new("area", DataType::Float64, false),
+            Field::new("founded", DataType::Date32(DateUnit::Day), false),
+            Field::new(
+                "updated",
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                false,
+            ),
+        ]);
+        assert_eq!(&expected, source.schema().as_ref());
+        assert_eq!(expected, avro_file_schema("./test/data/uk_cities.avro")?);
+
+        let batches = source.execute(Default::default())?;
+        assert_eq!(
+            vec![2, 1],
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+        );
+        let population = batches[1]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert!(population.is_null(0));
+        // the area is a decimal with a scale of 2
+        let area = batches[0]
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(vec![1572.1, 115.64], area.value_slice(0, 2).to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_avro_projection_and_limit() -> Result<()> {
+        let mut source = AvroDataSource::try_new("./test/data/uk_cities.avro", 1024)?;
+        source.project(vec!["updated".to_owned(), "name".to_owned()])?;
+        source.limit(2)?;
+        let batch = source.next_batch()?.unwrap();
+        assert_eq!(2, batch.num_rows());
+        let names = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!("Manchester", names.value(1));
+        assert!(source.next_batch()?.is_none());
+        Ok(())
+    }
+}
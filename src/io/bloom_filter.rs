@@ -0,0 +1,380 @@
+//! Reading the bloom filters of Parquet column chunks
+//!
+//! Parquet files can store a split block bloom filter for each column chunk, so that looking up
+//! a value that a row group does not contain can skip the row group, even if the value is within
+//! the range of the column's statistics. The `parquet` crate does not read bloom filters yet, so
+//! their offsets are read from the file's footer with a minimal Thrift compact protocol decoder.
+
+use std::convert::TryInto;
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+
+use twox_hash::XxHash64;
+
+use crate::error::{DataFrameError, Result};
+
+/// The number of bytes that are read to decode a bloom filter's header, which is much smaller
+const MAX_HEADER_SIZE: u64 = 256;
+
+/// The largest bloom filter that is read, which is the largest that Parquet writers create
+const MAX_BLOOM_FILTER_SIZE: usize = 128 * 1024 * 1024;
+
+/// The salts that a value's hash is multiplied by, to select a bit in each word of a block
+const SALT: [u32; 8] = [
+    0x47b6_137b,
+    0x4497_4d91,
+    0x8824_ad5b,
+    0xa2b7_289d,
+    0x7054_95c7,
+    0x2df1_424b,
+    0x9efc_4947,
+    0x5c6b_fb31,
+];
+
+// the types of the Thrift compact protocol
+const STOP: u8 = 0;
+const BOOLEAN_TRUE: u8 = 1;
+const BOOLEAN_FALSE: u8 = 2;
+const BYTE: u8 = 3;
+const I16: u8 = 4;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const DOUBLE: u8 = 7;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const SET: u8 = 10;
+const MAP: u8 = 11;
+const STRUCT: u8 = 12;
+
+/// A split block bloom filter, made of blocks of eight 32-bit words
+#[derive(Debug)]
+pub struct BloomFilter {
+    blocks: Vec<[u32; 8]>,
+}
+
+impl BloomFilter {
+    /// Read the bloom filter at an offset of a Parquet file
+    ///
+    /// Only bloom filters of uncompressed xxHash64 hashes with the split block algorithm are
+    /// read, which is the only kind that the format defines. Other bloom filters return `None`.
+    pub fn read<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<Self>> {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut header = vec![];
+        reader
+            .by_ref()
+            .take(MAX_HEADER_SIZE)
+            .read_to_end(&mut header)?;
+        let mut decoder = CompactDecoder::new(&header);
+        let mut num_bytes = None;
+        let mut supported = true;
+        decoder.read_struct(|decoder, id, field_type| {
+            match (id, field_type) {
+                (1, I32) => num_bytes = Some(decoder.read_int()?),
+                // the algorithm, hash and compression are unions, whose first fields are the
+                // split block algorithm, xxHash64 and no compression
+                (2, STRUCT) | (3, STRUCT) | (4, STRUCT) => decoder.read_struct(|_, id, _| {
+                    supported &= id == 1;
+                    Ok(false)
+                })?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        let num_bytes = match num_bytes {
+            Some(num_bytes) if supported => num_bytes as usize,
+            _ => return Ok(None),
+        };
+        if num_bytes == 0 || num_bytes % 32 != 0 || num_bytes > MAX_BLOOM_FILTER_SIZE {
+            return Ok(None);
+        }
+        reader.seek(SeekFrom::Start(offset + decoder.position as u64))?;
+        let mut bitset = vec![0; num_bytes];
+        reader.read_exact(&mut bitset)?;
+        let blocks = bitset
+            .chunks_exact(32)
+            .map(|block| {
+                let mut words = [0u32; 8];
+                for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+                    *word = u32::from_le_bytes(bytes.try_into().unwrap());
+                }
+                words
+            })
+            .collect();
+        Ok(Some(Self { blocks }))
+    }
+
+    /// Whether a value, given as its plain encoding, may have been inserted into the filter
+    ///
+    /// Values that were inserted always return true, while other values can also return true.
+    pub fn may_contain(&self, value: &[u8]) -> bool {
+        let mut hasher = XxHash64::with_seed(0);
+        hasher.write(value);
+        let hash = hasher.finish();
+        let block = &self.blocks[(((hash >> 32) * self.blocks.len() as u64) >> 32) as usize];
+        let key = hash as u32;
+        SALT.iter()
+            .zip(block.iter())
+            .all(|(salt, word)| word & (1 << (key.wrapping_mul(*salt) >> 27)) != 0)
+    }
+}
+
+/// Read the offsets of the bloom filters of each row group's column chunks from the footer of a
+/// Parquet file
+///
+/// The offsets are in the order of the row groups, and of their columns. Column chunks without
+/// a bloom filter have no offset.
+pub fn bloom_filter_offsets<R: Read + Seek>(reader: &mut R) -> Result<Vec<Vec<Option<u64>>>> {
+    let footer_start = reader.seek(SeekFrom::End(-8))?;
+    let mut footer = [0u8; 8];
+    reader.read_exact(&mut footer)?;
+    if &footer[4..] != b"PAR1" {
+        return Err(invalid("the file does not end with a Parquet footer"));
+    }
+    let metadata_length = u64::from(u32::from_le_bytes(footer[..4].try_into().unwrap()));
+    if metadata_length > footer_start {
+        return Err(invalid("the file metadata is longer than the file"));
+    }
+    reader.seek(SeekFrom::Start(footer_start - metadata_length))?;
+    let mut metadata = vec![0; metadata_length as usize];
+    reader.read_exact(&mut metadata)?;
+
+    let mut offsets = vec![];
+    // FileMetaData.row_groups
+    CompactDecoder::new(&metadata).read_struct(|decoder, id, field_type| {
+        if (id, field_type) != (4, LIST) {
+            return Ok(false);
+        }
+        decoder.read_list(|decoder| {
+            let mut columns = vec![];
+            // RowGroup.columns
+            decoder.read_struct(|decoder, id, field_type| {
+                if (id, field_type) != (1, LIST) {
+                    return Ok(false);
+                }
+                decoder.read_list(|decoder| {
+                    let mut offset = None;
+                    // ColumnChunk.meta_data
+                    decoder.read_struct(|decoder, id, field_type| {
+                        if (id, field_type) != (3, STRUCT) {
+                            return Ok(false);
+                        }
+                        // ColumnMetaData.bloom_filter_offset
+                        decoder.read_struct(|decoder, id, field_type| {
+                            if (id, field_type) != (14, I64) {
+                                return Ok(false);
+                            }
+                            offset = Some(decoder.read_int()? as u64);
+                            Ok(true)
+                        })?;
+                        Ok(true)
+                    })?;
+                    columns.push(offset);
+                    Ok(())
+                })?;
+                Ok(true)
+            })?;
+            offsets.push(columns);
+            Ok(())
+        })?;
+        Ok(true)
+    })?;
+    Ok(offsets)
+}
+
+fn invalid(message: &str) -> DataFrameError {
+    DataFrameError::ParseError(format!("Unable to read Parquet bloom filters: {}", message))
+}
+
+/// A decoder of the Thrift compact protocol, that reads the fields it needs and skips the rest
+struct CompactDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> CompactDecoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or_else(|| invalid("unexpected end of metadata"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn advance(&mut self, length: usize) -> Result<()> {
+        if length > self.data.len() - self.position {
+            return Err(invalid("unexpected end of metadata"));
+        }
+        self.position += length;
+        Ok(())
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if shift >= 64 {
+                return Err(invalid("integer is too long"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read a zigzag encoded `i16`, `i32` or `i64`
+    fn read_int(&mut self) -> Result<i64> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    /// Read the fields of a struct, skipping the fields that `read_field` does not read
+    ///
+    /// `read_field` is called with the id and type of each field, and returns whether it read
+    /// the field's value.
+    fn read_struct<F>(&mut self, mut read_field: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, i16, u8) -> Result<bool>,
+    {
+        let mut last_id = 0;
+        loop {
+            let header = self.read_byte()?;
+            let field_type = header & 0x0f;
+            if field_type == STOP {
+                return Ok(());
+            }
+            // field ids are stored as a delta from the previous field's id, if it is small
+            let id = match header >> 4 {
+                0 => self.read_int()? as i16,
+                delta => last_id + i16::from(delta),
+            };
+            if !read_field(self, id, field_type)? {
+                self.skip(field_type)?;
+            }
+            last_id = id;
+        }
+    }
+
+    /// Read the elements of a list or set, with `read_element`
+    fn read_list<F>(&mut self, mut read_element: F) -> Result<()>
+    where
+        F: FnMut(&mut Self) -> Result<()>,
+    {
+        let (size, _) = self.read_list_header()?;
+        for _ in 0..size {
+            read_element(self)?;
+        }
+        Ok(())
+    }
+
+    fn read_list_header(&mut self) -> Result<(usize, u8)> {
+        let header = self.read_byte()?;
+        let size = match header >> 4 {
+            15 => self.read_varint()? as usize,
+            size => size as usize,
+        };
+        Ok((size, header & 0x0f))
+    }
+
+    /// Skip the value of a struct's field
+    fn skip(&mut self, field_type: u8) -> Result<()> {
+        match field_type {
+            // the values of boolean fields are their types
+            BOOLEAN_TRUE | BOOLEAN_FALSE => Ok(()),
+            BYTE => self.advance(1),
+            I16 | I32 | I64 => self.read_varint().map(|_| ()),
+            DOUBLE => self.advance(8),
+            BINARY => {
+                let length = self.read_varint()? as usize;
+                self.advance(length)
+            }
+            LIST | SET => {
+                let (size, element_type) = self.read_list_header()?;
+                for _ in 0..size {
+                    self.skip_element(element_type)?;
+                }
+                Ok(())
+            }
+            MAP => {
+                let size = self.read_varint()? as usize;
+                if size > 0 {
+                    let types = self.read_byte()?;
+                    for _ in 0..size {
+                        self.skip_element(types >> 4)?;
+                        self.skip_element(types & 0x0f)?;
+                    }
+                }
+                Ok(())
+            }
+            STRUCT => self.read_struct(|_, _, _| Ok(false)),
+            _ => Err(invalid(&format!("unknown Thrift type {}", field_type))),
+        }
+    }
+
+    /// Skip an element of a list, set or map, where booleans are stored as a byte
+    fn skip_element(&mut self, element_type: u8) -> Result<()> {
+        match element_type {
+            BOOLEAN_TRUE | BOOLEAN_FALSE => self.advance(1),
+            _ => self.skip(element_type),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_read_bloom_filters() -> Result<()> {
+        // the file has two row groups, with ids 0, 2, ..., 18 and 20, 22, ..., 38, and names
+        // with a `city` prefix, and a bloom filter for each column chunk
+        let mut file = File::open("./test/data/cities_with_bloom_filters.parquet")?;
+        let offsets = bloom_filter_offsets(&mut file)?;
+        assert_eq!(2, offsets.len());
+        assert!(offsets.iter().all(|columns| columns.len() == 2));
+
+        let read = |file: &mut File, row_group: usize, column: usize| {
+            BloomFilter::read(file, offsets[row_group][column].unwrap())
+                .unwrap()
+                .unwrap()
+        };
+        let ids = read(&mut file, 0, 0);
+        assert!((0..20i64)
+            .step_by(2)
+            .all(|id| ids.may_contain(&id.to_le_bytes())));
+        assert!(!ids.may_contain(&7i64.to_le_bytes()));
+        assert!(!ids.may_contain(&24i64.to_le_bytes()));
+        let names = read(&mut file, 1, 1);
+        assert!(names.may_contain(b"city24"));
+        assert!(!names.may_contain(b"city10"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_unknown_fields() -> Result<()> {
+        // a struct with a list of booleans, a map from a string to a double, and an i32 field
+        // whose id is not a small delta from the previous field's
+        let data = [
+            0x19, 0x21, 0x01, 0x02, 0x1b, 0x01, 0x87, 0x01, b'a', 0, 0, 0, 0, 0, 0, 0xf0, 0x3f,
+            0x05, 0x28, 0x54, 0x00,
+        ];
+        let mut value = None;
+        CompactDecoder::new(&data).read_struct(|decoder, id, _| {
+            if id == 20 {
+                value = Some(decoder.read_int()?);
+                return Ok(true);
+            }
+            Ok(false)
+        })?;
+        assert_eq!(Some(42), value);
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Reading compressed files
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+
+use crate::error::Result;
+use crate::expression::Compression;
+
+impl Compression {
+    /// Detect the compression of a file from its extension, `.gz` for gzip and `.zst` for zstd
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::Uncompressed,
+        }
+    }
+}
+
+/// A file that is decompressed as it is read
+pub enum CompressedFile {
+    Uncompressed(File),
+    Gzip(MultiGzDecoder<File>),
+    Zstd(zstd::Decoder<BufReader<File>>),
+}
+
+impl CompressedFile {
+    /// Open a file with the given compression, or with the compression detected from the
+    /// file's extension if none is given
+    pub fn open(path: &str, compression: Option<Compression>) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(
+            match compression.unwrap_or_else(|| Compression::from_path(path)) {
+                Compression::Uncompressed => CompressedFile::Uncompressed(file),
+                Compression::Gzip => CompressedFile::Gzip(MultiGzDecoder::new(file)),
+                Compression::Zstd => CompressedFile::Zstd(zstd::Decoder::new(file)?),
+            },
+        )
+    }
+}
+
+impl Read for CompressedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedFile::Uncompressed(file) => file.read(buf),
+            CompressedFile::Gzip(decoder) => decoder.read(buf),
+            CompressedFile::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_from_path() {
+        assert_eq!(
+            Compression::Gzip,
+            Compression::from_path("data/cities.csv.gz")
+        );
+        assert_eq!(
+            Compression::Zstd,
+            Compression::from_path("data/cities.csv.zst")
+        );
+        assert_eq!(
+            Compression::Uncompressed,
+            Compression::from_path("data/cities.csv")
+        );
+    }
+}
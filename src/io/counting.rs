@@ -0,0 +1,133 @@
+//! Counting the rows of data sources as they are read
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::Result;
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+
+/// A data source that counts the rows of another source as they are read
+///
+/// Once the source is exhausted, the exact number of rows is reported by `row_count_hint`,
+/// so that the count is known when the data is read again. Until then, the wrapped source's
+/// hint is returned, which is `None` for sources that cannot cheaply count their rows.
+/// Changing the source's limit, filter, projection or sort discards the count.
+pub struct CountingSource<S: DataSource> {
+    source: S,
+    rows_read: usize,
+    is_exhausted: bool,
+}
+
+impl<S: DataSource> CountingSource<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            rows_read: 0,
+            is_exhausted: false,
+        }
+    }
+
+    /// The number of rows that have been read so far
+    pub fn rows_read(&self) -> usize {
+        self.rows_read
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn reset(&mut self) {
+        self.rows_read = 0;
+        self.is_exhausted = false;
+    }
+}
+
+impl<S: DataSource> DataSource for CountingSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        self.source.get_dataset()
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let batch = self.source.next_batch()?;
+        match &batch {
+            Some(batch) => self.rows_read += batch.num_rows(),
+            None => self.is_exhausted = true,
+        }
+        Ok(batch)
+    }
+    fn supports_projection(&self) -> bool {
+        self.source.supports_projection()
+    }
+    fn supports_filtering(&self) -> bool {
+        self.source.supports_filtering()
+    }
+    fn supports_sorting(&self) -> bool {
+        self.source.supports_sorting()
+    }
+    fn supports_limit(&self) -> bool {
+        self.source.supports_limit()
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.reset();
+        self.source.limit(limit)
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        self.reset();
+        self.source.filter(filter)
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        self.reset();
+        self.source.project(columns)
+    }
+    fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+        self.reset();
+        self.source.sort(criteria)
+    }
+    fn row_count_hint(&self) -> Option<usize> {
+        if self.is_exhausted {
+            Some(self.rows_read)
+        } else {
+            self.source.row_count_hint()
+        }
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::{CsvDataSource, CsvSourceOptions};
+
+    #[test]
+    fn test_counting_csv_source() -> Result<()> {
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let mut source = CountingSource::new(source);
+        assert_eq!(None, source.row_count_hint());
+        source.next_batch()?;
+        // the count is not exact until the source is exhausted
+        assert_eq!(10, source.rows_read());
+        assert_eq!(None, source.row_count_hint());
+        while source.next_batch()?.is_some() {}
+        assert_eq!(Some(37), source.row_count_hint());
+
+        // changing the source discards the count
+        source.limit(5)?;
+        assert_eq!(None, source.row_count_hint());
+        Ok(())
+    }
+}
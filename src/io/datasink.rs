@@ -0,0 +1,518 @@
+//! Data sinks that write record batches to external formats
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use serde_json::{Map, Number, Value};
+
+use crate::error::{DataFrameError, Result};
+use crate::io::datasource::{check_cancelled, DataSource};
+
+pub trait DataSink {
+    fn format(&self) -> &str;
+    fn schema(&self) -> SchemaRef;
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()>;
+    /// Flush any buffered output, after which no more batches should be written
+    fn finish(&mut self) -> Result<()>;
+
+    /// Write all remaining batches of a data source to the sink
+    fn write_source(&mut self, source: &mut dyn DataSource) -> Result<()> {
+        self.write_source_with_cancel(source, Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Write all remaining batches of a data source to the sink, until the `cancel` flag is set
+    ///
+    /// The flag is checked between batches. If it is set, the sink is not finished, and a
+    /// `DataFrameError::Cancelled` error is returned.
+    fn write_source_with_cancel(
+        &mut self,
+        source: &mut dyn DataSource,
+        cancel: Arc<AtomicBool>,
+    ) -> Result<()> {
+        loop {
+            check_cancelled(&cancel)?;
+            match source.next_batch()? {
+                Some(batch) => self.write_batch(&batch)?,
+                None => return self.finish(),
+            }
+        }
+    }
+}
+
+/// Check that a batch has the schema that a sink was created with
+pub(crate) fn validate_schema(sink_schema: &Schema, batch: &RecordBatch) -> Result<()> {
+    if sink_schema.fields() != batch.schema().fields() {
+        Err(DataFrameError::ComputeError(format!(
+            "Batch schema {:?} does not match sink schema {:?}",
+            batch.schema(),
+            sink_schema
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes record batches as newline-delimited JSON, with one object per row
+///
+/// Struct columns are written as nested objects, and list columns as arrays. The Arrow branch
+/// that this crate is pinned to only has a JSON reader, so rows are converted to `serde_json`
+/// values instead of being written with an Arrow JSON writer.
+pub struct JsonDataSink<W: Write> {
+    schema: SchemaRef,
+    writer: W,
+}
+
+impl<W: Write> JsonDataSink<W> {
+    pub fn new(writer: W, schema: SchemaRef) -> Self {
+        Self { schema, writer }
+    }
+
+    /// Consume the sink, returning the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> DataSink for JsonDataSink<W> {
+    fn format(&self) -> &str {
+        "json"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        validate_schema(&self.schema, batch)?;
+        for row in 0..batch.num_rows() {
+            let mut object = Map::new();
+            for (field, column) in self.schema.fields().iter().zip(batch.columns()) {
+                object.insert(field.name().clone(), json_value(column, row)?);
+            }
+            serde_json::to_writer(&mut self.writer, &Value::Object(object))
+                .map_err(|e| DataFrameError::IoError(e.to_string()))?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Options for writing CSV files
+///
+/// Formats are `chrono` format strings, and Arrow's defaults are used for any that aren't set.
+pub struct CsvSinkOptions {
+    /// Whether the column names are written as the first line
+    pub has_header: bool,
+    pub delimiter: u8,
+    pub date_format: Option<String>,
+    pub time_format: Option<String>,
+    pub timestamp_format: Option<String>,
+}
+
+impl Default for CsvSinkOptions {
+    fn default() -> Self {
+        Self {
+            has_header: true,
+            delimiter: b',',
+            date_format: None,
+            time_format: None,
+            timestamp_format: None,
+        }
+    }
+}
+
+/// Writes record batches as CSV with Arrow's CSV writer, which writes null values as empty
+/// fields
+pub struct CsvDataSink<W: Write> {
+    schema: SchemaRef,
+    options: CsvSinkOptions,
+    writer: W,
+    header_written: bool,
+}
+
+impl CsvDataSink<File> {
+    /// Create a sink that writes to a new file at `path`, replacing any existing file
+    pub fn try_new(path: &str, schema: SchemaRef, options: CsvSinkOptions) -> Result<Self> {
+        Ok(Self::new(File::create(path)?, schema, options))
+    }
+}
+
+impl<W: Write> CsvDataSink<W> {
+    pub fn new(writer: W, schema: SchemaRef, options: CsvSinkOptions) -> Self {
+        Self {
+            schema,
+            options,
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// Consume the sink, returning the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write the header once, separately from the batches, so that it is also written when
+    /// there are no batches
+    fn write_header(&mut self) -> Result<()> {
+        if self.options.has_header && !self.header_written {
+            let names: Vec<&String> = self.schema.fields().iter().map(|f| f.name()).collect();
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(self.options.delimiter)
+                .from_writer(&mut self.writer);
+            writer.write_record(names)?;
+            writer.flush()?;
+        }
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn batch_writer<T: Write>(&self, writer: T) -> arrow::csv::Writer<T> {
+        let mut builder = arrow::csv::WriterBuilder::new()
+            .has_headers(false)
+            .with_delimiter(self.options.delimiter);
+        if let Some(format) = &self.options.date_format {
+            builder = builder.with_date_format(format.clone());
+        }
+        if let Some(format) = &self.options.time_format {
+            builder = builder.with_time_format(format.clone());
+        }
+        if let Some(format) = &self.options.timestamp_format {
+            builder = builder.with_timestamp_format(format.clone());
+        }
+        builder.build(writer)
+    }
+}
+
+impl<W: Write> DataSink for CsvDataSink<W> {
+    fn format(&self) -> &str {
+        "csv"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        validate_schema(&self.schema, batch)?;
+        self.write_header()?;
+        // the batch is buffered, because Arrow's writer ignores write errors when it is dropped
+        let mut buffer = vec![];
+        self.batch_writer(&mut buffer).write(batch)?;
+        Ok(self.writer.write_all(&buffer)?)
+    }
+    fn finish(&mut self) -> Result<()> {
+        // an empty output still has a header
+        self.write_header()?;
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// The Arrow IPC formats that a sink can write
+enum IpcWriter<W: Write> {
+    File(FileWriter<W>),
+    Stream(StreamWriter<W>),
+}
+
+/// Writes record batches in Arrow's IPC file or stream format, which can be read back without
+/// any conversion
+///
+/// The schema is written when the sink is created.
+pub struct ArrowDataSink<W: Write> {
+    schema: SchemaRef,
+    writer: IpcWriter<W>,
+}
+
+impl ArrowDataSink<File> {
+    /// Create a sink that writes an IPC file to a new file at `path`, replacing any existing file
+    pub fn try_new(path: &str, schema: SchemaRef) -> Result<Self> {
+        Self::try_new_file(File::create(path)?, schema)
+    }
+}
+
+impl<W: Write> ArrowDataSink<W> {
+    /// Create a sink that writes the IPC file format, which supports random access to batches
+    pub fn try_new_file(writer: W, schema: SchemaRef) -> Result<Self> {
+        let writer = IpcWriter::File(FileWriter::try_new(writer, &schema)?);
+        Ok(Self { schema, writer })
+    }
+
+    /// Create a sink that writes the IPC stream format, which can be read as it is written,
+    /// e.g. from a socket or pipe
+    pub fn try_new_stream(writer: W, schema: SchemaRef) -> Result<Self> {
+        let writer = IpcWriter::Stream(StreamWriter::try_new(writer, &schema)?);
+        Ok(Self { schema, writer })
+    }
+}
+
+impl<W: Write> DataSink for ArrowDataSink<W> {
+    fn format(&self) -> &str {
+        "arrow"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        validate_schema(&self.schema, batch)?;
+        match &mut self.writer {
+            IpcWriter::File(writer) => writer.write(batch)?,
+            IpcWriter::Stream(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+    fn finish(&mut self) -> Result<()> {
+        match &mut self.writer {
+            IpcWriter::File(writer) => writer.finish()?,
+            IpcWriter::Stream(writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+/// Convert the value at an index of an array into JSON
+fn json_value(array: &ArrayRef, i: usize) -> Result<Value> {
+    if array.is_null(i) {
+        return Ok(Value::Null);
+    }
+    macro_rules! integer {
+        ($array_type:ty) => {{
+            let array = array.as_any().downcast_ref::<$array_type>().unwrap();
+            Value::from(array.value(i))
+        }};
+    }
+    macro_rules! float {
+        ($array_type:ty) => {{
+            let array = array.as_any().downcast_ref::<$array_type>().unwrap();
+            // NaN and infinite values are not representable in JSON
+            Number::from_f64(array.value(i) as f64)
+                .map(Value::Number)
+                .unwrap_or(Value::Null)
+        }};
+    }
+    let value = match array.data_type() {
+        DataType::Boolean => {
+            let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+            Value::Bool(array.value(i))
+        }
+        DataType::Int8 => integer!(Int8Array),
+        DataType::Int16 => integer!(Int16Array),
+        DataType::Int32 => integer!(Int32Array),
+        DataType::Int64 => integer!(Int64Array),
+        DataType::UInt8 => integer!(UInt8Array),
+        DataType::UInt16 => integer!(UInt16Array),
+        DataType::UInt32 => integer!(UInt32Array),
+        DataType::UInt64 => integer!(UInt64Array),
+        DataType::Float32 => float!(Float32Array),
+        DataType::Float64 => float!(Float64Array),
+        DataType::Date32(_) => integer!(Date32Array),
+        DataType::Date64(_) => integer!(Date64Array),
+        DataType::Timestamp(TimeUnit::Second, _) => integer!(TimestampSecondArray),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => integer!(TimestampMillisecondArray),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => integer!(TimestampMicrosecondArray),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => integer!(TimestampNanosecondArray),
+        DataType::Utf8 => {
+            let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+            Value::String(array.value(i).to_string())
+        }
+        DataType::List(_) => {
+            let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+            let values = array.value(i);
+            Value::Array(
+                (0..values.len())
+                    .map(|j| json_value(&values, j))
+                    .collect::<Result<Vec<Value>>>()?,
+            )
+        }
+        DataType::Struct(fields) => {
+            let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+            let mut object = Map::new();
+            for (field, column) in fields.iter().zip(array.columns()) {
+                object.insert(field.name().clone(), json_value(column, i)?);
+            }
+            Value::Object(object)
+        }
+        t => {
+            return Err(DataFrameError::ComputeError(format!(
+                "Writing {:?} data type to JSON is not yet supported",
+                t
+            )))
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_write_nested_json() -> Result<()> {
+        let location = StructArray::from(vec![
+            (
+                Field::new("lat", DataType::Float64, true),
+                Arc::new(Float64Array::from(vec![57.65, 53.0])) as ArrayRef,
+            ),
+            (
+                Field::new("lng", DataType::Float64, true),
+                Arc::new(Float64Array::from(vec![-3.34, -2.18])) as ArrayRef,
+            ),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("city", DataType::Utf8, true),
+            Field::new("location", location.data_type().clone(), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![Some("Elgin"), None])),
+                Arc::new(location),
+            ],
+        )?;
+
+        let mut sink = JsonDataSink::new(vec![], schema);
+        sink.write_batch(&batch)?;
+        sink.finish()?;
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(2, lines.len());
+
+        let first: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!("Elgin", first["city"]);
+        assert_eq!(57.65, first["location"]["lat"]);
+        assert_eq!(-3.34, first["location"]["lng"]);
+        let second: Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(Value::Null, second["city"]);
+        assert_eq!(53.0, second["location"]["lat"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_json_schema_mismatch() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("b", DataType::Int64, true)])),
+            vec![Arc::new(Int64Array::from(vec![1, 2]))],
+        )
+        .unwrap();
+        let mut sink = JsonDataSink::new(vec![], schema);
+        assert!(sink.write_batch(&batch).is_err());
+    }
+
+    #[test]
+    fn test_write_csv() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("city", DataType::Utf8, true),
+            Field::new("population", DataType::Int64, true),
+            Field::new("founded", DataType::Date32(DateUnit::Day), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![
+                    Some("Elgin"),
+                    None,
+                    Some("Leeds; UK"),
+                ])),
+                Arc::new(Int64Array::from(vec![Some(23_128), Some(10), None])),
+                Arc::new(Date32Array::from(vec![Some(18_262), None, Some(0)])),
+            ],
+        )?;
+
+        let options = CsvSinkOptions {
+            delimiter: b';',
+            date_format: Some("%d/%m/%Y".to_string()),
+            ..Default::default()
+        };
+        let mut sink = CsvDataSink::new(Cursor::new(vec![]), schema.clone(), options);
+        sink.write_batch(&batch)?;
+        sink.finish()?;
+        let output = String::from_utf8(sink.into_inner().into_inner()).unwrap();
+        // values that contain the delimiter are quoted
+        assert_eq!(
+            "city;population;founded\nElgin;23128;01/01/2020\n;10;\n\"Leeds; UK\";;01/01/1970\n",
+            output
+        );
+
+        let options = CsvSinkOptions {
+            has_header: false,
+            ..Default::default()
+        };
+        let mut sink = CsvDataSink::new(Cursor::new(vec![]), schema, options);
+        sink.write_batch(&batch)?;
+        sink.finish()?;
+        let output = String::from_utf8(sink.into_inner().into_inner()).unwrap();
+        assert_eq!(
+            "Elgin,23128,2020-01-01\n,10,\nLeeds; UK,,1970-01-01\n",
+            output
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_empty_csv() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("city", DataType::Utf8, true),
+            Field::new("population", DataType::Int64, true),
+        ]));
+        let mut sink = CsvDataSink::new(vec![], schema, CsvSinkOptions::default());
+        sink.finish()?;
+        assert_eq!(
+            "city,population\n",
+            String::from_utf8(sink.into_inner()).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_arrow() -> Result<()> {
+        use crate::io::datasource::{ArrowDataSource, DataSource};
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = |ids: Vec<i64>, names: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(ids)),
+                    Arc::new(StringArray::from(names)),
+                ],
+            )
+        };
+        let path = "target/arrow_sink.arrow";
+        let mut sink = ArrowDataSink::try_new(path, schema.clone())?;
+        assert_eq!("arrow", sink.format());
+        sink.write_batch(&batch(vec![1, 2], vec![Some("a"), None])?)?;
+        sink.write_batch(&batch(vec![3], vec![Some("c")])?)?;
+        // batches with a different schema are rejected
+        let other = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![4]))],
+        )?;
+        assert!(sink.write_batch(&other).is_err());
+        sink.finish()?;
+
+        let mut source = ArrowDataSource::try_new(path)?;
+        assert_eq!(schema, source.schema());
+        let first = source.next_batch()?.unwrap();
+        assert_eq!(2, first.num_rows());
+        let names = first
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!("a", names.value(0));
+        assert!(names.is_null(1));
+        assert_eq!(1, source.next_batch()?.unwrap().num_rows());
+        assert!(source.next_batch()?.is_none());
+        Ok(())
+    }
+}
@@ -1,16 +1,36 @@
 //! Data source evaluators and readers
 
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
-use std::{io::Read, rc::Rc};
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use arrow::array::{Array, ArrayRef, Date32Array, StringArray, TimestampMillisecondArray};
 use arrow::csv::{Reader as CsvReader, ReaderBuilder as CsvBuilder};
+use arrow::datatypes::{DataType, DateUnit, Field, Schema, TimeUnit};
+use arrow::ipc::reader::StreamReader as ArrowStreamReader;
 use arrow::{datatypes::SchemaRef, ipc::reader::FileReader as ArrowFileReader, record_batch::RecordBatch};
+use chrono::{NaiveDate, NaiveDateTime};
 use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
 use parquet::file::reader::SerializedFileReader;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 
 use crate::error::{DataFrameError, Result};
-use crate::expression::{DataSourceType, Dataset, Reader, SqlDatabase, SortCriteria, BooleanFilter};
+use crate::expression::{
+    BooleanFilter, Compression, CsvReadOptions, DataSourceType, Dataset, Reader, SortCriteria,
+    SqlDatabase,
+};
+use crate::io::avro::avro_file_schema;
+use crate::io::compression::CompressedFile;
+use crate::io::memory::{batch_size_for, MemoryTracker};
+use crate::io::null_values::NullValuesReader;
+use crate::io::parquet::null_array;
+use crate::io::rows::Rows;
 use crate::io::sql::postgres;
+use crate::io::sql::sqlite;
 use crate::io::sql::SqlDataSource;
 
 pub trait DataSourceEval {
@@ -23,18 +43,19 @@ impl DataSourceEval for Reader {
         use SqlDatabase::*;
         match &self.source {
             Csv(path, options) => {
-                let mut builder = CsvBuilder::new()
-                    .has_header(options.has_headers)
-                    .infer_schema(options.max_records)
-                    .with_batch_size(options.batch_size)
-                    .with_delimiter(options.delimiter.unwrap_or(b','));
-                if let Some(projection) = options.projection.clone() {
-                    builder = builder.with_projection(projection);
-                }
-                // TODO set schema if user has set one
-                let file = File::open(&path)?;
-                let csv_reader = builder.build(file)?;
-                let schema = csv_reader.schema();
+                let schema = match options.schema.clone() {
+                    // an explicit schema is used as is, without inferring types from the data
+                    Some(schema) => schema,
+                    None => infer_csv_file_schema(
+                        &path,
+                        options.compression,
+                        options.has_headers,
+                        options.delimiter.unwrap_or(b','),
+                        &options.null_values,
+                        options.max_records,
+                    )?,
+                };
+                let schema = project_schema(&schema, &options.projection);
                 Ok(Dataset {
                     name: "csv_source".to_owned(),
                     columns: schema.fields().iter().map(|f| f.clone().into()).collect(),
@@ -52,6 +73,29 @@ impl DataSourceEval for Reader {
                     columns: schema.fields().iter().map(|f| f.clone().into()).collect(),
                 })
             }
+            Avro(path) => Ok(Dataset {
+                name: "avro_file_source".to_owned(),
+                columns: avro_file_schema(path)?
+                    .fields()
+                    .iter()
+                    .map(|f| f.clone().into())
+                    .collect(),
+            }),
+            Flight(address, _) => Err(DataFrameError::UnsupportedOperation {
+                op: "read".to_owned(),
+                reason: format!(
+                    "Flight service {} can only be read without blocking, with a FlightDataSource",
+                    address
+                ),
+            }),
+            Memory(name) => Err(DataFrameError::ComputeError(format!(
+                "In-memory data source {} cannot be read by name",
+                name
+            ))),
+            CsvStream(name) => Err(DataFrameError::ComputeError(format!(
+                "CSV stream data source {} cannot be read by name",
+                name
+            ))),
             Arrow(path) => {
                 let file = File::open(&path)?;
                 let reader = ArrowFileReader::try_new(file)?;
@@ -65,6 +109,20 @@ impl DataSourceEval for Reader {
                         .collect(),
                 })
             }
+            ArrowStream(path) => {
+                // the schema is the stream's first message
+                let file = File::open(&path)?;
+                let reader = ArrowStreamReader::try_new(file)?;
+                Ok(Dataset {
+                    name: "ipc_stream_source".to_owned(),
+                    columns: reader
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|f| f.clone().into())
+                        .collect(),
+                })
+            }
             Sql(table, options) => match options.db {
                 Postgres => Ok(Dataset {
                     name: table.clone(),
@@ -77,6 +135,17 @@ impl DataSourceEval for Reader {
                     .map(|f| f.clone().into())
                     .collect(),
                 }),
+                Sqlite => Ok(Dataset {
+                    name: table.clone(),
+                    columns: sqlite::Sqlite::get_table_schema(
+                        options.connection_string.as_str(),
+                        table.as_str(),
+                    )?
+                    .fields()
+                    .iter()
+                    .map(|f| f.clone().into())
+                    .collect(),
+                }),
                 MsSql => unimplemented!("MSSQL data source not yet implemented"),
                 MySql => unimplemented!("MySQL data source not yet implemented"),
             },
@@ -84,6 +153,285 @@ impl DataSourceEval for Reader {
     }
 }
 
+/// The number of records that are sampled when automatically configuring a CSV source
+const CSV_SAMPLE_RECORDS: usize = 1000;
+
+/// The date formats that are recognised when inferring the types of CSV columns
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y"];
+
+/// The ISO 8601 timestamp formats that are recognised when inferring the types of CSV columns.
+/// Fractional seconds are optional.
+const TIMESTAMP_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// The format that `Date32` columns are parsed with if no format was detected or configured
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The format that `Timestamp` columns are parsed with if no format was detected or configured
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Tracks which date and timestamp formats all of a column's sampled values can be parsed with
+struct DateFormatCandidates {
+    dates: Vec<bool>,
+    timestamps: Vec<bool>,
+}
+
+impl DateFormatCandidates {
+    fn new() -> Self {
+        Self {
+            dates: vec![true; DATE_FORMATS.len()],
+            timestamps: vec![true; TIMESTAMP_FORMATS.len()],
+        }
+    }
+
+    fn update(&mut self, value: &str) {
+        for (format, candidate) in DATE_FORMATS.iter().zip(self.dates.iter_mut()) {
+            *candidate &= NaiveDate::parse_from_str(value, format).is_ok();
+        }
+        for (format, candidate) in TIMESTAMP_FORMATS.iter().zip(self.timestamps.iter_mut()) {
+            *candidate &= NaiveDateTime::parse_from_str(value, format).is_ok();
+        }
+    }
+
+    /// The type and format of the column, if all its values were parsed with the same format
+    fn detected(&self) -> Option<(DataType, &'static str)> {
+        if let Some(i) = self.dates.iter().position(|c| *c) {
+            return Some((DataType::Date32(DateUnit::Day), DATE_FORMATS[i]));
+        }
+        self.timestamps.iter().position(|c| *c).map(|i| {
+            (
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                TIMESTAMP_FORMATS[i],
+            )
+        })
+    }
+}
+
+impl Reader {
+    /// Create a CSV data source, detecting the file's delimiter and schema from a sample of records
+    ///
+    /// In addition to numbers and strings, booleans, dates and timestamps are recognised.
+    /// See `CsvSourceOptions::infer_date_formats` for the date formats that are detected.
+    pub fn csv_auto(path: &str) -> Result<CsvDataSource<CompressedFile>> {
+        let delimiter = detect_csv_delimiter(path)?;
+        let (schema, date_formats) = infer_csv_sample(path, delimiter)?;
+        CsvDataSource::try_new(
+            path,
+            CsvSourceOptions {
+                read_schema: Some(Arc::new(schema)),
+                delimiter: Some(delimiter),
+                date_formats,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+/// Infer the schema of a CSV file from up to `max_records` records, or all of its records
+///
+/// The Arrow CSV reader can only infer the schema of a seekable input. Uncompressed files without
+/// null value tokens are read directly, while the records of other files are read into memory,
+/// with their null values replaced, before inferring their schema.
+fn infer_csv_file_schema(
+    path: &str,
+    compression: Option<Compression>,
+    has_header: bool,
+    delimiter: u8,
+    null_values: &[String],
+    max_records: Option<usize>,
+) -> Result<SchemaRef> {
+    let builder = CsvBuilder::new()
+        .has_header(has_header)
+        .with_delimiter(delimiter)
+        .infer_schema(max_records);
+    match CompressedFile::open(path, compression)? {
+        CompressedFile::Uncompressed(file) if null_values.is_empty() => {
+            Ok(builder.build(file)?.schema())
+        }
+        input => {
+            let sample = read_csv_sample(&mut BufReader::new(input), has_header, max_records)?;
+            infer_csv_sample_schema(&sample, has_header, delimiter, null_values)
+        }
+    }
+}
+
+/// Infer the schema of a sample of CSV records that has been read into memory
+fn infer_csv_sample_schema(
+    sample: &[u8],
+    has_header: bool,
+    delimiter: u8,
+    null_values: &[String],
+) -> Result<SchemaRef> {
+    let mut replaced = vec![];
+    NullValuesReader::new(sample, null_values, has_header, delimiter).read_to_end(&mut replaced)?;
+    let reader = CsvBuilder::new()
+        .has_header(has_header)
+        .with_delimiter(delimiter)
+        .infer_schema(None)
+        .build(Cursor::new(replaced))?;
+    Ok(reader.schema())
+}
+
+/// Read the header and up to `max_records` records of CSV data, or all of its records, returning
+/// their bytes
+///
+/// Records end at line endings that are not within quoted fields.
+fn read_csv_sample<R: BufRead>(
+    input: &mut R,
+    has_header: bool,
+    max_records: Option<usize>,
+) -> Result<Vec<u8>> {
+    let max_records = max_records.map(|max| max + has_header as usize);
+    let mut sample = vec![];
+    let mut num_records = 0;
+    let mut in_quotes = false;
+    while max_records.map_or(true, |max| num_records < max) {
+        let start = sample.len();
+        if input.read_until(b'\n', &mut sample)? == 0 {
+            break;
+        }
+        // doubled quotes within quoted fields toggle the state twice, leaving it unchanged
+        let quotes = sample[start..].iter().filter(|b| **b == b'"').count();
+        in_quotes ^= quotes % 2 == 1;
+        if !in_quotes {
+            num_records += 1;
+        }
+    }
+    Ok(sample)
+}
+
+/// Find the delimiter that splits the sampled records into the same, and most, number of fields
+fn detect_csv_delimiter(path: &str) -> Result<u8> {
+    let mut detected = (b',', 0);
+    for delimiter in &[b',', b'\t', b';', b'|'] {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(*delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(CompressedFile::open(path, None)?);
+        let mut field_counts = HashSet::new();
+        for record in reader.records().take(CSV_SAMPLE_RECORDS) {
+            field_counts.insert(record?.len());
+        }
+        if field_counts.len() == 1 {
+            let count = *field_counts.iter().next().unwrap();
+            if count > detected.1 {
+                detected = (*delimiter, count);
+            }
+        }
+    }
+    Ok(detected.0)
+}
+
+/// Infer the schema of a CSV file from a sample of its records, with the formats of its date
+/// and timestamp columns
+fn infer_csv_sample(path: &str, delimiter: u8) -> Result<(Schema, HashMap<String, String>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(true)
+        .from_reader(CompressedFile::open(path, None)?);
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    // whether each column's non-empty values can all be parsed as a boolean, int and float
+    let mut candidates = vec![[true; 3]; headers.len()];
+    let mut date_candidates: Vec<DateFormatCandidates> = headers
+        .iter()
+        .map(|_| DateFormatCandidates::new())
+        .collect();
+    let mut has_values = vec![false; headers.len()];
+    let mut nullable = vec![false; headers.len()];
+    for record in reader.records().take(CSV_SAMPLE_RECORDS) {
+        for (i, value) in record?.iter().enumerate().take(headers.len()) {
+            if value.is_empty() {
+                nullable[i] = true;
+                continue;
+            }
+            has_values[i] = true;
+            let candidate = &mut candidates[i];
+            candidate[0] &=
+                value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false");
+            candidate[1] &= value.parse::<i64>().is_ok();
+            candidate[2] &= value.parse::<f64>().is_ok();
+            date_candidates[i].update(value);
+        }
+    }
+    let mut date_formats = HashMap::new();
+    let fields = headers
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let data_type = match (has_values[i], candidates[i]) {
+                (false, _) => DataType::Utf8,
+                (true, [true, _, _]) => DataType::Boolean,
+                (true, [_, true, _]) => DataType::Int64,
+                (true, [_, _, true]) => DataType::Float64,
+                (true, _) => match date_candidates[i].detected() {
+                    Some((data_type, format)) => {
+                        date_formats.insert(name.clone(), format.to_string());
+                        data_type
+                    }
+                    None => DataType::Utf8,
+                },
+            };
+            Field::new(name, data_type, nullable[i])
+        })
+        .collect();
+    Ok((Schema::new(fields), date_formats))
+}
+
+/// Detect the string columns of an inferred CSV schema that contain dates or timestamps
+///
+/// A sample of records is probed against the recognised date and timestamp formats, and
+/// columns whose non-empty values all match a format are converted to `Date32` or `Timestamp`.
+/// The detected formats are returned by column name.
+fn infer_csv_date_formats<R: Read>(
+    input: R,
+    options: &CsvSourceOptions,
+    schema: &Schema,
+) -> Result<(Schema, HashMap<String, String>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter.unwrap_or(b','))
+        .has_headers(options.has_header)
+        .from_reader(replace_null_values(input, options));
+    let mut candidates: Vec<Option<DateFormatCandidates>> = schema
+        .fields()
+        .iter()
+        .map(|f| match f.data_type() {
+            DataType::Utf8 => Some(DateFormatCandidates::new()),
+            _ => None,
+        })
+        .collect();
+    let mut has_values = vec![false; candidates.len()];
+    for record in reader.records().take(CSV_SAMPLE_RECORDS) {
+        for (i, value) in record?.iter().enumerate().take(candidates.len()) {
+            if let Some(candidate) = &mut candidates[i] {
+                if !value.is_empty() {
+                    has_values[i] = true;
+                    candidate.update(value);
+                }
+            }
+        }
+    }
+    let mut date_formats = HashMap::new();
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let detected = match &candidates[i] {
+                Some(candidate) if has_values[i] => candidate.detected(),
+                _ => None,
+            };
+            match detected {
+                Some((data_type, format)) => {
+                    date_formats.insert(field.name().clone(), format.to_string());
+                    Field::new(field.name(), data_type, field.is_nullable())
+                }
+                None => field.clone(),
+            }
+        })
+        .collect();
+    Ok((Schema::new(fields), date_formats))
+}
+
 pub trait DataSource {
     fn get_dataset(&self) -> Result<Dataset>;
     fn source(&self) -> DataSourceType;
@@ -108,6 +456,126 @@ pub trait DataSource {
     fn filter(&mut self, filter: BooleanFilter) -> Result<()>;
     fn project(&mut self, columns: Vec<String>) -> Result<()>;
     fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()>;
+
+    /// The number of rows that the source will return, if it is known without reading them
+    ///
+    /// Sources that cannot cheaply count their rows return `None`.
+    fn row_count_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// The memory used by the batches that the source has read, which are still in use
+    ///
+    /// Sources that do not track their memory return 0.
+    fn allocated_bytes(&self) -> usize {
+        0
+    }
+
+    /// Read all remaining batches of the data source
+    ///
+    /// The `cancel` flag is checked before each batch is read, and once it is set, reading
+    /// stops with a `DataFrameError::Cancelled` error.
+    fn execute(&mut self, cancel: Arc<AtomicBool>) -> Result<Vec<RecordBatch>> {
+        let mut batches = vec![];
+        loop {
+            check_cancelled(&cancel)?;
+            match self.next_batch()? {
+                Some(batch) => batches.push(batch),
+                None => return Ok(batches),
+            }
+        }
+    }
+
+    /// Iterate over the rows of the data source, reading batches as they are needed
+    fn rows(&mut self) -> Rows<'_, Self>
+    where
+        Self: Sized,
+    {
+        Rows::new(self)
+    }
+
+    /// Consume the data source, iterating over its batches
+    fn batches(self) -> DataSourceIter<Self>
+    where
+        Self: Sized,
+    {
+        DataSourceIter {
+            source: self,
+            is_complete: false,
+        }
+    }
+}
+
+/// An iterator over the batches of a data source
+///
+/// Iteration ends once the source has no more batches, or after the first error is returned.
+pub struct DataSourceIter<S: DataSource> {
+    source: S,
+    is_complete: bool,
+}
+
+impl<S: DataSource> DataSourceIter<S> {
+    /// Return the data source, e.g. to read its row count after iterating
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: DataSource> Iterator for DataSourceIter<S> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_complete {
+            return None;
+        }
+        match self.source.next_batch() {
+            Ok(Some(batch)) => Some(Ok(batch)),
+            Ok(None) => {
+                self.is_complete = true;
+                None
+            }
+            Err(e) => {
+                self.is_complete = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Read all batches of a data source into a concatenated array per column, keyed by name
+///
+/// This is the column layout that other Rust array and dataframe libraries take as input.
+/// The columns of a source without any rows are empty arrays.
+pub fn to_columns(source: &mut dyn DataSource) -> Result<HashMap<String, ArrayRef>> {
+    let schema = source.schema();
+    let mut chunks: Vec<Vec<ArrayRef>> = vec![vec![]; schema.fields().len()];
+    while let Some(batch) = source.next_batch()? {
+        for (i, column) in batch.columns().iter().enumerate() {
+            chunks[i].push(column.clone());
+        }
+    }
+    schema
+        .fields()
+        .iter()
+        .zip(chunks)
+        .map(|(field, chunks)| {
+            let column = match chunks.len() {
+                0 => null_array(field.data_type(), 0)?,
+                1 => chunks[0].clone(),
+                _ => arrow::compute::concat(&chunks)?,
+            };
+            Ok((field.name().clone(), column))
+        })
+        .collect()
+}
+
+/// Return a `DataFrameError::Cancelled` error if a cancellation flag has been set
+pub(crate) fn check_cancelled(cancel: &AtomicBool) -> Result<()> {
+    if cancel.load(Ordering::Relaxed) {
+        Err(DataFrameError::Cancelled)
+    } else {
+        Ok(())
+    }
 }
 
 pub struct CsvDataSource<R: Read> {
@@ -117,49 +585,589 @@ pub struct CsvDataSource<R: Read> {
     limit: Option<usize>,
     read_schema: SchemaRef,
     projected_schema: SchemaRef,
-    reader: arrow::csv::Reader<R>,
-    
+    reader: arrow::csv::Reader<NullValuesReader<R>>,
+    /// Opens the input again from its start, if it can be read more than once
+    reopen: Option<fn(&str, &CsvSourceOptions) -> Result<R>>,
+    /// The batches of a file that was parsed in parallel, which have not been returned yet
+    decoded: Option<VecDeque<RecordBatch>>,
+    rows_read: usize,
+    memory: MemoryTracker,
 }
 
 pub struct CsvSourceOptions {
-    infer_schema: bool,
-    read_schema: Option<SchemaRef>,
+    pub infer_schema: bool,
+    pub read_schema: Option<SchemaRef>,
+    pub has_header: bool,
+    pub delimiter: Option<u8>,
+    pub projection: Option<Vec<usize>>,
+    pub batch_size: usize,
+    /// Renames the CSV's columns after their schema has been read or inferred
+    pub header_rename: Option<HeaderRename>,
+    /// Whether inferred string columns are probed for dates and timestamps, in the
+    /// `%Y-%m-%d`, `%m/%d/%Y` and ISO 8601 formats.
+    ///
+    /// Columns that consistently match a format are read as `Date32` or millisecond
+    /// `Timestamp` columns, and parsed with the detected format.
+    pub infer_date_formats: bool,
+    /// The chrono formats that date and timestamp columns are parsed with, by column name.
+    ///
+    /// Columns without a format are parsed as `%Y-%m-%d` dates or ISO 8601 timestamps.
+    pub date_formats: HashMap<String, String>,
+    /// The compression of the file, which is detected from its extension if not set
+    pub compression: Option<Compression>,
+    /// Values that are read as nulls, such as `NA` or `\N`, in addition to empty fields
+    pub null_values: Vec<String>,
+    /// The number of threads that uncompressed files are parsed on
+    ///
+    /// With more than 1 thread, the file is split into a range of whole records per thread,
+    /// and all of the file's batches are parsed when the first batch is read.
+    pub num_threads: usize,
+    /// The number of bytes that batches should take, which overrides `batch_size`
+    ///
+    /// The number of rows per batch is estimated from the width of the columns that are read.
+    pub target_batch_bytes: Option<usize>,
+}
+
+/// Replacement names for the columns of a CSV file
+pub enum HeaderRename {
+    /// Rename columns by mapping their original names to new names
+    ByName(HashMap<String, String>),
+    /// Rename all columns by position
+    ByPosition(Vec<String>),
+}
+
+impl HeaderRename {
+    /// Apply the new names to a schema, checking that the resulting names are unique
+    fn rename_schema(&self, schema: &Schema) -> Result<Schema> {
+        let names: Vec<String> = match self {
+            HeaderRename::ByName(names) => schema
+                .fields()
+                .iter()
+                .map(|f| names.get(f.name()).unwrap_or(f.name()).clone())
+                .collect(),
+            HeaderRename::ByPosition(names) => {
+                if names.len() != schema.fields().len() {
+                    return Err(DataFrameError::ComputeError(format!(
+                        "Expected {} replacement column names, got {}",
+                        schema.fields().len(),
+                        names.len()
+                    )));
+                }
+                names.clone()
+            }
+        };
+        let mut unique = HashSet::new();
+        if let Some(name) = names.iter().find(|name| !unique.insert(name.as_str())) {
+            return Err(DataFrameError::ComputeError(format!(
+                "Renaming CSV headers results in duplicate column {}",
+                name
+            )));
+        }
+        Ok(Schema::new(
+            schema
+                .fields()
+                .iter()
+                .zip(names)
+                .map(|(f, name)| Field::new(&name, f.data_type().clone(), f.is_nullable()))
+                .collect(),
+        ))
+    }
+}
+
+impl Default for CsvSourceOptions {
+    fn default() -> Self {
+        Self {
+            infer_schema: true,
+            read_schema: None,
+            has_header: true,
+            delimiter: None,
+            projection: None,
+            batch_size: 1024,
+            header_rename: None,
+            infer_date_formats: false,
+            date_formats: HashMap::new(),
+            compression: None,
+            null_values: vec![],
+            num_threads: 1,
+            target_batch_bytes: None,
+        }
+    }
+}
+
+impl CsvSourceOptions {
+    /// Size batches to the target number of bytes, if set, for the columns that are read
+    fn tune_batch_size(&mut self, read_schema: &SchemaRef) {
+        if let Some(bytes) = self.target_batch_bytes {
+            let schema = project_schema(read_schema, &self.projection);
+            self.batch_size = batch_size_for(&schema, bytes);
+        }
+    }
+}
+
+impl CsvDataSource<CompressedFile> {
+    /// Open a CSV file, inferring its schema if one is not provided in the options
+    pub fn try_new(path: &str, options: CsvSourceOptions) -> Result<Self> {
+        let mut inferred_formats = HashMap::new();
+        let read_schema = match &options.read_schema {
+            Some(schema) => schema.clone(),
+            None if options.infer_schema => {
+                let schema = infer_csv_file_schema(
+                    path,
+                    options.compression,
+                    options.has_header,
+                    options.delimiter.unwrap_or(b','),
+                    &options.null_values,
+                    None,
+                )?;
+                if options.infer_date_formats {
+                    let input = CompressedFile::open(path, options.compression)?;
+                    let (schema, date_formats) = infer_csv_date_formats(input, &options, &schema)?;
+                    inferred_formats = date_formats;
+                    Arc::new(schema)
+                } else {
+                    schema
+                }
+            }
+            None => return Err(schema_required()),
+        };
+        let input = CompressedFile::open(path, options.compression)?;
+        Self::from_input(
+            path,
+            input,
+            read_schema,
+            inferred_formats,
+            options,
+            Some(Self::reopen_file),
+        )
+    }
+
+    fn reopen_file(path: &str, options: &CsvSourceOptions) -> Result<CompressedFile> {
+        CompressedFile::open(path, options.compression)
+    }
+}
+
+impl CsvDataSource<Box<dyn Read>> {
+    /// Read CSV data from a reader, such as stdin, an HTTP response or a cloud storage object
+    ///
+    /// The reader is only read once, so the source can't be projected after it is created, and
+    /// the columns to read should be set with the `projection` option instead. The data is not
+    /// decompressed.
+    ///
+    /// Schemas can only be inferred from seekable inputs, so if the options have no read schema,
+    /// the first 1000 records are buffered in memory to infer the schema from, and are then read
+    /// again as the source's first records.
+    pub fn from_reader<T: Read + 'static>(
+        name: &str,
+        reader: T,
+        options: CsvSourceOptions,
+    ) -> Result<Self> {
+        let mut inferred_formats = HashMap::new();
+        let (read_schema, input): (SchemaRef, Box<dyn Read>) = match &options.read_schema {
+            Some(schema) => (schema.clone(), Box::new(reader)),
+            None if options.infer_schema => {
+                let mut reader = BufReader::new(reader);
+                let sample =
+                    read_csv_sample(&mut reader, options.has_header, Some(CSV_SAMPLE_RECORDS))?;
+                let schema = infer_csv_sample_schema(
+                    &sample,
+                    options.has_header,
+                    options.delimiter.unwrap_or(b','),
+                    &options.null_values,
+                )?;
+                let schema = if options.infer_date_formats {
+                    let (schema, date_formats) =
+                        infer_csv_date_formats(sample.as_slice(), &options, &schema)?;
+                    inferred_formats = date_formats;
+                    Arc::new(schema)
+                } else {
+                    schema
+                };
+                (schema, Box::new(Cursor::new(sample).chain(reader)))
+            }
+            None => return Err(schema_required()),
+        };
+        Self::from_input(name, input, read_schema, inferred_formats, options, None)
+    }
+}
+
+/// The error returned when a CSV source has neither a read schema nor schema inference
+fn schema_required() -> DataFrameError {
+    DataFrameError::ComputeError(
+        "A CSV schema is required when schema inference is disabled".to_string(),
+    )
+}
+
+impl<R: Read> CsvDataSource<R> {
+    /// Create a data source that reads from an input with a schema, which was read or inferred
+    /// with the given date formats
+    fn from_input(
+        path: &str,
+        input: R,
+        read_schema: SchemaRef,
+        mut inferred_formats: HashMap<String, String>,
+        mut options: CsvSourceOptions,
+        reopen: Option<fn(&str, &CsvSourceOptions) -> Result<R>>,
+    ) -> Result<Self> {
+        let renamed_schema = match &options.header_rename {
+            Some(rename) => Arc::new(rename.rename_schema(&read_schema)?),
+            None => read_schema.clone(),
+        };
+        // inferred formats are keyed by the file's column names, and configured formats take
+        // precedence over them
+        for (field, renamed) in read_schema.fields().iter().zip(renamed_schema.fields()) {
+            if let Some(format) = inferred_formats.remove(field.name()) {
+                options
+                    .date_formats
+                    .entry(renamed.name().clone())
+                    .or_insert(format);
+            }
+        }
+        let read_schema = renamed_schema;
+        options.tune_batch_size(&read_schema);
+        let reader = Self::open_reader(input, &options, read_schema.clone());
+        let projected_schema = project_schema(&read_schema, &options.projection);
+        Ok(Self {
+            path: path.to_string(),
+            projection: projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect(),
+            options,
+            limit: None,
+            read_schema,
+            projected_schema,
+            reader,
+            reopen,
+            decoded: None,
+            rows_read: 0,
+            memory: MemoryTracker::new(),
+        })
+    }
+
+    fn open_reader(
+        input: R,
+        options: &CsvSourceOptions,
+        schema: SchemaRef,
+    ) -> CsvReader<NullValuesReader<R>> {
+        CsvReader::new(
+            replace_null_values(input, options),
+            csv_reader_schema(&schema),
+            options.has_header,
+            options.delimiter,
+            options.batch_size,
+            options.projection.clone(),
+        )
+    }
+
+    /// Whether the source's file is parsed in parallel, which requires it to be seekable
+    fn is_parallel(&self) -> bool {
+        self.options.num_threads > 1
+            && self.reopen.is_some()
+            && self
+                .options
+                .compression
+                .unwrap_or_else(|| Compression::from_path(&self.path))
+                == Compression::Uncompressed
+    }
+
+    /// Read the next batch of a file that is parsed in parallel, parsing the whole file when
+    /// the first batch is read
+    fn next_parallel_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if self.decoded.is_none() {
+            let ranges = split_csv_file(&self.path, self.options.num_threads)?;
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(self.options.num_threads)
+                .build()
+                .map_err(|e| DataFrameError::ComputeError(e.to_string()))?;
+            let (path, options) = (self.path.as_str(), &self.options);
+            let schema = csv_reader_schema(&self.read_schema);
+            let batches = pool.install(|| {
+                ranges
+                    .par_iter()
+                    .enumerate()
+                    // only the first range has the header
+                    .map(|(i, range)| read_csv_range(path, *range, i == 0, options, schema.clone()))
+                    .collect::<Result<Vec<Vec<RecordBatch>>>>()
+            })?;
+            self.decoded = Some(batches.into_iter().flatten().collect());
+        }
+        Ok(self.decoded.as_mut().unwrap().pop_front())
+    }
+}
+
+/// The schema that the Arrow CSV reader reads a schema's columns with
+///
+/// The CSV reader can't parse dates, so they are read as strings and parsed per batch.
+fn csv_reader_schema(schema: &SchemaRef) -> SchemaRef {
+    Arc::new(Schema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|f| match f.data_type() {
+                DataType::Date32(_) | DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                    Field::new(f.name(), DataType::Utf8, f.is_nullable())
+                }
+                _ => f.clone(),
+            })
+            .collect(),
+    ))
+}
+
+/// Split an uncompressed CSV file into up to `num_ranges` byte ranges of whole records
+///
+/// Records end at line endings that are not within quoted fields. They are found by a pass
+/// over the file's lines that only counts quotes, which is much cheaper than parsing them.
+fn split_csv_file(path: &str, num_ranges: usize) -> Result<Vec<(u64, u64)>> {
+    let length = std::fs::metadata(path)?.len();
+    let target = (length / num_ranges as u64).max(1);
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = vec![];
+    let mut ranges = vec![];
+    let (mut start, mut position) = (0, 0);
+    let mut in_quotes = false;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)? as u64;
+        if read == 0 {
+            break;
+        }
+        position += read;
+        // doubled quotes within quoted fields toggle the state twice, leaving it unchanged
+        in_quotes ^= line.iter().filter(|b| **b == b'"').count() % 2 == 1;
+        if !in_quotes && position - start >= target {
+            ranges.push((start, position));
+            start = position;
+        }
+    }
+    if start < length {
+        ranges.push((start, length));
+    }
+    Ok(ranges)
+}
+
+/// Parse the records in a byte range of a CSV file
+fn read_csv_range(
+    path: &str,
+    (start, end): (u64, u64),
     has_header: bool,
-    delimiter: Option<u8>,
-    projection: Option<Vec<usize>>
+    options: &CsvSourceOptions,
+    schema: SchemaRef,
+) -> Result<Vec<RecordBatch>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let input = NullValuesReader::new(
+        file.take(end - start),
+        &options.null_values,
+        has_header,
+        options.delimiter.unwrap_or(b','),
+    );
+    let reader = CsvReader::new(
+        input,
+        schema,
+        has_header,
+        options.delimiter,
+        options.batch_size,
+        options.projection.clone(),
+    );
+    Ok(reader.collect::<arrow::error::Result<Vec<RecordBatch>>>()?)
+}
+
+/// Replace the null values of CSV data with empty fields
+fn replace_null_values<R: Read>(input: R, options: &CsvSourceOptions) -> NullValuesReader<R> {
+    NullValuesReader::new(
+        input,
+        &options.null_values,
+        options.has_header,
+        options.delimiter.unwrap_or(b','),
+    )
+}
+
+/// Select the projected fields of a schema
+fn project_schema(schema: &SchemaRef, projection: &Option<Vec<usize>>) -> SchemaRef {
+    match projection {
+        Some(projection) => Arc::new(Schema::new(
+            projection
+                .iter()
+                .map(|i| schema.field(*i).clone())
+                .collect(),
+        )),
+        None => schema.clone(),
+    }
+}
+
+/// Parse an array of strings into dates with a chrono format
+fn parse_date32(array: &ArrayRef, format: &str) -> Result<ArrayRef> {
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    let epoch = NaiveDate::from_ymd(1970, 1, 1);
+    let days = (0..array.len())
+        .map(|i| {
+            if array.is_null(i) || array.value(i).is_empty() {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(array.value(i), format)
+                .map(|date| Some(date.signed_duration_since(epoch).num_days() as i32))
+                .map_err(|e| {
+                    DataFrameError::ParseError(format!(
+                        "Unable to parse {} as a date: {}",
+                        array.value(i),
+                        e
+                    ))
+                })
+        })
+        .collect::<Result<Vec<Option<i32>>>>()?;
+    Ok(Arc::new(Date32Array::from(days)))
+}
+
+/// Parse an array of strings into millisecond timestamps with a chrono format
+fn parse_timestamp_millisecond(array: &ArrayRef, format: &str) -> Result<ArrayRef> {
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    let millis = (0..array.len())
+        .map(|i| {
+            if array.is_null(i) || array.value(i).is_empty() {
+                return Ok(None);
+            }
+            NaiveDateTime::parse_from_str(array.value(i), format)
+                .map(|datetime| Some(datetime.timestamp_millis()))
+                .map_err(|e| {
+                    DataFrameError::ParseError(format!(
+                        "Unable to parse {} as a timestamp: {}",
+                        array.value(i),
+                        e
+                    ))
+                })
+        })
+        .collect::<Result<Vec<Option<i64>>>>()?;
+    Ok(Arc::new(TimestampMillisecondArray::from(millis)))
 }
 
 impl<R: Read> DataSource for CsvDataSource<R> {
-    
     fn get_dataset(&self) -> Result<Dataset> {
-        todo!()
+        Ok(Dataset {
+            name: "csv_source".to_owned(),
+            columns: self
+                .projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
     }
     fn source(&self) -> DataSourceType {
-        todo!()
+        if self.reopen.is_none() {
+            return DataSourceType::CsvStream(self.path.clone());
+        }
+        DataSourceType::Csv(
+            self.path.clone(),
+            CsvReadOptions {
+                has_headers: self.options.has_header,
+                delimiter: self.options.delimiter,
+                max_records: self.limit,
+                batch_size: self.options.batch_size,
+                projection: self.options.projection.clone(),
+                schema: self.options.read_schema.clone(),
+                compression: self.options.compression,
+                null_values: self.options.null_values.clone(),
+            },
+        )
     }
     fn format(&self) -> &str {
         "csv"
     }
     fn schema(&self) -> SchemaRef {
-        todo!()
+        self.projected_schema.clone()
     }
     fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
-        todo!()
+        if let Some(limit) = self.limit {
+            if self.rows_read >= limit {
+                return Ok(None);
+            }
+        }
+        let batch = if self.is_parallel() {
+            match self.next_parallel_batch()? {
+                Some(batch) => batch,
+                None => return Ok(None),
+            }
+        } else {
+            match self.reader.next() {
+                Some(batch) => batch?,
+                None => return Ok(None),
+            }
+        };
+        let batch = match self.limit {
+            Some(limit) if self.rows_read + batch.num_rows() > limit => {
+                let length = limit - self.rows_read;
+                RecordBatch::try_new(
+                    batch.schema(),
+                    batch.columns().iter().map(|c| c.slice(0, length)).collect(),
+                )?
+            }
+            _ => batch,
+        };
+        self.rows_read += batch.num_rows();
+        let date_formats = &self.options.date_formats;
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(self.projected_schema.fields())
+            .map(|(column, field)| {
+                let format = date_formats.get(field.name()).map(|f| f.as_str());
+                match field.data_type() {
+                    DataType::Date32(_) => {
+                        parse_date32(column, format.unwrap_or(DEFAULT_DATE_FORMAT))
+                    }
+                    DataType::Timestamp(TimeUnit::Millisecond, _) => parse_timestamp_millisecond(
+                        column,
+                        format.unwrap_or(DEFAULT_TIMESTAMP_FORMAT),
+                    ),
+                    _ => Ok(column.clone()),
+                }
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        let batch = RecordBatch::try_new(self.projected_schema.clone(), columns)?;
+        self.memory.track(&batch);
+        Ok(Some(batch))
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.memory.allocated_bytes()
     }
     fn limit(&mut self, limit: usize) -> Result<()> {
-        todo!()
+        self.limit = Some(limit);
+        Ok(())
     }
-    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
-        todo!()
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "CSV data source does not support filtering".to_string(),
+        ))
     }
     fn project(&mut self, columns: Vec<String>) -> Result<()> {
-        todo!()
+        let projection = columns
+            .iter()
+            .map(|name| self.read_schema.index_of(name))
+            .collect::<arrow::error::Result<Vec<usize>>>()?;
+        // the reader has to be reopened for the projection to take effect
+        let reopen = self.reopen.ok_or_else(|| {
+            DataFrameError::ComputeError(
+                "CSV stream data source does not support projection".to_string(),
+            )
+        })?;
+        self.options.projection = Some(projection);
+        self.options.tune_batch_size(&self.read_schema);
+        let input = reopen(&self.path, &self.options)?;
+        self.reader = Self::open_reader(input, &self.options, self.read_schema.clone());
+        self.decoded = None;
+        self.projected_schema = project_schema(&self.read_schema, &self.options.projection);
+        self.projection = columns;
+        self.rows_read = 0;
+        Ok(())
     }
-    fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
-        todo!()
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "CSV data source does not support sorting".to_string(),
+        ))
     }
     fn supports_projection(&self) -> bool {
-        true
+        self.reopen.is_some()
     }
     fn supports_filtering(&self) -> bool {
         false
@@ -169,5 +1177,759 @@ impl<R: Read> DataSource for CsvDataSource<R> {
     }
     fn supports_limit(&self) -> bool {
         true
-    }    
-}
\ No newline at end of file
+    }
+}
+
+/// A data source that reads the record batches of an Arrow IPC file
+pub struct ArrowDataSource {
+    path: String,
+    schema: SchemaRef,
+    reader: ArrowFileReader<File>,
+    limit: Option<usize>,
+    rows_read: usize,
+}
+
+impl ArrowDataSource {
+    pub fn try_new(path: &str) -> Result<Self> {
+        let reader = ArrowFileReader::try_new(File::open(path)?)?;
+        Ok(Self {
+            path: path.to_string(),
+            schema: reader.schema(),
+            reader,
+            limit: None,
+            rows_read: 0,
+        })
+    }
+}
+
+impl DataSource for ArrowDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: "ipc_file_source".to_owned(),
+            columns: self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Arrow(self.path.clone())
+    }
+    fn format(&self) -> &str {
+        "arrow"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if let Some(limit) = self.limit {
+            if self.rows_read >= limit {
+                return Ok(None);
+            }
+        }
+        let batch = match self.reader.next() {
+            Some(batch) => batch?,
+            None => return Ok(None),
+        };
+        // the file's batches are read whole, and sliced to the limit
+        let batch = match self.limit {
+            Some(limit) if self.rows_read + batch.num_rows() > limit => {
+                let length = limit - self.rows_read;
+                RecordBatch::try_new(
+                    batch.schema(),
+                    batch.columns().iter().map(|c| c.slice(0, length)).collect(),
+                )?
+            }
+            _ => batch,
+        };
+        self.rows_read += batch.num_rows();
+        Ok(Some(batch))
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Arrow data source does not support filtering".to_string(),
+        ))
+    }
+    fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Arrow data source does not support projection".to_string(),
+        ))
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Arrow data source does not support sorting".to_string(),
+        ))
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+}
+
+/// A data source over record batches that are already in memory
+///
+/// This is useful for tests, and for reading data that has been computed or received
+/// without writing it to a file first.
+pub struct MemoryDataSource {
+    name: String,
+    schema: SchemaRef,
+    projected_schema: SchemaRef,
+    /// The indices of the projected columns in the batches
+    projection: Option<Vec<usize>>,
+    batches: Vec<RecordBatch>,
+    /// The index of the next batch to return
+    index: usize,
+    limit: Option<usize>,
+    rows_read: usize,
+}
+
+impl MemoryDataSource {
+    /// Create a source over batches, which must all have the `schema`
+    pub fn try_new(name: &str, schema: SchemaRef, batches: Vec<RecordBatch>) -> Result<Self> {
+        for batch in &batches {
+            crate::io::datasink::validate_schema(&schema, batch)?;
+        }
+        Ok(Self {
+            name: name.to_string(),
+            projected_schema: schema.clone(),
+            schema,
+            projection: None,
+            batches,
+            index: 0,
+            limit: None,
+            rows_read: 0,
+        })
+    }
+}
+
+impl DataSource for MemoryDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: self.name.clone(),
+            columns: self
+                .projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Memory(self.name.clone())
+    }
+    fn format(&self) -> &str {
+        "memory"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if let Some(limit) = self.limit {
+            if self.rows_read >= limit {
+                return Ok(None);
+            }
+        }
+        let batch = match self.batches.get(self.index) {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+        self.index += 1;
+        let mut columns = match &self.projection {
+            Some(projection) => projection
+                .iter()
+                .map(|i| batch.column(*i).clone())
+                .collect(),
+            None => batch.columns().to_vec(),
+        };
+        if let Some(limit) = self.limit {
+            if self.rows_read + batch.num_rows() > limit {
+                let length = limit - self.rows_read;
+                columns = columns.iter().map(|c| c.slice(0, length)).collect();
+            }
+        }
+        let batch = RecordBatch::try_new(self.projected_schema.clone(), columns)?;
+        self.rows_read += batch.num_rows();
+        Ok(Some(batch))
+    }
+    fn row_count_hint(&self) -> Option<usize> {
+        let num_rows = self.batches.iter().map(|b| b.num_rows()).sum();
+        Some(self.limit.map_or(num_rows, |limit| limit.min(num_rows)))
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Memory data source does not support filtering".to_string(),
+        ))
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        let projection = columns
+            .iter()
+            .map(|name| self.schema.index_of(name))
+            .collect::<arrow::error::Result<Vec<usize>>>()?;
+        self.projected_schema = project_schema(&self.schema, &Some(projection.clone()));
+        self.projection = Some(projection);
+        Ok(())
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Memory data source does not support sorting".to_string(),
+        ))
+    }
+    fn supports_projection(&self) -> bool {
+        true
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_header_rename() -> Result<()> {
+        let mut names = HashMap::new();
+        names.insert("lat".to_string(), "latitude".to_string());
+        names.insert("lng".to_string(), "longitude".to_string());
+        let options = CsvSourceOptions {
+            header_rename: Some(HeaderRename::ByName(names)),
+            ..Default::default()
+        };
+        let source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let dataset = source.get_dataset()?;
+        let names: Vec<&str> = dataset.columns.iter().map(|c| c.name()).collect();
+        assert_eq!(vec!["city", "latitude", "longitude"], names);
+
+        let options = CsvSourceOptions {
+            header_rename: Some(HeaderRename::ByPosition(vec![
+                "town".to_string(),
+                "y".to_string(),
+                "x".to_string(),
+            ])),
+            ..Default::default()
+        };
+        let source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let names: Vec<String> = source
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(vec!["town", "y", "x"], names);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_auto() -> Result<()> {
+        let mut source = Reader::csv_auto("./test/data/events_semicolon.csv")?;
+        let schema = source.schema();
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            vec![
+                &DataType::Int64,
+                &DataType::Utf8,
+                &DataType::Boolean,
+                &DataType::Date32(DateUnit::Day),
+                &DataType::Float64
+            ],
+            types
+        );
+        let mut num_rows = 0;
+        while let Some(batch) = source.next_batch()? {
+            let dates = batch
+                .column(3)
+                .as_any()
+                .downcast_ref::<Date32Array>()
+                .unwrap();
+            // 2020-01-15
+            assert_eq!(18276, dates.value(0));
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(4, num_rows);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_header_rename_duplicates() {
+        let mut names = HashMap::new();
+        names.insert("lat".to_string(), "city".to_string());
+        let options = CsvSourceOptions {
+            header_rename: Some(HeaderRename::ByName(names)),
+            ..Default::default()
+        };
+        assert!(CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options).is_err());
+    }
+
+    /// A CSV source that sets a cancellation flag once it has read its first batch
+    struct CancellingSource {
+        source: CsvDataSource<CompressedFile>,
+        cancel: Arc<AtomicBool>,
+        batches_read: usize,
+    }
+
+    impl DataSource for CancellingSource {
+        fn get_dataset(&self) -> Result<Dataset> {
+            self.source.get_dataset()
+        }
+        fn source(&self) -> DataSourceType {
+            self.source.source()
+        }
+        fn format(&self) -> &str {
+            self.source.format()
+        }
+        fn schema(&self) -> SchemaRef {
+            self.source.schema()
+        }
+        fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+            let batch = self.source.next_batch()?;
+            self.batches_read += 1;
+            self.cancel.store(true, Ordering::Relaxed);
+            Ok(batch)
+        }
+        fn limit(&mut self, limit: usize) -> Result<()> {
+            self.source.limit(limit)
+        }
+        fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+            self.source.filter(filter)
+        }
+        fn project(&mut self, columns: Vec<String>) -> Result<()> {
+            self.source.project(columns)
+        }
+        fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+            self.source.sort(criteria)
+        }
+    }
+
+    #[test]
+    fn test_execute_cancelled() -> Result<()> {
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut source = CancellingSource {
+            source: CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?,
+            cancel: cancel.clone(),
+            batches_read: 0,
+        };
+        match source.execute(cancel) {
+            Err(DataFrameError::Cancelled) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Expected execution to be cancelled"),
+        }
+        // the source has 4 batches, but reading stopped after the first
+        assert_eq!(1, source.batches_read);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_dataset_with_schema() -> Result<()> {
+        use crate::expression::Column;
+
+        // the coordinates would be inferred as floats, but the schema reads them as strings
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("city", DataType::Utf8, false),
+            Field::new("lat", DataType::Utf8, true),
+            Field::new("lng", DataType::Utf8, true),
+        ]));
+        let reader = Reader {
+            source: DataSourceType::Csv(
+                "./test/data/uk_cities_with_headers.csv".to_owned(),
+                CsvReadOptions {
+                    has_headers: true,
+                    delimiter: None,
+                    max_records: None,
+                    batch_size: 1024,
+                    projection: None,
+                    schema: Some(schema.clone()),
+                    compression: None,
+                    null_values: vec![],
+                },
+            ),
+        };
+        let dataset = reader.get_dataset()?;
+        let columns: Vec<Column> = schema.fields().iter().map(|f| f.clone().into()).collect();
+        assert_eq!(columns, dataset.columns);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_columns() -> Result<()> {
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            projection: Some(vec![0, 1]),
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let columns = to_columns(&mut source)?;
+        assert_eq!(2, columns.len());
+        assert_eq!(37, columns["city"].len());
+        assert_eq!(37, columns["lat"].len());
+        assert_eq!(&DataType::Float64, columns["lat"].data_type());
+
+        // an empty source still has its columns
+        let mut source =
+            CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", Default::default())?;
+        source.limit(0)?;
+        let columns = to_columns(&mut source)?;
+        assert_eq!(3, columns.len());
+        assert!(columns.values().all(|column| column.is_empty()));
+        assert_eq!(&DataType::Utf8, columns["city"].data_type());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_infer_date_formats() -> Result<()> {
+        let options = CsvSourceOptions {
+            infer_date_formats: true,
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/members_us_dates.csv", options)?;
+        let schema = source.schema();
+        assert_eq!(&DataType::Utf8, schema.field(1).data_type());
+        assert_eq!(
+            &DataType::Date32(DateUnit::Day),
+            schema.field(2).data_type()
+        );
+        assert_eq!(
+            &DataType::Timestamp(TimeUnit::Millisecond, None),
+            schema.field(3).data_type()
+        );
+        let batch = source.next_batch()?.unwrap();
+        let joined = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .unwrap();
+        // 01/15/2020 and 12/31/2019
+        assert_eq!(18276, joined.value(0));
+        assert_eq!(18261, joined.value(1));
+        assert!(joined.is_null(2));
+        let updated = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        // 2020-02-29T08:00:00.250
+        assert_eq!(1_582_963_200_250, updated.value(1));
+
+        // without date inference, the dates are strings
+        let source =
+            CsvDataSource::try_new("./test/data/members_us_dates.csv", Default::default())?;
+        assert_eq!(&DataType::Utf8, source.schema().field(2).data_type());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_source() -> Result<()> {
+        use arrow::array::Int64Array;
+        use arrow::ipc::writer::FileWriter;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let path = "target/arrow_source.arrow";
+        let mut writer = FileWriter::try_new(File::create(path)?, &schema)?;
+        for start in &[0i64, 3] {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![*start, start + 1, start + 2])),
+                    Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+                ],
+            )?;
+            writer.write(&batch)?;
+        }
+        writer.finish()?;
+
+        let mut source = ArrowDataSource::try_new(path)?;
+        assert_eq!("arrow", source.format());
+        assert_eq!(schema, source.schema());
+        assert_eq!(2, source.get_dataset()?.columns.len());
+        let mut num_rows = 0;
+        while let Some(batch) = source.next_batch()? {
+            assert_eq!(schema, batch.schema());
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(6, num_rows);
+
+        // the limit ends reading part way through the second batch
+        let mut source = ArrowDataSource::try_new(path)?;
+        source.limit(4)?;
+        let batches = source.execute(Arc::new(AtomicBool::new(false)))?;
+        assert_eq!(
+            vec![3, 1],
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+        );
+        let ids = batches[1]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(3, ids.value(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_source() -> Result<()> {
+        use arrow::array::Int64Array;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = |ids: Vec<i64>, names: Vec<&str>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(ids)),
+                    Arc::new(StringArray::from(names)),
+                ],
+            )
+        };
+        let batches = vec![
+            batch(vec![1, 2, 3], vec!["a", "b", "c"])?,
+            batch(vec![4, 5], vec!["d", "e"])?,
+        ];
+
+        let mut source = MemoryDataSource::try_new("people", schema.clone(), batches.clone())?;
+        assert!(source.supports_projection());
+        assert!(source.supports_limit());
+        assert_eq!(Some(5), source.row_count_hint());
+        let mut num_rows = vec![];
+        while let Some(batch) = source.next_batch()? {
+            assert_eq!(schema, batch.schema());
+            num_rows.push(batch.num_rows());
+        }
+        assert_eq!(vec![3, 2], num_rows);
+
+        // only the projected columns are returned, up to the limit
+        let mut source = MemoryDataSource::try_new("people", schema.clone(), batches)?;
+        source.project(vec!["name".to_string()])?;
+        source.limit(4)?;
+        let names: Vec<&String> = source.schema().fields().iter().map(|f| f.name()).collect();
+        assert_eq!(vec!["name"], names);
+        let batches = source.execute(Arc::new(AtomicBool::new(false)))?;
+        assert_eq!(
+            vec![3, 1],
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+        );
+        assert_eq!(1, batches[1].num_columns());
+
+        // batches must have the source's schema
+        let other = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        assert!(
+            MemoryDataSource::try_new("people", other, vec![batch(vec![1], vec!["a"])?]).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_batches_iterator() -> Result<()> {
+        let options = || CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let path = "./test/data/uk_cities_with_headers.csv";
+        let mut source = CsvDataSource::try_new(path, options())?;
+        let mut expected = vec![];
+        while let Some(batch) = source.next_batch()? {
+            expected.push(batch.num_rows());
+        }
+
+        let source = CsvDataSource::try_new(path, options())?;
+        let mut num_rows = vec![];
+        for batch in source.batches() {
+            num_rows.push(batch?.num_rows());
+        }
+        assert_eq!(vec![10, 10, 10, 7], expected);
+        assert_eq!(expected, num_rows);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_column_nullability() -> Result<()> {
+        let source =
+            CsvDataSource::try_new("./test/data/people_with_nulls.csv", Default::default())?;
+        let dataset = source.get_dataset()?;
+        let (_, age) = dataset.get_column("age").unwrap();
+        assert!(age.is_nullable());
+
+        // a read schema can declare columns without nulls
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("city", DataType::Utf8, false),
+            Field::new("lat", DataType::Float64, true),
+            Field::new("lng", DataType::Float64, true),
+        ]));
+        let options = CsvSourceOptions {
+            infer_schema: false,
+            read_schema: Some(schema),
+            ..Default::default()
+        };
+        let source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let dataset = source.get_dataset()?;
+        let nullable: Vec<bool> = dataset.columns.iter().map(|c| c.is_nullable()).collect();
+        assert_eq!(vec![false, true, true], nullable);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_compressed() -> Result<()> {
+        fn read(
+            path: &str,
+            compression: Option<Compression>,
+        ) -> Result<(Vec<crate::expression::Column>, String)> {
+            let options = CsvSourceOptions {
+                compression,
+                ..Default::default()
+            };
+            let mut source = CsvDataSource::try_new(path, options)?;
+            let mut batches = vec![];
+            while let Some(batch) = source.next_batch()? {
+                batches.push(batch);
+            }
+            let output = arrow::util::pretty::pretty_format_batches(&batches)?;
+            Ok((source.get_dataset()?.columns, output))
+        }
+        let expected = read("./test/data/uk_cities_with_headers.csv", None)?;
+        // the compression is detected from the extension, and the schema inferred after
+        // decompressing
+        assert_eq!(
+            expected,
+            read("./test/data/uk_cities_with_headers.csv.gz", None)?
+        );
+        assert_eq!(
+            expected,
+            read("./test/data/uk_cities_with_headers.csv.zst", None)?
+        );
+        assert_eq!(
+            expected,
+            read(
+                "./test/data/uk_cities_with_headers.csv.gz",
+                Some(Compression::Gzip)
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_null_values() -> Result<()> {
+        let options = CsvSourceOptions {
+            null_values: vec!["NA".to_string(), "\\N".to_string()],
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/measurements_with_na.csv", options)?;
+        let schema = source.schema();
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            vec![&DataType::Utf8, &DataType::Float64, &DataType::Int64],
+            types
+        );
+        let batch = source.next_batch()?.unwrap();
+        let temperature = batch.column(1);
+        let nulls: Vec<bool> = (0..4).map(|i| temperature.is_null(i)).collect();
+        assert_eq!(vec![false, true, false, true], nulls);
+        let humidity = batch.column(2);
+        let nulls: Vec<bool> = (0..4).map(|i| humidity.is_null(i)).collect();
+        assert_eq!(vec![false, false, true, false], nulls);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_from_reader() -> Result<()> {
+        let data = std::fs::read("./test/data/uk_cities_with_headers.csv")?;
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::from_reader("cities", Cursor::new(data), options)?;
+        let types: Vec<DataType> = source
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect();
+        assert_eq!(
+            vec![DataType::Utf8, DataType::Float64, DataType::Float64],
+            types
+        );
+        // the records that the schema was inferred from are read again
+        let mut num_rows = vec![];
+        while let Some(batch) = source.next_batch()? {
+            num_rows.push(batch.num_rows());
+        }
+        assert_eq!(vec![10, 10, 10, 7], num_rows);
+
+        // streams can't be reopened with a new projection
+        assert!(!source.supports_projection());
+        assert!(source.project(vec!["city".to_string()]).is_err());
+        match source.source() {
+            DataSourceType::CsvStream(name) => assert_eq!("cities", name),
+            _ => panic!("Expected a CSV stream data source"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_parallel_multiline_records() -> Result<()> {
+        let path = "./test/data/notes_multiline.csv";
+        let read = |num_threads: usize| -> Result<String> {
+            let options = CsvSourceOptions {
+                batch_size: 2,
+                num_threads,
+                ..Default::default()
+            };
+            let mut source = CsvDataSource::try_new(path, options)?;
+            let mut batches = vec![];
+            while let Some(batch) = source.next_batch()? {
+                batches.push(batch);
+            }
+            assert_eq!(8, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+            Ok(arrow::util::pretty::pretty_format_batches(&batches)?)
+        };
+        // the file is split between records, and not within quoted fields
+        let ranges = split_csv_file(path, 4)?;
+        assert_eq!(4, ranges.len());
+        let sequential = read(1)?;
+        assert_eq!(sequential, read(4)?);
+        assert_eq!(sequential, read(16)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_target_batch_bytes() -> Result<()> {
+        // a city name is estimated at 36 bytes, and each coordinate at 8 bytes
+        let options = CsvSourceOptions {
+            target_batch_bytes: Some(520),
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let mut num_rows = vec![];
+        while let Some(batch) = source.next_batch()? {
+            num_rows.push(batch.num_rows());
+        }
+        assert_eq!(vec![10, 10, 10, 7], num_rows);
+
+        // batches of fewer columns have more rows
+        let mut source = CsvDataSource::try_new(
+            "./test/data/uk_cities_with_headers.csv",
+            CsvSourceOptions {
+                target_batch_bytes: Some(520),
+                ..Default::default()
+            },
+        )?;
+        source.project(vec!["lat".to_string(), "lng".to_string()])?;
+        assert_eq!(32, source.next_batch()?.unwrap().num_rows());
+        Ok(())
+    }
+}
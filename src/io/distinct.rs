@@ -0,0 +1,287 @@
+//! Removing duplicate rows of data sources as they are read
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::compute;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::functions::distinct::Distinct;
+use crate::io::datasource::{check_cancelled, DataSource};
+
+/// A data source that only returns the first occurrence of each distinct row of another source
+///
+/// Rows are compared on all columns, or on a subset with `distinct_on`. The keys of the rows
+/// that have been returned are kept across calls to `next_batch`, so duplicates are removed
+/// even if they are in different batches. Batches that have no rows left are skipped.
+pub struct DistinctSource<S: DataSource> {
+    source: S,
+    /// The indices of the columns that rows are compared on, or `None` for all columns
+    key_columns: Option<Vec<usize>>,
+    distinct: Distinct,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<S: DataSource> DistinctSource<S> {
+    /// Remove rows that are equal to an earlier row in all columns
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            key_columns: None,
+            distinct: Distinct::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Remove rows that are equal to an earlier row in the given columns, keeping the first
+    pub fn distinct_on(source: S, columns: &[&str]) -> Result<Self> {
+        let schema = source.schema();
+        let key_columns = columns
+            .iter()
+            .map(|name| {
+                schema
+                    .index_of(name)
+                    .map_err(|_| DataFrameError::InvalidOperation {
+                        message: format!("Column {} not found", name),
+                        suggestion: crate::utils::closest_match(
+                            name,
+                            schema.fields().iter().map(|f| f.name().as_str()),
+                        )
+                        .map(|similar| format!("did you mean '{}'?", similar)),
+                    })
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        Ok(Self {
+            source,
+            key_columns: Some(key_columns),
+            distinct: Distinct::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Stop reading once the `cancel` flag is set
+    ///
+    /// The flag is checked before each batch of the wrapped source is read, including batches
+    /// that are skipped because all of their rows are duplicates. If it is set, a
+    /// `DataFrameError::Cancelled` error is returned.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Check a bloom filter sized for `expected_rows` distinct rows before looking up the keys
+    /// of rows that have been returned, so that rows that are definitely new are not looked up
+    ///
+    /// This should be set before any batches are read, as it replaces the keys that have been
+    /// seen.
+    pub fn with_bloom_filter(mut self, expected_rows: usize) -> Self {
+        self.distinct = Distinct::with_bloom_filter(expected_rows);
+        self
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn distinct_batch(&mut self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let keys: Vec<ArrayRef> = match &self.key_columns {
+            Some(key_columns) => key_columns
+                .iter()
+                .map(|i| batch.column(*i).clone())
+                .collect(),
+            None => batch.columns().to_vec(),
+        };
+        let indices = self.distinct.distinct_indices(&keys)?;
+        if indices.len() == batch.num_rows() {
+            return Ok(batch.clone());
+        }
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| compute::take(column, &indices, None))
+            .collect::<arrow::error::Result<Vec<ArrayRef>>>()?;
+        Ok(RecordBatch::try_new(batch.schema(), columns)?)
+    }
+}
+
+impl<S: DataSource> DataSource for DistinctSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        self.source.get_dataset()
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        loop {
+            check_cancelled(&self.cancel)?;
+            let batch = match self.source.next_batch()? {
+                Some(batch) => self.distinct_batch(&batch)?,
+                None => return Ok(None),
+            };
+            if batch.num_rows() > 0 {
+                return Ok(Some(batch));
+            }
+        }
+    }
+    fn supports_filtering(&self) -> bool {
+        self.key_columns.is_none() && self.source.supports_filtering()
+    }
+    fn supports_sorting(&self) -> bool {
+        self.key_columns.is_none() && self.source.supports_sorting()
+    }
+    fn limit(&mut self, _limit: usize) -> Result<()> {
+        // limiting the wrapped source would apply the limit before duplicates are removed
+        Err(DataFrameError::ComputeError(
+            "Distinct data source does not support limits".to_string(),
+        ))
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        // filtering whole rows does not change which rows are duplicates, but filtering before
+        // comparing a subset of columns could change which occurrence is first
+        if self.key_columns.is_some() {
+            return Err(DataFrameError::ComputeError(
+                "Distinct data source does not support filtering on a subset of columns"
+                    .to_string(),
+            ));
+        }
+        self.source.filter(filter)
+    }
+    fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+        // projecting the wrapped source would change the columns that rows are compared on
+        Err(DataFrameError::ComputeError(
+            "Distinct data source does not support projection".to_string(),
+        ))
+    }
+    fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+        if self.key_columns.is_some() {
+            return Err(DataFrameError::ComputeError(
+                "Distinct data source does not support sorting on a subset of columns".to_string(),
+            ));
+        }
+        self.source.sort(criteria)
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::array::{Array, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    /// A source of 2 batches, where the second repeats rows of the first
+    fn fruit() -> MemoryDataSource {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("fruit", DataType::Utf8, false),
+            Field::new("count", DataType::Int64, false),
+        ]));
+        let batches = vec![
+            (vec!["apple", "pear", "apple"], vec![1, 2, 1]),
+            (vec!["pear", "apple", "plum"], vec![2, 3, 1]),
+        ]
+        .into_iter()
+        .map(|(fruit, count)| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(fruit)),
+                    Arc::new(Int64Array::from(count)),
+                ],
+            )
+            .unwrap()
+        })
+        .collect();
+        MemoryDataSource::try_new("fruit", schema, batches).unwrap()
+    }
+
+    fn rows(source: &mut dyn DataSource) -> Result<Vec<(String, i64)>> {
+        let mut rows = vec![];
+        while let Some(batch) = source.next_batch()? {
+            let fruit = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            let count = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            for i in 0..batch.num_rows() {
+                rows.push((fruit.value(i).to_string(), count.value(i)));
+            }
+        }
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_distinct_across_batches() -> Result<()> {
+        let mut source = DistinctSource::new(fruit());
+        // ("pear", 2) in the second batch duplicates a row of the first batch
+        let expected = vec![
+            ("apple".to_string(), 1),
+            ("pear".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("plum".to_string(), 1),
+        ];
+        assert_eq!(expected, rows(&mut source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_on_columns() -> Result<()> {
+        let mut source = DistinctSource::distinct_on(fruit(), &["fruit"])?;
+        // the first occurrence of each fruit is kept
+        let expected = vec![
+            ("apple".to_string(), 1),
+            ("pear".to_string(), 2),
+            ("plum".to_string(), 1),
+        ];
+        assert_eq!(expected, rows(&mut source)?);
+        assert!(DistinctSource::distinct_on(fruit(), &["fruits"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_with_bloom_filter() -> Result<()> {
+        let mut source = DistinctSource::new(fruit()).with_bloom_filter(16);
+        let expected = vec![
+            ("apple".to_string(), 1),
+            ("pear".to_string(), 2),
+            ("apple".to_string(), 3),
+            ("plum".to_string(), 1),
+        ];
+        assert_eq!(expected, rows(&mut source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_cancelled() -> Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut source = DistinctSource::new(fruit()).with_cancel(cancel.clone());
+        assert!(source.next_batch()?.is_some());
+        cancel.store(true, Ordering::Relaxed);
+        match source.next_batch() {
+            Err(DataFrameError::Cancelled) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Expected reading to be cancelled"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Exploding the lists of a list column into rows as data sources are read
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ListArray, UInt32Array};
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+
+/// Replace each row with one row per element of its list in `column`
+///
+/// The values of the other columns are repeated for each element. Rows with empty or null lists
+/// are dropped, unless `keep_empty` is set, in which case they are kept with a null element.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExplodeOperation {
+    pub column: String,
+    pub keep_empty: bool,
+}
+
+impl ExplodeOperation {
+    pub fn name() -> &'static str {
+        "explode"
+    }
+
+    /// Explode the batches of a data source, checking that the column is a list column
+    pub fn apply<S: DataSource>(&self, source: S) -> Result<ExplodeSource<S>> {
+        ExplodeSource::try_new(source, self.clone())
+    }
+}
+
+/// A data source that applies an `ExplodeOperation` to the batches of another source
+///
+/// Each batch is exploded on its own, so a batch can grow by the number of elements in its
+/// lists. Batches that have no rows left after exploding are skipped.
+pub struct ExplodeSource<S: DataSource> {
+    source: S,
+    operation: ExplodeOperation,
+    /// The index of the list column
+    index: usize,
+    schema: SchemaRef,
+}
+
+impl<S: DataSource> ExplodeSource<S> {
+    pub fn try_new(source: S, operation: ExplodeOperation) -> Result<Self> {
+        let source_schema = source.schema();
+        let index = source_schema.index_of(&operation.column).map_err(|_| {
+            DataFrameError::InvalidOperation {
+                message: format!("Column {} not found", operation.column),
+                suggestion: crate::utils::closest_match(
+                    &operation.column,
+                    source_schema.fields().iter().map(|f| f.name().as_str()),
+                )
+                .map(|similar| format!("did you mean '{}'?", similar)),
+            }
+        })?;
+        let fields = source_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match field.data_type() {
+                // element nullability is not known, so exploded values are always nullable
+                DataType::List(element) if i == index => {
+                    Ok(Field::new(field.name(), *element.clone(), true))
+                }
+                data_type if i == index => Err(DataFrameError::UnsupportedOperation {
+                    op: ExplodeOperation::name().to_string(),
+                    reason: format!(
+                        "Only list columns can be exploded, column {} is {:?}",
+                        field.name(),
+                        data_type
+                    ),
+                }),
+                _ => Ok(field.clone()),
+            })
+            .collect::<Result<Vec<Field>>>()?;
+        Ok(Self {
+            source,
+            operation,
+            index,
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    fn explode_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let lists = batch
+            .column(self.index)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        // the row of each output row, and the position of its element in the list values
+        let mut rows = Vec::with_capacity(lists.values().len());
+        let mut elements = Vec::with_capacity(lists.values().len());
+        for i in 0..lists.len() {
+            let length = if lists.is_null(i) {
+                0
+            } else {
+                lists.value_length(i) as u32
+            };
+            if length == 0 && self.operation.keep_empty {
+                rows.push(i as u32);
+                elements.push(None);
+            }
+            let offset = lists.value_offset(i) as u32;
+            for j in offset..offset + length {
+                rows.push(i as u32);
+                elements.push(Some(j));
+            }
+        }
+        let rows = UInt32Array::from(rows);
+        let elements = UInt32Array::from(elements);
+        let columns = batch
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                if i == self.index {
+                    compute::take(&lists.values(), &elements, None)
+                } else {
+                    compute::take(column, &rows, None)
+                }
+            })
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+impl<S: DataSource> DataSource for ExplodeSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: self.source.get_dataset()?.name,
+            columns: self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        while let Some(batch) = self.source.next_batch()? {
+            let batch = self.explode_batch(&batch)?;
+            if batch.num_rows() > 0 {
+                return Ok(Some(batch));
+            }
+        }
+        Ok(None)
+    }
+    fn limit(&mut self, _limit: usize) -> Result<()> {
+        // limiting the wrapped source would limit the rows before they are exploded
+        Err(DataFrameError::ComputeError(
+            "Explode data source does not support limits".to_string(),
+        ))
+    }
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        // the wrapped source would filter on the lists rather than their elements
+        Err(DataFrameError::ComputeError(
+            "Explode data source does not support filtering".to_string(),
+        ))
+    }
+    fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Explode data source does not support projection".to_string(),
+        ))
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Explode data source does not support sorting".to_string(),
+        ))
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::array::{Int64Array, Int64Builder, ListBuilder, StringArray};
+
+    /// A source of names and lists of scores, with an empty and a null list
+    fn scores() -> MemoryDataSource {
+        let mut scores = ListBuilder::new(Int64Builder::new(8));
+        scores.values().append_slice(&[1, 2, 3]).unwrap();
+        scores.append(true).unwrap();
+        scores.append(true).unwrap();
+        scores.values().append_slice(&[4, 5]).unwrap();
+        scores.append(true).unwrap();
+        scores.append(false).unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("scores", DataType::List(Box::new(DataType::Int64)), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d"])),
+                Arc::new(scores.finish()),
+            ],
+        )
+        .unwrap();
+        MemoryDataSource::try_new("scores", schema, vec![batch]).unwrap()
+    }
+
+    fn rows(source: &mut dyn DataSource) -> Result<Vec<(String, Option<i64>)>> {
+        let mut rows = vec![];
+        while let Some(batch) = source.next_batch()? {
+            let names = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            let scores = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            for i in 0..batch.num_rows() {
+                let score = if scores.is_null(i) {
+                    None
+                } else {
+                    Some(scores.value(i))
+                };
+                rows.push((names.value(i).to_string(), score));
+            }
+        }
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_explode_list_column() -> Result<()> {
+        let operation = ExplodeOperation {
+            column: "scores".to_owned(),
+            keep_empty: false,
+        };
+        let mut source = operation.apply(scores())?;
+        assert_eq!(&DataType::Int64, source.schema().field(1).data_type());
+        let expected = vec![
+            ("a".to_string(), Some(1)),
+            ("a".to_string(), Some(2)),
+            ("a".to_string(), Some(3)),
+            ("c".to_string(), Some(4)),
+            ("c".to_string(), Some(5)),
+        ];
+        assert_eq!(expected, rows(&mut source)?);
+
+        // empty and null lists are kept as null elements
+        let operation = ExplodeOperation {
+            column: "scores".to_owned(),
+            keep_empty: true,
+        };
+        let mut source = operation.apply(scores())?;
+        let rows = rows(&mut source)?;
+        assert_eq!(7, rows.len());
+        assert_eq!(("b".to_string(), None), rows[3]);
+        assert_eq!(("d".to_string(), None), rows[6]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_explode_requires_list_column() {
+        let operation = ExplodeOperation {
+            column: "name".to_owned(),
+            keep_empty: false,
+        };
+        assert!(operation.apply(scores()).is_err());
+        let operation = ExplodeOperation {
+            column: "score".to_owned(),
+            keep_empty: false,
+        };
+        match operation.apply(scores()) {
+            Err(DataFrameError::InvalidOperation { suggestion, .. }) => {
+                assert_eq!(Some("did you mean 'scores'?".to_string()), suggestion)
+            }
+            _ => panic!("Expected an invalid operation"),
+        }
+    }
+}
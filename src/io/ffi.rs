@@ -0,0 +1,125 @@
+//! Sharing record batches with other runtimes through the Arrow C Data Interface
+//!
+//! Exported arrays are handed over as pointers to `FFI_ArrowArray` and `FFI_ArrowSchema`
+//! structs, which other implementations such as pyarrow can import without copying or
+//! serializing the data. A record batch is exported as a struct array with a field per column,
+//! which is how the C Data Interface represents record batches.
+
+use std::sync::Arc;
+
+use arrow::array::{make_array_from_raw, Array, ArrayRef, StructArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+
+/// Export an array through the C Data Interface
+///
+/// The consumer of the pointers takes ownership of the exported structs, and is responsible for
+/// calling their release callbacks, which frees the array's buffers once no longer used.
+pub fn export_array(array: &ArrayRef) -> Result<(*const FFI_ArrowArray, *const FFI_ArrowSchema)> {
+    Ok(array.to_raw()?)
+}
+
+/// Import an array that was exported through the C Data Interface, taking ownership of it
+///
+/// # Safety
+///
+/// The pointers must point to valid structs that follow the C Data Interface, and that have not
+/// been released or imported already.
+pub unsafe fn import_array(
+    array: *const FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+) -> Result<ArrayRef> {
+    Ok(make_array_from_raw(array, schema)?)
+}
+
+/// Export a record batch through the C Data Interface as a struct array of its columns
+///
+/// The batch can be imported by pyarrow with `pyarrow.RecordBatch._import_from_c`.
+pub fn export_batch(
+    batch: &RecordBatch,
+) -> Result<(*const FFI_ArrowArray, *const FFI_ArrowSchema)> {
+    let columns = batch
+        .schema()
+        .fields()
+        .iter()
+        .cloned()
+        .zip(batch.columns().iter().cloned())
+        .collect::<Vec<(Field, ArrayRef)>>();
+    let array: ArrayRef = Arc::new(StructArray::from(columns));
+    export_array(&array)
+}
+
+/// Import a record batch that was exported through the C Data Interface as a struct array
+///
+/// # Safety
+///
+/// The pointers must point to valid structs that follow the C Data Interface, and that have not
+/// been released or imported already.
+pub unsafe fn import_batch(
+    array: *const FFI_ArrowArray,
+    schema: *const FFI_ArrowSchema,
+) -> Result<RecordBatch> {
+    let array = import_array(array, schema)?;
+    let fields = match array.data_type() {
+        DataType::Struct(fields) => fields.clone(),
+        data_type => {
+            return Err(DataFrameError::UnsupportedOperation {
+                op: "import_batch".to_string(),
+                reason: format!(
+                    "Record batches are imported from struct arrays, but the array is {:?}",
+                    data_type
+                ),
+            })
+        }
+    };
+    let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+    let columns = (0..array.num_columns())
+        .map(|i| array.column(i).clone())
+        .collect();
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int32Array, StringArray};
+
+    #[test]
+    fn test_batch_round_trip() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+                Arc::new(Float64Array::from(vec![Some(1.5), Some(2.5), None])),
+            ],
+        )?;
+        let (array, array_schema) = export_batch(&batch)?;
+        let imported = unsafe { import_batch(array, array_schema)? };
+        assert_eq!(schema, imported.schema());
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&[batch])?,
+            arrow::util::pretty::pretty_format_batches(&[imported])?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_batch_requires_struct_array() -> Result<()> {
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let (array, schema) = export_array(&array)?;
+        assert!(unsafe { import_batch(array, schema) }.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Filtering the rows of data sources as they are read
+
+use arrow::array::{ArrayRef, BooleanArray};
+use arrow::compute;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+
+/// A data source that filters the batches of another source in memory
+///
+/// This allows filtering sources that do not support filtering themselves. Batches that have
+/// no rows left after filtering are skipped, so `next_batch` does not return empty batches.
+pub struct FilterSource<S: DataSource> {
+    source: S,
+    filter: BooleanFilter,
+}
+
+impl<S: DataSource> FilterSource<S> {
+    pub fn new(source: S, filter: BooleanFilter) -> Self {
+        Self { source, filter }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+/// Return the rows of a batch that match a filter
+fn filter_batch(batch: &RecordBatch, filter: &BooleanFilter) -> Result<RecordBatch> {
+    let mask = compute::cast(&filter.eval_to_array(batch)?, &DataType::Boolean)?;
+    let mask = BooleanArray::from(mask.data());
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|column| compute::filter(column.as_ref(), &mask))
+        .collect::<arrow::error::Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
+
+impl<S: DataSource> DataSource for FilterSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        self.source.get_dataset()
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        while let Some(batch) = self.source.next_batch()? {
+            let batch = filter_batch(&batch, &self.filter)?;
+            if batch.num_rows() > 0 {
+                return Ok(Some(batch));
+            }
+        }
+        Ok(None)
+    }
+    fn supports_filtering(&self) -> bool {
+        true
+    }
+    fn supports_sorting(&self) -> bool {
+        self.source.supports_sorting()
+    }
+    fn limit(&mut self, _limit: usize) -> Result<()> {
+        // limiting the wrapped source would apply the limit before the filter
+        Err(DataFrameError::ComputeError(
+            "Filter data source does not support limits".to_string(),
+        ))
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        self.filter = BooleanFilter::And(Box::new(self.filter.clone()), Box::new(filter));
+        Ok(())
+    }
+    fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+        // projecting the wrapped source could remove the columns that are filtered on
+        Err(DataFrameError::ComputeError(
+            "Filter data source does not support projection".to_string(),
+        ))
+    }
+    fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+        self.source.sort(criteria)
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{Column, ColumnType};
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::array::{Array, Int64Array};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_filter_memory_source() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batches = vec![vec![1, 7, 3], vec![2, 4, 5], vec![6, 9, 0]]
+            .into_iter()
+            .map(|values| {
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))])
+                    .unwrap()
+            })
+            .collect();
+        let source = MemoryDataSource::try_new("numbers", schema, batches)?;
+        let filter = BooleanFilter::Gt(
+            BooleanFilter::column(Column {
+                name: "a".to_owned(),
+                column_type: ColumnType::Scalar(DataType::Int64),
+                nullable: true,
+            }),
+            BooleanFilter::scalar(5i64),
+        );
+        let mut source = FilterSource::new(source, filter);
+        let mut values: Vec<Vec<i64>> = vec![];
+        while let Some(batch) = source.next_batch()? {
+            // the second batch has no matching rows, and is skipped
+            assert!(batch.num_rows() > 0);
+            let array = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            values.push(array.value_slice(0, array.len()).to_vec());
+        }
+        assert_eq!(vec![vec![7], vec![6, 9]], values);
+        Ok(())
+    }
+}
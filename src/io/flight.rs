@@ -0,0 +1,477 @@
+//! Serving data sources over Arrow Flight, and reading from Flight services
+
+use std::pin::Pin;
+use std::thread;
+
+use arrow_flight::FlightData;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, Stream};
+use tonic::Status;
+
+use crate::error::{DataFrameError, Result};
+use crate::io::datasource::DataSource;
+
+#[cfg(feature = "async")]
+pub use client::FlightDataSource;
+
+/// The number of Flight messages that are read ahead of the client
+const FLIGHT_BUFFER_SIZE: usize = 2;
+
+/// The stream of Flight data messages returned by a `do_get` request
+pub type FlightDataStream =
+    Pin<Box<dyn Stream<Item = std::result::Result<FlightData, Status>> + Send + Sync + 'static>>;
+
+/// Stream the batches of a data source as the response of a Flight `do_get` request
+///
+/// The first message carries the source's schema, followed by a message per record batch.
+/// Data sources are not `Send`, so the source is opened and read on its own thread, with
+/// batches only read as the client consumes them. Reading stops if the client goes away.
+pub fn do_get_stream<F>(open: F) -> FlightDataStream
+where
+    F: FnOnce() -> Result<Box<dyn DataSource>> + Send + 'static,
+{
+    let (mut sender, receiver) = mpsc::channel(FLIGHT_BUFFER_SIZE);
+    thread::spawn(move || {
+        let mut source = match open() {
+            Ok(source) => source,
+            Err(e) => {
+                let _ = block_on(sender.send(Err(Status::from(e))));
+                return;
+            }
+        };
+        let schema = source.schema();
+        if block_on(sender.send(Ok(FlightData::from(schema.as_ref())))).is_err() {
+            return;
+        }
+        loop {
+            let message = match source.next_batch() {
+                Ok(Some(batch)) => Ok(FlightData::from(&batch)),
+                Ok(None) => break,
+                Err(e) => Err(Status::from(e)),
+            };
+            let is_err = message.is_err();
+            if block_on(sender.send(message)).is_err() || is_err {
+                break;
+            }
+        }
+    });
+    Box::pin(receiver)
+}
+
+/// Reading from Flight services, which is only done without blocking
+#[cfg(feature = "async")]
+mod client {
+    use std::collections::VecDeque;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use arrow::datatypes::{Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+    use arrow_flight::flight_descriptor::DescriptorType;
+    use arrow_flight::flight_service_client::FlightServiceClient;
+    use arrow_flight::utils::flight_data_to_batch;
+    use arrow_flight::{FlightData, FlightDescriptor, Ticket};
+    use async_trait::async_trait;
+    use tonic::transport::Channel;
+    use tonic::Streaming;
+
+    use crate::error::{DataFrameError, Result};
+    use crate::expression::{BooleanFilter, DataSourceType, Dataset, FlightRequest, SortCriteria};
+    use crate::io::async_source::AsyncDataSource;
+
+    /// A data source that reads the batches of a Flight service, without blocking
+    ///
+    /// A ticket is read with a single `do_get` request. A command is first resolved to endpoints
+    /// with `get_flight_info`, and the tickets of the endpoints are then read in order. Endpoints
+    /// are always read from the service that was connected to, their locations are not followed.
+    pub struct FlightDataSource {
+        client: FlightServiceClient<Channel>,
+        address: String,
+        request: FlightRequest,
+        schema: SchemaRef,
+        /// The tickets of the endpoints that have not been read yet
+        tickets: VecDeque<Ticket>,
+        /// The stream of the endpoint that is being read
+        stream: Option<Streaming<FlightData>>,
+        limit: Option<usize>,
+        rows_read: usize,
+    }
+
+    impl FlightDataSource {
+        /// Connect to a Flight service, and start reading the first endpoint to get its schema
+        pub async fn try_new(address: &str, request: FlightRequest) -> Result<Self> {
+            let mut client = FlightServiceClient::connect(address.to_string()).await?;
+            let mut tickets = match &request {
+                FlightRequest::Ticket(ticket) => vec![Ticket {
+                    ticket: ticket.clone(),
+                }],
+                FlightRequest::Command(command) => {
+                    let descriptor = FlightDescriptor {
+                        r#type: DescriptorType::Cmd as i32,
+                        cmd: command.clone(),
+                        path: vec![],
+                    };
+                    let info = client.get_flight_info(descriptor).await?.into_inner();
+                    info.endpoint
+                        .into_iter()
+                        .filter_map(|endpoint| endpoint.ticket)
+                        .collect()
+                }
+            }
+            .into_iter()
+            .collect::<VecDeque<Ticket>>();
+            let ticket = tickets.pop_front().ok_or_else(|| {
+                DataFrameError::IoError(format!("Flight service {} returned no endpoints", address))
+            })?;
+            let (stream, schema) = Self::open_stream(&mut client, ticket).await?;
+            Ok(Self {
+                client,
+                address: address.to_string(),
+                request,
+                schema: Arc::new(schema),
+                tickets,
+                stream: Some(stream),
+                limit: None,
+                rows_read: 0,
+            })
+        }
+
+        /// Request the data of a ticket, and read the schema that is its first message
+        async fn open_stream(
+            client: &mut FlightServiceClient<Channel>,
+            ticket: Ticket,
+        ) -> Result<(Streaming<FlightData>, Schema)> {
+            let mut stream = client.do_get(ticket).await?.into_inner();
+            let message = stream.message().await?.ok_or_else(|| {
+                DataFrameError::IoError("Flight stream ended without a schema".to_string())
+            })?;
+            let schema = Schema::try_from(&message)?;
+            Ok((stream, schema))
+        }
+    }
+
+    #[async_trait]
+    impl AsyncDataSource for FlightDataSource {
+        fn get_dataset(&self) -> Result<Dataset> {
+            Ok(Dataset {
+                name: self.address.clone(),
+                columns: self
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(|f| f.clone().into())
+                    .collect(),
+            })
+        }
+        fn source(&self) -> DataSourceType {
+            DataSourceType::Flight(self.address.clone(), self.request.clone())
+        }
+        fn format(&self) -> &str {
+            "flight"
+        }
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+        async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+            loop {
+                let remaining = self.limit.map(|limit| limit.saturating_sub(self.rows_read));
+                if remaining == Some(0) {
+                    return Ok(None);
+                }
+                if self.stream.is_none() {
+                    let ticket = match self.tickets.pop_front() {
+                        Some(ticket) => ticket,
+                        None => return Ok(None),
+                    };
+                    let (stream, schema) = Self::open_stream(&mut self.client, ticket).await?;
+                    if schema != *self.schema {
+                        return Err(DataFrameError::IoError(format!(
+                            "Endpoints of Flight service {} have different schemas",
+                            self.address
+                        )));
+                    }
+                    self.stream = Some(stream);
+                }
+                let message = match self.stream.as_mut().unwrap().message().await? {
+                    Some(message) => message,
+                    None => {
+                        self.stream = None;
+                        continue;
+                    }
+                };
+                // messages that are not record batches, such as dictionaries, are skipped
+                let batch = match flight_data_to_batch(&message, self.schema.clone())? {
+                    Some(batch) => batch,
+                    None => continue,
+                };
+                let batch = match remaining {
+                    Some(remaining) if remaining < batch.num_rows() => RecordBatch::try_new(
+                        self.schema.clone(),
+                        batch
+                            .columns()
+                            .iter()
+                            .map(|column| column.slice(0, remaining))
+                            .collect(),
+                    )?,
+                    _ => batch,
+                };
+                self.rows_read += batch.num_rows();
+                return Ok(Some(batch));
+            }
+        }
+        fn supports_limit(&self) -> bool {
+            true
+        }
+        async fn limit(&mut self, limit: usize) -> Result<()> {
+            self.limit = Some(limit);
+            Ok(())
+        }
+        async fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+            Err(DataFrameError::ComputeError(
+                "Flight data source does not support filtering".to_string(),
+            ))
+        }
+        async fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+            Err(DataFrameError::ComputeError(
+                "Flight data source does not support projection".to_string(),
+            ))
+        }
+        async fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+            Err(DataFrameError::ComputeError(
+                "Flight data source does not support sorting".to_string(),
+            ))
+        }
+    }
+}
+
+impl From<Status> for DataFrameError {
+    fn from(status: Status) -> Self {
+        DataFrameError::IoError(status.to_string())
+    }
+}
+
+impl From<tonic::transport::Error> for DataFrameError {
+    fn from(error: tonic::transport::Error) -> Self {
+        DataFrameError::IoError(error.to_string())
+    }
+}
+
+impl From<DataFrameError> for Status {
+    fn from(error: DataFrameError) -> Self {
+        match error {
+            DataFrameError::MemoryError(e) => Status::resource_exhausted(e),
+            DataFrameError::ParseError(e) => Status::invalid_argument(e),
+            DataFrameError::ComputeError(e) | DataFrameError::ArrowError(e) => Status::internal(e),
+            DataFrameError::DivideByZero => Status::invalid_argument("Division by zero"),
+            DataFrameError::IoError(e) | DataFrameError::SqlError(e) => Status::unavailable(e),
+            DataFrameError::NoneError => Status::internal("Unexpected missing value"),
+            DataFrameError::Cancelled => Status::cancelled("Execution was cancelled"),
+            DataFrameError::InvalidOperation {
+                message,
+                suggestion: Some(suggestion),
+            } => Status::invalid_argument(format!("{}; {}", message, suggestion)),
+            DataFrameError::InvalidOperation { message, .. } => Status::invalid_argument(message),
+            DataFrameError::UnsupportedOperation { op, reason } => {
+                Status::invalid_argument(format!("{} operation: {}", op, reason))
+            }
+            DataFrameError::SchemaMismatch {
+                column,
+                expected,
+                actual,
+            } => Status::invalid_argument(format!(
+                "Column {} is of type {:?}, but {:?} was expected",
+                column, actual, expected
+            )),
+            DataFrameError::ColumnNotFound(column) => {
+                Status::not_found(format!("Column {} not found", column))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use arrow::datatypes::Schema;
+    use arrow_flight::flight_service_client::FlightServiceClient;
+    use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+    use arrow_flight::utils::flight_data_to_batch;
+    use arrow_flight::{
+        Action, ActionType, Criteria, Empty, FlightDescriptor, FlightEndpoint, FlightInfo,
+        HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+    };
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Streaming};
+
+    use crate::io::datasource::CsvDataSource;
+
+    type TestStream<T> =
+        Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send + Sync + 'static>>;
+
+    /// A Flight service that serves a CSV file for any ticket, and two endpoints for any command
+    struct CsvFlightService;
+
+    #[tonic::async_trait]
+    impl FlightService for CsvFlightService {
+        type HandshakeStream = TestStream<HandshakeResponse>;
+        type ListFlightsStream = TestStream<FlightInfo>;
+        type DoGetStream = FlightDataStream;
+        type DoPutStream = TestStream<PutResult>;
+        type DoActionStream = TestStream<arrow_flight::Result>;
+        type ListActionsStream = TestStream<ActionType>;
+        type DoExchangeStream = TestStream<FlightData>;
+
+        async fn do_get(
+            &self,
+            _request: Request<Ticket>,
+        ) -> std::result::Result<Response<Self::DoGetStream>, Status> {
+            Ok(Response::new(do_get_stream(|| {
+                let source = CsvDataSource::try_new(
+                    "./test/data/uk_cities_with_headers.csv",
+                    Default::default(),
+                )?;
+                Ok(Box::new(source) as Box<dyn DataSource>)
+            })))
+        }
+
+        async fn handshake(
+            &self,
+            _request: Request<Streaming<HandshakeRequest>>,
+        ) -> std::result::Result<Response<Self::HandshakeStream>, Status> {
+            Err(Status::unimplemented("handshake"))
+        }
+
+        async fn list_flights(
+            &self,
+            _request: Request<Criteria>,
+        ) -> std::result::Result<Response<Self::ListFlightsStream>, Status> {
+            Err(Status::unimplemented("list_flights"))
+        }
+
+        async fn get_flight_info(
+            &self,
+            request: Request<FlightDescriptor>,
+        ) -> std::result::Result<Response<FlightInfo>, Status> {
+            let endpoint = |ticket: &[u8]| FlightEndpoint {
+                ticket: Some(Ticket {
+                    ticket: ticket.to_vec(),
+                }),
+                location: vec![],
+            };
+            Ok(Response::new(FlightInfo {
+                schema: vec![],
+                flight_descriptor: Some(request.into_inner()),
+                endpoint: vec![endpoint(b"first"), endpoint(b"second")],
+                total_records: -1,
+                total_bytes: -1,
+            }))
+        }
+
+        async fn get_schema(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> std::result::Result<Response<SchemaResult>, Status> {
+            Err(Status::unimplemented("get_schema"))
+        }
+
+        async fn do_put(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> std::result::Result<Response<Self::DoPutStream>, Status> {
+            Err(Status::unimplemented("do_put"))
+        }
+
+        async fn do_action(
+            &self,
+            _request: Request<Action>,
+        ) -> std::result::Result<Response<Self::DoActionStream>, Status> {
+            Err(Status::unimplemented("do_action"))
+        }
+
+        async fn list_actions(
+            &self,
+            _request: Request<Empty>,
+        ) -> std::result::Result<Response<Self::ListActionsStream>, Status> {
+            Err(Status::unimplemented("list_actions"))
+        }
+
+        async fn do_exchange(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> std::result::Result<Response<Self::DoExchangeStream>, Status> {
+            Err(Status::unimplemented("do_exchange"))
+        }
+    }
+
+    /// Serve `CsvFlightService` on a free port, returning the address of the service
+    ///
+    /// The port is listened on before returning, so clients can connect to it immediately.
+    async fn serve() -> String {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(FlightServiceServer::new(CsvFlightService))
+                .serve_with_incoming(listener.incoming())
+                .await
+        });
+        address
+    }
+
+    /// Serve a CSV source over Flight, and read it back with a Flight client
+    #[tokio::test]
+    async fn test_flight_round_trip() {
+        let mut client = FlightServiceClient::connect(serve().await).await.unwrap();
+        let mut stream = client
+            .do_get(Ticket { ticket: vec![] })
+            .await
+            .unwrap()
+            .into_inner();
+        let schema_message = stream.next().await.unwrap().unwrap();
+        let schema = Arc::new(Schema::try_from(&schema_message).unwrap());
+        assert_eq!(3, schema.fields().len());
+
+        let mut num_rows = 0;
+        while let Some(data) = stream.next().await {
+            let batch = flight_data_to_batch(&data.unwrap(), schema.clone())
+                .unwrap()
+                .unwrap();
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(37, num_rows);
+    }
+
+    /// Read a ticket and a command from a Flight service with a `FlightDataSource`
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_flight_data_source() -> Result<()> {
+        use crate::expression::FlightRequest;
+        use crate::io::async_source::AsyncDataSource;
+
+        let address = serve().await;
+        let address = address.as_str();
+        let mut source =
+            FlightDataSource::try_new(address, FlightRequest::Ticket(b"cities".to_vec())).await?;
+        assert_eq!(3, source.schema().fields().len());
+        let batches = source.collect().await?;
+        assert_eq!(37, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+        // the command is resolved to two endpoints, which are read one after the other
+        let request = FlightRequest::Command(b"select * from cities".to_vec());
+        let mut source = FlightDataSource::try_new(address, request.clone()).await?;
+        let batches = source.collect().await?;
+        assert_eq!(74, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+
+        let mut source = FlightDataSource::try_new(address, request).await?;
+        source.limit(40).await?;
+        let batches = source.collect().await?;
+        assert_eq!(40, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+        Ok(())
+    }
+}
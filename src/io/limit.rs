@@ -0,0 +1,207 @@
+//! Limiting and offsetting the rows of data sources as they are read
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+
+/// Skip `offset` rows, then return at most `limit` rows
+///
+/// Unlike a source's own limit, this can be applied to the output of any data source, including
+/// sources that do not support limits.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LimitOperation {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl LimitOperation {
+    pub fn name() -> &'static str {
+        "limit"
+    }
+
+    /// Apply the limit and offset to the batches of a data source
+    pub fn apply<S: DataSource>(&self, source: S) -> LimitSource<S> {
+        LimitSource::new(source, *self)
+    }
+}
+
+/// A data source that applies a `LimitOperation` to the batches of another source
+///
+/// Rows are counted across calls to `next_batch`, and batches that span the offset or the limit
+/// are sliced. Once the limit is reached, no more batches are read from the wrapped source.
+pub struct LimitSource<S: DataSource> {
+    source: S,
+    operation: LimitOperation,
+    rows_skipped: usize,
+    rows_returned: usize,
+}
+
+impl<S: DataSource> LimitSource<S> {
+    pub fn new(source: S, operation: LimitOperation) -> Self {
+        Self {
+            source,
+            operation,
+            rows_skipped: 0,
+            rows_returned: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+/// Slice `length` rows starting at `offset` out of a batch
+fn slice_batch(batch: &RecordBatch, offset: usize, length: usize) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        batch.schema(),
+        batch
+            .columns()
+            .iter()
+            .map(|c| c.slice(offset, length))
+            .collect(),
+    )?)
+}
+
+impl<S: DataSource> DataSource for LimitSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        self.source.get_dataset()
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let LimitOperation { limit, offset } = self.operation;
+        while self.rows_returned < limit {
+            let batch = match self.source.next_batch()? {
+                Some(batch) => batch,
+                None => return Ok(None),
+            };
+            // skip the rows that are still within the offset
+            let skip = (offset - self.rows_skipped).min(batch.num_rows());
+            self.rows_skipped += skip;
+            let length = (batch.num_rows() - skip).min(limit - self.rows_returned);
+            if length == 0 {
+                continue;
+            }
+            self.rows_returned += length;
+            if length == batch.num_rows() {
+                return Ok(Some(batch));
+            }
+            return Ok(Some(slice_batch(&batch, skip, length)?));
+        }
+        Ok(None)
+    }
+    fn supports_projection(&self) -> bool {
+        self.source.supports_projection()
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.operation.limit = self.operation.limit.min(limit);
+        Ok(())
+    }
+    fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        // filtering the wrapped source would change which rows are skipped
+        Err(DataFrameError::ComputeError(
+            "Limit data source does not support filtering".to_string(),
+        ))
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        self.source.project(columns)
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Limit data source does not support sorting".to_string(),
+        ))
+    }
+    fn row_count_hint(&self) -> Option<usize> {
+        let LimitOperation { limit, offset } = self.operation;
+        self.source
+            .row_count_hint()
+            .map(|num_rows| num_rows.saturating_sub(offset).min(limit))
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::array::{Array, Int32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    /// A source of 3 batches of 4 rows, with the values 0 to 11
+    fn numbers() -> MemoryDataSource {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..3)
+            .map(|i| {
+                let values: Vec<i32> = (i * 4..(i + 1) * 4).collect();
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+                    .unwrap()
+            })
+            .collect();
+        MemoryDataSource::try_new("numbers", schema, batches).unwrap()
+    }
+
+    fn values(source: &mut dyn DataSource) -> Result<Vec<Vec<i32>>> {
+        let mut values = vec![];
+        while let Some(batch) = source.next_batch()? {
+            let array = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap();
+            values.push((0..array.len()).map(|i| array.value(i)).collect());
+        }
+        Ok(values)
+    }
+
+    #[test]
+    fn test_offset_within_first_batch() -> Result<()> {
+        let operation = LimitOperation {
+            limit: 2,
+            offset: 1,
+        };
+        let mut source = operation.apply(numbers());
+        assert_eq!(Some(2), source.row_count_hint());
+        assert_eq!(vec![vec![1, 2]], values(&mut source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_spanning_batches() -> Result<()> {
+        let operation = LimitOperation {
+            limit: 6,
+            offset: 5,
+        };
+        let mut source = operation.apply(numbers());
+        // the first batch is skipped, and the last is cut short by the limit
+        assert_eq!(vec![vec![5, 6, 7], vec![8, 9, 10]], values(&mut source)?);
+        assert!(source.next_batch()?.is_none());
+
+        // an offset past the end of the source returns no rows
+        let operation = LimitOperation {
+            limit: 6,
+            offset: 20,
+        };
+        let mut source = operation.apply(numbers());
+        assert_eq!(Some(0), source.row_count_hint());
+        assert!(values(&mut source)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Accounting of the memory used by record batches
+
+use std::sync::{Arc, Weak};
+
+use arrow::array::{ArrayData, ArrayDataRef};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+
+/// Tracks the memory of the arrays of record batches, for as long as the batches are in use
+///
+/// Arrow does not yet support custom memory pools, so instead of accounting for allocations
+/// as they are made, the tracker keeps weak references to the arrays that it is given.
+/// An array's buffers are counted until all of its strong references are dropped.
+/// Buffers that are shared between arrays are counted once per array.
+#[derive(Debug, Default)]
+pub struct MemoryTracker {
+    arrays: Vec<Weak<ArrayData>>,
+}
+
+impl MemoryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track the arrays of a batch
+    pub fn track(&mut self, batch: &RecordBatch) {
+        // forget arrays that have been dropped, so that the tracker does not grow indefinitely
+        self.arrays.retain(|array| array.strong_count() > 0);
+        self.arrays.extend(
+            batch
+                .columns()
+                .iter()
+                .map(|column| Arc::downgrade(&column.data())),
+        );
+    }
+
+    /// The capacity in bytes of the buffers of tracked arrays that are still in use
+    pub fn allocated_bytes(&self) -> usize {
+        self.arrays
+            .iter()
+            .filter_map(|array| array.upgrade())
+            .map(|array| array_data_bytes(&array))
+            .sum()
+    }
+}
+
+/// The capacity in bytes of an array's buffers, including its null bitmap and child arrays
+fn array_data_bytes(data: &ArrayDataRef) -> usize {
+    let buffers: usize = data.buffers().iter().map(|buffer| buffer.capacity()).sum();
+    let null_bitmap = data
+        .null_bitmap()
+        .as_ref()
+        .map(|bitmap| bitmap.buffer_ref().capacity())
+        .unwrap_or(0);
+    let children: usize = data.child_data().iter().map(array_data_bytes).sum();
+    buffers + null_bitmap + children
+}
+
+/// The number of bytes that a variable-width value, such as a string, is estimated to take,
+/// excluding its offset
+const VARIABLE_WIDTH_BYTES: usize = 32;
+
+/// The number of values that a list is estimated to have
+const LIST_LENGTH: usize = 4;
+
+/// Estimate the number of bytes that a row of a schema takes, from the widths of its types
+///
+/// Fixed-width values are counted with their width, while the sizes of strings, binary values
+/// and lists are estimated.
+pub fn estimated_row_bytes(schema: &Schema) -> usize {
+    schema
+        .fields()
+        .iter()
+        .map(|field| estimated_value_bytes(field.data_type()))
+        .sum()
+}
+
+fn estimated_value_bytes(data_type: &DataType) -> usize {
+    use DataType::*;
+    match data_type {
+        Null => 0,
+        Boolean | Int8 | UInt8 => 1,
+        Int16 | UInt16 | Float16 => 2,
+        Int32 | UInt32 | Float32 | Date32(_) | Time32(_) => 4,
+        Int64 | UInt64 | Float64 | Date64(_) | Time64(_) | Timestamp(_, _) | Duration(_) => 8,
+        FixedSizeBinary(width) => *width as usize,
+        FixedSizeList(inner, length) => *length as usize * estimated_value_bytes(inner),
+        List(inner) | LargeList(inner) => 4 + LIST_LENGTH * estimated_value_bytes(inner),
+        Struct(fields) => fields
+            .iter()
+            .map(|field| estimated_value_bytes(field.data_type()))
+            .sum(),
+        Dictionary(key, _) => estimated_value_bytes(key),
+        _ => 4 + VARIABLE_WIDTH_BYTES,
+    }
+}
+
+/// The number of rows of a schema that fit in a batch of `target_batch_bytes`
+///
+/// Wide schemas get fewer rows per batch than narrow ones. Batches have at least 1 row.
+pub fn batch_size_for(schema: &Schema, target_batch_bytes: usize) -> usize {
+    (target_batch_bytes / estimated_row_bytes(schema).max(1)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::io::datasource::{CsvDataSource, CsvSourceOptions, DataSource};
+    use arrow::datatypes::Field;
+
+    #[test]
+    fn test_csv_allocated_bytes() -> Result<()> {
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        assert_eq!(0, source.allocated_bytes());
+
+        let first = source.next_batch()?.unwrap();
+        let after_first = source.allocated_bytes();
+        assert!(after_first > 0);
+        let second = source.next_batch()?.unwrap();
+        assert!(source.allocated_bytes() > after_first);
+
+        // memory is released as batches are dropped
+        drop(first);
+        assert!(source.allocated_bytes() < after_first * 2);
+        drop(second);
+        assert_eq!(0, source.allocated_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_size_for_schema_width() {
+        let narrow = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
+        let mut fields = vec![Field::new("name", DataType::Utf8, true)];
+        fields.extend((0..50).map(|i| Field::new(&format!("f{}", i), DataType::Float64, true)));
+        let wide = Schema::new(fields);
+
+        let budget = 8 * 1024 * 1024;
+        assert_eq!(4, estimated_row_bytes(&narrow));
+        assert_eq!(2 * 1024 * 1024, batch_size_for(&narrow, budget));
+        assert!(batch_size_for(&wide, budget) < batch_size_for(&narrow, budget));
+        // batches always have a row
+        assert_eq!(1, batch_size_for(&wide, 10));
+    }
+}
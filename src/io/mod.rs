@@ -1,2 +1,22 @@
+#[cfg(feature = "async")]
+pub mod async_source;
+pub mod avro;
+pub mod bloom_filter;
+pub mod compression;
+pub mod counting;
+pub mod datasink;
 pub mod datasource;
+pub mod distinct;
+pub mod explode;
+pub mod ffi;
+pub mod filter;
+#[cfg(feature = "flight")]
+pub mod flight;
+pub mod limit;
+pub mod memory;
+pub mod null_values;
+pub mod parquet;
+pub mod rows;
+pub mod sort;
 pub mod sql;
+pub mod union;
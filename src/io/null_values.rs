@@ -0,0 +1,127 @@
+//! Replacing the null value tokens of CSV files, such as `NA` or `\N`, with empty fields
+
+use std::collections::HashSet;
+use std::io::{self, Read};
+
+/// A reader of CSV data that rewrites fields matching a set of null values as empty fields
+///
+/// The Arrow CSV reader reads empty fields as nulls, both when inferring a schema and when
+/// parsing values, so this allows reading files that use other null markers. Fields of the
+/// header record are not replaced. If there are no null values, the data is read unchanged.
+pub enum NullValuesReader<R: Read> {
+    Unchanged(R),
+    Replacing(Box<NullValuesReplacer<R>>),
+}
+
+pub struct NullValuesReplacer<R: Read> {
+    reader: csv::Reader<R>,
+    null_values: HashSet<Vec<u8>>,
+    delimiter: u8,
+    skip_header: bool,
+    record: csv::ByteRecord,
+    /// The rewritten record that is being read, and the position read up to
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> NullValuesReader<R> {
+    pub fn new(reader: R, null_values: &[String], has_header: bool, delimiter: u8) -> Self {
+        if null_values.is_empty() {
+            return NullValuesReader::Unchanged(reader);
+        }
+        NullValuesReader::Replacing(Box::new(NullValuesReplacer {
+            reader: csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(reader),
+            null_values: null_values
+                .iter()
+                .map(|value| value.as_bytes().to_vec())
+                .collect(),
+            delimiter,
+            skip_header: has_header,
+            record: csv::ByteRecord::new(),
+            buffer: vec![],
+            position: 0,
+        }))
+    }
+}
+
+impl<R: Read> NullValuesReplacer<R> {
+    /// Read the next record into the buffer, returning false once there are no more records
+    fn next_record(&mut self) -> io::Result<bool> {
+        self.buffer.clear();
+        self.position = 0;
+        if !self
+            .reader
+            .read_byte_record(&mut self.record)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        {
+            return Ok(false);
+        }
+        let is_header = self.skip_header;
+        self.skip_header = false;
+        for (i, field) in self.record.iter().enumerate() {
+            if i > 0 {
+                self.buffer.push(self.delimiter);
+            }
+            if !is_header && self.null_values.contains(field) {
+                continue;
+            }
+            let needs_quotes = field
+                .iter()
+                .any(|b| *b == self.delimiter || *b == b'"' || *b == b'\n' || *b == b'\r');
+            if needs_quotes {
+                self.buffer.push(b'"');
+                for b in field {
+                    if *b == b'"' {
+                        self.buffer.push(b'"');
+                    }
+                    self.buffer.push(*b);
+                }
+                self.buffer.push(b'"');
+            } else {
+                self.buffer.extend_from_slice(field);
+            }
+        }
+        self.buffer.push(b'\n');
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for NullValuesReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let replacer = match self {
+            NullValuesReader::Unchanged(reader) => return reader.read(buf),
+            NullValuesReader::Replacing(replacer) => replacer,
+        };
+        if replacer.position == replacer.buffer.len() && !replacer.next_record()? {
+            return Ok(0);
+        }
+        let remaining = &replacer.buffer[replacer.position..];
+        let length = remaining.len().min(buf.len());
+        buf[..length].copy_from_slice(&remaining[..length]);
+        replacer.position += length;
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_null_values() -> io::Result<()> {
+        let data = "name,value,note\nNA,NA,\"a, \"\"quoted\"\" note\"\nb,\\N,NA\n";
+        let null_values = vec!["NA".to_string(), "\\N".to_string()];
+        let mut reader = NullValuesReader::new(data.as_bytes(), &null_values, true, b',');
+        let mut output = String::new();
+        reader.read_to_string(&mut output)?;
+        assert_eq!(
+            "name,value,note\n,,\"a, \"\"quoted\"\" note\"\nb,,\n",
+            output
+        );
+        Ok(())
+    }
+}
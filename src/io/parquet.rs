@@ -0,0 +1,1521 @@
+//! Parquet-specific readers, writers and utilities
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+use std::fs::{metadata, read_dir, File};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, Int32Array, Int64Array};
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+use parquet::arrow::arrow_writer::ArrowWriter;
+use parquet::arrow::{parquet_to_arrow_schema, ArrowReader, ParquetFileArrowReader};
+use parquet::basic::{Compression, Type as PhysicalType};
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::statistics::Statistics;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{
+    BooleanFilter, BooleanInput, DataSourceType, Dataset, Scalar, SortCriteria,
+};
+use crate::io::bloom_filter::{bloom_filter_offsets, BloomFilter};
+use crate::io::datasink::{validate_schema, DataSink};
+use crate::io::datasource::DataSource;
+use crate::io::memory::batch_size_for;
+
+/// Options for reading a Parquet file, or a directory of Parquet files
+#[derive(Debug, Clone, Default)]
+pub struct ParquetReadOptions {
+    /// Whether files with different columns can be read together
+    ///
+    /// The output schema is the union of the files' schemas, in the order that columns are
+    /// first seen, and a file's rows are null for the columns that the file does not have.
+    /// Without schema evolution, all files must have the first file's schema.
+    pub schema_evolution: bool,
+}
+
+/// Options for writing a Parquet file
+#[derive(Debug, Clone)]
+pub struct ParquetWriteOptions {
+    /// The codec that all columns are compressed with, e.g. `Compression::SNAPPY` or
+    /// `Compression::ZSTD`
+    pub compression: Compression,
+    /// The maximum number of rows in a row group, or the writer's default if not set
+    pub max_row_group_size: Option<usize>,
+}
+
+impl Default for ParquetWriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression::UNCOMPRESSED,
+            max_row_group_size: None,
+        }
+    }
+}
+
+impl ParquetWriteOptions {
+    fn writer_properties(&self) -> WriterProperties {
+        let mut builder = WriterProperties::builder().set_compression(self.compression);
+        if let Some(size) = self.max_row_group_size {
+            builder = builder.set_max_row_group_size(size);
+        }
+        builder.build()
+    }
+}
+
+/// Writes record batches to a Parquet file
+///
+/// The file is only valid once the sink is finished, which writes the file's footer.
+pub struct ParquetDataSink {
+    schema: SchemaRef,
+    writer: ArrowWriter<File>,
+}
+
+impl ParquetDataSink {
+    /// Create a sink that writes to a new file at `path`, replacing any existing file
+    pub fn try_new(path: &str, schema: SchemaRef, options: ParquetWriteOptions) -> Result<Self> {
+        let file = File::create(path)?;
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(options.writer_properties()))?;
+        Ok(Self { schema, writer })
+    }
+}
+
+impl DataSink for ParquetDataSink {
+    fn format(&self) -> &str {
+        "parquet"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        validate_schema(&self.schema, batch)?;
+        Ok(self.writer.write(batch)?)
+    }
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.writer.close()?)
+    }
+}
+
+/// A summary of a Parquet file's metadata, as read from its footer
+#[derive(Debug, Clone)]
+pub struct ParquetMetadataSummary {
+    /// The total number of rows across all row groups
+    pub num_rows: i64,
+    pub num_row_groups: usize,
+    pub schema: Schema,
+    /// The compression codec of each leaf column, taken from the first row group
+    pub column_compression: Vec<(String, Compression)>,
+    /// The size of the file in bytes
+    pub file_size: u64,
+}
+
+/// Read a Parquet file's metadata without reading any of its data
+///
+/// Only the file footer is read, which makes this cheaper than creating a data source
+/// when cataloging files.
+pub fn parquet_metadata(path: &str) -> Result<ParquetMetadataSummary> {
+    let file_size = metadata(path)?.len();
+    let file = File::open(path)?;
+    let file_reader = SerializedFileReader::new(file)?;
+    let parquet_metadata = file_reader.metadata();
+    let file_metadata = parquet_metadata.file_metadata();
+    let schema = parquet_to_arrow_schema(
+        file_metadata.schema_descr(),
+        file_metadata.key_value_metadata(),
+    )?;
+    let column_compression = if parquet_metadata.num_row_groups() > 0 {
+        parquet_metadata
+            .row_group(0)
+            .columns()
+            .iter()
+            .map(|column| (column.column_path().string(), column.compression()))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    Ok(ParquetMetadataSummary {
+        num_rows: file_metadata.num_rows(),
+        num_row_groups: parquet_metadata.num_row_groups(),
+        schema,
+        column_compression,
+        file_size,
+    })
+}
+
+/// Combine two schemas, appending the fields of `other` that `schema` does not have
+///
+/// Fields that are only in one of the schemas are nullable. An error is returned if a field
+/// has a different type in each schema.
+pub(crate) fn union_schemas(schema: &Schema, other: &Schema) -> Result<Schema> {
+    let mut fields = vec![];
+    for field in schema.fields() {
+        let nullable = match other.field_with_name(field.name()) {
+            Ok(other_field) if other_field.data_type() != field.data_type() => {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Column {:?} is {:?} in one file and {:?} in another",
+                    field.name(),
+                    field.data_type(),
+                    other_field.data_type()
+                )))
+            }
+            Ok(other_field) => field.is_nullable() || other_field.is_nullable(),
+            Err(_) => true,
+        };
+        fields.push(Field::new(
+            field.name(),
+            field.data_type().clone(),
+            nullable,
+        ));
+    }
+    for field in other.fields() {
+        if schema.field_with_name(field.name()).is_err() {
+            fields.push(Field::new(field.name(), field.data_type().clone(), true));
+        }
+    }
+    Ok(Schema::new(fields))
+}
+
+/// Arrange a batch's columns to match a schema, filling columns it does not have with nulls
+pub(crate) fn conform_batch(batch: &RecordBatch, schema: SchemaRef) -> Result<RecordBatch> {
+    let batch_schema = batch.schema();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch_schema.index_of(field.name()) {
+            Ok(i) => Ok(batch.column(i).clone()),
+            Err(_) => null_array(field.data_type(), batch.num_rows()),
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Create an array of nulls, by casting an integer array of nulls to the data type
+pub(crate) fn null_array(data_type: &DataType, len: usize) -> Result<ArrayRef> {
+    let nulls: ArrayRef = match data_type {
+        DataType::Date32(_) | DataType::Time32(_) => Arc::new(Int32Array::from(vec![None; len])),
+        _ => Arc::new(Int64Array::from(vec![None; len])),
+    };
+    Ok(compute::cast(&nulls, data_type)?)
+}
+
+/// A data source that reads a Parquet file in batches
+///
+/// Filters are pushed down to the file's row groups: comparisons (`=`, `>`, `>=`, `<`, `<=`)
+/// of a column with a numeric scalar are checked against each row group's column statistics,
+/// and row groups that cannot contain matching rows are skipped. Pruning is conservative, so
+/// a row group is only skipped if no row can match. Every filter, including those that cannot
+/// prune row groups, is then applied to the rows that are read.
+///
+/// Equality comparisons of a column with an integer or string scalar are also checked against
+/// the column's bloom filters, if the file has them, which skips row groups that do not
+/// contain the value even if it is within their min/max range.
+///
+/// Projections are also pushed down, so that only the projected columns, and any columns
+/// that the filter needs, are read from the file.
+pub struct ParquetDataSource {
+    path: String,
+    /// The schema of the file
+    file_schema: SchemaRef,
+    /// The schema of the batches that are returned, after projection
+    projected_schema: SchemaRef,
+    batch_size: usize,
+    /// The number of bytes that batches should take, which overrides `batch_size`
+    target_batch_bytes: Option<usize>,
+    /// The indices of the projected columns in the file schema
+    projection: Option<Vec<usize>>,
+    limit: Option<usize>,
+    rows_read: usize,
+    /// The number of rows that have been decoded, before they are filtered
+    rows_decoded: usize,
+    filter: Option<BooleanFilter>,
+    /// The row groups that may contain rows that match the filter
+    row_groups: Vec<usize>,
+    file_reader: Rc<SerializedFileReader<File>>,
+    reader: Option<ParquetRecordBatchReader>,
+    /// The number of threads that row groups are decoded on
+    num_threads: usize,
+    pool: Option<ThreadPool>,
+    /// The position in `row_groups` of the next row group to decode in parallel
+    next_row_group: usize,
+    /// Batches of row groups that have been decoded in parallel, but not yet returned
+    decoded: VecDeque<RecordBatch>,
+}
+
+impl ParquetDataSource {
+    pub fn try_new(path: &str, batch_size: usize) -> Result<Self> {
+        let file_reader = SerializedFileReader::new(File::open(path)?)?;
+        let parquet_metadata = file_reader.metadata();
+        let file_metadata = parquet_metadata.file_metadata();
+        let schema = parquet_to_arrow_schema(
+            file_metadata.schema_descr(),
+            file_metadata.key_value_metadata(),
+        )?;
+        let num_row_groups = parquet_metadata.num_row_groups();
+        let schema = Arc::new(schema);
+        Ok(Self {
+            path: path.to_string(),
+            file_schema: schema.clone(),
+            projected_schema: schema,
+            batch_size,
+            target_batch_bytes: None,
+            projection: None,
+            limit: None,
+            rows_read: 0,
+            rows_decoded: 0,
+            filter: None,
+            row_groups: (0..num_row_groups).collect(),
+            file_reader: Rc::new(file_reader),
+            reader: None,
+            num_threads: 1,
+            pool: None,
+            next_row_group: 0,
+            decoded: VecDeque::new(),
+        })
+    }
+
+    /// Decode row groups across threads, with each thread opening the file and decoding one
+    /// row group at a time.
+    ///
+    /// Batches are returned in the file's order. The batches of up to `num_threads` row groups
+    /// are decoded together and held in memory until they are returned.
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads.max(1);
+        self.pool = None;
+        self
+    }
+
+    /// Size batches to take about `target_batch_bytes` each, instead of a fixed number of rows
+    ///
+    /// The number of rows per batch is estimated from the width of the projected columns.
+    pub fn with_target_batch_bytes(mut self, target_batch_bytes: usize) -> Self {
+        self.target_batch_bytes = Some(target_batch_bytes);
+        self.reset_readers();
+        self
+    }
+
+    /// The number of rows that batches are read with
+    pub fn batch_size(&self) -> usize {
+        match self.target_batch_bytes {
+            Some(bytes) => batch_size_for(&self.projected_schema, bytes),
+            None => self.batch_size,
+        }
+    }
+
+    /// The indices of the row groups that will be read
+    pub fn row_groups(&self) -> &[usize] {
+        &self.row_groups
+    }
+
+    /// The indices of the file's leaf columns that have to be read for the projection and filter
+    ///
+    /// Parquet stores nested fields as several leaf columns, so a top-level field can map to
+    /// more than one leaf.
+    fn read_columns(&self) -> Result<Vec<usize>> {
+        let schema_descr = self.file_reader.metadata().file_metadata().schema_descr();
+        let projection = match &self.projection {
+            Some(projection) => projection,
+            None => return Ok((0..schema_descr.num_columns()).collect()),
+        };
+        let mut names: Vec<String> = projection
+            .iter()
+            .map(|i| self.file_schema.field(*i).name().clone())
+            .collect();
+        if let Some(filter) = &self.filter {
+            filter_columns(filter, &mut names);
+        }
+        let mut columns = vec![];
+        for name in names {
+            let leaves = (0..schema_descr.num_columns())
+                .filter(|i| schema_descr.get_column_root(*i).name() == name)
+                .collect::<Vec<usize>>();
+            if leaves.is_empty() {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Column {} not found in Parquet file {}",
+                    name, self.path
+                )));
+            }
+            columns.extend(leaves);
+        }
+        // the reader returns columns in the file's order
+        columns.sort_unstable();
+        columns.dedup();
+        Ok(columns)
+    }
+
+    /// Read the next batch of the row groups that are decoded in parallel, decoding the next
+    /// row groups once the batches of the previous ones have been returned
+    fn next_parallel_batch(&mut self) -> Result<Option<RecordBatch>> {
+        while self.decoded.is_empty() {
+            if self.next_row_group >= self.row_groups.len() {
+                return Ok(None);
+            }
+            if self.pool.is_none() {
+                let pool = ThreadPoolBuilder::new()
+                    .num_threads(self.num_threads)
+                    .build()
+                    .map_err(|e| DataFrameError::ComputeError(e.to_string()))?;
+                self.pool = Some(pool);
+            }
+            let end = (self.next_row_group + self.num_threads).min(self.row_groups.len());
+            let row_groups = &self.row_groups[self.next_row_group..end];
+            let columns = self.read_columns()?;
+            let (path, batch_size) = (self.path.as_str(), self.batch_size());
+            let batches = self.pool.as_ref().unwrap().install(|| {
+                row_groups
+                    .par_iter()
+                    .map(|row_group| read_row_group(path, *row_group, &columns, batch_size))
+                    .collect::<Result<Vec<Vec<RecordBatch>>>>()
+            })?;
+            self.decoded.extend(batches.into_iter().flatten());
+            self.next_row_group = end;
+        }
+        Ok(self.decoded.pop_front())
+    }
+
+    /// Restart reading after the columns or row groups to read have changed
+    fn reset_readers(&mut self) {
+        self.reader = None;
+        self.next_row_group = 0;
+        self.decoded.clear();
+    }
+}
+
+/// Decode the batches of a row group, with a file reader of its own
+fn read_row_group(
+    path: &str,
+    row_group: usize,
+    columns: &[usize],
+    batch_size: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut file_reader = SerializedFileReader::new(File::open(path)?)?;
+    file_reader.filter_row_groups(&|_, i| i == row_group);
+    let mut arrow_reader = ParquetFileArrowReader::new(Rc::new(file_reader));
+    let batches = arrow_reader
+        .get_record_reader_by_columns(columns.to_vec(), batch_size)?
+        .collect::<arrow::error::Result<Vec<RecordBatch>>>()?;
+    Ok(batches)
+}
+
+/// Collect the names of the columns that a filter reads
+fn filter_columns(filter: &BooleanFilter, names: &mut Vec<String>) {
+    use BooleanFilter::*;
+    match filter {
+        Input(BooleanInput::Column(column)) => names.push(column.name.clone()),
+        Input(BooleanInput::Scalar(_)) => {}
+        Not(filter) => filter_columns(filter, names),
+        And(left, right)
+        | Or(left, right)
+        | Gt(left, right)
+        | Ge(left, right)
+        | Eq(left, right)
+        | Ne(left, right)
+        | Lt(left, right)
+        | Le(left, right)
+        | ApproxEq(left, right, _) => {
+            filter_columns(left, names);
+            filter_columns(right, names);
+        }
+    }
+}
+
+impl DataSource for ParquetDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: "parquet_file_source".to_owned(),
+            columns: self
+                .projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Parquet(self.path.clone())
+    }
+    fn format(&self) -> &str {
+        "parquet"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        // the file is only read if a row group could have matching rows
+        if self.row_groups.is_empty() {
+            return Ok(None);
+        }
+        if let Some(limit) = self.limit {
+            if self.rows_read >= limit {
+                return Ok(None);
+            }
+        }
+        let batch = if self.num_threads > 1 {
+            match self.next_parallel_batch()? {
+                Some(batch) => batch,
+                None => return Ok(None),
+            }
+        } else {
+            if self.reader.is_none() {
+                // the reader only reads the row groups that may match the filter
+                let mut file_reader = SerializedFileReader::new(File::open(&self.path)?)?;
+                let row_groups = &self.row_groups;
+                file_reader.filter_row_groups(&|_, i| row_groups.contains(&i));
+                let mut arrow_reader = ParquetFileArrowReader::new(Rc::new(file_reader));
+                self.reader = Some(
+                    arrow_reader
+                        .get_record_reader_by_columns(self.read_columns()?, self.batch_size())?,
+                );
+            }
+            match self.reader.as_mut().unwrap().next() {
+                Some(batch) => batch?,
+                None => return Ok(None),
+            }
+        };
+        self.rows_decoded += batch.num_rows();
+        let columns = match &self.filter {
+            Some(filter) => {
+                let mask = compute::cast(&filter.eval_to_array(&batch)?, &DataType::Boolean)?;
+                let mask = BooleanArray::from(mask.data());
+                batch
+                    .columns()
+                    .iter()
+                    .map(|column| compute::filter(column.as_ref(), &mask))
+                    .collect::<arrow::error::Result<Vec<ArrayRef>>>()?
+            }
+            None => batch.columns().to_vec(),
+        };
+        // select the projected columns, dropping any columns that were only read for the filter
+        let batch_schema = batch.schema();
+        let mut columns = self
+            .projected_schema
+            .fields()
+            .iter()
+            .map(|field| Ok(columns[batch_schema.index_of(field.name())?].clone()))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        if let Some(limit) = self.limit {
+            let length = columns.first().map(|c| c.len()).unwrap_or(0);
+            if self.rows_read + length > limit {
+                let length = limit - self.rows_read;
+                columns = columns.iter().map(|c| c.slice(0, length)).collect();
+            }
+        }
+        let batch = RecordBatch::try_new(self.projected_schema.clone(), columns)?;
+        self.rows_read += batch.num_rows();
+        Ok(Some(batch))
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        let parquet_metadata = self.file_reader.metadata();
+        self.row_groups
+            .retain(|i| row_group_may_match(parquet_metadata.row_group(*i), &filter));
+        // the bloom filters are only read for the row groups that statistics did not skip
+        if !self.row_groups.is_empty() {
+            let mut bloom_filters = BloomFilters::try_new(&self.path)?;
+            let mut row_groups = vec![];
+            for i in &self.row_groups {
+                if bloom_filters.may_match(*i, parquet_metadata.row_group(*i), &filter)? {
+                    row_groups.push(*i);
+                }
+            }
+            self.row_groups = row_groups;
+        }
+        // filters are combined with any previous filter
+        self.filter = Some(match self.filter.take() {
+            Some(previous) => BooleanFilter::And(Box::new(previous), Box::new(filter)),
+            None => filter,
+        });
+        // the filter's columns may have to be read
+        self.reset_readers();
+        Ok(())
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        let projection = columns
+            .iter()
+            .map(|name| {
+                self.file_schema.index_of(name).map_err(|_| {
+                    DataFrameError::ComputeError(format!(
+                        "Cannot project unknown column {} from Parquet file {}",
+                        name, self.path
+                    ))
+                })
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        self.projected_schema = Arc::new(Schema::new(
+            projection
+                .iter()
+                .map(|i| self.file_schema.field(*i).clone())
+                .collect(),
+        ));
+        self.projection = Some(projection);
+        // the reader has to be recreated for the projection to take effect
+        self.reset_readers();
+        Ok(())
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Parquet data source does not support sorting".to_string(),
+        ))
+    }
+    fn supports_filtering(&self) -> bool {
+        true
+    }
+    fn supports_projection(&self) -> bool {
+        true
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+}
+
+/// A data source that reads the Parquet files of a directory, such as partitioned output with
+/// `part-*.parquet` files, as one dataset
+///
+/// Files with a `.parquet` extension are read in the order of their names, and all must have
+/// the same schema. Filters and projections are pushed down to each file.
+pub struct ParquetDirectoryDataSource {
+    path: String,
+    schema: SchemaRef,
+    sources: Vec<ParquetDataSource>,
+    /// The index of the file that is being read
+    current: usize,
+    limit: Option<usize>,
+    rows_read: usize,
+}
+
+impl ParquetDirectoryDataSource {
+    pub fn try_new(path: &str, batch_size: usize) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension() == Some(OsStr::new("parquet")))
+            .collect();
+        paths.sort();
+        let mut sources: Vec<ParquetDataSource> = vec![];
+        for file in &paths {
+            let file = file.to_string_lossy();
+            let source = ParquetDataSource::try_new(&file, batch_size)?;
+            if let Some(first) = sources.first() {
+                if first.schema().fields() != source.schema().fields() {
+                    return Err(DataFrameError::ComputeError(format!(
+                        "Parquet file {} has schema {:?}, which does not match the schema {:?} of {}",
+                        file,
+                        source.schema(),
+                        first.schema(),
+                        first.path
+                    )));
+                }
+            }
+            sources.push(source);
+        }
+        let schema = match sources.first() {
+            Some(source) => source.schema(),
+            None => {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Directory {} does not contain any Parquet files",
+                    path
+                )))
+            }
+        };
+        Ok(Self {
+            path: path.to_string(),
+            schema,
+            sources,
+            current: 0,
+            limit: None,
+            rows_read: 0,
+        })
+    }
+
+    /// The paths of the files that are read
+    pub fn files(&self) -> Vec<&str> {
+        self.sources.iter().map(|s| s.path.as_str()).collect()
+    }
+}
+
+impl DataSource for ParquetDirectoryDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: "parquet_directory_source".to_owned(),
+            columns: self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Parquet(self.path.clone())
+    }
+    fn format(&self) -> &str {
+        "parquet"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if let Some(limit) = self.limit {
+            if self.rows_read >= limit {
+                return Ok(None);
+            }
+        }
+        while let Some(source) = self.sources.get_mut(self.current) {
+            let batch = match source.next_batch()? {
+                Some(batch) => batch,
+                None => {
+                    self.current += 1;
+                    continue;
+                }
+            };
+            let batch = match self.limit {
+                Some(limit) if self.rows_read + batch.num_rows() > limit => {
+                    let length = limit - self.rows_read;
+                    RecordBatch::try_new(
+                        batch.schema(),
+                        batch.columns().iter().map(|c| c.slice(0, length)).collect(),
+                    )?
+                }
+                _ => batch,
+            };
+            self.rows_read += batch.num_rows();
+            return Ok(Some(batch));
+        }
+        Ok(None)
+    }
+    fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        for source in &mut self.sources {
+            source.filter(filter.clone())?;
+        }
+        Ok(())
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        for source in &mut self.sources {
+            source.project(columns.clone())?;
+        }
+        self.schema = self.sources[0].schema();
+        Ok(())
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Parquet data source does not support sorting".to_string(),
+        ))
+    }
+    fn supports_filtering(&self) -> bool {
+        true
+    }
+    fn supports_projection(&self) -> bool {
+        true
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+}
+
+/// Whether a row group could contain rows that satisfy a filter, based on its statistics
+///
+/// Only equality predicates between a column and a numeric scalar are checked, any other
+/// filter is assumed to match.
+fn row_group_may_match(row_group: &RowGroupMetaData, filter: &BooleanFilter) -> bool {
+    use BooleanFilter::*;
+    match filter {
+        And(left, right) => {
+            row_group_may_match(row_group, left) && row_group_may_match(row_group, right)
+        }
+        Or(left, right) => {
+            row_group_may_match(row_group, left) || row_group_may_match(row_group, right)
+        }
+        Eq(left, right) => comparison_may_match(row_group, left, right, Comparison::Eq),
+        Gt(left, right) => comparison_may_match(row_group, left, right, Comparison::Gt),
+        Ge(left, right) => comparison_may_match(row_group, left, right, Comparison::Ge),
+        Lt(left, right) => comparison_may_match(row_group, left, right, Comparison::Lt),
+        Le(left, right) => comparison_may_match(row_group, left, right, Comparison::Le),
+        _ => true,
+    }
+}
+
+/// A comparison that can be checked against column statistics
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    /// The comparison with its operands swapped, so that `a > b` becomes `b < a`
+    fn flip(self) -> Self {
+        match self {
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Gt => Comparison::Lt,
+            Comparison::Ge => Comparison::Le,
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+        }
+    }
+}
+
+/// Whether a row group may contain rows where a comparison of a column with a scalar holds
+///
+/// Comparisons that are not between a numeric column and a numeric scalar, and columns
+/// without statistics, keep the row group.
+fn comparison_may_match(
+    row_group: &RowGroupMetaData,
+    left: &BooleanFilter,
+    right: &BooleanFilter,
+    comparison: Comparison,
+) -> bool {
+    use BooleanFilter::*;
+    // normalise the comparison so that the column is on the left
+    let (column, scalar, comparison) = match (left, right) {
+        (Input(BooleanInput::Column(column)), Input(BooleanInput::Scalar(scalar))) => {
+            (column, scalar, comparison)
+        }
+        (Input(BooleanInput::Scalar(scalar)), Input(BooleanInput::Column(column))) => {
+            (column, scalar, comparison.flip())
+        }
+        _ => return true,
+    };
+    let value = match scalar {
+        Scalar::Int32(v) => Number::Int(i64::from(*v)),
+        Scalar::Int64(v) => Number::Int(*v),
+        Scalar::Float32(v) => Number::Float(f64::from(*v)),
+        Scalar::Float64(v) => Number::Float(*v),
+        _ => return true,
+    };
+    let statistics = row_group
+        .columns()
+        .iter()
+        .find(|c| c.column_path().string() == column.name)
+        .and_then(|c| c.statistics());
+    let (min, max) = match statistics.and_then(numeric_bounds) {
+        Some(bounds) => bounds,
+        None => return true,
+    };
+    // integers are compared as integers, as converting them to floats above 2^53 rounds them
+    match (value, min, max) {
+        (Number::Int(value), Number::Int(min), Number::Int(max)) => {
+            bounds_may_match(comparison, value, min, max)
+        }
+        (value, min, max) => match (value.to_f64(), min.to_f64(), max.to_f64()) {
+            (Some(value), Some(min), Some(max)) => bounds_may_match(comparison, value, min, max),
+            _ => true,
+        },
+    }
+}
+
+/// Whether a comparison with a value may hold for any value between a minimum and maximum
+fn bounds_may_match<T: PartialOrd>(comparison: Comparison, value: T, min: T, max: T) -> bool {
+    // comparisons with NaN bounds are false, which keeps the row group
+    match comparison {
+        Comparison::Eq => !(value < min || value > max),
+        Comparison::Gt => !(max <= value),
+        Comparison::Ge => !(max < value),
+        Comparison::Lt => !(min >= value),
+        Comparison::Le => !(min > value),
+    }
+}
+
+/// A number from a scalar or column statistics
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    /// The number as a float, if it can be converted without rounding
+    fn to_f64(self) -> Option<f64> {
+        // integers up to 2^53 are exactly representable as floats
+        const MAX_EXACT_INT: i64 = 1 << 53;
+        match self {
+            Number::Int(v) if -MAX_EXACT_INT <= v && v <= MAX_EXACT_INT => Some(v as f64),
+            Number::Int(_) => None,
+            Number::Float(v) => Some(v),
+        }
+    }
+}
+
+/// The bloom filters of a Parquet file's column chunks, which are read as filters need them
+struct BloomFilters {
+    file: File,
+    /// The offsets of the bloom filters of each row group's columns
+    offsets: Vec<Vec<Option<u64>>>,
+}
+
+impl BloomFilters {
+    fn try_new(path: &str) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let offsets = bloom_filter_offsets(&mut file)?;
+        Ok(Self { file, offsets })
+    }
+
+    /// Whether a row group could contain rows that satisfy a filter, based on its bloom filters
+    ///
+    /// Only equality predicates between a column and a scalar are checked, any other filter
+    /// is assumed to match.
+    fn may_match(
+        &mut self,
+        index: usize,
+        row_group: &RowGroupMetaData,
+        filter: &BooleanFilter,
+    ) -> Result<bool> {
+        use BooleanFilter::*;
+        match filter {
+            And(left, right) => Ok(self.may_match(index, row_group, left)?
+                && self.may_match(index, row_group, right)?),
+            Or(left, right) => Ok(self.may_match(index, row_group, left)?
+                || self.may_match(index, row_group, right)?),
+            Eq(left, right) => self.equality_may_match(index, row_group, left, right),
+            _ => Ok(true),
+        }
+    }
+
+    /// Whether a row group may contain rows where a column equals a scalar
+    ///
+    /// Columns without a bloom filter, and scalars that cannot be encoded as the column's
+    /// physical type, keep the row group.
+    fn equality_may_match(
+        &mut self,
+        index: usize,
+        row_group: &RowGroupMetaData,
+        left: &BooleanFilter,
+        right: &BooleanFilter,
+    ) -> Result<bool> {
+        use BooleanFilter::*;
+        let (column, scalar) = match (left, right) {
+            (Input(BooleanInput::Column(column)), Input(BooleanInput::Scalar(scalar)))
+            | (Input(BooleanInput::Scalar(scalar)), Input(BooleanInput::Column(column))) => {
+                (column, scalar)
+            }
+            _ => return Ok(true),
+        };
+        let position = match row_group
+            .columns()
+            .iter()
+            .position(|c| c.column_path().string() == column.name)
+        {
+            Some(position) => position,
+            None => return Ok(true),
+        };
+        let value = match plain_encoded(row_group.column(position).column_type(), scalar) {
+            Some(value) => value,
+            None => return Ok(true),
+        };
+        let offset = match self.offsets.get(index).and_then(|c| c.get(position)) {
+            Some(Some(offset)) => *offset,
+            _ => return Ok(true),
+        };
+        Ok(match BloomFilter::read(&mut self.file, offset)? {
+            Some(bloom_filter) => bloom_filter.may_contain(&value),
+            None => true,
+        })
+    }
+}
+
+/// The plain encoding of a scalar as a value of a column's physical type, which is what bloom
+/// filters hash
+///
+/// Floating point scalars are not encoded, as values that are equal, such as `0.0` and `-0.0`,
+/// can have different encodings.
+fn plain_encoded(physical_type: PhysicalType, scalar: &Scalar) -> Option<Vec<u8>> {
+    match (physical_type, scalar) {
+        (PhysicalType::INT32, Scalar::Int32(v)) => Some(v.to_le_bytes().to_vec()),
+        (PhysicalType::INT32, Scalar::Int64(v)) => {
+            i32::try_from(*v).ok().map(|v| v.to_le_bytes().to_vec())
+        }
+        (PhysicalType::INT64, Scalar::Int32(v)) => Some(i64::from(*v).to_le_bytes().to_vec()),
+        (PhysicalType::INT64, Scalar::Int64(v)) => Some(v.to_le_bytes().to_vec()),
+        (PhysicalType::BYTE_ARRAY, Scalar::String(v)) => Some(v.as_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// The minimum and maximum of numeric column statistics
+fn numeric_bounds(statistics: &Statistics) -> Option<(Number, Number)> {
+    if !statistics.has_min_max_set() {
+        return None;
+    }
+    match statistics {
+        Statistics::Int32(s) => Some((
+            Number::Int(i64::from(*s.min())),
+            Number::Int(i64::from(*s.max())),
+        )),
+        Statistics::Int64(s) => Some((Number::Int(*s.min()), Number::Int(*s.max()))),
+        Statistics::Float(s) => Some((
+            Number::Float(f64::from(*s.min())),
+            Number::Float(f64::from(*s.max())),
+        )),
+        Statistics::Double(s) => Some((Number::Float(*s.min()), Number::Float(*s.max()))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataframe::DataFrame;
+
+    #[test]
+    fn test_read_parquet_metadata() {
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        dataframe
+            .to_parquet("target/uk_cities_metadata.parquet")
+            .unwrap();
+
+        let summary = parquet_metadata("target/uk_cities_metadata.parquet").unwrap();
+        assert_eq!(37, summary.num_rows);
+        assert_eq!(1, summary.num_row_groups);
+        assert_eq!(3, summary.schema.fields().len());
+        assert_eq!(3, summary.column_compression.len());
+        assert!(summary.file_size > 0);
+    }
+
+    #[test]
+    fn test_parquet_filter_skips_row_groups() {
+        use crate::expression::Column;
+        use arrow::datatypes::DataType;
+
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        dataframe
+            .to_parquet("target/uk_cities_filter.parquet")
+            .unwrap();
+        let lat = || {
+            BooleanFilter::column(Column {
+                name: "lat".to_owned(),
+                column_type: crate::expression::ColumnType::Scalar(DataType::Float64),
+                nullable: true,
+            })
+        };
+
+        // no city is at latitude 1000, so every row group is skipped
+        let mut source =
+            ParquetDataSource::try_new("target/uk_cities_filter.parquet", 1024).unwrap();
+        source
+            .filter(BooleanFilter::Eq(lat(), BooleanFilter::scalar(1000.0)))
+            .unwrap();
+        assert!(source.row_groups().is_empty());
+        assert!(source.next_batch().unwrap().is_none());
+
+        // Elgin's latitude is within the row group's bounds
+        let mut source =
+            ParquetDataSource::try_new("target/uk_cities_filter.parquet", 1024).unwrap();
+        source
+            .filter(BooleanFilter::Eq(lat(), BooleanFilter::scalar(57.653484)))
+            .unwrap();
+        assert_eq!(&[0], source.row_groups());
+        let batch = source.next_batch().unwrap().unwrap();
+        assert_eq!(1, batch.num_rows());
+    }
+
+    #[test]
+    fn test_read_parquet_schema_evolution() {
+        use arrow::array::{Array, Float64Array, StringArray};
+
+        let frame = |schema: Schema, arrays: Vec<ArrayRef>| {
+            let columns = arrays
+                .into_iter()
+                .zip(schema.fields())
+                .map(|(array, field)| crate::table::Column::from_arrays(vec![array], field.clone()))
+                .collect();
+            DataFrame::from_columns(Arc::new(schema), columns)
+        };
+        std::fs::create_dir_all("target/schema_evolution").unwrap();
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        frame(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y"])),
+            ],
+        )
+        .to_parquet("target/schema_evolution/part-0.parquet")
+        .unwrap();
+        // a column is added to the second file
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Float64, true),
+        ]);
+        frame(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![3])),
+                Arc::new(StringArray::from(vec!["z"])),
+                Arc::new(Float64Array::from(vec![0.5])),
+            ],
+        )
+        .to_parquet("target/schema_evolution/part-1.parquet")
+        .unwrap();
+
+        let dataframe = DataFrame::from_parquet_with_options(
+            "target/schema_evolution",
+            ParquetReadOptions {
+                schema_evolution: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(3, dataframe.num_columns());
+        assert_eq!(3, dataframe.num_rows());
+        assert_eq!(
+            &DataType::Float64,
+            dataframe.schema().field_with_name("c").unwrap().data_type()
+        );
+        let c = dataframe.column_by_name("c").to_array().unwrap();
+        let c = c.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!(c.is_null(0));
+        assert!(c.is_null(1));
+        assert_eq!(0.5, c.value(2));
+    }
+
+    #[test]
+    fn test_parquet_sink() {
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        for (path, compression) in &[
+            ("target/uk_cities_snappy.parquet", Compression::SNAPPY),
+            ("target/uk_cities_zstd.parquet", Compression::ZSTD),
+        ] {
+            let options = ParquetWriteOptions {
+                compression: *compression,
+                max_row_group_size: Some(1024),
+            };
+            let mut sink =
+                ParquetDataSink::try_new(path, dataframe.schema().clone(), options).unwrap();
+            for batch in dataframe.to_record_batches() {
+                sink.write_batch(&batch).unwrap();
+            }
+            sink.finish().unwrap();
+
+            let summary = parquet_metadata(path).unwrap();
+            assert_eq!(37, summary.num_rows);
+            assert!(summary
+                .column_compression
+                .iter()
+                .all(|(_, c)| c == compression));
+
+            let mut source = ParquetDataSource::try_new(path, 1024).unwrap();
+            assert_eq!(dataframe.schema().fields(), source.schema().fields());
+            let mut num_rows = 0;
+            while let Some(batch) = source.next_batch().unwrap() {
+                num_rows += batch.num_rows();
+            }
+            assert_eq!(37, num_rows);
+        }
+    }
+
+    #[test]
+    fn test_parquet_source_batches() {
+        use crate::expression::Column;
+
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        dataframe
+            .to_parquet("target/uk_cities_batches.parquet")
+            .unwrap();
+
+        let mut source =
+            ParquetDataSource::try_new("target/uk_cities_batches.parquet", 10).unwrap();
+        assert_eq!("parquet", source.format());
+        assert!(source.supports_projection());
+        assert!(source.supports_limit());
+        let mut num_batches = 0;
+        let mut num_rows = 0;
+        while let Some(batch) = source.next_batch().unwrap() {
+            assert_eq!(3, batch.num_columns());
+            num_batches += 1;
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(4, num_batches);
+        assert_eq!(37, num_rows);
+
+        // the filter's column is read, but only the projected columns are returned.
+        // 20 cities are north of 52 degrees, of which the first 15 are returned
+        let mut source =
+            ParquetDataSource::try_new("target/uk_cities_batches.parquet", 10).unwrap();
+        source
+            .project(vec!["lng".to_string(), "city".to_string()])
+            .unwrap();
+        source
+            .filter(BooleanFilter::Gt(
+                BooleanFilter::column(Column {
+                    name: "lat".to_owned(),
+                    column_type: crate::expression::ColumnType::Scalar(DataType::Float64),
+                    nullable: true,
+                }),
+                BooleanFilter::scalar(52.0),
+            ))
+            .unwrap();
+        source.limit(15).unwrap();
+        let names: Vec<&String> = source.schema().fields().iter().map(|f| f.name()).collect();
+        assert_eq!(vec!["lng", "city"], names);
+        let mut num_rows = 0;
+        while let Some(batch) = source.next_batch().unwrap() {
+            assert_eq!(source.schema(), batch.schema());
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(15, num_rows);
+    }
+
+    #[test]
+    fn test_parquet_projection() {
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        dataframe
+            .to_parquet("target/uk_cities_projection.parquet")
+            .unwrap();
+
+        let mut source =
+            ParquetDataSource::try_new("target/uk_cities_projection.parquet", 100).unwrap();
+        // only the leaves of the projected columns are read
+        source
+            .project(vec!["lat".to_string(), "city".to_string()])
+            .unwrap();
+        assert_eq!(vec![0, 1], source.read_columns().unwrap());
+        let batch = source.next_batch().unwrap().unwrap();
+        assert_eq!(37, batch.num_rows());
+        assert_eq!(2, batch.num_columns());
+        assert_eq!(source.schema(), batch.schema());
+        assert_eq!("lat", batch.schema().field(0).name());
+        assert_eq!(&DataType::Float64, batch.schema().field(0).data_type());
+        assert_eq!("city", batch.schema().field(1).name());
+        assert_eq!(&DataType::Utf8, batch.schema().field(1).data_type());
+
+        // unknown columns are rejected, and the previous projection is kept
+        assert!(source.project(vec!["population".to_string()]).is_err());
+        assert_eq!(2, source.schema().fields().len());
+    }
+
+    #[test]
+    fn test_parquet_range_filter_skips_row_groups() {
+        use crate::expression::Column;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let path = "target/two_row_groups.parquet";
+        let mut sink = ParquetDataSink::try_new(path, schema.clone(), Default::default()).unwrap();
+        // each batch is written as a row group, with values 0..10 and 10..20
+        for start in &[0, 10] {
+            let values: Vec<i64> = (*start..start + 10).collect();
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))])
+                    .unwrap();
+            sink.write_batch(&batch).unwrap();
+        }
+        sink.finish().unwrap();
+        let a = || {
+            BooleanFilter::column(Column {
+                name: "a".to_owned(),
+                column_type: crate::expression::ColumnType::Scalar(DataType::Int64),
+                nullable: true,
+            })
+        };
+        let row_groups = |filter: BooleanFilter| {
+            let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+            source.filter(filter).unwrap();
+            source.row_groups().to_vec()
+        };
+
+        assert_eq!(
+            vec![0, 1],
+            row_groups(BooleanFilter::Ge(a(), BooleanFilter::scalar(5i64)))
+        );
+        // the first row group's maximum is 9
+        assert_eq!(
+            vec![1],
+            row_groups(BooleanFilter::Gt(a(), BooleanFilter::scalar(9i64)))
+        );
+        assert_eq!(
+            vec![0],
+            row_groups(BooleanFilter::Lt(a(), BooleanFilter::scalar(10i64)))
+        );
+        // the scalar may be on either side of the comparison
+        assert_eq!(
+            vec![0],
+            row_groups(BooleanFilter::Gt(BooleanFilter::scalar(5i64), a()))
+        );
+        // filters that cannot be checked against statistics keep every row group
+        assert_eq!(
+            vec![0, 1],
+            row_groups(BooleanFilter::Ne(a(), BooleanFilter::scalar(5i64)))
+        );
+
+        let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+        source
+            .filter(BooleanFilter::Gt(a(), BooleanFilter::scalar(15i64)))
+            .unwrap();
+        let batch = source.next_batch().unwrap().unwrap();
+        assert_eq!(4, batch.num_rows());
+        assert!(source.next_batch().unwrap().is_none());
+        // only the second row group is decoded
+        assert_eq!(10, source.rows_decoded);
+
+        // row groups that are skipped are also not decoded in parallel
+        let mut source = ParquetDataSource::try_new(path, 100)
+            .unwrap()
+            .with_num_threads(2);
+        source
+            .filter(BooleanFilter::Lt(a(), BooleanFilter::scalar(5i64)))
+            .unwrap();
+        let batch = source.next_batch().unwrap().unwrap();
+        assert_eq!(5, batch.num_rows());
+        assert!(source.next_batch().unwrap().is_none());
+        assert_eq!(10, source.rows_decoded);
+    }
+
+    #[test]
+    fn test_parquet_filter_compares_large_integers_exactly() {
+        use crate::expression::Column;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let path = "target/large_integer_row_groups.parquet";
+        let mut sink = ParquetDataSink::try_new(path, schema.clone(), Default::default()).unwrap();
+        // 2^53 + 1 is the smallest integer that rounds when it is converted to a float
+        let large = (1i64 << 53) + 1;
+        for values in vec![vec![0, large], vec![large + 1, large + 2]] {
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))])
+                    .unwrap();
+            sink.write_batch(&batch).unwrap();
+        }
+        sink.finish().unwrap();
+        let a = || {
+            BooleanFilter::column(Column {
+                name: "a".to_owned(),
+                column_type: crate::expression::ColumnType::Scalar(DataType::Int64),
+                nullable: false,
+            })
+        };
+        let row_groups = |filter: BooleanFilter| {
+            let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+            source.filter(filter).unwrap();
+            source.row_groups().to_vec()
+        };
+
+        // the first row group's maximum is greater than 2^53, although they are equal as floats
+        assert_eq!(
+            vec![0, 1],
+            row_groups(BooleanFilter::Gt(a(), BooleanFilter::scalar(large - 1)))
+        );
+        assert_eq!(
+            vec![1],
+            row_groups(BooleanFilter::Gt(a(), BooleanFilter::scalar(large)))
+        );
+        assert_eq!(
+            vec![0],
+            row_groups(BooleanFilter::Eq(a(), BooleanFilter::scalar(large)))
+        );
+    }
+
+    #[test]
+    fn test_parquet_bloom_filters_skip_row_groups() {
+        use crate::expression::{Column, ColumnType};
+
+        // the file has two row groups, with ids 0, 2, ..., 18 and 20, 22, ..., 38, and names
+        // with a `city` prefix, and a bloom filter for each column chunk but no statistics
+        let path = "./test/data/cities_with_bloom_filters.parquet";
+        let column = |name: &str, data_type: DataType| {
+            BooleanFilter::column(Column {
+                name: name.to_owned(),
+                column_type: ColumnType::Scalar(data_type),
+                nullable: false,
+            })
+        };
+        let id = || column("id", DataType::Int64);
+        let row_groups = |filter: BooleanFilter| {
+            let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+            source.filter(filter).unwrap();
+            source.row_groups().to_vec()
+        };
+
+        assert_eq!(
+            vec![1],
+            row_groups(BooleanFilter::Eq(id(), BooleanFilter::scalar(24i64)))
+        );
+        assert_eq!(
+            vec![0],
+            row_groups(BooleanFilter::Eq(
+                BooleanFilter::scalar("city10"),
+                column("name", DataType::Utf8)
+            ))
+        );
+        assert_eq!(
+            vec![1],
+            row_groups(BooleanFilter::Or(
+                Box::new(BooleanFilter::Eq(id(), BooleanFilter::scalar(7i64))),
+                Box::new(BooleanFilter::Eq(id(), BooleanFilter::scalar(24i64)))
+            ))
+        );
+        // filters other than equalities keep every row group
+        assert_eq!(
+            vec![0, 1],
+            row_groups(BooleanFilter::Ne(id(), BooleanFilter::scalar(7i64)))
+        );
+
+        // an absent value skips every row group, so that nothing is decoded
+        let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+        source
+            .filter(BooleanFilter::Eq(id(), BooleanFilter::scalar(7i64)))
+            .unwrap();
+        assert!(source.row_groups().is_empty());
+        assert!(source.next_batch().unwrap().is_none());
+        assert_eq!(0, source.rows_decoded);
+
+        let mut source = ParquetDataSource::try_new(path, 100).unwrap();
+        source
+            .filter(BooleanFilter::Eq(id(), BooleanFilter::scalar(24i64)))
+            .unwrap();
+        let batch = source.next_batch().unwrap().unwrap();
+        assert_eq!(1, batch.num_rows());
+        assert!(source.next_batch().unwrap().is_none());
+        assert_eq!(10, source.rows_decoded);
+    }
+
+    #[test]
+    fn test_parquet_directory_source() {
+        let dir = "target/parquet_directory";
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+        let dataframe = DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None);
+        dataframe
+            .to_parquet(&format!("{}/part-0.parquet", dir))
+            .unwrap();
+        dataframe
+            .limit(10)
+            .to_parquet(&format!("{}/part-1.parquet", dir))
+            .unwrap();
+        // files without a parquet extension are ignored
+        std::fs::write(format!("{}/_SUCCESS", dir), "").unwrap();
+
+        let mut source = ParquetDirectoryDataSource::try_new(dir, 1024).unwrap();
+        assert_eq!(2, source.files().len());
+        assert_eq!(dataframe.schema().fields(), source.schema().fields());
+        let mut num_rows = 0;
+        while let Some(batch) = source.next_batch().unwrap() {
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(37 + 10, num_rows);
+
+        // a file with different columns is rejected
+        DataFrame::from_csv("./test/data/uk_cities_with_headers.csv", None)
+            .drop(vec!["lng"])
+            .to_parquet(&format!("{}/part-2.parquet", dir))
+            .unwrap();
+        let err = ParquetDirectoryDataSource::try_new(dir, 1024)
+            .err()
+            .unwrap();
+        assert!(format!("{:?}", err).contains("part-2.parquet"));
+    }
+
+    #[test]
+    fn test_parquet_parallel_row_groups() {
+        use arrow::array::Array;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let path = "target/many_row_groups.parquet";
+        let mut sink = ParquetDataSink::try_new(path, schema.clone(), Default::default()).unwrap();
+        // each batch is written as a row group of 1000 values
+        for start in (0..10).map(|i| i * 1000) {
+            let values: Vec<i64> = (start..start + 1000).collect();
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))])
+                    .unwrap();
+            sink.write_batch(&batch).unwrap();
+        }
+        sink.finish().unwrap();
+
+        let read = |num_threads: usize| {
+            let mut source = ParquetDataSource::try_new(path, 256)
+                .unwrap()
+                .with_num_threads(num_threads);
+            let mut values = vec![];
+            while let Some(batch) = source.next_batch().unwrap() {
+                let array = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                values.extend((0..array.len()).map(|i| array.value(i)));
+            }
+            values
+        };
+        let sequential = read(1);
+        assert_eq!(10_000, sequential.len());
+        assert_eq!(49_995_000, sequential.iter().sum::<i64>());
+        // batches are returned in the same order, whether or not all row groups are decoded
+        // in the same wave
+        assert_eq!(sequential, read(4));
+        assert_eq!(sequential, read(16));
+    }
+
+    #[test]
+    fn test_parquet_struct_column() {
+        use crate::expression::ColumnType;
+        use arrow::array::{Array, Float64Array, StructArray};
+
+        let location = StructArray::from(vec![
+            (
+                Field::new("lat", DataType::Float64, false),
+                Arc::new(Float64Array::from(vec![51.507222, 53.479444])) as ArrayRef,
+            ),
+            (
+                Field::new("lng", DataType::Float64, false),
+                Arc::new(Float64Array::from(vec![-0.1275, -2.245278])) as ArrayRef,
+            ),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("location", location.data_type().clone(), false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2])), Arc::new(location)],
+        )
+        .unwrap();
+        let path = "target/struct_column.parquet";
+        let mut sink = ParquetDataSink::try_new(path, schema, Default::default()).unwrap();
+        sink.write_batch(&batch).unwrap();
+        sink.finish().unwrap();
+
+        let source = ParquetDataSource::try_new(path, 1024).unwrap();
+        let dataset = source.get_dataset().unwrap();
+        let (_, location) = dataset.get_column("location").unwrap();
+        match &location.column_type {
+            ColumnType::Struct(children) => {
+                let children: Vec<(&str, &ColumnType)> = children
+                    .iter()
+                    .map(|c| (c.name(), &c.column_type))
+                    .collect();
+                assert_eq!(
+                    vec![
+                        ("lat", &ColumnType::Scalar(DataType::Float64)),
+                        ("lng", &ColumnType::Scalar(DataType::Float64))
+                    ],
+                    children
+                );
+            }
+            t => panic!("Expected a struct column, found {:?}", t),
+        }
+        assert_eq!(
+            "struct<lat: Float64, lng: Float64>",
+            location.column_type.to_string()
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Row-by-row access to data sources
+
+use arrow::array::*;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+use crate::io::datasource::DataSource;
+
+/// Identifies a column in a row, either by its position or by its name
+pub trait RowIndex {
+    fn column_index(&self, schema: &Schema) -> Result<usize>;
+}
+
+impl RowIndex for usize {
+    fn column_index(&self, schema: &Schema) -> Result<usize> {
+        if *self < schema.fields().len() {
+            Ok(*self)
+        } else {
+            Err(DataFrameError::ComputeError(format!(
+                "Column index {} is out of bounds for {} columns",
+                self,
+                schema.fields().len()
+            )))
+        }
+    }
+}
+
+impl RowIndex for &str {
+    fn column_index(&self, schema: &Schema) -> Result<usize> {
+        Ok(schema.index_of(self)?)
+    }
+}
+
+/// A single row of a record batch
+///
+/// Accessors return `Ok(None)` for null values, and an error if the column is not of the
+/// requested type.
+#[derive(Debug, Clone)]
+pub struct Row {
+    batch: RecordBatch,
+    index: usize,
+}
+
+impl Row {
+    pub fn num_columns(&self) -> usize {
+        self.batch.num_columns()
+    }
+
+    pub fn is_null<I: RowIndex>(&self, col: I) -> Result<bool> {
+        let index = col.column_index(&self.batch.schema())?;
+        Ok(self.batch.column(index).is_null(self.index))
+    }
+
+    pub fn get_bool<I: RowIndex>(&self, col: I) -> Result<Option<bool>> {
+        let array = self.array::<BooleanArray, I>(col, "Boolean")?;
+        Ok(self.value(array, |a, i| a.value(i)))
+    }
+
+    pub fn get_i64<I: RowIndex>(&self, col: I) -> Result<Option<i64>> {
+        let array = self.array::<Int64Array, I>(col, "Int64")?;
+        Ok(self.value(array, |a, i| a.value(i)))
+    }
+
+    pub fn get_f64<I: RowIndex>(&self, col: I) -> Result<Option<f64>> {
+        let array = self.array::<Float64Array, I>(col, "Float64")?;
+        Ok(self.value(array, |a, i| a.value(i)))
+    }
+
+    pub fn get_str<I: RowIndex>(&self, col: I) -> Result<Option<&str>> {
+        let array = self.array::<StringArray, I>(col, "Utf8")?;
+        Ok(self.value(array, |a, i| a.value(i)))
+    }
+
+    fn array<T: Array + 'static, I: RowIndex>(&self, col: I, type_name: &str) -> Result<&T> {
+        let index = col.column_index(&self.batch.schema())?;
+        let column = self.batch.column(index);
+        column.as_any().downcast_ref::<T>().ok_or_else(|| {
+            DataFrameError::ComputeError(format!(
+                "Column {} is of type {:?}, not {}",
+                index,
+                column.data_type(),
+                type_name
+            ))
+        })
+    }
+
+    fn value<'a, T: Array, V>(&self, array: &'a T, f: impl Fn(&'a T, usize) -> V) -> Option<V> {
+        if array.is_null(self.index) {
+            None
+        } else {
+            Some(f(array, self.index))
+        }
+    }
+}
+
+/// An iterator over the rows of a data source
+///
+/// Batches are only read from the source once the rows of the previous batch are exhausted.
+pub struct Rows<'a, S: DataSource> {
+    source: &'a mut S,
+    batch: Option<RecordBatch>,
+    index: usize,
+    is_complete: bool,
+}
+
+impl<'a, S: DataSource> Rows<'a, S> {
+    pub(crate) fn new(source: &'a mut S) -> Self {
+        Self {
+            source,
+            batch: None,
+            index: 0,
+            is_complete: false,
+        }
+    }
+}
+
+impl<'a, S: DataSource> Iterator for Rows<'a, S> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.is_complete {
+            if let Some(batch) = &self.batch {
+                if self.index < batch.num_rows() {
+                    let row = Row {
+                        batch: batch.clone(),
+                        index: self.index,
+                    };
+                    self.index += 1;
+                    return Some(Ok(row));
+                }
+            }
+            match self.source.next_batch() {
+                Ok(Some(batch)) => {
+                    self.batch = Some(batch);
+                    self.index = 0;
+                }
+                Ok(None) => self.is_complete = true,
+                Err(e) => {
+                    self.is_complete = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::{CsvDataSource, CsvSourceOptions};
+
+    #[test]
+    fn test_csv_rows() -> Result<()> {
+        let mut source =
+            CsvDataSource::try_new("./test/data/people_with_nulls.csv", Default::default())?;
+        let rows = source.rows().collect::<Result<Vec<Row>>>()?;
+        assert_eq!(3, rows.len());
+        assert_eq!(Some("alice"), rows[0].get_str("name")?);
+        assert_eq!(Some(34), rows[0].get_i64("age")?);
+        assert_eq!(Some(1.5), rows[0].get_f64(2usize)?);
+        // bob's age is missing
+        assert_eq!(Some("bob"), rows[1].get_str(0usize)?);
+        assert_eq!(None, rows[1].get_i64("age")?);
+        assert!(rows[1].is_null("age")?);
+        // accessing a column with the wrong type is an error
+        assert!(rows[2].get_str("age").is_err());
+        assert!(rows[2].get_i64("height").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_rows_across_batches() -> Result<()> {
+        let options = CsvSourceOptions {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let mut source = CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", options)?;
+        let mut count = 0;
+        for row in source.rows() {
+            let row = row?;
+            assert!(row.get_f64("lat")?.is_some());
+            count += 1;
+        }
+        assert_eq!(37, count);
+        Ok(())
+    }
+}
@@ -0,0 +1,654 @@
+//! Sorting the rows of data sources across batches
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::compute;
+use arrow::compute::kernels::sort::{lexsort_to_indices, SortColumn};
+use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use arrow::ipc::{reader::FileReader as IpcFileReader, writer::FileWriter as IpcFileWriter};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::{check_cancelled, DataSource};
+
+/// A counter that makes the names of spilled runs unique within the process
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Options for sorting the rows of a data source
+#[derive(Debug, Clone)]
+pub struct SortSourceOptions {
+    /// The number of rows that are buffered before they are sorted and spilled to disk
+    pub max_rows_in_memory: usize,
+    /// The number of rows in each sorted batch
+    pub batch_size: usize,
+}
+
+impl Default for SortSourceOptions {
+    fn default() -> Self {
+        Self {
+            max_rows_in_memory: 1_000_000,
+            batch_size: 1024,
+        }
+    }
+}
+
+/// A data source that sorts all the rows of another source
+///
+/// Batches are buffered until `max_rows_in_memory` rows have been read, then sorted and
+/// spilled to a temporary Arrow IPC file as a sorted run. Once the wrapped source is
+/// exhausted, the sorted runs are merged, so that the output is globally sorted while at most
+/// one batch per run is held in memory. Inputs that fit in memory are sorted without spilling.
+///
+/// Nulls are sorted first or last according to the `nulls_first` of each criteria.
+pub struct SortSource<S: DataSource> {
+    source: S,
+    criteria: Vec<SortCriteria>,
+    options: SortSourceOptions,
+    /// The sorted runs that are being merged, which are only created on the first read
+    runs: Option<Vec<SortedRun>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<S: DataSource> SortSource<S> {
+    /// Create a source that sorts the rows of `source` by the criteria, in order of priority
+    ///
+    /// An error is returned if there are no criteria, or a criteria column does not exist.
+    pub fn try_new(
+        source: S,
+        criteria: Vec<SortCriteria>,
+        options: SortSourceOptions,
+    ) -> Result<Self> {
+        validate_criteria(&source.schema(), &criteria)?;
+        Ok(Self {
+            source,
+            criteria,
+            options,
+            runs: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Stop sorting once the `cancel` flag is set
+    ///
+    /// The flag is checked before each batch of the wrapped source is read, and before each
+    /// sorted batch is merged. If it is set, a `DataFrameError::Cancelled` error is returned.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Read the wrapped source, sorting and spilling runs of rows as the buffer fills up
+    fn create_runs(&mut self) -> Result<Vec<SortedRun>> {
+        let mut runs = vec![];
+        let mut buffer = vec![];
+        let mut buffered_rows = 0;
+        loop {
+            check_cancelled(&self.cancel)?;
+            let batch = match self.source.next_batch()? {
+                Some(batch) => batch,
+                None => break,
+            };
+            buffered_rows += batch.num_rows();
+            buffer.push(batch);
+            if buffered_rows >= self.options.max_rows_in_memory {
+                let sorted = sort_batches(&self.source.schema(), &buffer, &self.criteria)?;
+                runs.push(SortedRun::spill(&sorted, self.options.batch_size)?);
+                buffer.clear();
+                buffered_rows = 0;
+            }
+        }
+        if buffered_rows > 0 {
+            let sorted = sort_batches(&self.source.schema(), &buffer, &self.criteria)?;
+            runs.push(SortedRun::in_memory(&sorted, self.options.batch_size)?);
+        }
+        for run in &mut runs {
+            run.advance(&self.source.schema(), &self.criteria)?;
+        }
+        Ok(runs)
+    }
+}
+
+fn validate_criteria(schema: &SchemaRef, criteria: &[SortCriteria]) -> Result<()> {
+    if criteria.is_empty() {
+        return Err(DataFrameError::ComputeError(
+            "Sort criteria cannot be empty".to_string(),
+        ));
+    }
+    for c in criteria {
+        schema
+            .index_of(&c.column)
+            .map_err(|_| DataFrameError::ColumnNotFound(c.column.clone()))?;
+    }
+    Ok(())
+}
+
+/// Sort the rows of batches into a single batch
+fn sort_batches(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    criteria: &[SortCriteria],
+) -> Result<RecordBatch> {
+    let columns = (0..schema.fields().len())
+        .map(|i| {
+            let chunks: Vec<ArrayRef> = batches.iter().map(|b| b.column(i).clone()).collect();
+            match chunks.len() {
+                1 => Ok(chunks[0].clone()),
+                _ => compute::concat(&chunks),
+            }
+        })
+        .collect::<arrow::error::Result<Vec<ArrayRef>>>()?;
+    let sort_columns = criteria
+        .iter()
+        .map(|c| {
+            Ok(SortColumn {
+                values: columns[schema.index_of(&c.column)?].clone(),
+                options: Some(c.to_arrow_sort_options()),
+            })
+        })
+        .collect::<Result<Vec<SortColumn>>>()?;
+    let indices = lexsort_to_indices(&sort_columns)?;
+    let columns = columns
+        .iter()
+        .map(|column| compute::take(column, &indices, None))
+        .collect::<arrow::error::Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Split a batch into batches of at most `batch_size` rows
+fn split_batch(batch: &RecordBatch, batch_size: usize) -> Result<Vec<RecordBatch>> {
+    let mut batches = vec![];
+    let mut offset = 0;
+    while offset < batch.num_rows() {
+        let length = batch_size.min(batch.num_rows() - offset);
+        batches.push(RecordBatch::try_new(
+            batch.schema(),
+            batch
+                .columns()
+                .iter()
+                .map(|c| c.slice(offset, length))
+                .collect(),
+        )?);
+        offset += length;
+    }
+    Ok(batches)
+}
+
+/// Where the batches of a sorted run are read from
+enum RunReader {
+    Memory(std::vec::IntoIter<RecordBatch>),
+    /// A spilled run, whose file is deleted once the run is dropped
+    File(IpcFileReader<File>, PathBuf),
+}
+
+impl Drop for RunReader {
+    fn drop(&mut self) {
+        if let RunReader::File(_, path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A sorted sequence of rows, and the position of the next row to merge
+struct SortedRun {
+    reader: RunReader,
+    /// The current batch, which is `None` once the run is exhausted
+    batch: Option<RecordBatch>,
+    /// The sort columns of the current batch
+    keys: Vec<ArrayRef>,
+    row: usize,
+}
+
+impl SortedRun {
+    fn in_memory(sorted: &RecordBatch, batch_size: usize) -> Result<Self> {
+        let batches = split_batch(sorted, batch_size)?;
+        Ok(Self::new(RunReader::Memory(batches.into_iter())))
+    }
+
+    /// Write a sorted batch to a temporary file
+    fn spill(sorted: &RecordBatch, batch_size: usize) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "rust-dataframe-sort-{}-{}.arrow",
+            std::process::id(),
+            SPILL_COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        let mut writer = IpcFileWriter::try_new(File::create(&path)?, &sorted.schema())?;
+        for batch in split_batch(sorted, batch_size)? {
+            writer.write(&batch)?;
+        }
+        writer.finish()?;
+        let reader = IpcFileReader::try_new(File::open(&path)?)?;
+        Ok(Self::new(RunReader::File(reader, path)))
+    }
+
+    fn new(reader: RunReader) -> Self {
+        Self {
+            reader,
+            batch: None,
+            keys: vec![],
+            row: 0,
+        }
+    }
+
+    /// Move to the next non-empty batch of the run
+    fn advance(&mut self, schema: &SchemaRef, criteria: &[SortCriteria]) -> Result<()> {
+        loop {
+            let batch = match &mut self.reader {
+                RunReader::Memory(batches) => batches.next(),
+                RunReader::File(reader, _) => reader.next().transpose()?,
+            };
+            match batch {
+                Some(batch) if batch.num_rows() == 0 => continue,
+                Some(batch) => {
+                    self.keys = criteria
+                        .iter()
+                        .map(|c| Ok(batch.column(schema.index_of(&c.column)?).clone()))
+                        .collect::<Result<Vec<ArrayRef>>>()?;
+                    self.batch = Some(batch);
+                    self.row = 0;
+                }
+                None => {
+                    self.keys.clear();
+                    self.batch = None;
+                }
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Compare the current rows of 2 runs by the sort criteria
+fn compare_rows(a: &SortedRun, b: &SortedRun, criteria: &[SortCriteria]) -> Result<Ordering> {
+    for (i, c) in criteria.iter().enumerate() {
+        let (left, right) = (&a.keys[i], &b.keys[i]);
+        // nulls are placed first or last, regardless of the direction
+        let nulls = if c.nulls_first {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+        let ordering = match (left.is_null(a.row), right.is_null(b.row)) {
+            (true, true) => Ordering::Equal,
+            (true, false) => nulls,
+            (false, true) => nulls.reverse(),
+            (false, false) => {
+                let ordering = compare_values(left, a.row, right, b.row)?;
+                if c.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+    Ok(Ordering::Equal)
+}
+
+/// Compare 2 non-null values of arrays with the same type
+fn compare_values(left: &ArrayRef, i: usize, right: &ArrayRef, j: usize) -> Result<Ordering> {
+    macro_rules! compare {
+        ($array_type:ty) => {{
+            let left = left.as_any().downcast_ref::<$array_type>().unwrap();
+            let right = right.as_any().downcast_ref::<$array_type>().unwrap();
+            left.value(i)
+                .partial_cmp(&right.value(j))
+                .unwrap_or(Ordering::Equal)
+        }};
+    }
+    Ok(match left.data_type() {
+        DataType::Boolean => compare!(BooleanArray),
+        DataType::Int8 => compare!(Int8Array),
+        DataType::Int16 => compare!(Int16Array),
+        DataType::Int32 => compare!(Int32Array),
+        DataType::Int64 => compare!(Int64Array),
+        DataType::UInt8 => compare!(UInt8Array),
+        DataType::UInt16 => compare!(UInt16Array),
+        DataType::UInt32 => compare!(UInt32Array),
+        DataType::UInt64 => compare!(UInt64Array),
+        DataType::Float32 => compare!(Float32Array),
+        DataType::Float64 => compare!(Float64Array),
+        DataType::Date32(_) => compare!(Date32Array),
+        DataType::Date64(_) => compare!(Date64Array),
+        DataType::Timestamp(TimeUnit::Second, _) => compare!(TimestampSecondArray),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => compare!(TimestampMillisecondArray),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => compare!(TimestampMicrosecondArray),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => compare!(TimestampNanosecondArray),
+        DataType::Utf8 => compare!(StringArray),
+        t => {
+            return Err(DataFrameError::ComputeError(format!(
+                "Unable to merge sorted runs of {:?} data type",
+                t
+            )))
+        }
+    })
+}
+
+impl<S: DataSource> DataSource for SortSource<S> {
+    fn get_dataset(&self) -> Result<Dataset> {
+        self.source.get_dataset()
+    }
+    fn source(&self) -> DataSourceType {
+        self.source.source()
+    }
+    fn format(&self) -> &str {
+        self.source.format()
+    }
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if self.runs.is_none() {
+            self.runs = Some(self.create_runs()?);
+        }
+        check_cancelled(&self.cancel)?;
+        let schema = self.source.schema();
+        let runs = self.runs.as_mut().unwrap();
+        // the batches that rows are taken from, and the index of each run's current batch in it
+        let mut batches: Vec<RecordBatch> = vec![];
+        let mut run_batches: Vec<Option<usize>> = vec![None; runs.len()];
+        let mut indices: Vec<(usize, usize)> = vec![];
+        while indices.len() < self.options.batch_size {
+            // take the smallest row, preferring earlier runs if rows are equal
+            let mut smallest: Option<usize> = None;
+            for (i, run) in runs.iter().enumerate() {
+                if run.batch.is_none() {
+                    continue;
+                }
+                smallest = match smallest {
+                    Some(s) if compare_rows(run, &runs[s], &self.criteria)? != Ordering::Less => {
+                        Some(s)
+                    }
+                    _ => Some(i),
+                };
+            }
+            let i = match smallest {
+                Some(i) => i,
+                None => break,
+            };
+            let run = &mut runs[i];
+            let batch_index = match run_batches[i] {
+                Some(index) => index,
+                None => {
+                    batches.push(run.batch.clone().unwrap());
+                    run_batches[i] = Some(batches.len() - 1);
+                    batches.len() - 1
+                }
+            };
+            indices.push((batch_index, run.row));
+            run.row += 1;
+            if run.row == run.batch.as_ref().unwrap().num_rows() {
+                run.advance(&schema, &self.criteria)?;
+                run_batches[i] = None;
+            }
+        }
+        if indices.is_empty() {
+            return Ok(None);
+        }
+        // take the rows out of the concatenated batches
+        let mut offsets = Vec::with_capacity(batches.len());
+        let mut num_rows = 0;
+        for batch in &batches {
+            offsets.push(num_rows as u32);
+            num_rows += batch.num_rows();
+        }
+        let take_indices = UInt32Array::from(
+            indices
+                .iter()
+                .map(|(batch, row)| offsets[*batch] + *row as u32)
+                .collect::<Vec<u32>>(),
+        );
+        let sorted = sort_batches_by_indices(&schema, &batches, &take_indices)?;
+        Ok(Some(sorted))
+    }
+    fn supports_filtering(&self) -> bool {
+        self.source.supports_filtering()
+    }
+    fn supports_sorting(&self) -> bool {
+        true
+    }
+    fn limit(&mut self, _limit: usize) -> Result<()> {
+        // limiting the wrapped source would apply the limit before sorting
+        Err(DataFrameError::ComputeError(
+            "Sort data source does not support limits".to_string(),
+        ))
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        self.source.filter(filter)
+    }
+    fn project(&mut self, _columns: Vec<String>) -> Result<()> {
+        // projecting the wrapped source could remove the columns that are sorted on
+        Err(DataFrameError::ComputeError(
+            "Sort data source does not support projection".to_string(),
+        ))
+    }
+    fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+        validate_criteria(&self.source.schema(), &criteria)?;
+        self.criteria = criteria;
+        Ok(())
+    }
+    fn row_count_hint(&self) -> Option<usize> {
+        self.source.row_count_hint()
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.source.allocated_bytes()
+    }
+}
+
+/// Take rows out of batches, using indices into the concatenation of the batches
+fn sort_batches_by_indices(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    indices: &UInt32Array,
+) -> Result<RecordBatch> {
+    let columns = (0..schema.fields().len())
+        .map(|i| {
+            let chunks: Vec<ArrayRef> = batches.iter().map(|b| b.column(i).clone()).collect();
+            let column = match chunks.len() {
+                1 => chunks[0].clone(),
+                _ => compute::concat(&chunks)?,
+            };
+            compute::take(&column, indices, None)
+        })
+        .collect::<arrow::error::Result<Vec<ArrayRef>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn source(batches: Vec<(Vec<i64>, Vec<&str>)>) -> MemoryDataSource {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batches = batches
+            .into_iter()
+            .map(|(a, b)| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(a)),
+                        Arc::new(StringArray::from(b)),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect();
+        MemoryDataSource::try_new("sort", schema, batches).unwrap()
+    }
+
+    fn criteria(column: &str, descending: bool) -> SortCriteria {
+        SortCriteria {
+            column: column.to_owned(),
+            descending,
+            nulls_first: false,
+        }
+    }
+
+    /// Read the `a` and `b` values of each batch
+    fn values(source: &mut dyn DataSource) -> Result<Vec<Vec<(i64, String)>>> {
+        let mut values = vec![];
+        while let Some(batch) = source.next_batch()? {
+            let a = batch.column(0).as_any().downcast_ref::<Int64Array>();
+            let b = batch.column(1).as_any().downcast_ref::<StringArray>();
+            let (a, b) = (a.unwrap(), b.unwrap());
+            values.push(
+                (0..batch.num_rows())
+                    .map(|i| (a.value(i), b.value(i).to_owned()))
+                    .collect(),
+            );
+        }
+        Ok(values)
+    }
+
+    #[test]
+    fn test_sort_descending() -> Result<()> {
+        let input = source(vec![
+            (vec![3, 7, 1], vec!["x", "y", "z"]),
+            (vec![5, 2], vec!["v", "w"]),
+        ]);
+        let mut sorted = SortSource::try_new(input, vec![criteria("a", true)], Default::default())?;
+        let a: Vec<i64> = values(&mut sorted)?
+            .into_iter()
+            .flatten()
+            .map(|(a, _)| a)
+            .collect();
+        assert_eq!(vec![7, 5, 3, 2, 1], a);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_cancelled() -> Result<()> {
+        let input = source(vec![(vec![3, 1], vec!["x", "y"]), (vec![2], vec!["z"])]);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut sorted =
+            SortSource::try_new(input, vec![criteria("a", false)], Default::default())?
+                .with_cancel(cancel.clone());
+        cancel.store(true, AtomicOrdering::Relaxed);
+        match sorted.next_batch() {
+            Err(DataFrameError::Cancelled) => {}
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Expected sorting to be cancelled"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_spilled_runs() -> Result<()> {
+        let input = source(vec![
+            (vec![2, 1, 2], vec!["c", "a", "e"]),
+            (vec![1, 3, 2], vec!["d", "b", "a"]),
+            (vec![3, 1], vec!["a", "z"]),
+        ]);
+        // the first 2 batches are spilled as separate runs, and the last is kept in memory
+        let options = SortSourceOptions {
+            max_rows_in_memory: 3,
+            batch_size: 2,
+        };
+        let mut sorted = SortSource::try_new(
+            input,
+            vec![criteria("a", false), criteria("b", true)],
+            options,
+        )?;
+        let batches = values(&mut sorted)?;
+        assert!(batches.iter().all(|batch| batch.len() == 2));
+        let expected: Vec<(i64, String)> = vec![
+            (1, "z"),
+            (1, "d"),
+            (1, "a"),
+            (2, "e"),
+            (2, "c"),
+            (2, "a"),
+            (3, "b"),
+            (3, "a"),
+        ]
+        .into_iter()
+        .map(|(a, b)| (a, b.to_owned()))
+        .collect();
+        assert_eq!(expected, batches.concat());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_unknown_column() {
+        let input = source(vec![(vec![1], vec!["a"])]);
+        assert!(
+            SortSource::try_new(input, vec![criteria("c", false)], Default::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sort_null_placement() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+        let batches = vec![vec![Some(2), None, Some(1)], vec![None, Some(3)]];
+        let sorted_values = |criteria: SortCriteria, max_rows_in_memory: usize| -> Result<_> {
+            let batches = batches
+                .iter()
+                .map(|values| {
+                    let array = Int64Array::from(values.clone());
+                    RecordBatch::try_new(schema.clone(), vec![Arc::new(array)]).unwrap()
+                })
+                .collect();
+            let input = MemoryDataSource::try_new("nulls", schema.clone(), batches)?;
+            let options = SortSourceOptions {
+                max_rows_in_memory,
+                batch_size: 10,
+            };
+            let mut sorted = SortSource::try_new(input, vec![criteria], options)?;
+            let mut values = vec![];
+            while let Some(batch) = sorted.next_batch()? {
+                let array = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                let array = array.unwrap();
+                values.extend((0..array.len()).map(|i| {
+                    if array.is_null(i) {
+                        None
+                    } else {
+                        Some(array.value(i))
+                    }
+                }));
+            }
+            Ok(values)
+        };
+        let ascending_nulls_first = SortCriteria {
+            nulls_first: true,
+            ..SortCriteria::new("a", false)
+        };
+        let descending_nulls_last = SortCriteria {
+            nulls_first: false,
+            ..SortCriteria::new("a", true)
+        };
+        // sort in memory, and by merging a spilled run per batch
+        for max_rows_in_memory in &[100, 2] {
+            assert_eq!(
+                vec![None, None, Some(1), Some(2), Some(3)],
+                sorted_values(ascending_nulls_first.clone(), *max_rows_in_memory)?
+            );
+            assert_eq!(
+                vec![Some(3), Some(2), Some(1), None, None],
+                sorted_values(descending_nulls_last.clone(), *max_rows_in_memory)?
+            );
+        }
+        // SQL's default places nulls last when ascending
+        assert_eq!(
+            vec![Some(1), Some(2), Some(3), None, None],
+            sorted_values(SortCriteria::new("a", false), 100)?
+        );
+        Ok(())
+    }
+}
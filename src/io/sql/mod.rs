@@ -1,4 +1,5 @@
 pub mod postgres;
+pub mod sqlite;
 
 use std::sync::Arc;
 
@@ -6,6 +7,7 @@ use arrow::datatypes::Schema;
 use arrow::record_batch::RecordBatch;
 
 use crate::error::Result;
+use crate::expression::SortCriteria;
 pub trait SqlDataSource {
     fn get_table_schema(connection: &str, table_name: &str) -> Result<Schema>;
     fn read_table(
@@ -26,3 +28,46 @@ pub trait SqlDataSink {
     fn create_table(connection: &str, table_name: &str, schema: &Arc<Schema>) -> Result<()>;
     fn write_to_table(connection: &str, table_name: &str, batches: &[RecordBatch]) -> Result<()>;
 }
+
+/// Create the `ORDER BY` clause of a query that sorts by the criteria
+///
+/// The clause starts with a space, and is empty if there are no criteria.
+pub(crate) fn order_by_clause(criteria: &[SortCriteria]) -> String {
+    if criteria.is_empty() {
+        return String::new();
+    }
+    let criteria = criteria
+        .iter()
+        .map(|c| {
+            format!(
+                "\"{}\" {} nulls {}",
+                c.column,
+                if c.descending { "desc" } else { "asc" },
+                if c.nulls_first { "first" } else { "last" }
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!(" order by {}", criteria)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_by_clause() {
+        assert_eq!("", order_by_clause(&[]));
+        let criteria = vec![
+            SortCriteria::new("a", false),
+            SortCriteria {
+                nulls_first: false,
+                ..SortCriteria::new("b", true)
+            },
+        ];
+        assert_eq!(
+            " order by \"a\" asc nulls last, \"b\" desc nulls last",
+            order_by_clause(&criteria)
+        );
+    }
+}
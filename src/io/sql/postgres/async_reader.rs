@@ -0,0 +1,204 @@
+//! Reading PostgreSQL tables without blocking, with `tokio-postgres`
+
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::{pin_mut, TryStreamExt};
+use tokio_postgres::{Client, NoTls};
+
+use super::reader::{columns_to_schema, read_from_binary};
+use crate::error::{DataFrameError, Result};
+use crate::expression::{
+    BooleanFilter, DataSourceType, Dataset, SortCriteria, SqlDatabase, SqlReadOptions,
+};
+use crate::io::async_source::AsyncDataSource;
+use crate::io::sql::order_by_clause;
+
+/// A data source that reads a PostgreSQL table in batches, without blocking
+///
+/// Each batch is read with a binary `COPY` of a page of the table, so projections, sorts and
+/// limits are pushed down to the query. Filters are not yet translated to SQL.
+pub struct PostgresAsyncDataSource {
+    client: Client,
+    /// The error that stopped the connection task, if it failed
+    connection_error: Arc<Mutex<Option<tokio_postgres::Error>>>,
+    connection_string: String,
+    table: String,
+    /// The schema of the table
+    table_schema: SchemaRef,
+    /// The schema of the batches that are returned, after projection
+    projected_schema: SchemaRef,
+    batch_size: usize,
+    sort: Vec<SortCriteria>,
+    limit: Option<usize>,
+    rows_read: usize,
+    is_complete: bool,
+}
+
+impl PostgresAsyncDataSource {
+    /// Connect to a database, and read the schema of a table
+    ///
+    /// The connection is driven on a task that is spawned on the current tokio runtime. If the
+    /// connection fails, its error is returned by the next call to `next_batch`.
+    pub async fn try_new(connection_string: &str, table: &str, batch_size: usize) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(connection_string, NoTls).await?;
+        let connection_error = Arc::new(Mutex::new(None));
+        let task_error = connection_error.clone();
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                *task_error.lock().unwrap() = Some(e);
+            }
+        });
+        // the schema is read from the statement, so that empty tables have a schema
+        let statement = client
+            .prepare(format!("select * from {}", table).as_str())
+            .await?;
+        let schema = columns_to_schema(statement.columns()).map_err(|_| {
+            DataFrameError::SqlError(format!("Unable to get the schema of table {}", table))
+        })?;
+        let schema = Arc::new(schema);
+        Ok(Self {
+            client,
+            connection_error,
+            connection_string: connection_string.to_string(),
+            table: table.to_string(),
+            table_schema: schema.clone(),
+            projected_schema: schema,
+            batch_size,
+            sort: vec![],
+            limit: None,
+            rows_read: 0,
+            is_complete: false,
+        })
+    }
+
+    /// Take the error that stopped the connection task, if it failed
+    fn take_connection_error(&self) -> Option<DataFrameError> {
+        self.connection_error.lock().unwrap().take().map(Into::into)
+    }
+
+    /// The query that reads the next batch
+    fn batch_query(&self, batch_size: usize) -> String {
+        let columns = self
+            .projected_schema
+            .fields()
+            .iter()
+            .map(|f| format!("\"{}\"", f.name()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let order = order_by_clause(&self.sort);
+        format!(
+            "select {} from {}{} limit {} offset {}",
+            columns, self.table, order, batch_size, self.rows_read
+        )
+    }
+}
+
+#[async_trait]
+impl AsyncDataSource for PostgresAsyncDataSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: self.table.clone(),
+            columns: self
+                .projected_schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    fn source(&self) -> DataSourceType {
+        DataSourceType::Sql(
+            self.table.clone(),
+            SqlReadOptions {
+                connection_string: self.connection_string.clone(),
+                db: SqlDatabase::Postgres,
+                limit: self.limit,
+            },
+        )
+    }
+    fn format(&self) -> &str {
+        "postgres"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let batch_size = match self.limit {
+            Some(limit) => self.batch_size.min(limit.saturating_sub(self.rows_read)),
+            None => self.batch_size,
+        };
+        if let Some(e) = self.take_connection_error() {
+            return Err(e);
+        }
+        if self.is_complete || batch_size == 0 {
+            return Ok(None);
+        }
+        let query = format!(
+            "COPY ({}) TO stdout with (format binary)",
+            self.batch_query(batch_size)
+        );
+        // a query fails with a closed connection error if the connection failed, so the
+        // connection's error is returned instead, as it has the cause
+        let stream = match self.client.copy_out(query.as_str()).await {
+            Ok(stream) => stream,
+            Err(e) => return Err(self.take_connection_error().unwrap_or_else(|| e.into())),
+        };
+        pin_mut!(stream);
+        let mut bytes = vec![];
+        while let Some(chunk) = stream.try_next().await? {
+            bytes.extend_from_slice(&chunk);
+        }
+        let batch = read_from_binary(Cursor::new(bytes), &self.projected_schema)?;
+        // the binary reader does not know the projected schema's metadata
+        let batch = RecordBatch::try_new(self.projected_schema.clone(), batch.columns().to_vec())?;
+        self.rows_read += batch.num_rows();
+        if batch.num_rows() < batch_size {
+            self.is_complete = true;
+        }
+        if batch.num_rows() == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(batch))
+        }
+    }
+    fn supports_projection(&self) -> bool {
+        true
+    }
+    fn supports_sorting(&self) -> bool {
+        true
+    }
+    fn supports_limit(&self) -> bool {
+        true
+    }
+    async fn limit(&mut self, limit: usize) -> Result<()> {
+        self.limit = Some(limit);
+        Ok(())
+    }
+    async fn filter(&mut self, _filter: BooleanFilter) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Postgres data source does not support filtering".to_string(),
+        ))
+    }
+    async fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        let fields = columns
+            .iter()
+            .map(|name| Ok(self.table_schema.field_with_name(name)?.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        self.projected_schema = Arc::new(Schema::new_with_metadata(
+            fields,
+            self.table_schema.metadata().clone(),
+        ));
+        Ok(())
+    }
+    async fn sort(&mut self, criteria: Vec<SortCriteria>) -> Result<()> {
+        for c in &criteria {
+            self.table_schema.field_with_name(&c.column)?;
+        }
+        self.sort = criteria;
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 //! An interface for reading and writing record batches to and from PostgreSQL
 
+#[cfg(feature = "async")]
+pub mod async_reader;
 pub mod reader;
 pub mod writer;
 
@@ -438,9 +438,13 @@ fn read_table_by_rows(
 
 /// Generate Arrow schema from a row
 fn row_to_schema(row: &postgres::Row) -> Result<Schema, ()> {
+    columns_to_schema(row.columns())
+}
+
+/// Generate Arrow schema from the columns of a row or prepared statement
+pub(crate) fn columns_to_schema(columns: &[postgres::Column]) -> Result<Schema, ()> {
     let mut metadata = std::collections::HashMap::new();
-    let fields = row
-        .columns()
+    let fields = columns
         .iter()
         .map(|col: &postgres::Column| {
             metadata.insert(col.name().to_string(), col.type_().to_string());
@@ -450,7 +454,7 @@ fn row_to_schema(row: &postgres::Row) -> Result<Schema, ()> {
     Ok(Schema::new_with_metadata(fields, metadata))
 }
 
-fn read_from_binary<R>(mut reader: R, schema: &Schema) -> crate::error::Result<RecordBatch>
+pub(crate) fn read_from_binary<R>(mut reader: R, schema: &Schema) -> crate::error::Result<RecordBatch>
 where
     R: Read,
 {
@@ -0,0 +1,445 @@
+//! An interface for reading record batches from SQLite databases
+//!
+//! SQLite columns do not have strict types. Each column has a type affinity that is derived
+//! from its declared type, and values of any type can be stored in any column. Arrow types are
+//! therefore inferred from declared types using SQLite's affinity rules, and values are
+//! converted to the inferred type as they are read.
+
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::datatypes::{DataType, DateUnit, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::types::Value;
+use rusqlite::{Connection, Statement, NO_PARAMS};
+
+use crate::error::{DataFrameError, Result};
+use crate::io::sql::SqlDataSource;
+
+pub struct Sqlite;
+
+/// The formats that text values of timestamp columns are parsed with
+const TIMESTAMP_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+
+impl Sqlite {
+    /// Get the schema of a table from an open connection, using `PRAGMA table_info`
+    pub fn table_schema(connection: &Connection, table_name: &str) -> Result<Schema> {
+        let mut statement =
+            connection.prepare(&format!("pragma table_info(\"{}\")", table_name))?;
+        let fields = statement
+            .query_map(NO_PARAMS, |row| {
+                let name: String = row.get("name")?;
+                let declared_type: String = row.get("type")?;
+                let not_null: bool = row.get("notnull")?;
+                Ok(Field::new(
+                    &name,
+                    declared_type_to_arrow(&declared_type),
+                    !not_null,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<Field>>>()?;
+        if fields.is_empty() {
+            return Err(DataFrameError::SqlError(format!(
+                "Table {} not found",
+                table_name
+            )));
+        }
+        Ok(Schema::new(fields))
+    }
+
+    /// Read the results of a query from an open connection into batches of `batch_size` rows
+    ///
+    /// Columns of tables keep the types of their declared types. The types of computed columns
+    /// are inferred from the first non-null value in the first batch, and are `Utf8` if there
+    /// are no such values.
+    pub fn query_batches(
+        connection: &Connection,
+        query: &str,
+        limit: Option<usize>,
+        batch_size: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        read_batches(connection, query, None, limit, batch_size)
+    }
+}
+
+/// Read the results of a query into batches with `schema`, or a schema that is inferred from
+/// the declared types of the query's columns and the values of the first batch
+fn read_batches(
+    connection: &Connection,
+    query: &str,
+    mut schema: Option<SchemaRef>,
+    limit: Option<usize>,
+    batch_size: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut statement = connection.prepare(query)?;
+    let declared_types = declared_types(&statement);
+    let names = statement
+        .column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>();
+    let limit = limit.unwrap_or(std::usize::MAX);
+    let mut rows = statement.query(NO_PARAMS)?;
+    let mut batches = vec![];
+    let mut read_records = 0;
+    loop {
+        // read the values of the next batch by column
+        let mut values: Vec<Vec<Value>> = vec![Vec::with_capacity(batch_size); names.len()];
+        let mut batch_records = 0;
+        while batch_records < batch_size && read_records < limit {
+            match rows.next()? {
+                Some(row) => {
+                    for (i, column) in values.iter_mut().enumerate() {
+                        column.push(row.get(i)?);
+                    }
+                    batch_records += 1;
+                    read_records += 1;
+                }
+                None => break,
+            }
+        }
+        if batch_records == 0 {
+            break;
+        }
+        let schema = schema
+            .get_or_insert_with(|| {
+                let fields = names
+                    .iter()
+                    .zip(declared_types.iter())
+                    .zip(values.iter())
+                    .map(|((name, declared_type), values)| {
+                        let data_type = match declared_type {
+                            Some(declared_type) => declared_type_to_arrow(declared_type),
+                            None => value_type(values),
+                        };
+                        Field::new(name, data_type, true)
+                    })
+                    .collect();
+                Arc::new(Schema::new(fields))
+            })
+            .clone();
+        let columns = schema
+            .fields()
+            .iter()
+            .zip(values.iter())
+            .map(|(field, values)| values_to_array(field, values))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        batches.push(RecordBatch::try_new(schema, columns)?);
+        if batch_records < batch_size {
+            break;
+        }
+    }
+    Ok(batches)
+}
+
+impl SqlDataSource for Sqlite {
+    fn get_table_schema(connection: &str, table_name: &str) -> Result<Schema> {
+        let connection = Connection::open(connection)?;
+        Sqlite::table_schema(&connection, table_name)
+    }
+
+    fn read_table(
+        connection: &str,
+        table_name: &str,
+        limit: Option<usize>,
+        batch_size: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        let connection = Connection::open(connection)?;
+        // the schema is read from the table, so that types and nullability match its columns
+        let schema = Arc::new(Sqlite::table_schema(&connection, table_name)?);
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| format!("\"{}\"", f.name()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        read_batches(
+            &connection,
+            &format!("select {} from \"{}\"", columns, table_name),
+            Some(schema),
+            limit,
+            batch_size,
+        )
+    }
+
+    fn read_query(
+        connection: &str,
+        query: &str,
+        limit: Option<usize>,
+        batch_size: usize,
+    ) -> Result<Vec<RecordBatch>> {
+        let connection = Connection::open(connection)?;
+        Sqlite::query_batches(&connection, query, limit, batch_size)
+    }
+}
+
+/// Convert the declared type of a SQLite column to an Arrow type
+///
+/// The affinity rules of SQLite are applied in order, so that for example `CHARINT` is an
+/// integer. Columns with numeric affinity are read as `Float64`, except for declared types that
+/// name booleans, dates or timestamps. Columns without a declared type can hold any value, and
+/// are read as `Utf8`.
+pub fn declared_type_to_arrow(declared_type: &str) -> DataType {
+    let declared_type = declared_type.to_uppercase();
+    if declared_type.contains("INT") {
+        DataType::Int64
+    } else if declared_type.contains("CHAR")
+        || declared_type.contains("CLOB")
+        || declared_type.contains("TEXT")
+    {
+        DataType::Utf8
+    } else if declared_type.contains("BLOB") {
+        DataType::Binary
+    } else if declared_type.is_empty() {
+        DataType::Utf8
+    } else if declared_type.contains("REAL")
+        || declared_type.contains("FLOA")
+        || declared_type.contains("DOUB")
+    {
+        DataType::Float64
+    } else if declared_type.contains("BOOL") {
+        DataType::Boolean
+    } else if declared_type.contains("DATETIME") || declared_type.contains("TIMESTAMP") {
+        DataType::Timestamp(TimeUnit::Millisecond, None)
+    } else if declared_type.contains("DATE") {
+        DataType::Date32(DateUnit::Day)
+    } else {
+        DataType::Float64
+    }
+}
+
+/// The declared types of the columns of a statement, which computed columns do not have
+fn declared_types(statement: &Statement) -> Vec<Option<String>> {
+    statement
+        .columns()
+        .iter()
+        .map(|column| column.decl_type().map(|t| t.to_string()))
+        .collect()
+}
+
+/// The type of the first non-null value, for columns that have no declared type
+fn value_type(values: &[Value]) -> DataType {
+    values
+        .iter()
+        .find_map(|value| match value {
+            Value::Null => None,
+            Value::Integer(_) => Some(DataType::Int64),
+            Value::Real(_) => Some(DataType::Float64),
+            Value::Text(_) => Some(DataType::Utf8),
+            Value::Blob(_) => Some(DataType::Binary),
+        })
+        .unwrap_or(DataType::Utf8)
+}
+
+fn conversion_error(field: &Field, value: &Value) -> DataFrameError {
+    DataFrameError::SqlError(format!(
+        "Unable to read value {:?} of column {} as {:?}",
+        value,
+        field.name(),
+        field.data_type()
+    ))
+}
+
+/// Convert the values of a column to an array of the field's type
+///
+/// Values are converted the way SQLite would when comparing them to the column's affinity,
+/// so for example text in an integer column is parsed as an integer. Values that cannot be
+/// converted return an error.
+fn values_to_array(field: &Field, values: &[Value]) -> Result<ArrayRef> {
+    macro_rules! convert {
+        ($array:ty, $convert:expr) => {{
+            let converted = values
+                .iter()
+                .map(|value| match value {
+                    Value::Null => Ok(None),
+                    value => $convert(value)
+                        .map(Some)
+                        .ok_or_else(|| conversion_error(field, value)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(<$array>::from(converted)) as ArrayRef
+        }};
+    }
+    let array = match field.data_type() {
+        DataType::Int64 => convert!(Int64Array, |value: &Value| match value {
+            Value::Integer(v) => Some(*v),
+            Value::Real(v) if v.fract() == 0.0 => Some(*v as i64),
+            Value::Text(v) => v.trim().parse().ok(),
+            _ => None,
+        }),
+        DataType::Float64 => convert!(Float64Array, |value: &Value| match value {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Real(v) => Some(*v),
+            Value::Text(v) => v.trim().parse().ok(),
+            _ => None,
+        }),
+        DataType::Boolean => convert!(BooleanArray, |value: &Value| match value {
+            Value::Integer(v) => Some(*v != 0),
+            Value::Text(v) => match v.to_lowercase().as_str() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }),
+        // dates and timestamps are stored as text, or as days or seconds since the Unix epoch
+        DataType::Date32(DateUnit::Day) => convert!(Date32Array, |value: &Value| match value {
+            Value::Integer(v) => Some(*v as i32),
+            Value::Text(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d").ok().map(|date| {
+                date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+                    .num_days() as i32
+            }),
+            _ => None,
+        }),
+        DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            convert!(TimestampMillisecondArray, |value: &Value| match value {
+                Value::Integer(v) => Some(*v * 1000),
+                Value::Text(v) => TIMESTAMP_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(v, format).ok())
+                    .map(|timestamp| timestamp.timestamp_millis()),
+                _ => None,
+            })
+        }
+        DataType::Utf8 => {
+            let strings = values
+                .iter()
+                .map(|value| match value {
+                    Value::Null => None,
+                    Value::Integer(v) => Some(v.to_string()),
+                    Value::Real(v) => Some(v.to_string()),
+                    Value::Text(v) => Some(v.clone()),
+                    Value::Blob(v) => Some(String::from_utf8_lossy(v).into_owned()),
+                })
+                .collect::<Vec<Option<String>>>();
+            Arc::new(StringArray::from(
+                strings.iter().map(|s| s.as_deref()).collect::<Vec<_>>(),
+            )) as ArrayRef
+        }
+        DataType::Binary => {
+            let mut builder = BinaryBuilder::new(values.len());
+            for value in values {
+                match value {
+                    Value::Null => builder.append_null()?,
+                    Value::Blob(v) => builder.append_value(v)?,
+                    Value::Text(v) => builder.append_value(v.as_bytes())?,
+                    value => return Err(conversion_error(field, value)),
+                }
+            }
+            Arc::new(builder.finish()) as ArrayRef
+        }
+        data_type => {
+            return Err(DataFrameError::SqlError(format!(
+                "Reading SQLite values as {:?} is not supported",
+                data_type
+            )))
+        }
+    };
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cities() -> Result<Connection> {
+        let connection = Connection::open_in_memory()?;
+        connection.execute_batch(
+            "create table cities (
+                id integer primary key,
+                name varchar(50) not null,
+                population bigint,
+                area real,
+                founded date,
+                is_capital boolean,
+                notes
+            );
+            insert into cities values (1, 'London', 8982000, 1572.0, '0047-01-01', 1, null);
+            insert into cities values (2, 'Manchester', null, 115.6, '0079-01-01', 0, 'north');
+            insert into cities values (3, 'Elgin', '23128', 3, null, 0, 42);",
+        )?;
+        Ok(connection)
+    }
+
+    #[test]
+    fn test_declared_type_to_arrow() {
+        assert_eq!(DataType::Int64, declared_type_to_arrow("UNSIGNED BIG INT"));
+        assert_eq!(DataType::Int64, declared_type_to_arrow("CHARINT"));
+        assert_eq!(DataType::Utf8, declared_type_to_arrow("nvarchar(100)"));
+        assert_eq!(DataType::Binary, declared_type_to_arrow("BLOB"));
+        assert_eq!(DataType::Utf8, declared_type_to_arrow(""));
+        assert_eq!(
+            DataType::Float64,
+            declared_type_to_arrow("DOUBLE PRECISION")
+        );
+        assert_eq!(DataType::Float64, declared_type_to_arrow("DECIMAL(10,5)"));
+        assert_eq!(
+            DataType::Date32(DateUnit::Day),
+            declared_type_to_arrow("date")
+        );
+        assert_eq!(
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            declared_type_to_arrow("DATETIME")
+        );
+    }
+
+    #[test]
+    fn test_sqlite_table_schema() -> Result<()> {
+        let connection = cities()?;
+        let schema = Sqlite::table_schema(&connection, "cities")?;
+        let expected = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("population", DataType::Int64, true),
+            Field::new("area", DataType::Float64, true),
+            Field::new("founded", DataType::Date32(DateUnit::Day), true),
+            Field::new("is_capital", DataType::Boolean, true),
+            Field::new("notes", DataType::Utf8, true),
+        ]);
+        assert_eq!(expected, schema);
+        assert!(Sqlite::table_schema(&connection, "towns").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqlite_query_batches() -> Result<()> {
+        let connection = cities()?;
+        let batches = Sqlite::query_batches(
+            &connection,
+            "select name, population, area, notes, id * 2 as double_id from cities",
+            None,
+            2,
+        )?;
+        assert_eq!(2, batches.len());
+        assert_eq!(2, batches[0].num_rows());
+        assert_eq!(1, batches[1].num_rows());
+        // the computed column's type is inferred from its values
+        assert_eq!(&DataType::Int64, batches[0].schema().field(4).data_type());
+
+        let population = batches[1]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        // text inserted into an integer column is read as an integer
+        assert_eq!(23128, population.value(0));
+        let area = batches[1]
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(3.0, area.value(0));
+        let notes = batches[1]
+            .column(3)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!("42", notes.value(0));
+
+        let batches = Sqlite::query_batches(&connection, "select * from cities", Some(1), 2)?;
+        assert_eq!(1, batches.len());
+        assert_eq!(1, batches[0].num_rows());
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! Reading several data sources one after the other as a single source
+
+use std::sync::Arc;
+
+use arrow::array::ArrayRef;
+use arrow::compute;
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFrameError, Result};
+use crate::expression::{BooleanFilter, DataSourceType, Dataset, SortCriteria};
+use crate::io::datasource::DataSource;
+use crate::io::parquet::null_array;
+use crate::operation::scalar::promote_numeric;
+
+/// How the schemas of the sources of a union have to match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnionSchemaMode {
+    /// The sources must have columns with the same names and types, in the same order
+    Strict,
+    /// Numeric columns of different types are cast to the type that `promote_numeric` promotes
+    /// them to, while the names and order of the columns must still match
+    Coerce,
+    /// The schemas are combined with `merge_schemas`, so sources can have different columns,
+    /// and columns that a source does not have are filled with nulls
+    Merge,
+}
+
+/// A data source that returns all the batches of its sources, one source after the other
+///
+/// The sources must have compatible schemas, according to the `UnionSchemaMode`. A column of
+/// the union is nullable if it is nullable in any source.
+pub struct UnionSource {
+    sources: Vec<Box<dyn DataSource>>,
+    mode: UnionSchemaMode,
+    schema: SchemaRef,
+    /// The index of the source that batches are being read from
+    current: usize,
+}
+
+impl UnionSource {
+    pub fn try_new(sources: Vec<Box<dyn DataSource>>, mode: UnionSchemaMode) -> Result<Self> {
+        let schema = union_schema(&sources, mode)?;
+        Ok(Self {
+            sources,
+            mode,
+            schema,
+            current: 0,
+        })
+    }
+
+    pub fn into_inner(self) -> Vec<Box<dyn DataSource>> {
+        self.sources
+    }
+
+    /// Arrange the columns of a batch to match the union's schema, casting them to its types
+    ///
+    /// Columns that the batch does not have are filled with nulls.
+    fn coerce_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let batch_schema = batch.schema();
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| match batch_schema.index_of(field.name()) {
+                Ok(i) if batch.column(i).data_type() == field.data_type() => {
+                    Ok(batch.column(i).clone())
+                }
+                Ok(i) => Ok(compute::cast(batch.column(i), field.data_type())?),
+                Err(_) => null_array(field.data_type(), batch.num_rows()),
+            })
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+/// Merge schemas that have compatible, but not necessarily the same, columns
+///
+/// The merged schema has the columns of all schemas, in the order that they first appear.
+/// Numeric columns of different types are promoted with `promote_numeric`, and a column is
+/// nullable if it is nullable in any schema, or if any schema does not have it. Columns of
+/// other types must have the same type in each schema that has them.
+pub fn merge_schemas(schemas: &[SchemaRef]) -> Result<SchemaRef> {
+    let mut fields: Vec<Field> = vec![];
+    for schema in schemas {
+        for other in schema.fields() {
+            let field = match fields.iter_mut().find(|field| field.name() == other.name()) {
+                Some(field) => field,
+                None => {
+                    fields.push(other.clone());
+                    continue;
+                }
+            };
+            let data_type = if field.data_type() == other.data_type() {
+                field.data_type().clone()
+            } else {
+                promote_numeric(field.data_type(), other.data_type()).map_err(|_| {
+                    DataFrameError::SchemaMismatch {
+                        column: field.name().clone(),
+                        expected: field.data_type().clone(),
+                        actual: other.data_type().clone(),
+                    }
+                })?
+            };
+            *field = Field::new(
+                field.name(),
+                data_type,
+                field.is_nullable() || other.is_nullable(),
+            );
+        }
+    }
+    // columns that any of the schemas do not have are filled with nulls
+    let fields = fields
+        .into_iter()
+        .map(|field| {
+            let nullable = field.is_nullable()
+                || schemas
+                    .iter()
+                    .any(|schema| schema.field_with_name(field.name()).is_err());
+            Field::new(field.name(), field.data_type().clone(), nullable)
+        })
+        .collect();
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Check that the schemas of the sources match, returning the schema of their union
+fn union_schema(sources: &[Box<dyn DataSource>], mode: UnionSchemaMode) -> Result<SchemaRef> {
+    if mode == UnionSchemaMode::Merge && !sources.is_empty() {
+        let schemas: Vec<SchemaRef> = sources.iter().map(|source| source.schema()).collect();
+        return merge_schemas(&schemas);
+    }
+    let first = match sources.first() {
+        Some(source) => source.schema(),
+        None => {
+            return Err(DataFrameError::InvalidOperation {
+                message: "A union requires at least one data source".to_string(),
+                suggestion: None,
+            })
+        }
+    };
+    let mut fields = first.fields().clone();
+    for (i, source) in sources.iter().enumerate().skip(1) {
+        let schema = source.schema();
+        if schema.fields().len() != fields.len() {
+            return Err(DataFrameError::InvalidOperation {
+                message: format!(
+                    "Source {} of the union has {} columns, but the first source has {}",
+                    i,
+                    schema.fields().len(),
+                    fields.len()
+                ),
+                suggestion: None,
+            });
+        }
+        for (field, other) in fields.iter_mut().zip(schema.fields()) {
+            if field.name() != other.name() {
+                return Err(DataFrameError::InvalidOperation {
+                    message: format!(
+                        "Source {} of the union has column {} where the first source has {}",
+                        i,
+                        other.name(),
+                        field.name()
+                    ),
+                    suggestion: Some("columns must be in the same order in all sources".to_owned()),
+                });
+            }
+            let mismatch = || DataFrameError::SchemaMismatch {
+                column: field.name().clone(),
+                expected: field.data_type().clone(),
+                actual: other.data_type().clone(),
+            };
+            let data_type = if field.data_type() == other.data_type() {
+                field.data_type().clone()
+            } else if mode == UnionSchemaMode::Coerce {
+                promote_numeric(field.data_type(), other.data_type()).map_err(|_| mismatch())?
+            } else {
+                return Err(mismatch());
+            };
+            *field = Field::new(
+                field.name(),
+                data_type,
+                field.is_nullable() || other.is_nullable(),
+            );
+        }
+    }
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+impl DataSource for UnionSource {
+    fn get_dataset(&self) -> Result<Dataset> {
+        Ok(Dataset {
+            name: "union_source".to_owned(),
+            columns: self
+                .schema
+                .fields()
+                .iter()
+                .map(|f| f.clone().into())
+                .collect(),
+        })
+    }
+    /// The type of the first source
+    fn source(&self) -> DataSourceType {
+        self.sources[0].source()
+    }
+    fn format(&self) -> &str {
+        "union"
+    }
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        while self.current < self.sources.len() {
+            match self.sources[self.current].next_batch()? {
+                Some(batch) => return Ok(Some(self.coerce_batch(&batch)?)),
+                None => self.current += 1,
+            }
+        }
+        Ok(None)
+    }
+    fn supports_projection(&self) -> bool {
+        self.sources
+            .iter()
+            .all(|source| source.supports_projection())
+    }
+    fn supports_filtering(&self) -> bool {
+        // filters are typed with the union's columns, which coerced or merged sources may not
+        // have
+        self.mode == UnionSchemaMode::Strict
+            && self
+                .sources
+                .iter()
+                .all(|source| source.supports_filtering())
+    }
+    fn limit(&mut self, _limit: usize) -> Result<()> {
+        Err(DataFrameError::ComputeError(
+            "Union data source does not support limits".to_string(),
+        ))
+    }
+    fn filter(&mut self, filter: BooleanFilter) -> Result<()> {
+        if !self.supports_filtering() {
+            return Err(DataFrameError::ComputeError(
+                "Union data source does not support filtering".to_string(),
+            ));
+        }
+        for source in &mut self.sources {
+            source.filter(filter.clone())?;
+        }
+        Ok(())
+    }
+    fn project(&mut self, columns: Vec<String>) -> Result<()> {
+        for source in &mut self.sources {
+            source.project(columns.clone())?;
+        }
+        self.schema = union_schema(&self.sources, self.mode)?;
+        Ok(())
+    }
+    fn sort(&mut self, _criteria: Vec<SortCriteria>) -> Result<()> {
+        // sorting each source would not sort their union
+        Err(DataFrameError::ComputeError(
+            "Union data source does not support sorting".to_string(),
+        ))
+    }
+    fn row_count_hint(&self) -> Option<usize> {
+        self.sources
+            .iter()
+            .map(|source| source.row_count_hint())
+            .sum()
+    }
+    fn allocated_bytes(&self) -> usize {
+        self.sources
+            .iter()
+            .map(|source| source.allocated_bytes())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::MemoryDataSource;
+    use arrow::array::{Array, Float64Array, Int32Array, Int64Array};
+    use arrow::datatypes::DataType;
+
+    fn source(name: &str, values: ArrayRef) -> Box<dyn DataSource> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "a",
+            values.data_type().clone(),
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![values]).unwrap();
+        Box::new(MemoryDataSource::try_new(name, schema, vec![batch]).unwrap())
+    }
+
+    #[test]
+    fn test_union_sources() -> Result<()> {
+        let sources = vec![
+            source("first", Arc::new(Int64Array::from(vec![1, 2, 3]))),
+            source("second", Arc::new(Int64Array::from(vec![4, 5]))),
+        ];
+        let mut union = UnionSource::try_new(sources, UnionSchemaMode::Strict)?;
+        assert_eq!(Some(5), union.row_count_hint());
+        assert_eq!(&DataType::Int64, union.schema().field(0).data_type());
+        let batches = union.execute(Default::default())?;
+        assert_eq!(2, batches.len());
+        assert_eq!(5, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_union_schema_coercion() -> Result<()> {
+        let sources = || {
+            vec![
+                source("ints", Arc::new(Int32Array::from(vec![1, 2]))),
+                source("floats", Arc::new(Float64Array::from(vec![0.5]))),
+            ]
+        };
+        match UnionSource::try_new(sources(), UnionSchemaMode::Strict) {
+            Err(DataFrameError::SchemaMismatch {
+                column,
+                expected,
+                actual,
+            }) => {
+                assert_eq!("a", column);
+                assert_eq!(DataType::Int32, expected);
+                assert_eq!(DataType::Float64, actual);
+            }
+            _ => panic!("Expected a schema mismatch"),
+        }
+
+        let mut union = UnionSource::try_new(sources(), UnionSchemaMode::Coerce)?;
+        assert_eq!(&DataType::Float64, union.schema().field(0).data_type());
+        let batches = union.execute(Default::default())?;
+        for batch in &batches {
+            assert_eq!(&DataType::Float64, batch.column(0).data_type());
+        }
+        assert_eq!(3, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_schemas() -> Result<()> {
+        let first = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("score", DataType::Float32, false),
+        ]));
+        let second = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float32, false),
+            Field::new("comment", DataType::Utf8, false),
+        ]));
+        let merged = merge_schemas(&[first.clone(), second])?;
+        let expected = Schema::new(vec![
+            // the integer types are promoted
+            Field::new("id", DataType::Int64, false),
+            // name is nullable in the second schema
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float32, false),
+            // comment is missing from the first schema
+            Field::new("comment", DataType::Utf8, true),
+        ]);
+        assert_eq!(&expected, merged.as_ref());
+
+        let incompatible = Arc::new(Schema::new(vec![Field::new("id", DataType::Utf8, false)]));
+        assert_eq!(
+            Err(DataFrameError::SchemaMismatch {
+                column: "id".to_owned(),
+                expected: DataType::Int32,
+                actual: DataType::Utf8,
+            }),
+            merge_schemas(&[first, incompatible])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_union_merged_sources() -> Result<()> {
+        let sources = vec![
+            source("ints", Arc::new(Int32Array::from(vec![1, 2]))),
+            Box::new(MemoryDataSource::try_new(
+                "names",
+                Arc::new(Schema::new(vec![Field::new("b", DataType::Utf8, false)])),
+                vec![],
+            )?) as Box<dyn DataSource>,
+            source("floats", Arc::new(Float64Array::from(vec![0.5]))),
+        ];
+        let mut union = UnionSource::try_new(sources, UnionSchemaMode::Merge)?;
+        let schema = union.schema();
+        assert_eq!(&DataType::Float64, schema.field(0).data_type());
+        assert!(schema.field(1).is_nullable());
+        let batches = union.execute(Default::default())?;
+        assert_eq!(2, batches.len());
+        // the sources do not have column b, which is filled with nulls
+        assert_eq!(2, batches[0].column(1).null_count());
+        assert_eq!(&DataType::Float64, batches[0].column(0).data_type());
+        Ok(())
+    }
+}
@@ -105,6 +105,7 @@ impl LazyFrame {
                 columns[index] = Column {
                     name: new_name.to_owned(),
                     column_type: column.column_type.clone(),
+                    nullable: column.nullable,
                 };
                 let output = Dataset {
                     name: "renamed_dataset".to_owned(),
@@ -194,24 +195,33 @@ impl LazyFrame {
 
     /// drop columns
     ///
-    /// TODO: drop should be infallible as it should just filter for non-existent columns
+    /// An error is returned if a column does not exist.
     pub fn drop(&self, col_names: Vec<&str>) -> Result<Self, DataFrameError> {
-        let mut columns = vec![];
-        let mut projected = vec![];
-        for col in self.output.columns.clone() {
-            if col_names.contains(&col.name.as_str()) {
-                projected.push(col.name);
-                continue;
-            }
-            columns.push(col.clone());
-        }
-        let out_dataset = Dataset {
-            name: "".to_string(),
-            columns,
+        use crate::operation::projection::DropColumnsOperation;
+        let (transformation, out_dataset) =
+            DropColumnsOperation::transform(&self.output, &col_names)?;
+        let computation = Computation {
+            input: vec![self.output.clone()],
+            transformations: vec![transformation],
+            output: out_dataset.clone(),
         };
+        let expression = Expression::Compute(Box::new(self.expression.clone()), computation);
+        Ok(Self {
+            id: "projected_frame".to_owned(),
+            expression,
+            output: out_dataset,
+        })
+    }
+
+    /// project columns in order, renaming each `(input, output)` column
+    ///
+    /// An error is returned if an input column does not exist.
+    pub fn project(&self, columns: Vec<(&str, &str)>) -> Result<Self, DataFrameError> {
+        use crate::operation::projection::ProjectOperation;
+        let (transformation, out_dataset) = ProjectOperation::transform(&self.output, &columns)?;
         let computation = Computation {
             input: vec![self.output.clone()],
-            transformations: vec![Transformation::Drop(projected)],
+            transformations: vec![transformation],
             output: out_dataset.clone(),
         };
         let expression = Expression::Compute(Box::new(self.expression.clone()), computation);
@@ -331,6 +341,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -373,6 +386,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -383,7 +399,7 @@ mod tests {
         frame = frame
             .with_column(
                 "sum",
-                Function::Scalar(ScalarFunction::Add),
+                Function::Scalar(ScalarFunction::Add(Default::default())),
                 vec!["lat", "lng"],
                 None,
             )
@@ -418,6 +434,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -452,6 +471,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -480,6 +502,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -521,6 +546,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -13,6 +13,8 @@ pub mod io;
 pub mod lazyframe;
 pub mod operation;
 pub mod optimiser;
+pub mod pretty;
+pub mod sourceframe;
 pub mod table;
 pub mod utils;
 pub mod context;
@@ -0,0 +1,243 @@
+//! Aggregate Operations
+
+use crate::error::DataFrameError;
+use crate::expression::*;
+use arrow::datatypes::DataType;
+
+pub trait AggregateOperation {
+    fn name() -> &'static str;
+    fn function() -> AggregateFunction;
+    /// Validate that the input column can be aggregated, returning the aggregated column
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError>;
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+fn is_orderable(data_type: &DataType) -> bool {
+    is_numeric(data_type)
+        || matches!(
+            data_type,
+            DataType::Boolean
+                | DataType::Utf8
+                | DataType::Date32(_)
+                | DataType::Date64(_)
+                | DataType::Time32(_)
+                | DataType::Time64(_)
+                | DataType::Timestamp(_, _)
+        )
+}
+
+/// Whether values of the type can be hashed and compared for equality
+fn is_hashable(data_type: &DataType) -> bool {
+    is_numeric(data_type)
+        || matches!(
+            data_type,
+            DataType::Boolean
+                | DataType::Utf8
+                | DataType::Date32(_)
+                | DataType::Date64(_)
+                | DataType::Timestamp(_, _)
+        )
+}
+
+/// Create the output column of an aggregation, if the input's type satisfies `is_valid_type`
+fn aggregate_transform(
+    op_name: &str,
+    input: &Column,
+    name: Option<String>,
+    is_valid_type: impl Fn(&DataType) -> bool,
+    output_type: Option<DataType>,
+) -> Result<Column, DataFrameError> {
+    match &input.column_type {
+        ColumnType::Scalar(data_type) if is_valid_type(data_type) => Ok(Column {
+            name: name.unwrap_or(format!("{}({})", op_name, &input.name)),
+            column_type: ColumnType::Scalar(output_type.unwrap_or_else(|| data_type.clone())),
+            nullable: input.nullable,
+        }),
+        column_type => Err(DataFrameError::UnsupportedOperation {
+            op: op_name.to_string(),
+            reason: format!("Aggregation does not support {:?} columns", column_type),
+        }),
+    }
+}
+
+/// Aggregation that sums numeric values
+pub struct SumAggregation;
+
+impl AggregateOperation for SumAggregation {
+    fn name() -> &'static str {
+        "sum"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Sum
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        aggregate_transform(Self::name(), input, name, is_numeric, None)
+    }
+}
+
+/// Aggregation that counts the non-null values of a column of any type
+pub struct CountAggregation;
+
+impl AggregateOperation for CountAggregation {
+    fn name() -> &'static str {
+        "count"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Count
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        Ok(Column {
+            name: name.unwrap_or(format!("{}({})", Self::name(), &input.name)),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: false,
+        })
+    }
+}
+
+/// Aggregation that finds the minimum value of an orderable column
+pub struct MinAggregation;
+
+impl AggregateOperation for MinAggregation {
+    fn name() -> &'static str {
+        "min"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Min
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        aggregate_transform(Self::name(), input, name, is_orderable, None)
+    }
+}
+
+/// Aggregation that finds the maximum value of an orderable column
+pub struct MaxAggregation;
+
+impl AggregateOperation for MaxAggregation {
+    fn name() -> &'static str {
+        "max"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Max
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        aggregate_transform(Self::name(), input, name, is_orderable, None)
+    }
+}
+
+/// Aggregation that computes the mean of numeric values
+pub struct MeanAggregation;
+
+impl AggregateOperation for MeanAggregation {
+    fn name() -> &'static str {
+        "avg"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Avg
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        aggregate_transform(
+            Self::name(),
+            input,
+            name,
+            is_numeric,
+            Some(DataType::Float64),
+        )
+    }
+}
+
+/// Aggregation that finds the most frequent value of a hashable column
+///
+/// If more than one value is the most frequent, the value that was seen first is returned.
+pub struct ModeAggregation;
+
+impl AggregateOperation for ModeAggregation {
+    fn name() -> &'static str {
+        "mode"
+    }
+
+    fn function() -> AggregateFunction {
+        AggregateFunction::Mode
+    }
+
+    fn transform(input: &Column, name: Option<String>) -> Result<Column, DataFrameError> {
+        aggregate_transform(Self::name(), input, name, is_hashable, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_output_types() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let sum = SumAggregation::transform(&a, None).unwrap();
+        assert_eq!("sum(a)", sum.name);
+        assert_eq!(ColumnType::Scalar(DataType::Int64), sum.column_type);
+        let count = CountAggregation::transform(&a, None).unwrap();
+        assert_eq!(ColumnType::Scalar(DataType::Int64), count.column_type);
+        let min = MinAggregation::transform(&a, None).unwrap();
+        assert_eq!(ColumnType::Scalar(DataType::Int64), min.column_type);
+        let max = MaxAggregation::transform(&a, Some("largest".to_owned())).unwrap();
+        assert_eq!("largest", max.name);
+        let mean = MeanAggregation::transform(&a, None).unwrap();
+        assert_eq!("avg(a)", mean.name);
+        assert_eq!(ColumnType::Scalar(DataType::Float64), mean.column_type);
+    }
+
+    #[test]
+    fn aggregate_input_types() {
+        let s = Column {
+            name: "s".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        assert!(CountAggregation::transform(&s, None).is_ok());
+        assert!(MinAggregation::transform(&s, None).is_ok());
+        assert!(MaxAggregation::transform(&s, None).is_ok());
+        match SumAggregation::transform(&s, None) {
+            Err(DataFrameError::UnsupportedOperation { op, .. }) => assert_eq!("sum", op),
+            result => panic!("Expected an unsupported operation, found {:?}", result),
+        }
+        let mode = ModeAggregation::transform(&s, None).unwrap();
+        assert_eq!("mode(s)", mode.name);
+        assert_eq!(ColumnType::Scalar(DataType::Utf8), mode.column_type);
+        assert!(MeanAggregation::transform(&s, None).is_err());
+
+        let l = Column {
+            name: "l".to_owned(),
+            column_type: ColumnType::Array(DataType::Int64),
+            nullable: true,
+        };
+        assert!(MinAggregation::transform(&l, None).is_err());
+    }
+}
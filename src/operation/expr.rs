@@ -0,0 +1,302 @@
+//! Column expressions
+//!
+//! Expressions are built from columns and literals with `col` and `lit`, and combined with
+//! arithmetic operators and comparisons, e.g. `col("a") + col("b")` or `col("a").gt(lit(2))`.
+//! Arithmetic expressions and `array_contains` are lowered into the calculations of scalar
+//! operations, and comparisons into boolean filters.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::error::DataFrameError;
+use crate::expression::*;
+use crate::operation::scalar::*;
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Column(String),
+    Literal(Scalar),
+    Binary(Box<Expr>, BinaryOperator, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    /// Whether the list on the left contains the value on the right
+    ArrayContains,
+}
+
+/// A reference to a column, by name
+pub fn col(name: &str) -> Expr {
+    Expr::Column(name.to_owned())
+}
+
+/// A literal value
+pub fn lit<T: Into<Scalar>>(value: T) -> Expr {
+    Expr::Literal(value.into())
+}
+
+impl Expr {
+    fn binary(self, op: BinaryOperator, other: Expr) -> Self {
+        Expr::Binary(Box::new(self), op, Box::new(other))
+    }
+
+    pub fn gt(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Gt, other)
+    }
+
+    pub fn ge(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Ge, other)
+    }
+
+    pub fn lt(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Lt, other)
+    }
+
+    pub fn le(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Le, other)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn eq(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Eq, other)
+    }
+
+    pub fn ne(self, other: Expr) -> Self {
+        self.binary(BinaryOperator::Ne, other)
+    }
+
+    /// Whether the list expression contains the value of another expression, e.g.
+    /// `col("tags").array_contains(lit("rust"))`
+    pub fn array_contains(self, value: Expr) -> Self {
+        self.binary(BinaryOperator::ArrayContains, value)
+    }
+
+    /// Lower an arithmetic expression into the calculations that compute it
+    ///
+    /// Column types are resolved from the dataset, and literals are repeated for each row.
+    /// Subexpressions of only literals are folded into a single literal. The last calculation
+    /// produces the expression's output column.
+    pub fn to_calculations(&self, dataset: &Dataset) -> Result<Vec<Calculation>, DataFrameError> {
+        let mut calculations = vec![];
+        self.lower(dataset, &mut calculations)?;
+        Ok(crate::optimiser::fold_constants(calculations))
+    }
+
+    /// Lower a comparison expression into a boolean filter
+    pub fn to_filter(&self, dataset: &Dataset) -> Result<BooleanFilter, DataFrameError> {
+        use BinaryOperator::*;
+        match self {
+            Expr::Column(name) => Ok(BooleanFilter::Input(BooleanInput::Column(resolve_column(
+                dataset, name,
+            )?))),
+            Expr::Literal(scalar) => Ok(BooleanFilter::Input(BooleanInput::Scalar(scalar.clone()))),
+            Expr::Binary(left, op, right) => {
+                let left = Box::new(left.to_filter(dataset)?);
+                let right = Box::new(right.to_filter(dataset)?);
+                match op {
+                    Gt => Ok(BooleanFilter::Gt(left, right)),
+                    Ge => Ok(BooleanFilter::Ge(left, right)),
+                    Lt => Ok(BooleanFilter::Lt(left, right)),
+                    Le => Ok(BooleanFilter::Le(left, right)),
+                    Eq => Ok(BooleanFilter::Eq(left, right)),
+                    Ne => Ok(BooleanFilter::Ne(left, right)),
+                    Add | Subtract | Multiply | Divide => Err(DataFrameError::InvalidOperation {
+                        message: format!("{:?} cannot be used as a filter", op),
+                        suggestion: Some("compare the expression with another value".to_owned()),
+                    }),
+                    ArrayContains => Err(DataFrameError::InvalidOperation {
+                        message: format!("{:?} cannot be used as a filter", op),
+                        suggestion: Some(
+                            "calculate the expression, and filter on its output".to_owned(),
+                        ),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Append the calculations of the expression, returning its output column
+    fn lower(
+        &self,
+        dataset: &Dataset,
+        calculations: &mut Vec<Calculation>,
+    ) -> Result<Column, DataFrameError> {
+        use BinaryOperator::*;
+        match self {
+            Expr::Column(name) => resolve_column(dataset, name),
+            Expr::Literal(scalar) => {
+                let literal = Calculation::literal(scalar.clone(), None)?;
+                let output = literal.output.clone();
+                calculations.push(literal);
+                Ok(output)
+            }
+            Expr::Binary(left, op, right) => {
+                let inputs = vec![
+                    left.lower(dataset, calculations)?,
+                    right.lower(dataset, calculations)?,
+                ];
+                let lowered = match op {
+                    Add => AddOperation::transform(inputs, None, None),
+                    Subtract => SubtractOperation::transform(inputs, None, None),
+                    Multiply => MultiplyOperation::transform(inputs, None, None),
+                    Divide => DivideOperation::transform(inputs, None, None),
+                    ArrayContains => ArrayContainsOperation::transform(inputs, None, None),
+                    Gt | Ge | Lt | Le | Eq | Ne => {
+                        return Err(DataFrameError::InvalidOperation {
+                            message: format!("{:?} comparison cannot be used in a calculation", op),
+                            suggestion: Some("use the comparison as a filter".to_owned()),
+                        })
+                    }
+                }?;
+                let output = lowered.last().unwrap().output.clone();
+                calculations.extend(lowered);
+                Ok(output)
+            }
+        }
+    }
+}
+
+fn resolve_column(dataset: &Dataset, name: &str) -> Result<Column, DataFrameError> {
+    match dataset.get_column(name) {
+        Some((_, column)) => Ok(column.clone()),
+        None => Err(DataFrameError::InvalidOperation {
+            message: format!("Column {} not found", name),
+            suggestion: crate::utils::closest_match(
+                name,
+                dataset.columns.iter().map(|c| c.name.as_str()),
+            )
+            .map(|similar| format!("did you mean '{}'?", similar)),
+        }),
+    }
+}
+
+macro_rules! expr_operator {
+    ( $trait:ident, $method:ident, $op:ident ) => {
+        impl $trait for Expr {
+            type Output = Expr;
+
+            fn $method(self, other: Expr) -> Expr {
+                self.binary(BinaryOperator::$op, other)
+            }
+        }
+    };
+}
+
+expr_operator!(Add, add, Add);
+expr_operator!(Sub, sub, Subtract);
+expr_operator!(Mul, mul, Multiply);
+expr_operator!(Div, div, Divide);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::DataType;
+
+    fn dataset() -> Dataset {
+        Dataset {
+            name: "numbers".to_owned(),
+            columns: vec![
+                Column {
+                    name: "a".to_owned(),
+                    column_type: ColumnType::Scalar(DataType::Int64),
+                    nullable: true,
+                },
+                Column {
+                    name: "b".to_owned(),
+                    column_type: ColumnType::Scalar(DataType::Int32),
+                    nullable: true,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_add_expression() {
+        let ds = dataset();
+        let calculations = (col("a") + col("b")).to_calculations(&ds).unwrap();
+        let expected = AddOperation::transform(ds.columns.clone(), None, None).unwrap();
+        assert_eq!(expected, calculations);
+    }
+
+    #[test]
+    fn test_nested_expression() {
+        let ds = dataset();
+        let calculations = (col("a") + col("b") * col("a"))
+            .to_calculations(&ds)
+            .unwrap();
+        // b is promoted to a's type before it is multiplied, so the product is not cast
+        assert_eq!(3, calculations.len());
+        assert_eq!("multiply(b, a)", calculations[1].output.name);
+        assert_eq!("add(a, multiply(b, a))", calculations[2].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int64),
+            calculations[2].output.column_type
+        );
+
+        let calculations = (col("a") * lit(2i64)).to_calculations(&ds).unwrap();
+        assert_eq!(2, calculations.len());
+        assert_eq!("multiply(a, 2)", calculations[1].output.name);
+        let err = (col("aa") + col("a")).to_calculations(&ds).unwrap_err();
+        assert_eq!(
+            DataFrameError::InvalidOperation {
+                message: "Column aa not found".to_string(),
+                suggestion: Some("did you mean 'a'?".to_string()),
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn test_comparison_expression() {
+        let ds = dataset();
+        let filter = col("a").gt(lit(2i64)).to_filter(&ds).unwrap();
+        match filter {
+            BooleanFilter::Gt(left, right) => {
+                assert!(matches!(
+                    *left,
+                    BooleanFilter::Input(BooleanInput::Column(_))
+                ));
+                assert!(matches!(
+                    *right,
+                    BooleanFilter::Input(BooleanInput::Scalar(Scalar::Int64(2)))
+                ));
+            }
+            _ => panic!("expected a Gt filter"),
+        }
+        assert!((col("a") + col("b")).to_filter(&ds).is_err());
+    }
+
+    #[test]
+    fn test_array_contains_expression() {
+        let mut ds = dataset();
+        ds.columns.push(Column {
+            name: "tags".to_owned(),
+            column_type: ColumnType::Array(DataType::Utf8),
+            nullable: true,
+        });
+        let calculations = col("tags")
+            .array_contains(lit("rust"))
+            .to_calculations(&ds)
+            .unwrap();
+        // the literal is calculated first
+        assert_eq!(2, calculations.len());
+        assert_eq!("array_contains(tags, 'rust')", calculations[1].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            calculations[1].output.column_type
+        );
+        assert!(col("tags")
+            .array_contains(lit("rust"))
+            .to_filter(&ds)
+            .is_err());
+    }
+}
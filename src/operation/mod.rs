@@ -1 +1,5 @@
+pub mod aggregate;
+pub mod expr;
+pub mod projection;
 pub mod scalar;
+pub mod window;
@@ -0,0 +1,164 @@
+//! Projection Operations
+
+use crate::error::DataFrameError;
+use crate::expression::*;
+
+/// Operation that removes columns from a dataset by name
+///
+/// The remaining columns keep their original order. This is the complement of selecting the
+/// columns that are kept.
+pub struct DropColumnsOperation;
+
+impl DropColumnsOperation {
+    pub fn name() -> &'static str {
+        "drop"
+    }
+
+    /// Validate that the columns exist, returning the transformation and the output dataset
+    pub fn transform(
+        input: &Dataset,
+        col_names: &[&str],
+    ) -> Result<(Transformation, Dataset), DataFrameError> {
+        for col in col_names {
+            if input.get_column(col).is_none() {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Column {:?} cannot be dropped as it does not exist",
+                    col
+                )));
+            }
+        }
+        let columns = input
+            .columns
+            .iter()
+            .filter(|column| !col_names.contains(&column.name.as_str()))
+            .cloned()
+            .collect();
+        let dropped = col_names.iter().map(|col| col.to_string()).collect();
+        Ok((
+            Transformation::Drop(dropped),
+            Dataset {
+                name: input.name.clone(),
+                columns,
+            },
+        ))
+    }
+}
+
+/// Operation that selects columns in a given order, renaming them
+///
+/// Unlike a select, the output columns follow the order of the projection, and an input column
+/// can be projected more than once under different names.
+pub struct ProjectOperation;
+
+impl ProjectOperation {
+    pub fn name() -> &'static str {
+        "project"
+    }
+
+    /// Validate the `(input_column, output_name)` pairs, returning the transformation and the
+    /// output dataset
+    pub fn transform(
+        input: &Dataset,
+        columns: &[(&str, &str)],
+    ) -> Result<(Transformation, Dataset), DataFrameError> {
+        let mut output_columns = Vec::with_capacity(columns.len());
+        for (input_name, output_name) in columns {
+            let column = input
+                .get_column(input_name)
+                .ok_or_else(|| DataFrameError::ColumnNotFound(input_name.to_string()))?;
+            if output_columns
+                .iter()
+                .any(|column: &Column| column.name == *output_name)
+            {
+                return Err(DataFrameError::ComputeError(format!(
+                    "Column {:?} cannot be projected more than once",
+                    output_name
+                )));
+            }
+            output_columns.push(Column {
+                name: output_name.to_string(),
+                column_type: column.1.column_type.clone(),
+                nullable: column.1.nullable,
+            });
+        }
+        let projected = columns
+            .iter()
+            .map(|(input, output)| (input.to_string(), output.to_string()))
+            .collect();
+        Ok((
+            Transformation::Project(projected),
+            Dataset {
+                name: input.name.clone(),
+                columns: output_columns,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::DataType;
+
+    fn dataset() -> Dataset {
+        let column = |name: &str, data_type: DataType| Column {
+            name: name.to_owned(),
+            column_type: ColumnType::Scalar(data_type),
+            nullable: true,
+        };
+        Dataset {
+            name: "cities".to_owned(),
+            columns: vec![
+                column("city", DataType::Utf8),
+                column("lat", DataType::Float64),
+                column("lng", DataType::Float64),
+            ],
+        }
+    }
+
+    #[test]
+    fn drop_columns() {
+        let (transformation, output) =
+            DropColumnsOperation::transform(&dataset(), &["lat"]).unwrap();
+        match transformation {
+            Transformation::Drop(cols) => assert_eq!(vec!["lat".to_string()], cols),
+            t => panic!("Expected a drop transformation, found {:?}", t),
+        }
+        let names: Vec<&str> = output.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["city", "lng"], names);
+    }
+
+    #[test]
+    fn drop_unknown_column() {
+        assert!(DropColumnsOperation::transform(&dataset(), &["lat", "population"]).is_err());
+    }
+
+    #[test]
+    fn project_columns() {
+        let (transformation, output) =
+            ProjectOperation::transform(&dataset(), &[("lng", "x"), ("city", "city")]).unwrap();
+        match transformation {
+            Transformation::Project(cols) => assert_eq!(
+                vec![
+                    ("lng".to_string(), "x".to_string()),
+                    ("city".to_string(), "city".to_string())
+                ],
+                cols
+            ),
+            t => panic!("Expected a project transformation, found {:?}", t),
+        }
+        let names: Vec<&str> = output.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["x", "city"], names);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            output.columns[0].column_type
+        );
+        assert_eq!(
+            Err(DataFrameError::ColumnNotFound("population".to_owned())),
+            ProjectOperation::transform(&dataset(), &[("population", "p")])
+                .map(|(_, output)| output.columns.len())
+        );
+        // output names must be unique
+        assert!(ProjectOperation::transform(&dataset(), &[("lat", "a"), ("lng", "a")]).is_err());
+    }
+}
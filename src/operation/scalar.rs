@@ -1,9 +1,9 @@
 //! Scalar Operations
 
+use crate::error::DataFrameError;
 use crate::evaluation::*;
 use crate::expression::*;
 use arrow::datatypes::DataType;
-use arrow::error::ArrowError;
 
 pub trait ScalarOperation {
     fn name() -> &'static str;
@@ -11,12 +11,285 @@ pub trait ScalarOperation {
         inputs: Vec<Column>,
         name: Option<String>,
         to_type: Option<DataType>,
-    ) -> Result<Vec<Calculation>, ArrowError>;
+    ) -> Result<Vec<Calculation>, DataFrameError>;
+}
+
+/// An error for inputs that an operation does not support
+fn unsupported(op: &str, reason: impl Into<String>) -> DataFrameError {
+    DataFrameError::UnsupportedOperation {
+        op: op.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Whether any of the inputs can have null values, which most operations propagate
+fn any_nullable(inputs: &[Column]) -> bool {
+    inputs.iter().any(|input| input.nullable)
+}
+
+/// Promote two numeric types to a type that can represent the values of both
+///
+/// * integers of the same signedness are widened to the wider type
+/// * signed and unsigned integers are widened to a signed type that can hold both, or to
+///   `Float64` if one is a `UInt64`
+/// * integers and floats are widened to a float that can represent the integer, i.e. `Float32`
+///   for integers of up to 16 bits, and `Float64` for wider integers
+/// * floats are widened to the wider float
+pub fn promote_numeric(a: &DataType, b: &DataType) -> Result<DataType, DataFrameError> {
+    use DataType::*;
+    // the number of bits of an integer type, and whether it is signed
+    let integer = |t: &DataType| match t {
+        Int8 => Some((8, true)),
+        Int16 => Some((16, true)),
+        Int32 => Some((32, true)),
+        Int64 => Some((64, true)),
+        UInt8 => Some((8, false)),
+        UInt16 => Some((16, false)),
+        UInt32 => Some((32, false)),
+        UInt64 => Some((64, false)),
+        _ => None,
+    };
+    let float = |t: &DataType| match t {
+        Float16 => Some(16),
+        Float32 => Some(32),
+        Float64 => Some(64),
+        _ => None,
+    };
+    let signed = |bits: usize| match bits {
+        8 => Int8,
+        16 => Int16,
+        32 => Int32,
+        _ => Int64,
+    };
+    let unsigned = |bits: usize| match bits {
+        8 => UInt8,
+        16 => UInt16,
+        32 => UInt32,
+        _ => UInt64,
+    };
+    let float_of = |bits: usize| match bits {
+        16 => Float16,
+        32 => Float32,
+        _ => Float64,
+    };
+    match (integer(a), integer(b), float(a), float(b)) {
+        (Some((a_bits, a_signed)), Some((b_bits, b_signed)), _, _) => {
+            if a_signed == b_signed {
+                let bits = a_bits.max(b_bits);
+                Ok(if a_signed {
+                    signed(bits)
+                } else {
+                    unsigned(bits)
+                })
+            } else {
+                let (signed_bits, unsigned_bits) = if a_signed {
+                    (a_bits, b_bits)
+                } else {
+                    (b_bits, a_bits)
+                };
+                if unsigned_bits == 64 {
+                    Ok(Float64)
+                } else {
+                    Ok(signed(signed_bits.max(unsigned_bits * 2)))
+                }
+            }
+        }
+        (Some((int_bits, _)), None, None, Some(float_bits))
+        | (None, Some((int_bits, _)), Some(float_bits), None) => {
+            let int_float_bits = if int_bits <= 16 { 32 } else { 64 };
+            Ok(float_of(float_bits.max(int_float_bits)))
+        }
+        (None, None, Some(a_bits), Some(b_bits)) => Ok(float_of(a_bits.max(b_bits))),
+        _ => Err(unsupported(
+            "promote",
+            format!(
+                "Cannot promote {:?} and {:?} to a common numeric type",
+                a, b
+            ),
+        )),
+    }
+}
+
+fn is_integer(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    is_integer(data_type) || matches!(data_type, DataType::Float32 | DataType::Float64)
+}
+
+/// Create the calculations of a binary bitwise operation
+///
+/// Both inputs must be integer scalars. For `and`, `or` and `xor`, the inputs are cast to the
+/// integer type that `promote_numeric` returns. For shifts, the output is of the type of the
+/// first input, and the shift amount is cast to it.
+fn bitwise_transform(
+    op_name: &str,
+    label: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 2 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 2 inputs", label),
+        ));
+    }
+    let a = &inputs[0];
+    let b = &inputs[1];
+    let data_type = match (&a.column_type, &b.column_type) {
+        (ColumnType::Scalar(a_type), ColumnType::Scalar(b_type))
+            if is_integer(a_type) && is_integer(b_type) =>
+        {
+            match function {
+                ScalarFunction::ShiftLeft | ScalarFunction::ShiftRight => a_type.clone(),
+                _ if a_type == b_type => a_type.clone(),
+                _ => match promote_numeric(a_type, b_type)? {
+                    data_type if is_integer(&data_type) => data_type,
+                    data_type => {
+                        return Err(unsupported(
+                            op_name,
+                            format!(
+                                "{} operation cannot coerce {:?} and {:?} to a common integer type, found {:?}",
+                                label, a_type, b_type, data_type
+                            ),
+                        ))
+                    }
+                },
+            }
+        }
+        (a_type, b_type) => {
+            return Err(unsupported(
+                op_name,
+                format!(
+                    "{} operation only works on integer scalar columns, found {:?} and {:?}",
+                    label, a_type, b_type
+                ),
+            ))
+        }
+    };
+    let mut calculations = vec![];
+    let mut calculation_inputs = vec![];
+    for input in &inputs {
+        if input.column_type == ColumnType::Scalar(data_type.clone()) {
+            calculation_inputs.push(input.clone());
+        } else {
+            let cast = CastOperation::transform(
+                vec![input.clone()],
+                Some(input.name.clone()),
+                Some(data_type.clone()),
+            )?;
+            calculation_inputs.push(cast[0].output.clone());
+            calculations.extend(cast);
+        }
+    }
+    calculations.push(Calculation {
+        name: op_name.to_string(),
+        inputs: calculation_inputs,
+        output: Column {
+            name: name.unwrap_or(format!("{}({}, {})", op_name, &a.name, &b.name)),
+            column_type: ColumnType::Scalar(data_type),
+            nullable: any_nullable(&inputs),
+        },
+        function: Function::Scalar(function),
+    });
+    Ok(calculations)
+}
+
+/// Create the calculations of a binary arithmetic operation
+///
+/// If the inputs have different types, they are cast to the type that `promote_numeric`
+/// promotes them to.
+fn arithmetic_transform(
+    op_name: &str,
+    label: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    // for now we support 2 inputs at a time
+    // the output data type is also ignored
+    if inputs.len() != 2 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 2 inputs", label),
+        ));
+    }
+    let a = &inputs[0];
+    let b = &inputs[1];
+    match (&a.column_type, &b.column_type) {
+        (ColumnType::Scalar(a_type), ColumnType::Scalar(b_type)) => {
+            let data_type = if a_type == b_type {
+                a_type.clone()
+            } else {
+                promote_numeric(a_type, b_type)?
+            };
+            // inputs that are not of the promoted type are cast to it first
+            let mut calculations = vec![];
+            let mut calculation_inputs = vec![];
+            for input in &inputs {
+                if input.column_type == ColumnType::Scalar(data_type.clone()) {
+                    calculation_inputs.push(input.clone());
+                } else {
+                    let cast = CastOperation::transform(
+                        vec![input.clone()],
+                        Some(input.name.clone()),
+                        Some(data_type.clone()),
+                    )?;
+                    calculation_inputs.push(cast[0].output.clone());
+                    calculations.extend(cast);
+                }
+            }
+            calculations.push(Calculation {
+                name: op_name.to_string(),
+                inputs: calculation_inputs,
+                output: Column {
+                    name: name.unwrap_or(format!("{}({}, {})", op_name, &a.name, &b.name)),
+                    column_type: ColumnType::Scalar(data_type),
+                    nullable: any_nullable(&inputs),
+                },
+                function: Function::Scalar(function),
+            });
+            Ok(calculations)
+        }
+        _ => Err(unsupported(
+            op_name,
+            format!("{} operation only works on scalar columns", label),
+        )),
+    }
 }
 
 /// Operation to add two numeric columns together
 pub struct AddOperation;
 
+impl AddOperation {
+    /// Create a calculation that checks for integer overflow if `options.checked` is set
+    pub fn transform_with_options(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        options: ArithmeticOptions,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        arithmetic_transform(
+            Self::name(),
+            "Add",
+            ScalarFunction::Add(options),
+            inputs,
+            name,
+        )
+    }
+}
+
 impl ScalarOperation for AddOperation {
     fn name() -> &'static str {
         "add"
@@ -25,119 +298,129 @@ impl ScalarOperation for AddOperation {
     fn transform(
         inputs: Vec<Column>,
         name: Option<String>,
-        to_type: Option<DataType>,
-    ) -> Result<Vec<Calculation>, ArrowError> {
-        // add n columns together provided that they are of the same data type
-        // for now we support 2 inputs at a time
-        // the output data type is also ignored
-        if inputs.len() != 2 {
-            Err(ArrowError::ComputeError(
-                "Add operation expects 2 inputs".to_string(),
-            ))
-        } else {
-            let a = &inputs[0];
-            let b = &inputs[1];
-            match (&a.column_type, &b.column_type) {
-                (ColumnType::Array(_), _) | (_, ColumnType::Array(_)) => {
-                    Err(ArrowError::ComputeError(
-                        "Add operation only works on scalar columns".to_string(),
-                    ))
-                }
-                (ColumnType::Scalar(a_type), ColumnType::Scalar(b_type)) => {
-                    if a_type != b_type {
-                        // TODO coerce types and reduce this boilerplate, only using to test concepts
-                        // cast b_type to a_type
-                        let cast_op = CastOperation::transform(
-                            vec![b.clone()],
-                            Some(b.name.clone()),
-                            Some(a_type.clone()),
-                        )?;
-                        let cast_op = cast_op.first().unwrap();
-                        Ok(vec![
-                            cast_op.clone(),
-                            Calculation {
-                                name: Self::name().to_string(),
-                                inputs: vec![a.clone(), cast_op.output.clone()],
-                                output: Column {
-                                    name: name.unwrap_or(format!(
-                                        "{}({}, {})",
-                                        Self::name(),
-                                        &a.name,
-                                        &b.name
-                                    )),
-                                    column_type: a_type.clone().into(),
-                                },
-                                function: Function::Scalar(ScalarFunction::Add),
-                            },
-                        ])
-                    } else {
-                        Ok(vec![Calculation {
-                            name: Self::name().to_string(),
-                            inputs: inputs.clone(),
-                            output: Column {
-                                name: name.unwrap_or(format!(
-                                    "{}({}, {})",
-                                    Self::name(),
-                                    &a.name,
-                                    &b.name
-                                )),
-                                column_type: a_type.clone().into(),
-                            },
-                            function: Function::Scalar(ScalarFunction::Add),
-                        }])
-                    }
-                }
-            }
-        }
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::transform_with_options(inputs, name, ArithmeticOptions::default())
     }
 }
 
 pub struct CastOperation;
 
-impl ScalarOperation for CastOperation {
-    fn name() -> &'static str {
-        "cast"
+impl CastOperation {
+    /// Create a cast calculation that handles unrepresentable values according to `mode`
+    pub fn transform_with_mode(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+        mode: CastMode,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::cast_transform(inputs, name, to_type, mode, None)
     }
 
-    fn transform(
+    /// Create a cast calculation that parses strings into a temporal type with a chrono `format`
+    ///
+    /// Inputs that are not strings, or target types that are not temporal, ignore the format.
+    pub fn transform_with_format(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+        mode: CastMode,
+        format: &str,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::cast_transform(inputs, name, to_type, mode, Some(format.to_string()))
+    }
+
+    fn cast_transform(
         inputs: Vec<Column>,
         name: Option<String>,
         to_type: Option<DataType>,
-    ) -> Result<Vec<Calculation>, ArrowError> {
+        mode: CastMode,
+        format: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
         // cast columns to the output type
         // we've made provision for casting more than 1 column at a time, but for now we only cast 1
         if inputs.len() != 1 {
-            Err(ArrowError::ComputeError(
+            Err(unsupported(
+                Self::name(),
                 "Cast operation expects 1 input".to_string(),
             ))
         } else {
             let a = &inputs[0];
             let to_type = to_type.ok_or_else(|| {
-                ArrowError::InvalidArgumentError(
+                unsupported(
+                    Self::name(),
                     "Cast requires a target output datatype".to_string(),
                 )
             })?;
 
-            match &a.column_type {
-                ColumnType::Array(_) => Err(ArrowError::ComputeError(
-                    "Cast operation is currently only supported on scalar columns".to_string(),
-                )),
-                _ => Ok(vec![Calculation {
-                    name: Self::name().to_string(),
-                    inputs: inputs.clone(),
-                    output: Column {
-                        name: name.unwrap_or(format!("{}({} as datatype)", Self::name(), &a.name)),
-                        column_type: ColumnType::Scalar(to_type),
-                    },
-                    function: Function::Cast,
-                }]),
-            }
+            // list columns are cast to list types, by casting their elements
+            let column_type = match (&a.column_type, to_type) {
+                (ColumnType::Array(_), DataType::List(inner)) => ColumnType::Array(*inner),
+                (ColumnType::Array(_), to_type) => {
+                    return Err(unsupported(
+                        Self::name(),
+                        format!(
+                            "Array columns can only be cast to list types, not {:?}",
+                            to_type
+                        ),
+                    ))
+                }
+                (ColumnType::Struct(_), _) => {
+                    return Err(unsupported(
+                        Self::name(),
+                        "Struct columns cannot be cast".to_string(),
+                    ))
+                }
+                (_, to_type) => ColumnType::Scalar(to_type),
+            };
+            Ok(vec![Calculation {
+                name: Self::name().to_string(),
+                inputs: inputs.clone(),
+                output: Column {
+                    name: name.unwrap_or(format!("{}({} as datatype)", Self::name(), &a.name)),
+                    column_type,
+                    nullable: a.nullable || mode == CastMode::Safe,
+                },
+                function: Function::Cast(mode, format),
+            }])
         }
     }
 }
 
+impl ScalarOperation for CastOperation {
+    fn name() -> &'static str {
+        "cast"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::transform_with_mode(inputs, name, to_type, CastMode::default())
+    }
+}
+
+/// Operation to subtract the second numeric column from the first
 pub struct SubtractOperation;
 
+impl SubtractOperation {
+    /// Create a calculation that checks for integer overflow if `options.checked` is set
+    pub fn transform_with_options(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        options: ArithmeticOptions,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        arithmetic_transform(
+            Self::name(),
+            "Subtract",
+            ScalarFunction::Subtract(options),
+            inputs,
+            name,
+        )
+    }
+}
+
 impl ScalarOperation for SubtractOperation {
     fn name() -> &'static str {
         "subtract"
@@ -146,197 +429,2596 @@ impl ScalarOperation for SubtractOperation {
     fn transform(
         inputs: Vec<Column>,
         name: Option<String>,
-        to_type: Option<DataType>,
-    ) -> Result<Vec<Calculation>, ArrowError> {
-        // add n columns together provided that they are of the same data type
-        // for now we support 2 inputs at a time
-        // the output data type is also ignored
-        if inputs.len() != 2 {
-            Err(ArrowError::ComputeError(
-                "Subtract operation expects 2 inputs".to_string(),
-            ))
-        } else {
-            let a = &inputs[0];
-            let b = &inputs[1];
-            match (&a.column_type, &b.column_type) {
-                (ColumnType::Array(_), _) | (_, ColumnType::Array(_)) => {
-                    Err(ArrowError::ComputeError(
-                        "Subtract operation only works on scalar columns".to_string(),
-                    ))
-                }
-                (ColumnType::Scalar(a_type), ColumnType::Scalar(b_type)) => {
-                    if a_type != b_type {
-                        // TODO coerce types and reduce this boilerplate, only using to test concepts
-                        // cast b_type to a_type
-                        let cast_op = CastOperation::transform(
-                            vec![b.clone()],
-                            Some(b.name.clone()),
-                            Some(a_type.clone()),
-                        )?;
-                        let cast_op = cast_op.first().unwrap();
-                        Ok(vec![
-                            cast_op.clone(),
-                            Calculation {
-                                name: Self::name().to_string(),
-                                inputs: vec![a.clone(), cast_op.output.clone()],
-                                output: Column {
-                                    name: name.unwrap_or(format!(
-                                        "{}({}, {})",
-                                        Self::name(),
-                                        &a.name,
-                                        &b.name
-                                    )),
-                                    column_type: a_type.clone().into(),
-                                },
-                                function: Function::Scalar(ScalarFunction::Add),
-                            },
-                        ])
-                    } else {
-                        Ok(vec![Calculation {
-                            name: Self::name().to_string(),
-                            inputs: inputs.clone(),
-                            output: Column {
-                                name: name.unwrap_or(format!(
-                                    "{}({}, {})",
-                                    Self::name(),
-                                    &a.name,
-                                    &b.name
-                                )),
-                                column_type: ColumnType::Scalar(a_type.clone()),
-                            },
-                            function: Function::Scalar(ScalarFunction::Subtract),
-                        }])
-                    }
-                }
-            }
-        }
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::transform_with_options(inputs, name, ArithmeticOptions::default())
     }
 }
 
-// pub struct TrigOperation(TrigFunction);
+/// Operation to multiply two numeric columns together
+pub struct MultiplyOperation;
 
-// pub enum TrigFunction {
-//     Sin,
-//     Cos,
-//     Tan,
-//     Cot,
-//     Sec,
-//     Cosec,
-// }
+impl MultiplyOperation {
+    /// Create a calculation that checks for integer overflow if `options.checked` is set
+    pub fn transform_with_options(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        options: ArithmeticOptions,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        arithmetic_transform(
+            Self::name(),
+            "Multiply",
+            ScalarFunction::Multiply(options),
+            inputs,
+            name,
+        )
+    }
+}
 
-pub struct SinOperation;
+impl ScalarOperation for MultiplyOperation {
+    fn name() -> &'static str {
+        "multiply"
+    }
 
-impl ScalarOperation for SinOperation {
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::transform_with_options(inputs, name, ArithmeticOptions::default())
+    }
+}
+
+/// Operation to divide the first numeric column by the second
+pub struct DivideOperation;
+
+impl ScalarOperation for DivideOperation {
     fn name() -> &'static str {
-        // note that we override this name with the actual operation
-        "sin"
+        "divide"
     }
 
     fn transform(
         inputs: Vec<Column>,
         name: Option<String>,
-        to_type: Option<DataType>,
-    ) -> Result<Vec<Calculation>, ArrowError> {
-        // add n columns together provided that they are of the same data type
-        // for now we support 2 inputs at a time
-        // the output data type is also ignored
-        if inputs.len() != 1 {
-            Err(ArrowError::ComputeError(
-                "Sine operation expects 2 inputs".to_string(),
-            ))
-        } else {
-            let a = &inputs[0];
-            match &a.column_type {
-                ColumnType::Array(_) => Err(ArrowError::ComputeError(
-                    "Sine operation is currently only supported on scalar columns".to_string(),
-                )),
-                ColumnType::Scalar(dtype) => {
-                    // check if datatype is a float, and convert to f64 if not
-                    match dtype {
-                        DataType::Int8
-                        | DataType::Int16
-                        | DataType::Int32
-                        | DataType::Int64
-                        | DataType::UInt8
-                        | DataType::UInt16
-                        | DataType::UInt32
-                        | DataType::UInt64 => {
-                            let cast_name = name.clone().unwrap_or(format!(
-                                "{}({} as datatype)",
-                                CastOperation::name(),
-                                &a.name
-                            ));
-                            let cast_output = Column {
-                                name: cast_name,
-                                column_type: ColumnType::Scalar(DataType::Float64),
-                            };
-                            Ok(vec![
-                                Calculation {
-                                    name: CastOperation::name().to_string(),
-                                    inputs: inputs.clone(),
-                                    output: cast_output.clone(),
-                                    function: Function::Cast,
-                                },
-                                Calculation {
-                                    name: Self::name().to_string(),
-                                    inputs: vec![cast_output],
-                                    output: Column {
-                                        name: name.unwrap_or(format!(
-                                            "{}({} as datatype)",
-                                            Self::name(),
-                                            &a.name
-                                        )),
-                                        column_type: ColumnType::Scalar(DataType::Float64),
-                                    },
-                                    function: Function::Scalar(ScalarFunction::Sine),
-                                },
-                            ])
-                        }
-                        DataType::Float32 | DataType::Float64 => Ok(vec![Calculation {
-                            name: Self::name().to_string(),
-                            inputs: inputs.clone(),
-                            output: Column {
-                                name: name.unwrap_or(format!(
-                                    "{}({} as datatype)",
-                                    Self::name(),
-                                    &a.name
-                                )),
-                                column_type: ColumnType::Scalar(dtype.clone()),
-                            },
-                            function: Function::Scalar(ScalarFunction::Sine),
-                        }]),
-                        _ => Err(ArrowError::ComputeError(format!(
-                            "Cannot perform {} operation from {:?} data type",
-                            Self::name(),
-                            dtype,
-                        ))),
-                    }
-                }
-            }
-        }
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        arithmetic_transform(Self::name(), "Divide", ScalarFunction::Divide, inputs, name)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Operation to compute the bitwise AND of two integer columns
+pub struct BitAndOperation;
 
-    #[test]
-    fn scalar_operations() {
-        let a = Column {
-            name: "a".to_owned(),
-            column_type: ColumnType::Scalar(DataType::Int64),
-        };
-        let b = Column {
-            name: "b".to_owned(),
-            column_type: ColumnType::Scalar(DataType::Int32),
-        };
+impl ScalarOperation for BitAndOperation {
+    fn name() -> &'static str {
+        "bitand"
+    }
 
-        let add = AddOperation::transform(vec![a, b], None, None).unwrap();
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        bitwise_transform(Self::name(), "BitAnd", ScalarFunction::BitAnd, inputs, name)
+    }
+}
 
-        assert_eq!(
-            "[Calculation { name: \"cast\", inputs: [Column { name: \"b\", column_type: Scalar(Int32) }], output: Column { name: \"b\", column_type: Scalar(Int64) }, function: Cast }, Calculation { name: \"add\", inputs: [Column { name: \"a\", column_type: Scalar(Int64) }, Column { name: \"b\", column_type: Scalar(Int64) }], output: Column { name: \"add(a, b)\", column_type: Scalar(Int64) }, function: Scalar(Add) }]",
-            format!("{:?}", add)
-        );
+/// Operation to compute the bitwise OR of two integer columns
+pub struct BitOrOperation;
+
+impl ScalarOperation for BitOrOperation {
+    fn name() -> &'static str {
+        "bitor"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        bitwise_transform(Self::name(), "BitOr", ScalarFunction::BitOr, inputs, name)
+    }
+}
+
+/// Operation to compute the bitwise XOR of two integer columns
+pub struct BitXorOperation;
+
+impl ScalarOperation for BitXorOperation {
+    fn name() -> &'static str {
+        "bitxor"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        bitwise_transform(Self::name(), "BitXor", ScalarFunction::BitXor, inputs, name)
+    }
+}
+
+/// Operation to shift an integer column left by an integer amount
+pub struct ShiftLeftOperation;
+
+impl ScalarOperation for ShiftLeftOperation {
+    fn name() -> &'static str {
+        "shift_left"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        bitwise_transform(
+            Self::name(),
+            "ShiftLeft",
+            ScalarFunction::ShiftLeft,
+            inputs,
+            name,
+        )
+    }
+}
+
+/// Operation to shift an integer column right by an integer amount
+pub struct ShiftRightOperation;
+
+impl ScalarOperation for ShiftRightOperation {
+    fn name() -> &'static str {
+        "shift_right"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        bitwise_transform(
+            Self::name(),
+            "ShiftRight",
+            ScalarFunction::ShiftRight,
+            inputs,
+            name,
+        )
+    }
+}
+
+/// Operation to concatenate 2 or more string columns together
+///
+/// Non-Utf8 scalar inputs are not rejected, but are cast to Utf8 first, so that numbers
+/// can be concatenated with strings.
+pub struct ConcatOperation;
+
+impl ConcatOperation {
+    /// Create a concat calculation that handles null inputs according to `null_handling`
+    pub fn transform_with_null_handling(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        null_handling: NullHandling,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() < 2 {
+            return Err(unsupported(
+                Self::name(),
+                "Concat operation expects 2 or more inputs".to_string(),
+            ));
+        }
+        let mut calculations = vec![];
+        let mut concat_inputs = Vec::with_capacity(inputs.len());
+        for input in &inputs {
+            match &input.column_type {
+                ColumnType::Array(_) | ColumnType::Struct(_) => {
+                    return Err(unsupported(
+                        Self::name(),
+                        "Concat operation only works on scalar columns".to_string(),
+                    ))
+                }
+                ColumnType::Scalar(DataType::Utf8) => concat_inputs.push(input.clone()),
+                ColumnType::Scalar(_) => {
+                    // cast the input to a string
+                    let cast_op =
+                        CastOperation::transform(vec![input.clone()], None, Some(DataType::Utf8))?;
+                    let cast_op = cast_op.first().unwrap();
+                    concat_inputs.push(cast_op.output.clone());
+                    calculations.push(cast_op.clone());
+                }
+            }
+        }
+        let input_names: Vec<&str> = inputs.iter().map(|col| col.name()).collect();
+        calculations.push(Calculation {
+            name: Self::name().to_string(),
+            inputs: concat_inputs,
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", Self::name(), input_names.join(", "))),
+                column_type: ColumnType::Scalar(DataType::Utf8),
+                nullable: null_handling == NullHandling::Propagate && any_nullable(&inputs),
+            },
+            function: Function::Scalar(ScalarFunction::Concat(null_handling)),
+        });
+        Ok(calculations)
+    }
+}
+
+impl ScalarOperation for ConcatOperation {
+    fn name() -> &'static str {
+        "concat"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        // the output data type is ignored, as concat always produces strings
+        Self::transform_with_null_handling(inputs, name, NullHandling::default())
+    }
+}
+
+/// Create a calculation for a single-input string operation that produces a string
+fn utf8_unary_transform(
+    op_name: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 1 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 1 input", op_name),
+        ));
+    }
+    let a = &inputs[0];
+    match &a.column_type {
+        ColumnType::Scalar(DataType::Utf8) => Ok(vec![Calculation {
+            name: op_name.to_string(),
+            inputs: inputs.clone(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", op_name, &a.name)),
+                column_type: ColumnType::Scalar(DataType::Utf8),
+                nullable: a.nullable,
+            },
+            function: Function::Scalar(function),
+        }]),
+        t => Err(unsupported(
+            op_name,
+            format!(
+                "Cannot perform {} operation on {:?} column, a Utf8 column is required",
+                op_name, t
+            ),
+        )),
+    }
+}
+
+/// Operation to convert a string column to uppercase
+pub struct UpperOperation;
+
+impl ScalarOperation for UpperOperation {
+    fn name() -> &'static str {
+        "upper"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_unary_transform(Self::name(), ScalarFunction::Upper, inputs, name)
+    }
+}
+
+/// Operation to convert a string column to lowercase
+pub struct LowerOperation;
+
+impl ScalarOperation for LowerOperation {
+    fn name() -> &'static str {
+        "lower"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_unary_transform(Self::name(), ScalarFunction::Lower, inputs, name)
+    }
+}
+
+/// Operation to remove leading and trailing whitespace from a string column
+pub struct TrimOperation;
+
+impl ScalarOperation for TrimOperation {
+    fn name() -> &'static str {
+        "trim"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_unary_transform(Self::name(), ScalarFunction::Trim, inputs, name)
+    }
+}
+
+/// Operation to extract a substring from a string column
+///
+/// As the start position and length are literals, use `SubstringOperation::transform_with`
+/// to create the calculation.
+pub struct SubstringOperation;
+
+impl SubstringOperation {
+    /// Create a substring calculation, with `start` being a 1-based position.
+    ///
+    /// A negative `start` counts from the end of the string, and an omitted `length`
+    /// takes the rest of the string.
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        start: i64,
+        length: Option<i64>,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_unary_transform(
+            Self::name(),
+            ScalarFunction::Substring { start, length },
+            inputs,
+            name,
+        )
+    }
+}
+
+impl ScalarOperation for SubstringOperation {
+    fn name() -> &'static str {
+        "substring"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Substring operation requires a start position, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Create a calculation for a single-input string predicate that produces a boolean
+fn utf8_predicate_transform(
+    op_name: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 1 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 1 input", op_name),
+        ));
+    }
+    let a = &inputs[0];
+    match &a.column_type {
+        ColumnType::Scalar(DataType::Utf8) => Ok(vec![Calculation {
+            name: op_name.to_string(),
+            inputs: inputs.clone(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", op_name, &a.name)),
+                column_type: ColumnType::Scalar(DataType::Boolean),
+                nullable: a.nullable,
+            },
+            function: Function::Scalar(function),
+        }]),
+        t => Err(unsupported(
+            op_name,
+            format!(
+                "Cannot perform {} operation on {:?} column, a Utf8 column is required",
+                op_name, t
+            ),
+        )),
+    }
+}
+
+/// Operation to match a string column against a SQL `LIKE` pattern
+///
+/// Use `LikeOperation::transform_with` to supply the pattern.
+pub struct LikeOperation;
+
+impl LikeOperation {
+    /// Create a `LIKE` calculation, where `%` and `_` are wildcards in the pattern
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        pattern: &str,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_predicate_transform(
+            Self::name(),
+            ScalarFunction::Like(pattern.to_string()),
+            inputs,
+            name,
+        )
+    }
+}
+
+impl ScalarOperation for LikeOperation {
+    fn name() -> &'static str {
+        "like"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Like operation requires a pattern, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Operation to match a string column against a case-insensitive SQL `LIKE` pattern
+///
+/// Use `ILikeOperation::transform_with` to supply the pattern.
+pub struct ILikeOperation;
+
+impl ILikeOperation {
+    /// Create a case-insensitive `LIKE` calculation
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        pattern: &str,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_predicate_transform(
+            Self::name(),
+            ScalarFunction::ILike(pattern.to_string()),
+            inputs,
+            name,
+        )
+    }
+}
+
+impl ScalarOperation for ILikeOperation {
+    fn name() -> &'static str {
+        "ilike"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "ILike operation requires a pattern, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Operation to match a string column against a regular expression
+///
+/// Use `RegexMatchOperation::transform_with` to supply the pattern.
+pub struct RegexMatchOperation;
+
+impl RegexMatchOperation {
+    /// Create a regex match calculation, returning an error if the pattern is not a valid regex
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        pattern: &str,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        // compile the pattern here so that the error is not deferred to evaluation, and the
+        // regex is not compiled for each evaluation
+        let pattern = RegexPattern::new(pattern).map_err(|e| {
+            unsupported(
+                Self::name(),
+                format!("Invalid regex pattern {}: {}", pattern, e),
+            )
+        })?;
+        Self::transform_with_pattern(inputs, pattern, name)
+    }
+
+    /// Create a regex match calculation from a compiled pattern
+    pub fn transform_with_pattern(
+        inputs: Vec<Column>,
+        pattern: RegexPattern,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        utf8_predicate_transform(
+            Self::name(),
+            ScalarFunction::RegexMatch(pattern),
+            inputs,
+            name,
+        )
+    }
+}
+
+impl ScalarOperation for RegexMatchOperation {
+    fn name() -> &'static str {
+        "regex_match"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Regex match operation requires a pattern, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Create a calculation for a null predicate, which accepts a column of any type
+fn null_predicate_transform(
+    op_name: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 1 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 1 input", op_name),
+        ));
+    }
+    let a = &inputs[0];
+    Ok(vec![Calculation {
+        name: op_name.to_string(),
+        inputs: inputs.clone(),
+        output: Column {
+            name: name.unwrap_or(format!("{}({})", op_name, &a.name)),
+            column_type: ColumnType::Scalar(DataType::Boolean),
+            nullable: false,
+        },
+        function: Function::Scalar(function),
+    }])
+}
+
+/// Operation to check which values of a column are null
+pub struct IsNullOperation;
+
+impl ScalarOperation for IsNullOperation {
+    fn name() -> &'static str {
+        "is_null"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        null_predicate_transform(Self::name(), ScalarFunction::IsNull, inputs, name)
+    }
+}
+
+/// Operation to check which values of a column are not null
+pub struct IsNotNullOperation;
+
+impl ScalarOperation for IsNotNullOperation {
+    fn name() -> &'static str {
+        "is_not_null"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        null_predicate_transform(Self::name(), ScalarFunction::IsNotNull, inputs, name)
+    }
+}
+
+/// The type that columns of `types` are cast to, which is their type if they all have the same
+/// type, or otherwise the type that `promote_numeric` promotes them to
+pub(crate) fn common_type(types: &[&DataType]) -> Result<DataType, DataFrameError> {
+    types[1..]
+        .iter()
+        .try_fold(types[0].clone(), |common, data_type| {
+            if common == **data_type {
+                Ok(common)
+            } else {
+                promote_numeric(&common, data_type)
+            }
+        })
+}
+
+/// Operation that returns the first non-null value of its inputs for each row
+pub struct CoalesceOperation;
+
+impl ScalarOperation for CoalesceOperation {
+    fn name() -> &'static str {
+        "coalesce"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() < 2 {
+            return Err(unsupported(
+                Self::name(),
+                "Coalesce operation expects 2 or more inputs".to_string(),
+            ));
+        }
+        let mut types = Vec::with_capacity(inputs.len());
+        for input in &inputs {
+            match &input.column_type {
+                ColumnType::Array(_) | ColumnType::Struct(_) => {
+                    return Err(unsupported(
+                        Self::name(),
+                        "Coalesce operation only works on scalar columns".to_string(),
+                    ))
+                }
+                ColumnType::Scalar(data_type) => types.push(data_type),
+            }
+        }
+        let output_type = common_type(&types)?;
+        let mut calculations = vec![];
+        let mut coalesce_inputs = Vec::with_capacity(inputs.len());
+        for (input, data_type) in inputs.iter().zip(types) {
+            if *data_type == output_type {
+                coalesce_inputs.push(input.clone());
+            } else {
+                let cast_op =
+                    CastOperation::transform(vec![input.clone()], None, Some(output_type.clone()))?;
+                let cast_op = cast_op.first().unwrap();
+                coalesce_inputs.push(cast_op.output.clone());
+                calculations.push(cast_op.clone());
+            }
+        }
+        let input_names: Vec<&str> = inputs.iter().map(|col| col.name()).collect();
+        calculations.push(Calculation {
+            name: Self::name().to_string(),
+            inputs: coalesce_inputs,
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", Self::name(), input_names.join(", "))),
+                column_type: ColumnType::Scalar(output_type),
+                nullable: inputs.iter().all(|input| input.nullable),
+            },
+            function: Function::Scalar(ScalarFunction::Coalesce),
+        });
+        Ok(calculations)
+    }
+}
+
+/// Operation that chooses values from one of two columns, based on a boolean condition column
+///
+/// The inputs are the condition, the values to use where it is true, and the values to use
+/// where it is false or null, i.e. `CASE WHEN condition THEN a ELSE b END`.
+pub struct CaseWhenOperation;
+
+impl ScalarOperation for CaseWhenOperation {
+    fn name() -> &'static str {
+        "case_when"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 3 {
+            return Err(unsupported(
+                Self::name(),
+                "Case when operation expects 3 inputs".to_string(),
+            ));
+        }
+        let (condition, a, b) = (&inputs[0], &inputs[1], &inputs[2]);
+        if condition.column_type != ColumnType::Scalar(DataType::Boolean) {
+            return Err(unsupported(
+                Self::name(),
+                format!(
+                    "Case when condition must be a boolean column, found {:?}",
+                    condition.column_type
+                ),
+            ));
+        }
+        let (a_type, b_type) = match (&a.column_type, &b.column_type) {
+            (ColumnType::Scalar(a_type), ColumnType::Scalar(b_type)) => (a_type, b_type),
+            _ => {
+                return Err(unsupported(
+                    Self::name(),
+                    "Case when operation only works on scalar columns".to_string(),
+                ))
+            }
+        };
+        let output_type = common_type(&[a_type, b_type])?;
+        let mut calculations = vec![];
+        let mut case_inputs = vec![condition.clone()];
+        for (input, data_type) in vec![(a, a_type), (b, b_type)] {
+            if *data_type == output_type {
+                case_inputs.push(input.clone());
+            } else {
+                let cast_op =
+                    CastOperation::transform(vec![input.clone()], None, Some(output_type.clone()))?;
+                let cast_op = cast_op.first().unwrap();
+                case_inputs.push(cast_op.output.clone());
+                calculations.push(cast_op.clone());
+            }
+        }
+        calculations.push(Calculation {
+            name: Self::name().to_string(),
+            inputs: case_inputs,
+            output: Column {
+                name: name.unwrap_or(format!(
+                    "{}({}, {}, {})",
+                    Self::name(),
+                    &condition.name,
+                    &a.name,
+                    &b.name
+                )),
+                column_type: ColumnType::Scalar(output_type),
+                nullable: a.nullable || b.nullable,
+            },
+            function: Function::Scalar(ScalarFunction::CaseWhen),
+        });
+        Ok(calculations)
+    }
+}
+
+/// Operation to join the elements of a list column into a string
+///
+/// Use `ArrayToStringOperation::transform_with` to supply the separator.
+pub struct ArrayToStringOperation;
+
+impl ArrayToStringOperation {
+    /// Create a calculation that joins each list's elements with the separator
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        separator: &str,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 1 {
+            return Err(unsupported(
+                Self::name(),
+                format!("{} operation expects 1 input", Self::name()),
+            ));
+        }
+        let a = &inputs[0];
+        match &a.column_type {
+            ColumnType::Array(_) => Ok(vec![Calculation {
+                name: Self::name().to_string(),
+                inputs: inputs.clone(),
+                output: Column {
+                    name: name.unwrap_or(format!("{}({})", Self::name(), &a.name)),
+                    column_type: ColumnType::Scalar(DataType::Utf8),
+                    nullable: a.nullable,
+                },
+                function: Function::Scalar(ScalarFunction::ArrayToString(separator.to_string())),
+            }]),
+            ColumnType::Scalar(_) | ColumnType::Struct(_) => Err(unsupported(
+                Self::name(),
+                format!("{} operation only works on array columns", Self::name()),
+            )),
+        }
+    }
+}
+
+impl ScalarOperation for ArrayToStringOperation {
+    fn name() -> &'static str {
+        "array_to_string"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Array to string operation requires a separator, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Operation to extract a named field from a struct column
+///
+/// Use `GetFieldOperation::transform_with` to supply the field's name.
+pub struct GetFieldOperation;
+
+impl GetFieldOperation {
+    /// Create a calculation that returns the field's values, checking that the struct has the
+    /// field
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        field: &str,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 1 {
+            return Err(unsupported(
+                Self::name(),
+                format!("{} operation expects 1 input", Self::name()),
+            ));
+        }
+        let a = &inputs[0];
+        let children = match &a.column_type {
+            ColumnType::Struct(children) => children,
+            _ => {
+                return Err(unsupported(
+                    Self::name(),
+                    format!("{} operation only works on struct columns", Self::name()),
+                ))
+            }
+        };
+        let child = match children.iter().find(|child| child.name == field) {
+            Some(child) => child,
+            None => {
+                return Err(DataFrameError::InvalidOperation {
+                    message: format!("Struct column {} has no field {}", a.name, field),
+                    suggestion: crate::utils::closest_match(
+                        field,
+                        children.iter().map(|child| child.name.as_str()),
+                    )
+                    .map(|similar| format!("did you mean '{}'?", similar)),
+                })
+            }
+        };
+        Ok(vec![Calculation {
+            name: Self::name().to_string(),
+            inputs: inputs.clone(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({}.{})", Self::name(), &a.name, field)),
+                column_type: child.column_type.clone(),
+                nullable: a.nullable || child.nullable,
+            },
+            function: Function::Scalar(ScalarFunction::GetField(field.to_string())),
+        }])
+    }
+}
+
+impl ScalarOperation for GetFieldOperation {
+    fn name() -> &'static str {
+        "get_field"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Get field operation requires a field name, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Create the calculations of an element-wise arithmetic operation on two list columns
+///
+/// Both inputs must be lists of numbers. If their element types differ, the lists are cast to
+/// lists of the type that `promote_numeric` promotes them to.
+fn array_arithmetic_transform(
+    op_name: &str,
+    label: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 2 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 2 inputs", label),
+        ));
+    }
+    let a = &inputs[0];
+    let b = &inputs[1];
+    let data_type = match (&a.column_type, &b.column_type) {
+        (ColumnType::Array(a_type), ColumnType::Array(b_type))
+            if is_numeric(a_type) && is_numeric(b_type) =>
+        {
+            if a_type == b_type {
+                a_type.clone()
+            } else {
+                promote_numeric(a_type, b_type)?
+            }
+        }
+        (a_type, b_type) => {
+            return Err(unsupported(
+                op_name,
+                format!(
+                    "{} operation only works on numeric array columns, found {} and {}",
+                    label, a_type, b_type
+                ),
+            ))
+        }
+    };
+    let mut calculations = vec![];
+    let mut calculation_inputs = vec![];
+    for input in &inputs {
+        if input.column_type == ColumnType::Array(data_type.clone()) {
+            calculation_inputs.push(input.clone());
+        } else {
+            let cast = CastOperation::transform(
+                vec![input.clone()],
+                Some(input.name.clone()),
+                Some(DataType::List(Box::new(data_type.clone()))),
+            )?;
+            calculation_inputs.push(cast[0].output.clone());
+            calculations.extend(cast);
+        }
+    }
+    calculations.push(Calculation {
+        name: op_name.to_string(),
+        inputs: calculation_inputs,
+        output: Column {
+            name: name.unwrap_or(format!("{}({}, {})", op_name, &a.name, &b.name)),
+            column_type: ColumnType::Array(data_type),
+            nullable: any_nullable(&inputs),
+        },
+        function: Function::Scalar(function),
+    });
+    Ok(calculations)
+}
+
+/// Operation to add the elements at the same positions of two list columns
+///
+/// The lists of each row must have the same length.
+pub struct ArrayAddOperation;
+
+impl ScalarOperation for ArrayAddOperation {
+    fn name() -> &'static str {
+        "array_add"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        array_arithmetic_transform(
+            Self::name(),
+            "Array add",
+            ScalarFunction::ArrayAdd,
+            inputs,
+            name,
+        )
+    }
+}
+
+/// Operation to subtract the elements of the second list column from those at the same
+/// positions of the first
+///
+/// The lists of each row must have the same length.
+pub struct ArraySubtractOperation;
+
+impl ScalarOperation for ArraySubtractOperation {
+    fn name() -> &'static str {
+        "array_subtract"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        array_arithmetic_transform(
+            Self::name(),
+            "Array subtract",
+            ScalarFunction::ArraySubtract,
+            inputs,
+            name,
+        )
+    }
+}
+
+/// Create the calculation of an operation that reduces each list of a list column to a scalar
+///
+/// `output_type` returns the type of the scalar for lists of the given element type, or `None`
+/// if the operation does not support them. If `empty_is_null` is set, the output is nullable
+/// as empty lists reduce to null.
+fn array_reduce_transform(
+    op_name: &str,
+    label: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+    output_type: impl Fn(&DataType) -> Option<DataType>,
+    empty_is_null: bool,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 1 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 1 input", label),
+        ));
+    }
+    let a = &inputs[0];
+    let data_type = match &a.column_type {
+        ColumnType::Array(data_type) => output_type(data_type),
+        _ => None,
+    };
+    match data_type {
+        Some(data_type) => Ok(vec![Calculation {
+            name: op_name.to_string(),
+            inputs: inputs.clone(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", op_name, &a.name)),
+                column_type: ColumnType::Scalar(data_type),
+                nullable: a.nullable || empty_is_null,
+            },
+            function: Function::Scalar(function),
+        }]),
+        None => Err(unsupported(
+            op_name,
+            format!(
+                "{} operation does not support {} columns",
+                label, a.column_type
+            ),
+        )),
+    }
+}
+
+/// Operation to count the elements of each list of a list column
+///
+/// Null elements are counted, and null lists have a null length.
+pub struct ArrayLengthOperation;
+
+impl ScalarOperation for ArrayLengthOperation {
+    fn name() -> &'static str {
+        "array_length"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        array_reduce_transform(
+            Self::name(),
+            "Array length",
+            ScalarFunction::ArrayLength,
+            inputs,
+            name,
+            |_| Some(DataType::Int32),
+            false,
+        )
+    }
+}
+
+/// Operation to sum the elements of each list of a numeric list column
+///
+/// The sum is of the same type as the elements. Null elements are skipped, so empty lists and
+/// lists of only nulls sum to 0, while null lists have a null sum.
+pub struct ArraySumOperation;
+
+impl ScalarOperation for ArraySumOperation {
+    fn name() -> &'static str {
+        "array_sum"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        array_reduce_transform(
+            Self::name(),
+            "Array sum",
+            ScalarFunction::ArraySum,
+            inputs,
+            name,
+            |data_type| Some(data_type.clone()).filter(is_numeric),
+            false,
+        )
+    }
+}
+
+/// Operation to find the largest element of each list of a numeric list column
+///
+/// Null elements are skipped, so empty lists and lists of only nulls have a null maximum.
+pub struct ArrayMaxOperation;
+
+impl ScalarOperation for ArrayMaxOperation {
+    fn name() -> &'static str {
+        "array_max"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        array_reduce_transform(
+            Self::name(),
+            "Array max",
+            ScalarFunction::ArrayMax,
+            inputs,
+            name,
+            |data_type| Some(data_type.clone()).filter(is_numeric),
+            true,
+        )
+    }
+}
+
+/// Operation to check whether the list of each row contains the row's value
+///
+/// The first input is the list column, and the second is the value to search for, which can be
+/// the output of a literal calculation. Numeric lists and values of different types are cast to
+/// the type that `promote_numeric` promotes them to. Null elements never match.
+pub struct ArrayContainsOperation;
+
+impl ScalarOperation for ArrayContainsOperation {
+    fn name() -> &'static str {
+        "array_contains"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 2 {
+            return Err(unsupported(
+                Self::name(),
+                "Array contains operation expects 2 inputs".to_string(),
+            ));
+        }
+        let a = &inputs[0];
+        let b = &inputs[1];
+        let data_type = match (&a.column_type, &b.column_type) {
+            (ColumnType::Array(a_type), ColumnType::Scalar(b_type))
+                if a_type == b_type && (is_numeric(a_type) || a_type == &DataType::Utf8) =>
+            {
+                a_type.clone()
+            }
+            (ColumnType::Array(a_type), ColumnType::Scalar(b_type))
+                if is_numeric(a_type) && is_numeric(b_type) =>
+            {
+                promote_numeric(a_type, b_type)?
+            }
+            (a_type, b_type) => {
+                return Err(unsupported(
+                    Self::name(),
+                    format!(
+                        "Array contains operation cannot search {} columns for {} values",
+                        a_type, b_type
+                    ),
+                ))
+            }
+        };
+        let mut calculations = vec![];
+        let mut calculation_inputs = vec![];
+        for (input, column_type) in inputs.iter().zip(vec![
+            ColumnType::Array(data_type.clone()),
+            ColumnType::Scalar(data_type.clone()),
+        ]) {
+            if input.column_type == column_type {
+                calculation_inputs.push(input.clone());
+            } else {
+                let cast = CastOperation::transform(
+                    vec![input.clone()],
+                    Some(input.name.clone()),
+                    Some(column_type.into()),
+                )?;
+                calculation_inputs.push(cast[0].output.clone());
+                calculations.extend(cast);
+            }
+        }
+        calculations.push(Calculation {
+            name: Self::name().to_string(),
+            inputs: calculation_inputs,
+            output: Column {
+                name: name.unwrap_or(format!("{}({}, {})", Self::name(), &a.name, &b.name)),
+                column_type: ColumnType::Scalar(DataType::Boolean),
+                nullable: any_nullable(&inputs),
+            },
+            function: Function::Scalar(ScalarFunction::ArrayContains),
+        });
+        Ok(calculations)
+    }
+}
+
+/// Create a calculation for a single-input float operation that produces the same float type
+///
+/// Integer inputs are rejected rather than cast, as rounding an integer would not change it.
+fn float_unary_transform(
+    op_name: &str,
+    function: ScalarFunction,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != 1 {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects 1 input", op_name),
+        ));
+    }
+    let a = &inputs[0];
+    match &a.column_type {
+        ColumnType::Scalar(dtype @ DataType::Float32)
+        | ColumnType::Scalar(dtype @ DataType::Float64) => Ok(vec![Calculation {
+            name: op_name.to_string(),
+            inputs: inputs.clone(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", op_name, &a.name)),
+                column_type: ColumnType::Scalar(dtype.clone()),
+                nullable: a.nullable,
+            },
+            function: Function::Scalar(function),
+        }]),
+        t => Err(unsupported(
+            op_name,
+            format!(
+                "Cannot perform {} operation on {:?} column, a float column is required",
+                op_name, t
+            ),
+        )),
+    }
+}
+
+/// Operation to round float values up to the nearest integer
+pub struct CeilOperation;
+
+impl ScalarOperation for CeilOperation {
+    fn name() -> &'static str {
+        "ceil"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_unary_transform(Self::name(), ScalarFunction::Ceil, inputs, name)
+    }
+}
+
+/// Operation to round float values down to the nearest integer
+pub struct FloorOperation;
+
+impl ScalarOperation for FloorOperation {
+    fn name() -> &'static str {
+        "floor"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_unary_transform(Self::name(), ScalarFunction::Floor, inputs, name)
+    }
+}
+
+/// Operation to round float values to a number of decimal places, with halves rounded away from 0
+///
+/// `transform` rounds to the nearest integer, use `RoundOperation::transform_with` to supply
+/// the number of decimal places.
+pub struct RoundOperation;
+
+impl RoundOperation {
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        decimals: i32,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_unary_transform(Self::name(), ScalarFunction::Round(decimals), inputs, name)
+    }
+}
+
+impl ScalarOperation for RoundOperation {
+    fn name() -> &'static str {
+        "round"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Self::transform_with(inputs, 0, name)
+    }
+}
+
+/// Create the calculations of a float operation, casting any non-float inputs to `Float64`
+///
+/// Inputs keep their float type if they all share it, otherwise they are all cast to `Float64`.
+fn float_math_transform(
+    op_name: &str,
+    function: ScalarFunction,
+    num_inputs: usize,
+    inputs: Vec<Column>,
+    name: Option<String>,
+) -> Result<Vec<Calculation>, DataFrameError> {
+    if inputs.len() != num_inputs {
+        return Err(unsupported(
+            op_name,
+            format!("{} operation expects {} input(s)", op_name, num_inputs),
+        ));
+    }
+    let is_numeric = |t: &DataType| {
+        matches!(
+            t,
+            DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                | DataType::Float32
+                | DataType::Float64
+        )
+    };
+    let mut input_types = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+        match &input.column_type {
+            ColumnType::Scalar(dtype) if is_numeric(dtype) => input_types.push(dtype.clone()),
+            t => {
+                return Err(unsupported(
+                    op_name,
+                    format!("Cannot perform {} operation on {:?} column", op_name, t),
+                ))
+            }
+        }
+    }
+    let output_type = match input_types.first() {
+        Some(DataType::Float32) if input_types.iter().all(|t| t == &DataType::Float32) => {
+            DataType::Float32
+        }
+        _ => DataType::Float64,
+    };
+    let mut calculations = vec![];
+    let mut float_inputs = Vec::with_capacity(inputs.len());
+    for (input, dtype) in inputs.iter().zip(input_types) {
+        if dtype == output_type {
+            float_inputs.push(input.clone());
+        } else {
+            let cast_output = Column {
+                name: format!(
+                    "{}({} as {:?})",
+                    CastOperation::name(),
+                    &input.name,
+                    output_type
+                ),
+                column_type: ColumnType::Scalar(output_type.clone()),
+                nullable: input.nullable,
+            };
+            calculations.push(Calculation {
+                name: CastOperation::name().to_string(),
+                inputs: vec![input.clone()],
+                output: cast_output.clone(),
+                function: Function::Cast(CastMode::default(), None),
+            });
+            float_inputs.push(cast_output);
+        }
+    }
+    let input_names: Vec<&str> = inputs.iter().map(|input| input.name.as_str()).collect();
+    calculations.push(Calculation {
+        name: op_name.to_string(),
+        inputs: float_inputs,
+        output: Column {
+            name: name.unwrap_or(format!("{}({})", op_name, input_names.join(", "))),
+            column_type: ColumnType::Scalar(output_type),
+            nullable: any_nullable(&inputs),
+        },
+        function: Function::Scalar(function),
+    });
+    Ok(calculations)
+}
+
+/// Operation to compute the square root of values, which is NaN for negative values
+pub struct SqrtOperation;
+
+impl ScalarOperation for SqrtOperation {
+    fn name() -> &'static str {
+        "sqrt"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_math_transform(Self::name(), ScalarFunction::Sqrt, 1, inputs, name)
+    }
+}
+
+/// Operation to raise e to the power of values
+pub struct ExpOperation;
+
+impl ScalarOperation for ExpOperation {
+    fn name() -> &'static str {
+        "exp"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_math_transform(Self::name(), ScalarFunction::Exp, 1, inputs, name)
+    }
+}
+
+/// Operation to compute the natural logarithm of values
+pub struct LnOperation;
+
+impl ScalarOperation for LnOperation {
+    fn name() -> &'static str {
+        "ln"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_math_transform(Self::name(), ScalarFunction::Ln, 1, inputs, name)
+    }
+}
+
+/// Operation to compute the base 10 logarithm of values
+pub struct Log10Operation;
+
+impl ScalarOperation for Log10Operation {
+    fn name() -> &'static str {
+        "log10"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_math_transform(Self::name(), ScalarFunction::Log10, 1, inputs, name)
+    }
+}
+
+/// Operation to raise a base column to the power of an exponent column
+pub struct PowOperation;
+
+impl ScalarOperation for PowOperation {
+    fn name() -> &'static str {
+        "pow"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        float_math_transform(Self::name(), ScalarFunction::Pow, 2, inputs, name)
+    }
+}
+
+/// Operation to extract a part of a date or timestamp column as an `Int32` column
+///
+/// Use `ExtractOperation::transform_with` to supply the part to extract.
+pub struct ExtractOperation;
+
+impl ExtractOperation {
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        part: DatePart,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 1 {
+            return Err(unsupported(
+                Self::name(),
+                "Extract operation expects 1 input".to_string(),
+            ));
+        }
+        let a = &inputs[0];
+        match &a.column_type {
+            ColumnType::Scalar(DataType::Date32(_))
+            | ColumnType::Scalar(DataType::Date64(_))
+            | ColumnType::Scalar(DataType::Timestamp(_, _)) => Ok(vec![Calculation {
+                name: Self::name().to_string(),
+                inputs: inputs.clone(),
+                output: Column {
+                    name: name.unwrap_or(format!("{}({:?} from {})", Self::name(), part, &a.name)),
+                    column_type: ColumnType::Scalar(DataType::Int32),
+                    nullable: a.nullable,
+                },
+                function: Function::Scalar(ScalarFunction::Extract(part)),
+            }]),
+            t => Err(unsupported(
+                Self::name(),
+                format!(
+                    "Cannot extract {:?} from {:?} column, a date or timestamp column is required",
+                    part, t
+                ),
+            )),
+        }
+    }
+}
+
+impl ScalarOperation for ExtractOperation {
+    fn name() -> &'static str {
+        "extract"
+    }
+
+    fn transform(
+        _inputs: Vec<Column>,
+        _name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Extract operation requires a date part, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Operation to truncate a timestamp column to a granularity, e.g. the start of each value's day
+///
+/// Use `DateTruncOperation::transform_with` to supply the granularity.
+pub struct DateTruncOperation;
+
+impl DateTruncOperation {
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        granularity: Granularity,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.len() != 1 {
+            return Err(unsupported(
+                Self::name(),
+                "Date trunc operation expects 1 input".to_string(),
+            ));
+        }
+        let a = &inputs[0];
+        match &a.column_type {
+            ColumnType::Scalar(dtype @ DataType::Timestamp(_, _)) => Ok(vec![Calculation {
+                name: Self::name().to_string(),
+                inputs: inputs.clone(),
+                output: Column {
+                    name: name.unwrap_or(format!("{}({})", Self::name(), &a.name)),
+                    column_type: ColumnType::Scalar(dtype.clone()),
+                    nullable: a.nullable,
+                },
+                function: Function::Scalar(ScalarFunction::DateTrunc(granularity)),
+            }]),
+            t => Err(unsupported(
+                Self::name(),
+                format!(
+                    "Cannot truncate {:?} column, a timestamp column is required",
+                    t
+                ),
+            )),
+        }
+    }
+}
+
+impl ScalarOperation for DateTruncOperation {
+    fn name() -> &'static str {
+        "date_trunc"
+    }
+
+    fn transform(
+        _inputs: Vec<Column>,
+        _name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Date trunc operation requires a granularity, use transform_with".to_string(),
+        ))
+    }
+}
+
+/// Operation that tags each row with a `UInt32` partition id, from the hash of its key columns
+///
+/// Use `HashPartitionOperation::transform_with` to supply the number of partitions.
+pub struct HashPartitionOperation;
+
+impl HashPartitionOperation {
+    pub fn transform_with(
+        inputs: Vec<Column>,
+        num_partitions: u32,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if inputs.is_empty() {
+            return Err(unsupported(
+                Self::name(),
+                "Hash partition operation expects 1 or more key inputs".to_string(),
+            ));
+        }
+        if num_partitions == 0 {
+            return Err(unsupported(
+                Self::name(),
+                "Hash partition operation requires at least 1 partition".to_string(),
+            ));
+        }
+        for input in &inputs {
+            if let ColumnType::Array(_) = input.column_type {
+                return Err(unsupported(
+                    Self::name(),
+                    "Hash partition operation only works on scalar columns".to_string(),
+                ));
+            }
+        }
+        let input_names: Vec<&str> = inputs.iter().map(|input| input.name.as_str()).collect();
+        Ok(vec![Calculation {
+            name: Self::name().to_string(),
+            output: Column {
+                name: name.unwrap_or(format!("{}({})", Self::name(), input_names.join(", "))),
+                column_type: ColumnType::Scalar(DataType::UInt32),
+                nullable: false,
+            },
+            inputs,
+            function: Function::Scalar(ScalarFunction::HashPartition(num_partitions)),
+        }])
+    }
+}
+
+impl ScalarOperation for HashPartitionOperation {
+    fn name() -> &'static str {
+        "hash_partition"
+    }
+
+    fn transform(
+        _inputs: Vec<Column>,
+        _name: Option<String>,
+        _to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        Err(unsupported(
+            Self::name(),
+            "Hash partition operation requires a partition count, use transform_with".to_string(),
+        ))
+    }
+}
+
+// pub struct TrigOperation(TrigFunction);
+
+// pub enum TrigFunction {
+//     Sin,
+//     Cos,
+//     Tan,
+//     Cot,
+//     Sec,
+//     Cosec,
+// }
+
+pub struct SinOperation;
+
+impl ScalarOperation for SinOperation {
+    fn name() -> &'static str {
+        // note that we override this name with the actual operation
+        "sin"
+    }
+
+    fn transform(
+        inputs: Vec<Column>,
+        name: Option<String>,
+        to_type: Option<DataType>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        // add n columns together provided that they are of the same data type
+        // for now we support 2 inputs at a time
+        // the output data type is also ignored
+        if inputs.len() != 1 {
+            Err(unsupported(
+                Self::name(),
+                "Sine operation expects 2 inputs".to_string(),
+            ))
+        } else {
+            let a = &inputs[0];
+            match &a.column_type {
+                ColumnType::Array(_) | ColumnType::Struct(_) => Err(unsupported(
+                    Self::name(),
+                    "Sine operation is currently only supported on scalar columns".to_string(),
+                )),
+                ColumnType::Scalar(dtype) => {
+                    // check if datatype is a float, and convert to f64 if not
+                    match dtype {
+                        DataType::Int8
+                        | DataType::Int16
+                        | DataType::Int32
+                        | DataType::Int64
+                        | DataType::UInt8
+                        | DataType::UInt16
+                        | DataType::UInt32
+                        | DataType::UInt64 => {
+                            let cast_name = name.clone().unwrap_or(format!(
+                                "{}({} as datatype)",
+                                CastOperation::name(),
+                                &a.name
+                            ));
+                            let cast_output = Column {
+                                name: cast_name,
+                                column_type: ColumnType::Scalar(DataType::Float64),
+                                nullable: a.nullable,
+                            };
+                            Ok(vec![
+                                Calculation {
+                                    name: CastOperation::name().to_string(),
+                                    inputs: inputs.clone(),
+                                    output: cast_output.clone(),
+                                    function: Function::Cast(CastMode::default(), None),
+                                },
+                                Calculation {
+                                    name: Self::name().to_string(),
+                                    inputs: vec![cast_output],
+                                    output: Column {
+                                        name: name.unwrap_or(format!(
+                                            "{}({} as datatype)",
+                                            Self::name(),
+                                            &a.name
+                                        )),
+                                        column_type: ColumnType::Scalar(DataType::Float64),
+                                        nullable: a.nullable,
+                                    },
+                                    function: Function::Scalar(ScalarFunction::Sine),
+                                },
+                            ])
+                        }
+                        DataType::Float32 | DataType::Float64 => Ok(vec![Calculation {
+                            name: Self::name().to_string(),
+                            inputs: inputs.clone(),
+                            output: Column {
+                                name: name.unwrap_or(format!(
+                                    "{}({} as datatype)",
+                                    Self::name(),
+                                    &a.name
+                                )),
+                                column_type: ColumnType::Scalar(dtype.clone()),
+                                nullable: a.nullable,
+                            },
+                            function: Function::Scalar(ScalarFunction::Sine),
+                        }]),
+                        _ => Err(unsupported(
+                            Self::name(),
+                            format!(
+                                "Cannot perform {} operation from {:?} data type",
+                                Self::name(),
+                                dtype,
+                            ),
+                        )),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalar_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+
+        let add = AddOperation::transform(vec![a, b], None, None).unwrap();
+
+        assert_eq!(
+            "[Calculation { name: \"cast\", inputs: [Column { name: \"b\", column_type: Scalar(Int32), nullable: true }], output: Column { name: \"b\", column_type: Scalar(Int64), nullable: true }, function: Cast(Safe) }, Calculation { name: \"add\", inputs: [Column { name: \"a\", column_type: Scalar(Int64), nullable: true }, Column { name: \"b\", column_type: Scalar(Int64), nullable: true }], output: Column { name: \"add(a, b)\", column_type: Scalar(Int64), nullable: true }, function: Scalar(Add(ArithmeticOptions { checked: false })) }]",
+            format!("{:?}", add)
+        );
+    }
+
+    #[test]
+    fn concat_strings() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let concat = ConcatOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        assert_eq!(1, concat.len());
+        assert_eq!(vec![a, b], concat[0].inputs);
+        assert_eq!("concat(a, b)", concat[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            concat[0].output.column_type
+        );
+    }
+
+    #[test]
+    fn concat_casts_non_strings() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let concat = ConcatOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        assert_eq!(2, concat.len());
+        // the integer column is cast to a string first
+        assert_eq!("cast", concat[0].name);
+        assert_eq!(vec![a], concat[0].inputs);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            concat[0].output.column_type
+        );
+        assert_eq!(vec![concat[0].output.clone(), b], concat[1].inputs);
+        assert_eq!("concat(a, b)", concat[1].output.name);
+    }
+
+    #[test]
+    fn string_case_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let upper = UpperOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!("upper(a)", upper[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            upper[0].output.column_type
+        );
+        assert_eq!("Scalar(Upper)", format!("{:?}", upper[0].function));
+
+        let lower = LowerOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!("lower(a)", lower[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            lower[0].output.column_type
+        );
+        assert_eq!("Scalar(Lower)", format!("{:?}", lower[0].function));
+
+        let trim = TrimOperation::transform(vec![a], None, None).unwrap();
+        assert_eq!("trim(a)", trim[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            trim[0].output.column_type
+        );
+        assert_eq!("Scalar(Trim)", format!("{:?}", trim[0].function));
+    }
+
+    #[test]
+    fn string_operations_reject_non_strings() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+
+        assert!(UpperOperation::transform(vec![a.clone()], None, None).is_err());
+        assert!(LowerOperation::transform(vec![a.clone()], None, None).is_err());
+        assert!(TrimOperation::transform(vec![a], None, None).is_err());
+    }
+
+    #[test]
+    fn substring_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let substring =
+            SubstringOperation::transform_with(vec![a.clone()], -3, None, None).unwrap();
+        assert_eq!("substring(a)", substring[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            substring[0].output.column_type
+        );
+        assert_eq!(
+            "Scalar(Substring { start: -3, length: None })",
+            format!("{:?}", substring[0].function)
+        );
+        // the start position is required
+        assert!(SubstringOperation::transform(vec![a], None, None).is_err());
+    }
+
+    #[test]
+    fn like_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let like = LikeOperation::transform_with(vec![a.clone()], "foo%", None).unwrap();
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            like[0].output.column_type
+        );
+        assert_eq!("Scalar(Like(\"foo%\"))", format!("{:?}", like[0].function));
+
+        let ilike = ILikeOperation::transform_with(vec![a], "%bar%", None).unwrap();
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            ilike[0].output.column_type
+        );
+        assert_eq!(
+            "Scalar(ILike(\"%bar%\"))",
+            format!("{:?}", ilike[0].function)
+        );
+    }
+
+    #[test]
+    fn regex_match_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+
+        let regex =
+            RegexMatchOperation::transform_with(vec![a.clone()], r"^foo\d+$", None).unwrap();
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            regex[0].output.column_type
+        );
+        assert_eq!(
+            "Scalar(RegexMatch(\"^foo\\\\d+$\"))",
+            format!("{:?}", regex[0].function)
+        );
+
+        // malformed patterns are rejected before evaluation
+        assert!(RegexMatchOperation::transform_with(vec![a], "foo(", None).is_err());
+    }
+
+    #[test]
+    fn null_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Array(DataType::Int64),
+            nullable: true,
+        };
+
+        // null checks accept strings and lists, unlike numeric operations
+        let is_null = IsNullOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!("is_null(a)", is_null[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            is_null[0].output.column_type
+        );
+        let is_not_null = IsNotNullOperation::transform(vec![b], None, None).unwrap();
+        assert_eq!("is_not_null(b)", is_not_null[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            is_not_null[0].output.column_type
+        );
+        assert!(IsNullOperation::transform(vec![], None, None).is_err());
+    }
+
+    #[test]
+    fn coalesce_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let coalesce = CoalesceOperation::transform(vec![a.clone(), b], None, None).unwrap();
+        assert_eq!(1, coalesce.len());
+        assert_eq!("coalesce(a, b)", coalesce[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int64),
+            coalesce[0].output.column_type
+        );
+
+        // mixed numeric types are promoted
+        let c = Column {
+            name: "c".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let d = Column {
+            name: "d".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        let coalesce = CoalesceOperation::transform(vec![c, d.clone()], None, None).unwrap();
+        assert_eq!(2, coalesce.len());
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            coalesce[0].output.column_type
+        );
+        assert_eq!(vec![coalesce[0].output.clone(), d], coalesce[1].inputs);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            coalesce[1].output.column_type
+        );
+
+        let e = Column {
+            name: "e".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        assert!(CoalesceOperation::transform(vec![a, e], None, None).is_err());
+    }
+
+    #[test]
+    fn case_when_operation() {
+        let condition = Column {
+            name: "cond".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Boolean),
+            nullable: true,
+        };
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        let case_when =
+            CaseWhenOperation::transform(vec![condition.clone(), a.clone(), b.clone()], None, None)
+                .unwrap();
+        // a is cast to float before the selection
+        assert_eq!(2, case_when.len());
+        assert_eq!("case_when(cond, a, b)", case_when[1].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            case_when[1].output.column_type
+        );
+        assert_eq!(
+            vec![condition, case_when[0].output.clone(), b],
+            case_when[1].inputs
+        );
+
+        // the condition has to be a boolean
+        assert!(CaseWhenOperation::transform(vec![a.clone(), a.clone(), a], None, None).is_err());
+    }
+
+    #[test]
+    fn array_to_string_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int32),
+            nullable: true,
+        };
+        let joined = ArrayToStringOperation::transform_with(vec![a], ",", None).unwrap();
+        assert_eq!("array_to_string(a)", joined[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Utf8),
+            joined[0].output.column_type
+        );
+        assert_eq!(
+            "Scalar(ArrayToString(\",\"))",
+            format!("{:?}", joined[0].function)
+        );
+
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        assert!(ArrayToStringOperation::transform_with(vec![b], ",", None).is_err());
+    }
+
+    #[test]
+    fn cast_array_column() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int32),
+            nullable: true,
+        };
+        let calc = CastOperation::transform(
+            vec![a.clone()],
+            None,
+            Some(DataType::List(Box::new(DataType::Int64))),
+        )
+        .unwrap();
+        assert_eq!(
+            ColumnType::Array(DataType::Int64),
+            calc[0].output.column_type
+        );
+        // array columns can't be cast to scalars
+        assert!(CastOperation::transform(vec![a], None, Some(DataType::Int64)).is_err());
+    }
+
+    #[test]
+    fn cast_with_format() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        let calc = CastOperation::transform_with_format(
+            vec![a],
+            None,
+            Some(DataType::Timestamp(
+                arrow::datatypes::TimeUnit::Microsecond,
+                None,
+            )),
+            CastMode::Safe,
+            "%Y-%m-%d %H:%M:%S",
+        )
+        .unwrap();
+        match &calc[0].function {
+            Function::Cast(CastMode::Safe, Some(format)) => assert_eq!("%Y-%m-%d %H:%M:%S", format),
+            f => panic!("Unexpected cast function {:?}", f),
+        }
+    }
+
+    #[test]
+    fn rounding_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float32),
+            nullable: true,
+        };
+        let ceil = CeilOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!("ceil(a)", ceil[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float32),
+            ceil[0].output.column_type
+        );
+        let round = RoundOperation::transform_with(vec![a], 2, None).unwrap();
+        assert_eq!("round(a)", round[0].output.name);
+        match &round[0].function {
+            Function::Scalar(ScalarFunction::Round(2)) => {}
+            f => panic!("Unexpected round function {:?}", f),
+        }
+
+        // integers are rejected
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        assert!(FloorOperation::transform(vec![b], None, None).is_err());
+    }
+
+    #[test]
+    fn math_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        // integers are cast to Float64 before the square root is taken
+        let sqrt = SqrtOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!(2, sqrt.len());
+        assert_eq!("cast", sqrt[0].name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            sqrt[0].output.column_type
+        );
+        assert_eq!(vec![sqrt[0].output.clone()], sqrt[1].inputs);
+        assert_eq!("sqrt(a)", sqrt[1].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            sqrt[1].output.column_type
+        );
+
+        // only the integer base is cast
+        let pow = PowOperation::transform(vec![a, b.clone()], None, None).unwrap();
+        assert_eq!(2, pow.len());
+        assert_eq!(b, pow[1].inputs[1]);
+        assert_eq!("pow(a, b)", pow[1].output.name);
+        match &pow[1].function {
+            Function::Scalar(ScalarFunction::Pow) => {}
+            f => panic!("Unexpected pow function {:?}", f),
+        }
+
+        // floats are not cast
+        let ln = LnOperation::transform(vec![b.clone()], None, None).unwrap();
+        assert_eq!(1, ln.len());
+        assert!(PowOperation::transform(vec![b], None, None).is_err());
+    }
+
+    #[test]
+    fn extract_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Timestamp(
+                arrow::datatypes::TimeUnit::Millisecond,
+                None,
+            )),
+            nullable: true,
+        };
+        let year = ExtractOperation::transform_with(vec![a], DatePart::Year, None).unwrap();
+        assert_eq!("extract(Year from a)", year[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int32),
+            year[0].output.column_type
+        );
+        match &year[0].function {
+            Function::Scalar(ScalarFunction::Extract(DatePart::Year)) => {}
+            f => panic!("Unexpected extract function {:?}", f),
+        }
+
+        // non-temporal columns are rejected
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        assert!(ExtractOperation::transform_with(vec![b], DatePart::Month, None).is_err());
+    }
+
+    #[test]
+    fn date_trunc_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Timestamp(
+                arrow::datatypes::TimeUnit::Microsecond,
+                None,
+            )),
+            nullable: true,
+        };
+        let trunc =
+            DateTruncOperation::transform_with(vec![a.clone()], Granularity::Day, None).unwrap();
+        assert_eq!("date_trunc(a)", trunc[0].output.name);
+        assert_eq!(a.column_type, trunc[0].output.column_type);
+        match &trunc[0].function {
+            Function::Scalar(ScalarFunction::DateTrunc(Granularity::Day)) => {}
+            f => panic!("Unexpected date trunc function {:?}", f),
+        }
+
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        assert!(DateTruncOperation::transform_with(vec![b], Granularity::Hour, None).is_err());
+    }
+
+    #[test]
+    fn serialize_calculations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+
+        let add = AddOperation::transform(vec![a, b], None, None).unwrap();
+        assert_eq!(2, add.len());
+        let json = serde_json::to_string(&add).unwrap();
+        let deserialized: Vec<Calculation> = serde_json::from_str(&json).unwrap();
+        assert_eq!(add, deserialized);
+    }
+
+    #[test]
+    fn unsupported_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Array(DataType::Int64),
+            nullable: true,
+        };
+        match AddOperation::transform(vec![a, b], None, None) {
+            Err(DataFrameError::UnsupportedOperation { op, .. }) => assert_eq!("add", op),
+            result => panic!("Expected an unsupported operation, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn numeric_promotion() {
+        use DataType::*;
+        let cases = vec![
+            // integers of the same signedness
+            (Int8, Int32, Int32),
+            (Int64, Int16, Int64),
+            (UInt8, UInt16, UInt16),
+            (UInt64, UInt32, UInt64),
+            // signed and unsigned integers
+            (Int8, UInt8, Int16),
+            (UInt16, Int16, Int32),
+            (Int32, UInt32, Int64),
+            (Int64, UInt8, Int64),
+            (UInt8, Int64, Int64),
+            (Int64, UInt64, Float64),
+            // integers and floats
+            (Int8, Float32, Float32),
+            (UInt16, Float32, Float32),
+            (Int32, Float32, Float64),
+            (Int64, Float32, Float64),
+            (Float32, UInt64, Float64),
+            (Int8, Float64, Float64),
+            // floats
+            (Float32, Float64, Float64),
+            (Float64, Float32, Float64),
+            (Float32, Float32, Float32),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(
+                expected,
+                promote_numeric(&a, &b).unwrap(),
+                "promoting {:?} and {:?}",
+                a,
+                b
+            );
+            // promotion is symmetric
+            assert_eq!(expected, promote_numeric(&b, &a).unwrap());
+        }
+        assert!(matches!(
+            promote_numeric(&Int64, &Utf8),
+            Err(DataFrameError::UnsupportedOperation { .. })
+        ));
+        assert!(promote_numeric(&Boolean, &Float64).is_err());
+    }
+
+    #[test]
+    fn arithmetic_promotes_inputs() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float32),
+            nullable: true,
+        };
+        // both inputs are cast, as neither is of the promoted type
+        let add = AddOperation::transform(vec![a, b], None, None).unwrap();
+        assert_eq!(3, add.len());
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            add[0].output.column_type
+        );
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            add[1].output.column_type
+        );
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            add[2].output.column_type
+        );
+    }
+
+    #[test]
+    fn bitwise_operation_types() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let bitand = BitAndOperation::transform(vec![a.clone(), b], None, None).unwrap();
+        assert_eq!(1, bitand.len());
+        assert_eq!("bitand(a, b)", bitand[0].output.name);
+        assert_eq!(Function::Scalar(ScalarFunction::BitAnd), bitand[0].function);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int32),
+            bitand[0].output.column_type
+        );
+
+        // integers are coerced to a common integer type
+        let c = Column {
+            name: "c".to_owned(),
+            column_type: ColumnType::Scalar(DataType::UInt8),
+            nullable: true,
+        };
+        let bitor = BitOrOperation::transform(vec![a.clone(), c.clone()], None, None).unwrap();
+        assert_eq!(2, bitor.len());
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int32),
+            bitor[1].output.column_type
+        );
+
+        // shifts keep the type of the shifted column
+        let shift = ShiftRightOperation::transform(vec![c, a.clone()], None, None).unwrap();
+        assert_eq!(2, shift.len());
+        assert_eq!(
+            ColumnType::Scalar(DataType::UInt8),
+            shift[1].output.column_type
+        );
+
+        // floats and arrays are rejected
+        let f = Column {
+            name: "f".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        let l = Column {
+            name: "l".to_owned(),
+            column_type: ColumnType::Array(DataType::Int32),
+            nullable: true,
+        };
+        assert!(BitXorOperation::transform(vec![a.clone(), f], None, None).is_err());
+        assert!(ShiftLeftOperation::transform(vec![l, a], None, None).is_err());
+    }
+
+    #[test]
+    fn nullability_propagation() {
+        let column = |name: &str, nullable: bool| Column {
+            name: name.to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable,
+        };
+        let (a, b) = (column("a", true), column("b", false));
+        let add = AddOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        assert!(add[0].output.is_nullable());
+        let add = AddOperation::transform(vec![b.clone(), b.clone()], None, None).unwrap();
+        assert!(!add[0].output.is_nullable());
+        // a coalesce is only null if all its inputs are
+        let coalesce = CoalesceOperation::transform(vec![a, b], None, None).unwrap();
+        assert!(!coalesce.last().unwrap().output.is_nullable());
+    }
+
+    #[test]
+    fn struct_inputs_are_rejected() {
+        let location = Column {
+            name: "location".to_owned(),
+            column_type: ColumnType::Struct(vec![Column {
+                name: "lat".to_owned(),
+                column_type: ColumnType::Scalar(DataType::Float64),
+                nullable: true,
+            }]),
+            nullable: true,
+        };
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        assert!(AddOperation::transform(vec![a.clone(), location.clone()], None, None).is_err());
+        assert!(
+            CastOperation::transform(vec![location.clone()], None, Some(DataType::Utf8)).is_err()
+        );
+        assert!(CoalesceOperation::transform(vec![location, a], None, None).is_err());
+    }
+
+    #[test]
+    fn array_arithmetic_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int32),
+            nullable: false,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Array(DataType::Float64),
+            nullable: true,
+        };
+        // the integer lists are cast to lists of floats first
+        let sum = ArrayAddOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        assert_eq!(2, sum.len());
+        assert_eq!(
+            ColumnType::Array(DataType::Float64),
+            sum[0].output.column_type
+        );
+        assert_eq!("array_add(a, b)", sum[1].output.name);
+        assert_eq!(
+            ColumnType::Array(DataType::Float64),
+            sum[1].output.column_type
+        );
+        assert!(sum[1].output.nullable);
+
+        let difference = ArraySubtractOperation::transform(vec![b.clone(), b], None, None).unwrap();
+        assert_eq!(1, difference.len());
+        assert_eq!(
+            "Scalar(ArraySubtract)",
+            format!("{:?}", difference[0].function)
+        );
+
+        let names = Column {
+            name: "names".to_owned(),
+            column_type: ColumnType::Array(DataType::Utf8),
+            nullable: true,
+        };
+        let c = Column {
+            name: "c".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        assert!(ArrayAddOperation::transform(vec![a.clone(), names], None, None).is_err());
+        assert!(ArrayAddOperation::transform(vec![a, c], None, None).is_err());
+    }
+
+    #[test]
+    fn array_reduce_operations() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int64),
+            nullable: false,
+        };
+        let length = ArrayLengthOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!("array_length(a)", length[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int32),
+            length[0].output.column_type
+        );
+        let sum = ArraySumOperation::transform(vec![a.clone()], None, None).unwrap();
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int64),
+            sum[0].output.column_type
+        );
+        assert!(!sum[0].output.nullable);
+        // empty lists have no maximum
+        let max = ArrayMaxOperation::transform(vec![a], None, None).unwrap();
+        assert!(max[0].output.nullable);
+
+        let names = Column {
+            name: "names".to_owned(),
+            column_type: ColumnType::Array(DataType::Utf8),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int64),
+            nullable: true,
+        };
+        assert!(ArrayLengthOperation::transform(vec![names.clone()], None, None).is_ok());
+        assert!(ArraySumOperation::transform(vec![names], None, None).is_err());
+        assert!(ArrayLengthOperation::transform(vec![b.clone()], None, None).is_err());
+        assert!(ArrayMaxOperation::transform(vec![b], None, None).is_err());
+    }
+
+    #[test]
+    fn array_contains_operation() {
+        let tags = Column {
+            name: "tags".to_owned(),
+            column_type: ColumnType::Array(DataType::Utf8),
+            nullable: false,
+        };
+        let tag = Column {
+            name: "tag".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: false,
+        };
+        let contains =
+            ArrayContainsOperation::transform(vec![tags.clone(), tag], None, None).unwrap();
+        assert_eq!(1, contains.len());
+        assert_eq!("array_contains(tags, tag)", contains[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Boolean),
+            contains[0].output.column_type
+        );
+
+        // the list of integers is cast to a list of floats to search for a float
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Array(DataType::Int32),
+            nullable: true,
+        };
+        let b = Column {
+            name: "b".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Float64),
+            nullable: true,
+        };
+        let contains =
+            ArrayContainsOperation::transform(vec![a.clone(), b.clone()], None, None).unwrap();
+        assert_eq!(2, contains.len());
+        assert_eq!(
+            ColumnType::Array(DataType::Float64),
+            contains[0].output.column_type
+        );
+        assert_eq!(contains[0].output, contains[1].inputs[0]);
+
+        assert!(ArrayContainsOperation::transform(vec![tags, b.clone()], None, None).is_err());
+        assert!(ArrayContainsOperation::transform(vec![b, a], None, None).is_err());
     }
 }
@@ -0,0 +1,94 @@
+//! Window Operations
+
+use crate::error::DataFrameError;
+use crate::expression::*;
+use arrow::datatypes::DataType;
+
+/// Operation that computes the trailing moving average of a numeric column
+///
+/// The average of each row is over the row and up to `window - 1` preceding rows, so the first
+/// rows of the column have partial windows.
+pub struct MovingAverageOperation;
+
+impl MovingAverageOperation {
+    pub fn name() -> &'static str {
+        "moving_avg"
+    }
+
+    pub fn transform(
+        input: &Column,
+        window: usize,
+        name: Option<String>,
+    ) -> Result<Vec<Calculation>, DataFrameError> {
+        if window == 0 {
+            return Err(DataFrameError::UnsupportedOperation {
+                op: Self::name().to_string(),
+                reason: "Moving average window must have at least 1 row".to_string(),
+            });
+        }
+        match &input.column_type {
+            ColumnType::Scalar(data_type) if is_numeric(data_type) => Ok(vec![Calculation {
+                name: Self::name().to_string(),
+                inputs: vec![input.clone()],
+                output: Column {
+                    name: name.unwrap_or(format!("{}({})", Self::name(), &input.name)),
+                    column_type: ColumnType::Scalar(DataType::Float64),
+                    nullable: true,
+                },
+                function: Function::Window(WindowFunction::MovingAverage(window)),
+            }]),
+            t => Err(DataFrameError::UnsupportedOperation {
+                op: Self::name().to_string(),
+                reason: format!("Cannot compute a moving average of {:?} column", t),
+            }),
+        }
+    }
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_average_operation() {
+        let a = Column {
+            name: "a".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Int32),
+            nullable: true,
+        };
+        let calc = MovingAverageOperation::transform(&a, 3, None).unwrap();
+        assert_eq!("moving_avg(a)", calc[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Float64),
+            calc[0].output.column_type
+        );
+        match &calc[0].function {
+            Function::Window(WindowFunction::MovingAverage(3)) => {}
+            f => panic!("Unexpected window function {:?}", f),
+        }
+        assert!(MovingAverageOperation::transform(&a, 0, None).is_err());
+
+        let s = Column {
+            name: "s".to_owned(),
+            column_type: ColumnType::Scalar(DataType::Utf8),
+            nullable: true,
+        };
+        assert!(MovingAverageOperation::transform(&s, 3, None).is_err());
+    }
+}
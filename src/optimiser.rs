@@ -110,7 +110,14 @@ fn optimise_read(
     let mut mutated = read.clone();
 
     match &reader.source {
-        DataSourceType::Arrow(_) | DataSourceType::Json(_) | DataSourceType::Parquet(_) => {
+        DataSourceType::Arrow(_)
+        | DataSourceType::ArrowStream(_)
+        | DataSourceType::Json(_)
+        | DataSourceType::Parquet(_)
+        | DataSourceType::Avro(_)
+        | DataSourceType::Memory(_)
+        | DataSourceType::CsvStream(_)
+        | DataSourceType::Flight(_, _) => {
             // no projection support
             output.push(input.clone());
         }
@@ -236,6 +243,136 @@ fn optimise_project_calc(
     (output, mutated)
 }
 
+/// Replace calculations whose inputs are all literals with a literal of their result
+///
+/// Scalar functions and casts of literals are evaluated once on a single row, instead of for
+/// each row of the data. Literals that are then no longer used are removed, except for the last
+/// calculation, which is the output of the calculations. Calculations that cannot be evaluated,
+/// such as a division by zero, or that return a null, are left unchanged, so that they fail or
+/// return nulls when the calculations are evaluated.
+pub(crate) fn fold_constants(calculations: Vec<Calculation>) -> Vec<Calculation> {
+    use arrow::datatypes::DataType;
+    use std::collections::{HashMap, HashSet};
+
+    // the values of the literal columns, by name, which later calculations may overwrite
+    let mut literals: HashMap<String, Scalar> = HashMap::new();
+    let mut folded = Vec::with_capacity(calculations.len());
+    for calculation in calculations {
+        let calculation = match &calculation.function {
+            Function::Scalar(_) | Function::Cast(_, _)
+                if !calculation.inputs.is_empty()
+                    && calculation
+                        .inputs
+                        .iter()
+                        .all(|input| literals.contains_key(&input.name)) =>
+            {
+                let output_type = DataType::from(calculation.output.column_type.clone());
+                evaluate_literal(&calculation, &literals)
+                    .filter(|value| value.data_type().as_ref() == Some(&output_type))
+                    .and_then(|value| {
+                        Calculation::literal(value, Some(calculation.output.name.clone())).ok()
+                    })
+                    .unwrap_or(calculation)
+            }
+            _ => calculation,
+        };
+        match &calculation.function {
+            Function::Literal(value) => {
+                literals.insert(calculation.output.name.clone(), value.clone());
+            }
+            _ => {
+                literals.remove(&calculation.output.name);
+            }
+        }
+        folded.push(calculation);
+    }
+
+    // remove literals whose columns are not read, going backwards as columns can be overwritten
+    let mut used: HashSet<String> = HashSet::new();
+    let last = folded.len().saturating_sub(1);
+    let mut output: Vec<Calculation> = folded
+        .into_iter()
+        .enumerate()
+        .rev()
+        .filter(|(i, calculation)| {
+            let is_literal = matches!(calculation.function, Function::Literal(_));
+            if is_literal && *i != last && !used.contains(&calculation.output.name) {
+                return false;
+            }
+            used.remove(&calculation.output.name);
+            used.extend(calculation.inputs.iter().map(|input| input.name.clone()));
+            true
+        })
+        .map(|(_, calculation)| calculation)
+        .collect();
+    output.reverse();
+    output
+}
+
+/// Evaluate a calculation of literal inputs on a single row, returning its value
+fn evaluate_literal(
+    calculation: &Calculation,
+    literals: &std::collections::HashMap<String, Scalar>,
+) -> Option<Scalar> {
+    use crate::dataframe::DataFrame;
+    use crate::evaluation::Evaluate;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    let mut fields: Vec<Field> = vec![];
+    let mut columns = vec![];
+    for input in &calculation.inputs {
+        if fields.iter().any(|f| f.name() == &input.name) {
+            continue;
+        }
+        let value = &literals[&input.name];
+        let field: Field = Calculation::literal(value.clone(), Some(input.name.clone()))
+            .ok()?
+            .output
+            .into();
+        columns.push(crate::table::Column::from_arrays(
+            vec![value.to_array(1)],
+            field.clone(),
+        ));
+        fields.push(field);
+    }
+    let frame = DataFrame::from_columns(Arc::new(Schema::new(fields)), columns);
+    // errors are left to be returned when the calculation is evaluated
+    let frame = frame.try_calculate(calculation).ok()?;
+    let array = frame
+        .column_by_name(&calculation.output.name)
+        .data()
+        .chunks()
+        .first()?
+        .clone();
+    if array.is_null(0) {
+        return None;
+    }
+    macro_rules! scalar {
+        ($array:ty, $variant:ident) => {
+            Scalar::$variant(
+                array
+                    .as_any()
+                    .downcast_ref::<$array>()
+                    .unwrap()
+                    .value(0)
+                    .into(),
+            )
+        };
+    }
+    let value = match array.data_type() {
+        DataType::Int32 => scalar!(Int32Array, Int32),
+        DataType::Int64 => scalar!(Int64Array, Int64),
+        DataType::Float32 => scalar!(Float32Array, Float32),
+        DataType::Float64 => scalar!(Float64Array, Float64),
+        DataType::Boolean => scalar!(BooleanArray, Boolean),
+        DataType::Utf8 => scalar!(StringArray, String),
+        _ => return None,
+    };
+    Some(value)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +390,9 @@ mod tests {
                     max_records: None,
                     batch_size: 1024,
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -287,6 +427,9 @@ mod tests {
                     max_records: None,
                     batch_size: 1024,
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -317,6 +460,9 @@ mod tests {
                     max_records: None,
                     batch_size: 1024,
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -350,6 +496,9 @@ mod tests {
                     delimiter: None,
                     max_records: Some(1024),
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -379,6 +528,9 @@ mod tests {
                     max_records: None,
                     batch_size: 1024,
                     projection: None,
+                    schema: None,
+                    compression: None,
+                    null_values: vec![],
                 },
             ),
         };
@@ -395,4 +547,40 @@ mod tests {
         let optimised = optimise(&computations);
         dbg!(optimised);
     }
+
+    #[test]
+    fn test_fold_constants() {
+        use crate::operation::expr::{col, lit};
+        use arrow::datatypes::DataType;
+
+        let dataset = Dataset {
+            name: "numbers".to_owned(),
+            columns: vec![Column {
+                name: "a".to_owned(),
+                column_type: ColumnType::Scalar(DataType::Int64),
+                nullable: true,
+            }],
+        };
+        // the Int32 literal is cast to Int64 before it is added, and both are folded
+        let lowered = (lit(5i32) + lit(3i64)).to_calculations(&dataset).unwrap();
+        assert_eq!(1, lowered.len());
+        assert_eq!(Function::Literal(Scalar::Int64(8)), lowered[0].function);
+        assert_eq!("add(5, 3)", lowered[0].output.name);
+        assert_eq!(
+            ColumnType::Scalar(DataType::Int64),
+            lowered[0].output.column_type
+        );
+
+        // only the literal subexpression is folded
+        let lowered = (col("a") + lit(2i64) * lit(3i64))
+            .to_calculations(&dataset)
+            .unwrap();
+        assert_eq!(2, lowered.len());
+        assert_eq!(Function::Literal(Scalar::Int64(6)), lowered[0].function);
+        assert_eq!("add(a, multiply(2, 3))", lowered[1].output.name);
+
+        // a division by zero is left to fail when it is evaluated
+        let lowered = (lit(1i64) / lit(0i64)).to_calculations(&dataset).unwrap();
+        assert_eq!(3, lowered.len());
+    }
 }
@@ -0,0 +1,131 @@
+//! Pretty-printing of record batches
+//!
+//! Batches are formatted as ASCII tables with `arrow::util::pretty`. Large batches only show
+//! their first and last rows, separated by a `...` row, and the total row count is shown
+//! below the table.
+
+use arrow::record_batch::RecordBatch;
+use arrow::util::pretty;
+
+use crate::error::Result;
+
+/// Format batches as a table, showing at most `max_rows` rows
+///
+/// If the batches have more rows than `max_rows`, the first and last rows are shown.
+pub fn format_batches(batches: &[RecordBatch], max_rows: usize) -> Result<String> {
+    let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    let footer = format!("{} rows", total_rows);
+    if total_rows <= max_rows {
+        return Ok(format!(
+            "{}\n{}",
+            pretty::pretty_format_batches(batches)?,
+            footer
+        ));
+    }
+    // the head gets the extra row if the rows cannot be split evenly
+    let head = (max_rows + 1) / 2;
+    let tail = max_rows / 2;
+    let mut shown = slice_rows(batches, 0, head)?;
+    shown.extend(slice_rows(batches, total_rows - tail, tail)?);
+    let table = pretty::pretty_format_batches(&shown)?;
+    let mut lines: Vec<String> = table.lines().map(|line| line.to_string()).collect();
+    // the separator row is sized to the table's columns, which are read from its top border.
+    // The dots are truncated in columns that are narrower than them.
+    let separator = lines[0]
+        .split('+')
+        .filter(|border| !border.is_empty())
+        .map(|border| format!(" {:w$.w$} ", "...", w = border.len() - 2))
+        .collect::<Vec<String>>()
+        .join("|");
+    // the table starts with a border, the header, and another border
+    lines.insert(3 + head, format!("|{}|", separator));
+    lines.push(footer);
+    Ok(lines.join("\n"))
+}
+
+/// Print batches to console as a table, showing at most `max_rows` rows
+pub fn print_batches(batches: &[RecordBatch], max_rows: usize) -> Result<()> {
+    println!("{}", format_batches(batches, max_rows)?);
+    Ok(())
+}
+
+/// Slice `len` rows starting at `offset` out of a sequence of batches
+fn slice_rows(batches: &[RecordBatch], offset: usize, len: usize) -> Result<Vec<RecordBatch>> {
+    let mut sliced = vec![];
+    let mut start = 0;
+    let end = offset + len;
+    for batch in batches {
+        let batch_end = start + batch.num_rows();
+        if batch_end > offset && start < end {
+            let from = offset.max(start) - start;
+            let to = end.min(batch_end) - start;
+            sliced.push(RecordBatch::try_new(
+                batch.schema(),
+                batch
+                    .columns()
+                    .iter()
+                    .map(|c| c.slice(from, to - from))
+                    .collect(),
+            )?);
+        }
+        start = batch_end;
+    }
+    Ok(sliced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_format_truncated_batches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        // the rows are split across batches, and every third name is null
+        let batches = (0..2)
+            .map(|b| {
+                let ids: Vec<i64> = (b * 50..(b + 1) * 50).collect();
+                let names: Vec<Option<String>> = ids
+                    .iter()
+                    .map(|i| {
+                        if i % 3 == 0 {
+                            None
+                        } else {
+                            Some(format!("n{}", i))
+                        }
+                    })
+                    .collect();
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(ids)),
+                        Arc::new(StringArray::from(
+                            names.iter().map(|n| n.as_deref()).collect::<Vec<_>>(),
+                        )),
+                    ],
+                )
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let formatted = format_batches(&batches, 10)?;
+        let lines: Vec<&str> = formatted.lines().collect();
+        // 3 header lines, 5 head rows, the separator, 5 tail rows, a border and the footer
+        assert_eq!(16, lines.len());
+        assert!(lines[4].contains("n1"));
+        assert_eq!("| .. | ...  |", lines[8]);
+        assert!(lines[9].contains("n95"));
+        assert!(lines[12].contains("n98"));
+        assert_eq!("100 rows", lines[15]);
+
+        // small batches are not truncated
+        let formatted = format_batches(&batches[..1], 50)?;
+        assert!(!formatted.contains("..."));
+        assert!(formatted.ends_with("50 rows"));
+        Ok(())
+    }
+}
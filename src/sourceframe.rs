@@ -0,0 +1,220 @@
+//! A dataframe over a data source
+//!
+//! A `SourceFrame` records the transformations that are applied to it, and only reads its
+//! data source once it is collected. Transformations that the source supports are pushed
+//! down to it, and the rest are evaluated in memory.
+
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+
+use crate::dataframe::DataFrame;
+use crate::error::{DataFrameError, Result};
+use crate::evaluation::Evaluate;
+use crate::expression::*;
+use crate::io::datasource::DataSource;
+
+pub struct SourceFrame {
+    source: Box<dyn DataSource>,
+    /// The input dataset of the source
+    input: Dataset,
+    /// The dataset that is produced by the transformations
+    output: Dataset,
+    transformations: Vec<Transformation>,
+}
+
+impl SourceFrame {
+    pub fn try_new(source: Box<dyn DataSource>) -> Result<Self> {
+        let input = source.get_dataset()?;
+        Ok(Self {
+            source,
+            output: input.clone(),
+            input,
+            transformations: vec![],
+        })
+    }
+
+    pub fn column(&self, name: &str) -> Option<(usize, &Column)> {
+        self.output.get_column(name)
+    }
+
+    /// project columns
+    pub fn select(mut self, col_names: Vec<&str>) -> Result<Self> {
+        let mut columns = vec![];
+        for col in &col_names {
+            let (_, column) = self.output.get_column(col).ok_or_else(|| {
+                DataFrameError::ComputeError(format!(
+                    "Column {:?} cannot be selected as it does not exist",
+                    col
+                ))
+            })?;
+            columns.push(column.clone());
+        }
+        self.output = Dataset {
+            name: self.output.name.clone(),
+            columns,
+        };
+        self.transformations.push(Transformation::Select(
+            col_names.iter().map(|s| s.to_string()).collect(),
+        ));
+        Ok(self)
+    }
+
+    /// Apply a filter using a `BooleanFilter` which evaluates to a `BooleanArray`
+    pub fn filter(mut self, condition: BooleanFilter) -> Self {
+        self.transformations.push(Transformation::Filter(condition));
+        self
+    }
+
+    /// Create a column from the function
+    pub fn with_column(
+        mut self,
+        col_name: &str,
+        function: Function,
+        input_col_names: Vec<&str>,
+        as_type: Option<DataType>,
+    ) -> Result<Self> {
+        let transformations = Calculation::calculate(
+            &self.output,
+            input_col_names,
+            function,
+            Some(col_name.to_owned()),
+            as_type,
+        )?;
+        for transformation in &transformations {
+            match transformation {
+                Transformation::Calculate(calculation) => {
+                    self.output = self.output.append_column(calculation.output.clone())
+                }
+                _ => {
+                    return Err(DataFrameError::ComputeError(format!(
+                        "Cannot create a column from {:?} transformation",
+                        transformation
+                    )))
+                }
+            }
+        }
+        self.transformations.extend(transformations);
+        Ok(self)
+    }
+
+    pub fn sort(mut self, criteria: &[SortCriteria]) -> Result<Self> {
+        if criteria.is_empty() {
+            return Err(DataFrameError::ComputeError(
+                "Sort criteria cannot be empty".to_string(),
+            ));
+        }
+        for c in criteria {
+            self.output.get_column(&c.column).ok_or_else(|| {
+                DataFrameError::ComputeError(format!(
+                    "Column {:?} used in sort expression does not exist in dataframe",
+                    &c.column
+                ))
+            })?;
+        }
+        self.transformations
+            .push(Transformation::Sort(criteria.to_vec()));
+        Ok(self)
+    }
+
+    /// Limit data to the specified number of records.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.transformations.push(Transformation::Limit(limit));
+        self
+    }
+
+    /// Read the data source, and apply the transformations to its batches
+    pub fn collect(mut self) -> Result<Vec<RecordBatch>> {
+        let pushed = self.push_down()?;
+        let remaining = self.transformations.split_off(pushed);
+        let mut batches = vec![];
+        while let Some(batch) = self.source.next_batch()? {
+            batches.push(batch);
+        }
+        if remaining.is_empty() || batches.is_empty() {
+            return Ok(batches);
+        }
+        let schema = self.source.schema();
+        let frame =
+            DataFrame::from_table(crate::table::Table::from_record_batches(schema, batches));
+        let computation = Computation {
+            input: vec![self.source.get_dataset()?],
+            transformations: remaining,
+            output: self.output,
+        };
+        Ok(frame.evaluate(&[computation]).to_record_batches())
+    }
+
+    /// Push the leading transformations that the source supports down to it, returning
+    /// the number of transformations that were pushed down
+    ///
+    /// Pushing down stops at the first transformation that the source does not support, and
+    /// after a limit, as a source would apply a later filter before the limit.
+    fn push_down(&mut self) -> Result<usize> {
+        let mut pushed = 0;
+        let mut is_sorted = false;
+        for transformation in &self.transformations {
+            match transformation {
+                Transformation::Select(columns) if self.source.supports_projection() => {
+                    self.source.project(columns.clone())?
+                }
+                Transformation::Filter(filter) if self.source.supports_filtering() => {
+                    self.source.filter(filter.clone())?
+                }
+                // a second sort would replace the first, instead of sorting its output
+                Transformation::Sort(criteria) if self.source.supports_sorting() && !is_sorted => {
+                    self.source.sort(criteria.clone())?;
+                    is_sorted = true;
+                }
+                Transformation::Limit(limit) if self.source.supports_limit() => {
+                    self.source.limit(*limit)?;
+                    return Ok(pushed + 1);
+                }
+                _ => break,
+            }
+            pushed += 1;
+        }
+        Ok(pushed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::datasource::CsvDataSource;
+    use arrow::array::*;
+
+    #[test]
+    fn test_source_frame() -> Result<()> {
+        let source =
+            CsvDataSource::try_new("./test/data/uk_cities_with_headers.csv", Default::default())?;
+        let frame = SourceFrame::try_new(Box::new(source))?
+            .select(vec!["city", "lat"])?
+            .filter(BooleanFilter::Gt(
+                Box::new(BooleanFilter::Input(BooleanInput::Column(Column {
+                    name: "lat".to_owned(),
+                    column_type: ColumnType::Scalar(DataType::Float64),
+                    nullable: true,
+                }))),
+                Box::new(BooleanFilter::Input(BooleanInput::Scalar(Scalar::Float64(
+                    54.0,
+                )))),
+            ))
+            .limit(5);
+        assert_eq!(2, frame.output.columns.len());
+        let batches = frame.collect()?;
+        let num_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(5, num_rows);
+        for batch in &batches {
+            assert_eq!(2, batch.num_columns());
+            let lat = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .unwrap();
+            for i in 0..lat.len() {
+                assert!(lat.value(i) > 54.0);
+            }
+        }
+        Ok(())
+    }
+}